@@ -73,6 +73,13 @@ user-aborted-op = User aborted the operation.
 count-pkg-has-desc = { $count } package(s) will be
 dep-issue-1 = oma cannot install the specified package due to dependency issue(s).
 dep-issue-2 = Please copy or take a screenshot of the following and report it to an AOSC OS maintainer:
+dep-issue-explain = The dependency problem(s) can be traced back to the requested package(s) as follows:
+dep-issue-conflict = but { $pkg } ({ $ver }) requires { $dep }
+dep-issue-unsatisfiable = but no available version satisfies it
+dep-issue-suggestions = Possible solution(s):
+dep-issue-suggest-remove = Also remove { $pkg }: `oma remove { $pkg }'
+dep-issue-suggest-pick = Install version { $ver } of { $pkg }: `oma install { $pkg }={ $ver }' or `oma pick { $pkg }'
+dep-issue-suggest-topic = Enable topic { $topic } which provides { $pkg }: `oma topics --opt-in { $topic }'
 how-to-op-with-x = Press [PgUp/Dn], arrow keys, or use the mouse wheel to scroll.
 end-review = Press [q] to end review
 cc-to-abort = Press [Ctrl-c] to abort
@@ -256,3 +263,224 @@ send-request-timeout = Send network request timeout
 download-timeout = Download file timeout
 checksum-mismatch-download-err = Checksum mismatch
 suggest = The following packages are recommended to enhance the functionality of the packages you have installed:
+conffile-changed = The configuration file { $path } from package { $pkg } has been modified locally, but the package ships a newer version.
+conffile-prompt = How would you like to handle { $path }?
+conffile-keep = Keep the currently installed version
+conffile-take-new = Install the package maintainer's version
+conffile-merge = Merge both versions (3-way merge)
+conffile-edit = Edit the current file
+conffile-show-diff = Show differences between both versions
+conffile-skip = Decide later (leave the file untouched)
+conffile-remember = Always make this choice for { $path } in future transactions?
+conffile-policy-applied = Applied saved choice for { $path }: { $policy }.
+conffile-diff-title = Changes to { $path } (current → package maintainer's version)
+conffile-merge-conflict = Unable to merge { $path } automatically, please resolve the conflicts in the editor.
+conffile-merge-confirm = Replace { $path } with the merged version?
+conffile-merge-unavailable = Unable to perform a 3-way merge, please install git or diffutils.
+conffile-editor-failed = Failed to launch text editor.
+conffile-report-1 = The following configuration files require your attention:
+conffile-report-2 = Please compare them with the files currently in use and merge or remove them as appropriate.
+conffile-report-kind-dist = new version from package maintainer
+conffile-report-kind-new = new version not yet installed
+conffile-report-kind-old = previous version
+report-services-need-restart = The following services are still using outdated binaries or libraries and need to be restarted:
+report-restart-services-tips = You may restart them with the { $cmd } option in your next transaction, or restart them manually.
+report-processes-need-restart = The following processes are still using outdated binaries or libraries, please restart them or log in again:
+report-reboot-required = Please restart your system to complete the update ({ $reason }).
+report-reboot-reason-flag = requested by packages
+report-reboot-reason-kernel = running kernel has been replaced
+report-skip-restart = Not restarting { $unit } as it may end your session, please restart your system later.
+report-service-restarted = Restarted { $unit }.
+report-restart-failed = Failed to restart { $unit }: { $reason }
+failed-to-restart-service = Failed to restart service { $unit }.
+changelog-title = Changelog of { $pkg } ({ $ver })
+changelog-not-found = Unable to find changelog of { $pkg }.
+changelog-local-fallback = Unable to fetch changelog from the repository, showing { $path } of the installed version instead.
+changelog-no-new-entries = New entries of { $pkg } { $ver } are unavailable without the changelog from the repository.
+changelog-news = Important news:
+changelog-changes = Changes:
+changelog-fetching = Fetching changelogs ...
+changelog-preview = Changelogs of packages to be upgraded:
+
+# unattended
+unattended-outside-window = Not in the maintenance window ({ $window }), skipping unattended upgrade.
+unattended-invalid-window = Invalid maintenance window "{ $window }", expected a time range like "02:00-05:00".
+unattended-on-battery = Computer is using battery, skipping unattended upgrade.
+unattended-lock-retry = oma or dpkg is being used by another process, retrying in { $secs } seconds ({ $times })...
+unattended-dpkg-locked = dpkg is being used by another package manager.
+unattended-remove-required = Refusing to upgrade unattended, as the following package(s) would be removed: { $pkgs }
+unattended-blacklisted = Refusing to upgrade unattended, as the following blacklisted package(s) would be changed: { $pkgs }
+unattended-kept-back = Keeping back { $pkgs }, as they require blacklisted package(s) to be changed.
+unattended-failed = Unattended upgrade failed.
+unattended-report-success = Unattended upgrade succeeded, { $count } package(s) upgraded.
+unattended-report-failure = Unattended upgrade failed:
+unattended-notify-failed = Failed to run notify command: { $status }
+unattended-reboot-scheduled = System will be rebooted in { $mins } minute(s) to apply updates ({ $reason }).
+unattended-reboot-message = Rebooting to apply package updates.
+unattended-reboot-failed = Failed to schedule a reboot.
+
+# source
+source-downloading = Downloading source package { $pkg } ({ $ver }) ...
+source-successfully-download = Successfully downloaded { $len } source file(s) to path: { $path }.
+source-not-found = Unable to find source package for { $pkg }, please make sure `deb-src' entries are enabled in your sources.list.
+source-no-mirror = Unable to find the mirror of source package { $pkg }.
+build-dep-invalid-control = { $path } is not a valid debian/control file.
+build-dep-unsatisfiable = Unable to satisfy build dependencies: { $deps }
+why-not-installed = { $pkg } is not installed.
+why-manual = { $pkg } was installed manually.
+why-required = { $pkg } is required by the following dependency chain:
+why-unneeded = { $pkg } was installed automatically and is no longer required, it will be removed by autoremove.
+why-not-no-candidate = { $pkg } has no installable version, available version(s):
+why-not-already-installed = { $pkg } ({ $ver }) is already installed.
+why-not-hold = { $pkg } is marked as held, use `oma mark unhold { $pkg }' to release it.
+why-not-not-downloadable = { $pkg } ({ $ver }) is not available from any repository.
+why-not-broken = Unable to install { $pkg } due to the following dependency problem(s):
+why-not-installable = { $pkg } can be installed.
+why-not-need-remove = { $pkg } can be installed, but { $count } package(s) have to be removed:
+solver-unsat = oma cannot satisfy the request, because of the following conflicting constraint(s):
+solver-reason-install = { $pkg } is requested to be installed
+solver-reason-remove = { $pkg } is requested to be removed
+solver-reason-keep = { $pkg } is essential or held, it must stay installed
+solver-reason-depends = { $pkg } ({ $ver }) depends on { $dep }
+solver-reason-conflicts = { $pkg } ({ $ver }) conflicts with { $dep }
+solver-reason-one-version = Only one version of { $pkg } can be installed
+solver-gave-up = The SAT solver gave up searching for a solution, please try again with `--solver apt'.
+solver-compare-apt = Result of the apt solver (will be used):
+solver-compare-sat = Result of the SAT solver:
+solver-compare-sat-failed = The SAT solver failed: { $reason }
+arch-native = (native)
+arch-enabled = Enabled architecture { $arch }.
+arch-disabled = Disabled architecture { $arch }.
+arch-already-enabled = Architecture { $arch } is already enabled.
+arch-not-enabled = Architecture { $arch } is not enabled.
+arch-remove-native = Unable to remove the native architecture { $arch }.
+arch-has-installed-pkgs = { $count ->
+    [one] { $count } package of architecture { $arch } is still installed:
+    *[other] { $count } packages of architecture { $arch } are still installed:
+}
+arch-remove-installed-tips = Please remove them first using { $cmd }.
+arch-not-enabled-tips = Architecture { $arch } is not enabled, you may enable it using { $cmd }.
+multiarch-version-skew = Copies of Multi-Arch: same package { $pkg } have different versions, dpkg will refuse to configure them:
+bootstrap-target-not-empty = { $path } already contains a system, please specify an empty directory.
+bootstrap-preparing = Bootstrapping { $suite } into { $path } ...
+bootstrap-no-keyring = No keyring is available to verify the mirror, please specify one using --keyring, or trust keyrings of the host using --host-keyrings.
+bootstrap-essential = Unpacking { $count } essential package(s) ...
+bootstrap-configure-essential = Configuring essential package(s) ...
+bootstrap-command-failed = Failed to run { $cmd }.
+bootstrap-reexec-failed = Failed to re-execute oma using { $cmd }, please make sure it is installed.
+bootstrap-unshare-too-old = unshare from util-linux { $version } is too old, 2.38 or later is required. Please upgrade util-linux or use --mode fakechroot.
+bootstrap-success = Successfully bootstrapped the system into { $path }.
+bundle-nothing-to-do = No package needs to be bundled.
+bundle-no-inrelease = Unable to find the signed InRelease file of { $index }, please refresh the repository metadata first.
+bundle-created = Bundled { $count } package(s) into { $path }.
+bundle-bad-manifest = The bundle is broken or created by an incompatible version of oma.
+bundle-arch-mismatch = The bundle is created for architecture { $bundle }, but this system is { $arch }.
+bundle-verifying = Verifying the bundle ...
+bundle-verify-failed = Failed to verify { $path } in the bundle.
+bundle-untrusted-deb = { $path } in the bundle is not listed in any verified repository metadata.
+bundle-tar-failed = Failed to run tar.
+repo-already-exists = { $path } is already a local repository.
+repo-not-found = { $path } is not a local repository, please create one using `oma repo init'.
+repo-generating-key = Generating signing key ...
+repo-generate-key-failed = Failed to generate signing key.
+repo-init-success = Created local repository at { $path }.
+repo-sources-tips = To use this repository, add the following line to your sources.list: { $line }
+repo-secret-key-tips = Secret key of this repository is stored in { $path }, keep it safe.
+repo-invalid-deb = { $path } is not a valid package file.
+repo-added = Added { $name } ({ $version }, { $arch }) to the repository.
+repo-pkg-exists = A different package file of { $name } ({ $version }, { $arch }) already exists in the repository, please remove it first.
+repo-removed = Removed { $name } ({ $version }, { $arch }) from the repository.
+repo-no-match = No package in the repository matches { $name }.
+repo-indexing = Indexing { $count } package(s) ...
+repo-no-key = This repository is not configured to be signed.
+repo-sign-failed = Failed to sign the repository using { $path }.
+repo-signed = Signed { $path }.
+repo-command-failed = Failed to run { $cmd }.
+local-deb-downgrade = Installing { $name } from local file will downgrade it from { $installed } to { $local }.
+local-deb-replace-origin = { $name } ({ $installed }) is installed from { $origin }, it will be replaced by the local file.
+local-deb-file-conflict = Following file(s) of { $name } are owned by other installed package(s):
+invalid-search-regex = Invalid regular expression.
+tui-start-8 = Show package details
+tui-detail-info = Information
+tui-detail-depends = Dependencies
+tui-detail-rdepends = Reverse dependencies
+tui-detail-versions = Versions
+tui-detail-changelog = Changelog
+tui-detail-installed = installed
+tui-detail-provided-by = Provided by
+tui-detail-virtual = { $pkg } is a virtual package, see its providers in the dependencies tab.
+tui-detail-changelog-loading = Loading changelog ...
+tui-detail-switch-tab = Switch tab
+tui-detail-enter = Jump to package / Pick version
+tui-detail-back = Back
+tui-start-9 = Show/Hide history
+tui-history = History
+tui-history-inspect = Inspect
+tui-history-undo = Undo
+tui-history-can-not-undo = This transaction can not be undone.
+tui-commit-downloading = Downloading
+tui-commit-installing = Installing
+tui-commit-log = Log
+tui-commit-review = Review changes
+tui-commit-confirm = Confirm
+tui-commit-cancel = Cancel
+tui-commit-scroll = Scroll
+tui-commit-cancelled = The transaction was cancelled.
+tui-start-10 = Show/Hide topics and mirrors
+tui-sources = Topics and Mirrors
+tui-sources-topics = Topics
+tui-sources-mirrors = Mirrors
+tui-sources-topic-pkgs = { $count ->
+    [one] { $count } package
+    *[other] { $count } packages
+}
+tui-sources-testing = testing ...
+tui-sources-unreachable = unreachable
+tui-sources-toggle = Enable/Disable
+tui-sources-reorder = Reorder
+tui-sources-latency = Test latency
+tui-sources-apply = Apply
+tui-sources-no-mirror = At least one mirror must be enabled.
+tui-sources-unchanged = Nothing was changed.
+theme-invalid = Failed to load color theme { $path }, using built-in colors.
+keymap-invalid-preset = Unknown keymap preset "{ $preset }", using the default keymap.
+keymap-invalid-action = Unknown key binding action "{ $action }", ignoring.
+keymap-invalid-key = Invalid key "{ $key }", ignoring.
+search-tips-with-regex-query = Search (regex): { $query }
+filter-tips-with-query = Filter (press [Ctrl-r] for regex): { $query }
+filter-tips-with-regex-query = Filter (regex): { $query }
+filter-tips-with-result = { $count ->
+    [one] Showing { $count } matching line, press [Esc] to show all lines.
+    *[other] Showing { $count } matching lines, press [Esc] to show all lines.
+}
+pager-tips-invalid-pattern = Invalid pattern (press [/] or [&] to retry).
+save-tips-with-path = Save to: { $path }
+save-tips-with-ansi-path = Save with colors to: { $path }
+save-tips-done = Saved to { $path }.
+save-tips-failed = Failed to save to { $path }.
+review-title = Review pending changes
+review-header = { $changes } package(s) to change, { $deselected } deselected
+review-group-by = Group by
+review-group-by-operation = Operation
+review-group-by-topic = Topic
+review-group-by-section = Section
+review-no-topic = Not in topics
+review-install = Install
+review-upgrade = Upgrade
+review-downgrade = Downgrade
+review-reinstall = Reinstall
+review-remove = Remove
+review-held-back = held back
+review-held-back-tips = This package is held back because of deselected packages, select them again to change it.
+review-toggle = Select/Deselect
+review-collapse = Collapse/Expand
+review-confirm = Confirm
+review-cancel = Cancel
+review-cascade = Changes caused by deselected packages
+plan-state-mismatch = Package { $pkg } has been changed since the plan was made (expected: { $expected }, found: { $found }).
+plan-not-installed = not installed
+plan-checksum-mismatch = Package file of { $pkg } { $ver } is different from the one in the plan.
+plan-changed = Changes to be made are different from the plan, please make the plan again.
+plan-bad-file = The plan is broken or made by an incompatible version of oma.
+plan-arch-mismatch = The plan is made for architecture { $plan }, but this system is { $arch }.
+bundle-missing-deb = Package { $name } { $version } is listed in the bundle, but its file is missing.
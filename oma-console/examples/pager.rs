@@ -1,5 +1,6 @@
 use crossterm::style::Stylize;
 use oma_console::{
+    input::InputConfig,
     pager::{OmaPager, Pager, PagerUIText},
     print::OmaColorFormat,
 };
@@ -27,11 +28,48 @@ impl PagerUIText for OmaPagerUIText {
     fn search_tips_not_found(&self) -> String {
         "Pattern not found (Press /)".to_string()
     }
+
+    fn search_tips_with_regex_query(&self, query: &str) -> String {
+        format!("Search (regex): {}", query)
+    }
+
+    fn filter_tips_with_query(&self, query: &str, regex: bool) -> String {
+        if regex {
+            format!("Filter (regex): {}", query)
+        } else {
+            format!("Filter: {}", query)
+        }
+    }
+
+    fn filter_tips_with_result(&self, count: usize) -> String {
+        format!("{} matching lines, press Esc to show all lines", count)
+    }
+
+    fn tips_invalid_pattern(&self) -> String {
+        "Invalid pattern (Press / or &)".to_string()
+    }
+
+    fn save_tips_with_path(&self, path: &str, ansi: bool) -> String {
+        if ansi {
+            format!("Save with colors to: {}", path)
+        } else {
+            format!("Save to: {}", path)
+        }
+    }
+
+    fn save_tips_done(&self, path: &str) -> String {
+        format!("Saved to {}", path)
+    }
+
+    fn save_tips_failed(&self, path: &str) -> String {
+        format!("Failed to save to {}", path)
+    }
 }
 
 fn main() -> io::Result<()> {
     let cf = OmaColorFormat::new(true, Duration::from_millis(100));
-    let pager = OmaPager::new(Some("QAQ".to_string()), &cf, &OmaPagerUIText);
+    let input = InputConfig::default();
+    let pager = OmaPager::new(Some("QAQ".to_string()), &cf, &OmaPagerUIText, &input);
     let mut p = Pager::External(Box::new(pager));
     let mut w = p.get_writer()?;
     w.write_all("QAQ\n".cyan().to_string().as_bytes()).ok();
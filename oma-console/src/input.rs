@@ -0,0 +1,330 @@
+use std::{collections::HashMap, fmt::Display, str::FromStr};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// What a key does in the pager and the TUI
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyAction {
+    Up,
+    Down,
+    Left,
+    Right,
+    PageUp,
+    PageDown,
+    Top,
+    Bottom,
+    Search,
+    NextMatch,
+    PreviousMatch,
+    /// Show only lines matching a pattern
+    Filter,
+    /// Jump to the next section, like the next group of packages
+    NextSection,
+    PreviousSection,
+    /// Save the shown text to a file, without colors
+    Save,
+    /// Save the shown text to a file, with ANSI colors
+    SaveAnsi,
+    Quit,
+}
+
+impl FromStr for KeyAction {
+    type Err = InputParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "up" => KeyAction::Up,
+            "down" => KeyAction::Down,
+            "left" => KeyAction::Left,
+            "right" => KeyAction::Right,
+            "page_up" => KeyAction::PageUp,
+            "page_down" => KeyAction::PageDown,
+            "top" => KeyAction::Top,
+            "bottom" => KeyAction::Bottom,
+            "search" => KeyAction::Search,
+            "next_match" => KeyAction::NextMatch,
+            "previous_match" => KeyAction::PreviousMatch,
+            "filter" => KeyAction::Filter,
+            "next_section" => KeyAction::NextSection,
+            "previous_section" => KeyAction::PreviousSection,
+            "save" => KeyAction::Save,
+            "save_ansi" => KeyAction::SaveAnsi,
+            "quit" => KeyAction::Quit,
+            _ => return Err(InputParseError(s.to_string())),
+        })
+    }
+}
+
+/// Invalid key, action or keymap preset name
+#[derive(Debug)]
+pub struct InputParseError(String);
+
+impl Display for InputParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid key binding: {}", self.0)
+    }
+}
+
+impl std::error::Error for InputParseError {}
+
+/// A key with modifiers, like `C-n` (Ctrl+N), `M-v` (Alt+V), `G` or `PageDown`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyBinding {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        // Shift is a part of the character, `G` is `G` and not `S-g`
+        let modifiers = match code {
+            KeyCode::Char(_) => modifiers - KeyModifiers::SHIFT,
+            _ => modifiers,
+        };
+
+        Self { code, modifiers }
+    }
+
+    fn plain(c: char) -> Self {
+        Self::new(KeyCode::Char(c), KeyModifiers::NONE)
+    }
+
+    fn ctrl(c: char) -> Self {
+        Self::new(KeyCode::Char(c), KeyModifiers::CONTROL)
+    }
+
+    fn alt(c: char) -> Self {
+        Self::new(KeyCode::Char(c), KeyModifiers::ALT)
+    }
+}
+
+impl From<&KeyEvent> for KeyBinding {
+    fn from(value: &KeyEvent) -> Self {
+        Self::new(value.code, value.modifiers)
+    }
+}
+
+impl FromStr for KeyBinding {
+    type Err = InputParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || InputParseError(s.to_string());
+
+        let mut modifiers = KeyModifiers::NONE;
+        let mut key = s;
+
+        // `-` itself is a valid key
+        while let Some((m, rest)) = key.split_once('-').filter(|(_, rest)| !rest.is_empty()) {
+            modifiers |= match m {
+                "C" => KeyModifiers::CONTROL,
+                "M" | "A" => KeyModifiers::ALT,
+                "S" => KeyModifiers::SHIFT,
+                _ => return Err(err()),
+            };
+            key = rest;
+        }
+
+        let code = match key {
+            "Up" => KeyCode::Up,
+            "Down" => KeyCode::Down,
+            "Left" => KeyCode::Left,
+            "Right" => KeyCode::Right,
+            "PageUp" => KeyCode::PageUp,
+            "PageDown" => KeyCode::PageDown,
+            "Home" => KeyCode::Home,
+            "End" => KeyCode::End,
+            "Enter" => KeyCode::Enter,
+            "Esc" => KeyCode::Esc,
+            "Tab" => KeyCode::Tab,
+            "Backspace" => KeyCode::Backspace,
+            "Space" => KeyCode::Char(' '),
+            x if x.chars().count() == 1 => KeyCode::Char(x.chars().next().unwrap()),
+            x => match x.strip_prefix('F').and_then(|n| n.parse::<u8>().ok()) {
+                Some(n) if (1..=12).contains(&n) => KeyCode::F(n),
+                _ => return Err(err()),
+            },
+        };
+
+        Ok(Self::new(code, modifiers))
+    }
+}
+
+/// Built-in key bindings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KeymapPreset {
+    /// Arrow keys, and keys like less(1)
+    #[default]
+    Default,
+    /// Like `Default`, with Ctrl+F/B/D/U to scroll pages
+    Vim,
+    /// Ctrl+N/P/F/B, Ctrl+V/Alt+V, Alt+</> and Ctrl+S
+    Emacs,
+}
+
+impl FromStr for KeymapPreset {
+    type Err = InputParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "default" => KeymapPreset::Default,
+            "vim" => KeymapPreset::Vim,
+            "emacs" => KeymapPreset::Emacs,
+            _ => return Err(InputParseError(s.to_string())),
+        })
+    }
+}
+
+/// Keymap
+///
+/// `Keymap` maps keys to `KeyAction`s, keys with special meanings in the pager or the TUI
+/// (like Ctrl+C, Esc and Enter) are not looked up in the keymap.
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: HashMap<KeyBinding, KeyAction>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::new(KeymapPreset::Default)
+    }
+}
+
+impl Keymap {
+    pub fn new(preset: KeymapPreset) -> Self {
+        let mut bindings = HashMap::from([
+            (
+                KeyBinding::new(KeyCode::Up, KeyModifiers::NONE),
+                KeyAction::Up,
+            ),
+            (
+                KeyBinding::new(KeyCode::Down, KeyModifiers::NONE),
+                KeyAction::Down,
+            ),
+            (
+                KeyBinding::new(KeyCode::Left, KeyModifiers::NONE),
+                KeyAction::Left,
+            ),
+            (
+                KeyBinding::new(KeyCode::Right, KeyModifiers::NONE),
+                KeyAction::Right,
+            ),
+            (
+                KeyBinding::new(KeyCode::PageUp, KeyModifiers::NONE),
+                KeyAction::PageUp,
+            ),
+            (
+                KeyBinding::new(KeyCode::PageDown, KeyModifiers::NONE),
+                KeyAction::PageDown,
+            ),
+            (
+                KeyBinding::new(KeyCode::Home, KeyModifiers::NONE),
+                KeyAction::Top,
+            ),
+            (
+                KeyBinding::new(KeyCode::End, KeyModifiers::NONE),
+                KeyAction::Bottom,
+            ),
+            (KeyBinding::plain('/'), KeyAction::Search),
+            (KeyBinding::plain('n'), KeyAction::NextMatch),
+            (KeyBinding::plain('N'), KeyAction::PreviousMatch),
+            (KeyBinding::plain('&'), KeyAction::Filter),
+            (KeyBinding::plain(']'), KeyAction::NextSection),
+            (KeyBinding::plain('['), KeyAction::PreviousSection),
+            (KeyBinding::plain('s'), KeyAction::Save),
+            (KeyBinding::plain('S'), KeyAction::SaveAnsi),
+            (KeyBinding::plain('q'), KeyAction::Quit),
+            (KeyBinding::plain('Q'), KeyAction::Quit),
+        ]);
+
+        let less_like = [
+            (KeyBinding::plain('k'), KeyAction::Up),
+            (KeyBinding::plain('j'), KeyAction::Down),
+            (KeyBinding::plain('h'), KeyAction::Left),
+            (KeyBinding::plain('l'), KeyAction::Right),
+            (KeyBinding::plain('g'), KeyAction::Top),
+            (KeyBinding::plain('G'), KeyAction::Bottom),
+        ];
+
+        match preset {
+            KeymapPreset::Default => {
+                bindings.extend(less_like);
+                bindings.extend([
+                    (KeyBinding::plain('u'), KeyAction::PageUp),
+                    (KeyBinding::plain('U'), KeyAction::PageUp),
+                    (KeyBinding::plain('d'), KeyAction::PageDown),
+                    (KeyBinding::plain('D'), KeyAction::PageDown),
+                ]);
+            }
+            KeymapPreset::Vim => {
+                bindings.extend(less_like);
+                bindings.extend([
+                    (KeyBinding::ctrl('b'), KeyAction::PageUp),
+                    (KeyBinding::ctrl('u'), KeyAction::PageUp),
+                    (KeyBinding::ctrl('f'), KeyAction::PageDown),
+                    (KeyBinding::ctrl('d'), KeyAction::PageDown),
+                ]);
+            }
+            KeymapPreset::Emacs => {
+                bindings.extend([
+                    (KeyBinding::ctrl('p'), KeyAction::Up),
+                    (KeyBinding::ctrl('n'), KeyAction::Down),
+                    (KeyBinding::ctrl('b'), KeyAction::Left),
+                    (KeyBinding::ctrl('f'), KeyAction::Right),
+                    (KeyBinding::alt('v'), KeyAction::PageUp),
+                    (KeyBinding::ctrl('v'), KeyAction::PageDown),
+                    (KeyBinding::alt('<'), KeyAction::Top),
+                    (KeyBinding::alt('>'), KeyAction::Bottom),
+                    (KeyBinding::ctrl('s'), KeyAction::Search),
+                ]);
+            }
+        }
+
+        Self { bindings }
+    }
+
+    /// Replace all keys of `action` with `keys`
+    pub fn rebind(&mut self, action: KeyAction, keys: impl IntoIterator<Item = KeyBinding>) {
+        self.bindings.retain(|_, x| *x != action);
+        self.bindings
+            .extend(keys.into_iter().map(|key| (key, action)));
+    }
+
+    /// Get the action of a key event
+    pub fn action(&self, event: &KeyEvent) -> Option<KeyAction> {
+        self.bindings.get(&KeyBinding::from(event)).copied()
+    }
+}
+
+/// Input settings of the pager and the TUI
+#[derive(Debug, Clone, Default)]
+pub struct InputConfig {
+    pub keymap: Keymap,
+    /// Scroll and select with the mouse, off by default to keep text selection of the terminal
+    pub mouse: bool,
+}
+
+#[test]
+fn test_parse_key_binding() {
+    assert_eq!(
+        "C-n".parse::<KeyBinding>().unwrap(),
+        KeyBinding::new(KeyCode::Char('n'), KeyModifiers::CONTROL)
+    );
+    assert_eq!(
+        "M-<".parse::<KeyBinding>().unwrap(),
+        KeyBinding::new(KeyCode::Char('<'), KeyModifiers::ALT)
+    );
+    assert_eq!(
+        "G".parse::<KeyBinding>().unwrap(),
+        KeyBinding::new(KeyCode::Char('G'), KeyModifiers::SHIFT)
+    );
+    assert_eq!(
+        "-".parse::<KeyBinding>().unwrap(),
+        KeyBinding::new(KeyCode::Char('-'), KeyModifiers::NONE)
+    );
+    assert_eq!(
+        "S-PageDown".parse::<KeyBinding>().unwrap(),
+        KeyBinding::new(KeyCode::PageDown, KeyModifiers::SHIFT)
+    );
+    assert!("X-a".parse::<KeyBinding>().is_err());
+    assert!("F13".parse::<KeyBinding>().is_err());
+}
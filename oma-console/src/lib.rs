@@ -12,6 +12,7 @@
 //! ## Modules
 //!
 //! - `pager`: Implements a terminal pager with the `crossterm` and `ratatui` crates.
+//! - `input`: Implements configurable key bindings and mouse settings for the pager.
 //! - `pb`: Implements numerous styles of progress bars with the `indicatif` crate.
 //! - `writer`: Implements a formatted message writer to the terminal.
 //! - `print`: Implements a formatted message logger with support for different logging levels (normal, debug, error, etc.).
@@ -20,6 +21,9 @@
 #[cfg(feature = "pager")]
 pub mod pager;
 
+#[cfg(feature = "pager")]
+pub mod input;
+
 #[cfg(feature = "progress_bar_style")]
 pub mod pb;
 
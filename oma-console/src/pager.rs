@@ -1,12 +1,15 @@
 use std::{
-    io::{self, stderr, stdin, stdout, BufRead, ErrorKind, IsTerminal, Write},
+    fs::File,
+    io::{self, stderr, stdin, stdout, BufRead, BufWriter, ErrorKind, IsTerminal, Write},
     time::{Duration, Instant},
 };
 
-use aho_corasick::{AhoCorasick, BuildError};
+use aho_corasick::AhoCorasick;
 use ansi_to_tui::IntoText;
 use crossterm::{
-    event::{self, Event, KeyCode, KeyModifiers},
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyModifiers, MouseEventKind,
+    },
     execute,
     terminal::{enable_raw_mode, EnterAlternateScreen},
 };
@@ -14,15 +17,22 @@ use ratatui::{
     backend::{Backend, CrosstermBackend},
     layout::{Alignment, Constraint, Layout},
     restore,
-    style::{Color, Stylize},
+    style::Stylize,
     text::Text,
     widgets::{Block, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState},
     Frame, Terminal,
 };
-use termbg::Theme;
+use regex::Regex;
 use tracing::debug;
 
-use crate::{print::OmaColorFormat, writer::Writer};
+use crate::{
+    input::{InputConfig, KeyAction},
+    print::{Action, OmaColorFormat},
+    writer::Writer,
+};
+
+/// Lines to scroll per mouse wheel step
+const MOUSE_SCROLL_LINES: usize = 3;
 
 pub enum Pager<'a> {
     Plain,
@@ -38,17 +48,25 @@ impl<'a> Pager<'a> {
         ui_text: &'a dyn PagerUIText,
         title: Option<String>,
         color_format: &'a OmaColorFormat,
+        input: &'a InputConfig,
     ) -> io::Result<Self> {
         if !stdout().is_terminal() || !stderr().is_terminal() || !stdin().is_terminal() {
             return Ok(Pager::Plain);
         }
 
-        let app = OmaPager::new(title, color_format, ui_text);
+        let app = OmaPager::new(title, color_format, ui_text, input);
         let res = Pager::External(Box::new(app));
 
         Ok(res)
     }
 
+    /// Set lines where sections (like groups of a transaction) start, for jumping between sections
+    pub fn set_sections(&mut self, sections: Vec<usize>) {
+        if let Pager::External(app) = self {
+            app.sections = sections;
+        }
+    }
+
     /// Get writer to writer something to pager
     pub fn get_writer(&mut self) -> io::Result<Box<dyn Write + '_>> {
         let res = match self {
@@ -67,6 +85,9 @@ impl<'a> Pager<'a> {
     pub fn wait_for_exit(self) -> io::Result<PagerExit> {
         let success = if let Pager::External(app) = self {
             let mut terminal = prepare_create_tui()?;
+            if app.input.mouse {
+                enable_mouse_capture()?;
+            }
             let res = app.run(&mut terminal, Duration::from_millis(250))?;
             exit_tui(&mut terminal)?;
 
@@ -80,6 +101,7 @@ impl<'a> Pager<'a> {
 }
 
 pub fn exit_tui(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> io::Result<()> {
+    execute!(stdout(), DisableMouseCapture)?;
     restore();
     terminal.show_cursor()?;
 
@@ -89,6 +111,7 @@ pub fn exit_tui(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> io::Re
 pub fn prepare_create_tui() -> io::Result<Terminal<CrosstermBackend<io::Stdout>>> {
     let hook = std::panic::take_hook();
     std::panic::set_hook(Box::new(move |info| {
+        let _ = execute!(stdout(), DisableMouseCapture);
         restore();
         hook(info);
     }));
@@ -104,6 +127,11 @@ pub fn prepare_create_tui() -> io::Result<Terminal<CrosstermBackend<io::Stdout>>
     Ok(terminal)
 }
 
+/// Capture mouse events, call after `prepare_create_tui`
+pub fn enable_mouse_capture() -> io::Result<()> {
+    execute!(stdout(), EnableMouseCapture)
+}
+
 enum PagerInner {
     Working(Vec<u8>),
     Finished(Vec<String>),
@@ -143,6 +171,14 @@ pub struct OmaPager<'a> {
     ui_text: &'a dyn PagerUIText,
     /// A terminal writer to print oma-style message
     writer: Writer,
+    /// Key bindings and mouse settings
+    input: &'a InputConfig,
+    /// Indices of the lines shown by the filter, `None` to show all lines.
+    filter: Option<Vec<usize>>,
+    /// Indices of the lines where sections start.
+    sections: Vec<usize>,
+    /// Whether search and filter patterns are regular expressions.
+    regex: bool,
 }
 
 impl Write for OmaPager<'_> {
@@ -168,15 +204,34 @@ pub trait PagerUIText {
     fn searct_tips_with_query(&self, query: &str) -> String;
     fn search_tips_with_empty(&self) -> String;
     fn search_tips_not_found(&self) -> String;
+    fn search_tips_with_regex_query(&self, query: &str) -> String;
+    fn filter_tips_with_query(&self, query: &str, regex: bool) -> String;
+    fn filter_tips_with_result(&self, count: usize) -> String;
+    fn tips_invalid_pattern(&self) -> String;
+    fn save_tips_with_path(&self, path: &str, ansi: bool) -> String;
+    fn save_tips_done(&self, path: &str) -> String;
+    fn save_tips_failed(&self, path: &str) -> String;
 }
 
 #[derive(PartialEq, Eq)]
 enum TuiMode {
     Search,
     SearchInputText,
+    FilterInputText,
+    SaveInputText { ansi: bool },
     Noemal,
 }
 
+impl TuiMode {
+    /// Whether the tips bar is a prompt reading text
+    fn is_prompt(&self) -> bool {
+        matches!(
+            self,
+            TuiMode::SearchInputText | TuiMode::FilterInputText | TuiMode::SaveInputText { .. }
+        )
+    }
+}
+
 pub enum PagerExit {
     NormalExit,
     Sigint,
@@ -198,6 +253,7 @@ impl<'a> OmaPager<'a> {
         title: Option<String>,
         theme: &'a OmaColorFormat,
         ui_text: &'a dyn PagerUIText,
+        input: &'a InputConfig,
     ) -> Self {
         Self {
             inner: PagerInner::Working(vec![]),
@@ -216,6 +272,10 @@ impl<'a> OmaPager<'a> {
             mode: TuiMode::Noemal,
             ui_text,
             writer: Writer::default(),
+            input,
+            filter: None,
+            sections: vec![],
+            regex: false,
         }
     }
     /// Run the pager
@@ -255,6 +315,8 @@ impl<'a> OmaPager<'a> {
         self.inner_len = text.len();
 
         let mut query = String::new();
+        let mut filter_query = String::new();
+        let mut save_path = String::new();
 
         let mut last_tick = Instant::now();
         // Start the loop, waiting for the keyboard interrupts.
@@ -268,199 +330,136 @@ impl<'a> OmaPager<'a> {
                         {
                             return Ok(PagerExit::Sigint);
                         }
-                        match key.code {
-                            KeyCode::Char(c) if c == 'q' || c == 'Q' => {
-                                if self.mode == TuiMode::SearchInputText {
-                                    query.push(c);
-                                    self.tips = self.ui_text.searct_tips_with_query(&query);
-                                    continue;
-                                }
-                                return Ok(PagerExit::NormalExit);
-                            }
-                            KeyCode::Down => {
-                                self.down();
-                            }
-                            KeyCode::Up => {
-                                self.up();
-                            }
-                            KeyCode::Left => {
-                                self.left();
-                            }
-                            KeyCode::Right => {
-                                self.right();
-                            }
-                            KeyCode::Char('j') => {
-                                if self.mode == TuiMode::SearchInputText {
-                                    query.push('j');
-                                    self.tips = self.ui_text.searct_tips_with_query(&query);
-                                    continue;
-                                }
-                                self.down();
-                            }
-                            KeyCode::Char('k') => {
-                                if self.mode == TuiMode::SearchInputText {
-                                    query.push('k');
-                                    self.tips = self.ui_text.searct_tips_with_query(&query);
-                                    continue;
-                                }
-                                self.up();
-                            }
-                            KeyCode::Char('h') => {
-                                if self.mode == TuiMode::SearchInputText {
-                                    query.push('h');
-                                    self.tips = self.ui_text.searct_tips_with_query(&query);
-                                    continue;
-                                }
-                                self.left();
-                            }
-                            KeyCode::Char('l') => {
-                                if self.mode == TuiMode::SearchInputText {
-                                    query.push('l');
-                                    self.tips = self.ui_text.searct_tips_with_query(&query);
-                                    continue;
-                                }
-                                self.right();
+
+                        if key.code == KeyCode::Esc {
+                            // Esc outside of prompts and search results shows all lines again
+                            if self.mode == TuiMode::Noemal && self.filter.is_some() {
+                                self.set_filter(None);
                             }
-                            KeyCode::Char('g') => {
-                                if self.mode == TuiMode::SearchInputText {
-                                    query.push('g');
-                                    self.tips = self.ui_text.searct_tips_with_query(&query);
-                                    continue;
-                                }
-                                self.goto_begin();
+                            if self.mode != TuiMode::Noemal {
+                                self.mode = TuiMode::Noemal;
                             }
-                            KeyCode::Char('G') => {
-                                if self.mode == TuiMode::SearchInputText {
-                                    query.push('G');
-                                    self.tips = self.ui_text.searct_tips_with_query(&query);
+                            // clear highlight
+                            self.clear_highlight();
+                            // clear search tips
+                            self.tips = self.ui_text.normal_tips();
+                            continue;
+                        }
+
+                        if self.mode.is_prompt() {
+                            let input = match self.mode {
+                                TuiMode::SearchInputText => &mut query,
+                                TuiMode::FilterInputText => &mut filter_query,
+                                _ => &mut save_path,
+                            };
+
+                            let handled = match key.code {
+                                KeyCode::Enter => {
+                                    let input = input.clone();
+                                    self.submit_prompt(&input);
                                     continue;
                                 }
-                                self.goto_end();
-                            }
-                            KeyCode::Enter => {
-                                if self.mode != TuiMode::SearchInputText {
-                                    continue;
+                                KeyCode::Backspace => {
+                                    input.pop();
+                                    true
                                 }
-                                if query.trim().is_empty() {
-                                    self.tips = self.ui_text.search_tips_with_empty();
-                                } else {
-                                    self.search_results = self.search(&query);
-                                    if self.search_results.is_empty() {
-                                        self.tips = self.ui_text.search_tips_not_found();
-                                    } else {
-                                        self.current_result_index = 0;
-                                        self.jump_to(
-                                            self.search_results[self.current_result_index],
-                                        );
-                                        self.tips = self.ui_text.search_tips_with_result();
-                                    }
+                                // Toggle regular expressions
+                                KeyCode::Char('r')
+                                    if key.modifiers == KeyModifiers::CONTROL
+                                        && !matches!(self.mode, TuiMode::SaveInputText { .. }) =>
+                                {
+                                    self.regex = !self.regex;
+                                    true
                                 }
-                                self.mode = TuiMode::Search;
-                            }
-                            KeyCode::Esc => {
-                                if self.mode != TuiMode::Noemal {
-                                    self.mode = TuiMode::Noemal;
-                                }
-                                // clear highlight
-                                self.clear_highlight();
-                                // clear search tips
-                                self.tips = self.ui_text.normal_tips();
-                            }
-                            KeyCode::Backspace => {
-                                if self.mode == TuiMode::SearchInputText {
-                                    query.pop();
-                                    // update tips with search patterns
-                                    self.tips = self.ui_text.searct_tips_with_query(&query);
+                                KeyCode::Char(input_char)
+                                    if !key
+                                        .modifiers
+                                        .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+                                {
+                                    input.push(input_char);
+                                    true
                                 }
+                                _ => false,
+                            };
+
+                            if handled {
+                                // update tips with the input
+                                self.tips = self.prompt_tips(input);
+                                continue;
                             }
-                            KeyCode::Char('/') => {
-                                if self.mode != TuiMode::SearchInputText {
+                        }
+
+                        let Some(action) = self.input.keymap.action(&key) else {
+                            continue;
+                        };
+
+                        match action {
+                            KeyAction::Quit => return Ok(PagerExit::NormalExit),
+                            KeyAction::Up => self.up(),
+                            KeyAction::Down => self.down(),
+                            KeyAction::Left => self.left(),
+                            KeyAction::Right => self.right(),
+                            KeyAction::PageUp => self.page_up(),
+                            KeyAction::PageDown => self.page_down(),
+                            KeyAction::Top => self.goto_begin(),
+                            KeyAction::Bottom => self.goto_end(),
+                            KeyAction::NextSection => self.next_section(),
+                            KeyAction::PreviousSection => self.previous_section(),
+                            KeyAction::Search => {
+                                if !self.mode.is_prompt() {
                                     self.clear_highlight();
                                     self.mode = TuiMode::SearchInputText;
                                     // update tips with search patterns
-                                    self.tips = self.ui_text.searct_tips_with_query(&query);
-                                } else {
-                                    query.push('/');
-                                    self.tips = self.ui_text.searct_tips_with_query(&query);
-                                    continue;
+                                    self.tips = self.prompt_tips(&query);
                                 }
                             }
-                            KeyCode::Char('n') => match self.mode {
-                                TuiMode::Search => {
-                                    if !self.search_results.is_empty() {
-                                        self.current_result_index = (self.current_result_index + 1)
-                                            % self.search_results.len();
-                                        self.jump_to(
-                                            self.search_results[self.current_result_index],
-                                        );
-                                    }
-                                }
-                                TuiMode::SearchInputText => {
-                                    query.push('n');
-                                    self.tips = self.ui_text.searct_tips_with_query(&query);
-                                    continue;
-                                }
-                                TuiMode::Noemal => continue,
-                            },
-                            KeyCode::Char('N') => match self.mode {
-                                TuiMode::Search => {
-                                    if !self.search_results.is_empty() {
-                                        if self.current_result_index == 0 {
-                                            self.current_result_index =
-                                                self.search_results.len() - 1;
-                                        } else {
-                                            self.current_result_index -= 1;
-                                        }
-                                        self.jump_to(
-                                            self.search_results[self.current_result_index],
-                                        );
-                                    }
-                                }
-                                TuiMode::SearchInputText => {
-                                    query.push('N');
-                                    self.tips = self.ui_text.searct_tips_with_query(&query);
-                                    continue;
-                                }
-                                TuiMode::Noemal => continue,
-                            },
-                            KeyCode::Char(c) if c == 'u' || c == 'U' => {
-                                if self.mode == TuiMode::SearchInputText {
-                                    query.push(c);
-                                    self.tips = self.ui_text.searct_tips_with_query(&query);
-                                    continue;
+                            KeyAction::Filter => {
+                                if !self.mode.is_prompt() {
+                                    self.clear_highlight();
+                                    self.mode = TuiMode::FilterInputText;
+                                    self.tips = self.prompt_tips(&filter_query);
                                 }
-                                self.page_up();
                             }
-                            KeyCode::Char(c) if c == 'd' || c == 'D' => {
-                                if self.mode == TuiMode::SearchInputText {
-                                    query.push(c);
-                                    self.tips = self.ui_text.searct_tips_with_query(&query);
-                                    continue;
+                            KeyAction::Save | KeyAction::SaveAnsi => {
+                                if !self.mode.is_prompt() {
+                                    self.mode = TuiMode::SaveInputText {
+                                        ansi: action == KeyAction::SaveAnsi,
+                                    };
+                                    self.tips = self.prompt_tips(&save_path);
                                 }
-                                self.page_down();
                             }
-                            KeyCode::Char(input_char) => {
-                                if self.mode == TuiMode::SearchInputText {
-                                    query.push(input_char);
-                                    // update tips with search patterns
-                                    self.tips = self.ui_text.searct_tips_with_query(&query);
+                            KeyAction::NextMatch => {
+                                if self.mode == TuiMode::Search && !self.search_results.is_empty() {
+                                    self.current_result_index =
+                                        (self.current_result_index + 1) % self.search_results.len();
+                                    self.jump_to(self.search_results[self.current_result_index]);
                                 }
                             }
-                            KeyCode::PageUp => {
-                                self.page_up();
-                            }
-                            KeyCode::PageDown => {
-                                self.page_down();
+                            KeyAction::PreviousMatch => {
+                                if self.mode == TuiMode::Search && !self.search_results.is_empty() {
+                                    if self.current_result_index == 0 {
+                                        self.current_result_index = self.search_results.len() - 1;
+                                    } else {
+                                        self.current_result_index -= 1;
+                                    }
+                                    self.jump_to(self.search_results[self.current_result_index]);
+                                }
                             }
-                            KeyCode::End => {
-                                self.goto_end();
+                        }
+                    }
+                    Event::Mouse(mouse) => match mouse.kind {
+                        MouseEventKind::ScrollUp => {
+                            for _ in 0..MOUSE_SCROLL_LINES {
+                                self.up();
                             }
-                            KeyCode::Home => {
-                                self.goto_begin();
+                        }
+                        MouseEventKind::ScrollDown => {
+                            for _ in 0..MOUSE_SCROLL_LINES {
+                                self.down();
                             }
-                            _ => {}
                         }
-                    }
+                        _ => continue,
+                    },
                     _ => continue,
                 }
             }
@@ -538,28 +537,191 @@ impl<'a> OmaPager<'a> {
     }
     /// Search for a pattern in the pager content
     /// # Returns:
-    /// The lines contain this pattern (In vec<usize>)
-    fn search(&mut self, pattern: &str) -> Vec<usize> {
+    /// The rows contain this pattern (In vec<usize>), or `None` if the pattern is invalid
+    fn search(&mut self, pattern: &str) -> Option<Vec<usize>> {
+        let highlight = Highlight::new(pattern, self.regex)?;
         let mut result: Vec<usize> = Vec::new();
 
         if let PagerInner::Finished(ref mut text) = self.inner {
-            match Highlight::new(pattern) {
-                Ok(highlight) => {
-                    for (i, line) in text.iter_mut().enumerate() {
-                        if line.contains(pattern) {
-                            result.push(i);
-                            // highlight the pattern
-                            *line = highlight.replace(line);
-                        }
-                    }
-                }
-                Err(e) => {
-                    debug!("{e}");
+            for row in 0..self.inner_len {
+                let i = self.filter.as_ref().map_or(row, |filter| filter[row]);
+                let line = &mut text[i];
+                if highlight.is_match(line) {
+                    result.push(row);
+                    // highlight the pattern
+                    *line = highlight.replace(line);
                 }
             }
         }
 
-        result
+        Some(result)
+    }
+
+    /// Indices of lines matching the pattern, or `None` if the pattern is invalid
+    fn filter_lines(&self, pattern: &str) -> Option<Vec<usize>> {
+        let highlight = Highlight::new(pattern, self.regex)?;
+
+        let PagerInner::Finished(ref text) = self.inner else {
+            return Some(vec![]);
+        };
+
+        Some(
+            text.iter()
+                .enumerate()
+                .filter(|(_, line)| highlight.is_match(line))
+                .map(|(i, _)| i)
+                .collect(),
+        )
+    }
+
+    /// Show only the lines in `filter`, or all lines if `None`
+    fn set_filter(&mut self, filter: Option<Vec<usize>>) {
+        // Search results are rows of the current view
+        self.clear_highlight();
+        self.search_results.clear();
+        self.current_result_index = 0;
+
+        let PagerInner::Finished(ref text) = self.inner else {
+            return;
+        };
+
+        self.inner_len = filter.as_ref().map_or(text.len(), |x| x.len());
+        self.filter = filter;
+        self.goto_begin();
+    }
+
+    /// Rows where sections start in the current view
+    fn section_rows(&self) -> Vec<usize> {
+        match &self.filter {
+            Some(filter) => self
+                .sections
+                .iter()
+                .map(|section| filter.partition_point(|i| i < section))
+                .collect(),
+            None => self.sections.clone(),
+        }
+    }
+
+    fn next_section(&mut self) {
+        if let Some(row) = self
+            .section_rows()
+            .into_iter()
+            .find(|row| *row > self.vertical_scroll && *row < self.inner_len)
+        {
+            self.jump_to(row);
+        }
+    }
+
+    fn previous_section(&mut self) {
+        match self
+            .section_rows()
+            .into_iter()
+            .rev()
+            .find(|row| *row < self.vertical_scroll)
+        {
+            Some(row) => self.jump_to(row),
+            None => self.goto_begin(),
+        }
+    }
+
+    /// Save lines of the current view to `path`, without search highlights
+    fn save(&self, path: &str, ansi: bool) -> io::Result<()> {
+        let PagerInner::Finished(ref text) = self.inner else {
+            return Ok(());
+        };
+
+        let clear_highlighter = ClearHighlight::new();
+        let mut f = BufWriter::new(File::create(path)?);
+
+        for row in 0..self.inner_len {
+            let i = self.filter.as_ref().map_or(row, |filter| filter[row]);
+            let line = clear_highlighter.replace(&text[i]);
+
+            if ansi {
+                writeln!(f, "{line}")?;
+            } else {
+                writeln!(f, "{}", console::strip_ansi_codes(&line))?;
+            }
+        }
+
+        f.flush()
+    }
+
+    fn prompt_tips(&self, input: &str) -> String {
+        match self.mode {
+            TuiMode::SearchInputText if self.regex => {
+                self.ui_text.search_tips_with_regex_query(input)
+            }
+            TuiMode::SearchInputText => self.ui_text.searct_tips_with_query(input),
+            TuiMode::FilterInputText => self.ui_text.filter_tips_with_query(input, self.regex),
+            TuiMode::SaveInputText { ansi } => self.ui_text.save_tips_with_path(input, ansi),
+            TuiMode::Search | TuiMode::Noemal => self.ui_text.normal_tips(),
+        }
+    }
+
+    /// Handle Enter in prompts
+    fn submit_prompt(&mut self, input: &str) {
+        match self.mode {
+            TuiMode::SearchInputText => {
+                self.mode = TuiMode::Search;
+
+                if input.trim().is_empty() {
+                    self.tips = self.ui_text.search_tips_with_empty();
+                    return;
+                }
+
+                let Some(results) = self.search(input) else {
+                    self.search_results.clear();
+                    self.tips = self.ui_text.tips_invalid_pattern();
+                    return;
+                };
+
+                self.search_results = results;
+                if self.search_results.is_empty() {
+                    self.tips = self.ui_text.search_tips_not_found();
+                } else {
+                    self.current_result_index = 0;
+                    self.jump_to(self.search_results[self.current_result_index]);
+                    self.tips = self.ui_text.search_tips_with_result();
+                }
+            }
+            TuiMode::FilterInputText => {
+                self.mode = TuiMode::Noemal;
+
+                if input.is_empty() {
+                    self.set_filter(None);
+                    self.tips = self.ui_text.normal_tips();
+                    return;
+                }
+
+                self.tips = match self.filter_lines(input) {
+                    None => self.ui_text.tips_invalid_pattern(),
+                    Some(lines) if lines.is_empty() => self.ui_text.search_tips_not_found(),
+                    Some(lines) => {
+                        let count = lines.len();
+                        self.set_filter(Some(lines));
+                        self.ui_text.filter_tips_with_result(count)
+                    }
+                };
+            }
+            TuiMode::SaveInputText { ansi } => {
+                self.mode = TuiMode::Noemal;
+
+                if input.trim().is_empty() {
+                    self.tips = self.ui_text.normal_tips();
+                    return;
+                }
+
+                self.tips = match self.save(input, ansi) {
+                    Ok(()) => self.ui_text.save_tips_done(input),
+                    Err(e) => {
+                        debug!("{e}");
+                        self.ui_text.save_tips_failed(input)
+                    }
+                };
+            }
+            TuiMode::Search | TuiMode::Noemal => {}
+        }
     }
 
     /// Jump to line
@@ -571,8 +733,9 @@ impl<'a> OmaPager<'a> {
     fn clear_highlight(&mut self) {
         if let PagerInner::Finished(ref mut text) = self.inner {
             let clear_highlighter = ClearHighlight::new();
-            for line_index in &self.search_results {
-                if let Some(line) = text.get_mut(*line_index) {
+            for row in &self.search_results {
+                let i = self.filter.as_ref().map_or(*row, |filter| filter[*row]);
+                if let Some(line) = text.get_mut(i) {
                     *line = clear_highlighter.replace(line);
                 }
             }
@@ -592,26 +755,12 @@ impl<'a> OmaPager<'a> {
 
         let chunks = Layout::vertical(layout).split(area);
 
-        let color = self.theme.theme;
-
-        let title_bg_color = match color {
-            Some(Theme::Dark) => Color::Indexed(25),
-            Some(Theme::Light) => Color::Indexed(189),
-            None => Color::Indexed(25),
-        };
-
-        let title_fg_color = match color {
-            Some(Theme::Dark) => Color::White,
-            Some(Theme::Light) => Color::Black,
-            None => Color::White,
-        };
-
         if let Some(title) = &self.title {
             let title = Block::new()
                 .title_alignment(Alignment::Left)
                 .title(title.to_string())
-                .fg(title_fg_color)
-                .bg(title_bg_color);
+                .fg(self.theme.tui_color(Action::TitleFg))
+                .bg(self.theme.tui_color(Action::TitleBg));
 
             f.render_widget(title, chunks[0]);
         }
@@ -638,17 +787,16 @@ impl<'a> OmaPager<'a> {
             unreachable!()
         };
 
-        let text = if let Some(text) =
-            text.get(self.vertical_scroll..self.vertical_scroll + self.area_height as usize)
-        {
-            // 根据屏幕高度来决定显示多少行
-            text
-        } else {
-            // 达到末尾，即剩余行数小于屏幕高度
-            &text[self.vertical_scroll..]
-        };
+        // 根据屏幕高度来决定显示多少行，达到末尾时剩余行数小于屏幕高度
+        let end = (self.vertical_scroll + self.area_height as usize).min(self.inner_len);
 
-        let text = text.join("\n");
+        let text = (self.vertical_scroll..end)
+            .map(|row| {
+                let i = self.filter.as_ref().map_or(row, |filter| filter[row]);
+                text[i].as_str()
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
         let text = match text.to_text() {
             Ok(text) => text,
             Err(e) => {
@@ -676,30 +824,100 @@ impl<'a> OmaPager<'a> {
         f.render_widget(
             Paragraph::new(
                 Text::from(self.tips.clone())
-                    .bg(Color::White)
-                    .fg(Color::Black),
+                    .bg(self.theme.tui_color(Action::TipsBg))
+                    .fg(self.theme.tui_color(Action::TipsFg)),
             ),
             if has_title { chunks[2] } else { chunks[1] },
         );
     }
 }
 
-struct Highlight<'a> {
-    pattern: &'a str,
-    ac: AhoCorasick,
+enum Highlight<'a> {
+    Plain { pattern: &'a str, ac: AhoCorasick },
+    Regex(Regex),
 }
 
 impl<'a> Highlight<'a> {
-    fn new(pattern: &'a str) -> Result<Self, BuildError> {
-        Ok(Self {
-            ac: AhoCorasick::new([pattern])?,
-            pattern,
-        })
+    fn new(pattern: &'a str, regex: bool) -> Option<Self> {
+        let res = if regex {
+            Regex::new(pattern)
+                .map(Highlight::Regex)
+                .map_err(|e| e.to_string())
+        } else {
+            AhoCorasick::new([pattern])
+                .map(|ac| Highlight::Plain { pattern, ac })
+                .map_err(|e| e.to_string())
+        };
+
+        match res {
+            Ok(highlight) => Some(highlight),
+            Err(e) => {
+                debug!("{e}");
+                None
+            }
+        }
+    }
+
+    fn is_match(&self, input: &str) -> bool {
+        let input = console::strip_ansi_codes(input);
+
+        match self {
+            Highlight::Plain { pattern, .. } => input.contains(pattern),
+            Highlight::Regex(regex) => regex.is_match(&input),
+        }
     }
 
+    /// Highlight matches in `input`
+    ///
+    /// Matches are searched in the visible text, so patterns never match (and break) escape
+    /// sequences in the line.
     fn replace(&self, input: &str) -> String {
-        self.ac
-            .replace_all(input, &[format!("\x1b[47m{}\x1b[49m", self.pattern)])
+        // Offsets of visible bytes in `input`
+        let mut offsets = vec![];
+        let mut text = String::new();
+        let mut pos = 0;
+
+        for (s, is_ansi) in console::AnsiCodeIterator::new(input) {
+            if !is_ansi {
+                offsets.extend(pos..pos + s.len());
+                text.push_str(s);
+            }
+            pos += s.len();
+        }
+
+        let matches = match self {
+            Highlight::Plain { ac, .. } => ac
+                .find_iter(&text)
+                .map(|m| (m.start(), m.end()))
+                .collect::<Vec<_>>(),
+            Highlight::Regex(regex) => regex
+                .find_iter(&text)
+                .map(|m| (m.start(), m.end()))
+                .collect(),
+        };
+
+        let mut res = String::with_capacity(input.len());
+        let mut last = 0;
+
+        for (start, end) in matches {
+            if start == end {
+                continue;
+            }
+
+            let start = offsets[start];
+            let end = offsets[end - 1] + 1;
+
+            res.push_str(&input[last..start]);
+            res.push_str("\x1b[47m");
+            res.push_str(&input[start..end]);
+            res.push_str("\x1b[49m");
+
+            last = end;
+        }
+
+        res.push_str(&input[last..]);
+
+        res
     }
 }
 
@@ -714,3 +932,23 @@ impl ClearHighlight {
         self.0.replace_all(input, &["", ""])
     }
 }
+
+#[test]
+fn test_highlight() {
+    let line = "\x1b[31mfoo\x1b[0m bar 31m";
+
+    let highlight = Highlight::new("31m", false).unwrap();
+    assert!(highlight.is_match(line));
+    assert_eq!(
+        highlight.replace(line),
+        "\x1b[31mfoo\x1b[0m bar \x1b[47m31m\x1b[49m"
+    );
+
+    let highlight = Highlight::new("o+ b", true).unwrap();
+    assert_eq!(
+        highlight.replace(line),
+        "\x1b[31mf\x1b[47moo\x1b[0m b\x1b[49mar 31m"
+    );
+
+    assert!(!Highlight::new("\\x1b", true).unwrap().is_match(line));
+}
@@ -1,6 +1,7 @@
 use std::{borrow::Cow, collections::BTreeMap, time::Duration};
 
 use console::{style, Color, StyledObject};
+use serde::Deserialize;
 use termbg::Theme;
 use tracing::{debug, field::Field, Level};
 use tracing_subscriber::Layer;
@@ -15,6 +16,7 @@ enum StyleFollow {
     TermTheme,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Action {
     Emphasis,
     Foreground,
@@ -25,6 +27,18 @@ pub enum Action {
     Note,
     UpgradeTips,
     PendingBg,
+    /// Background of title bars in the pager and the TUI
+    TitleBg,
+    /// Text of title bars in the pager and the TUI
+    TitleFg,
+    /// Background of the tips bar in the pager
+    TipsBg,
+    /// Text of the tips bar in the pager
+    TipsFg,
+    /// Background of the selected item in the TUI
+    Highlight,
+    /// Keys in tips of the TUI
+    KeyHint,
 }
 
 impl Action {
@@ -39,6 +53,12 @@ impl Action {
             Action::Note => 178,
             Action::UpgradeTips => 87,
             Action::PendingBg => 25,
+            Action::TitleBg => 25,
+            Action::TitleFg => 15,
+            Action::TipsBg => 15,
+            Action::TipsFg => 16,
+            Action::Highlight => 237,
+            Action::KeyHint => 75,
         }
     }
 
@@ -53,9 +73,156 @@ impl Action {
             Action::Note => 172,
             Action::UpgradeTips => 63,
             Action::PendingBg => 189,
+            Action::TitleBg => 189,
+            Action::TitleFg => 16,
+            Action::TipsBg => 15,
+            Action::TipsFg => 16,
+            Action::Highlight => 253,
+            Action::KeyHint => 26,
         }
     }
+
+    /// Whether this color is used as background
+    fn is_background(&self) -> bool {
+        matches!(
+            self,
+            Action::PendingBg | Action::TitleBg | Action::TipsBg | Action::Highlight
+        )
+    }
+}
+
+/// A color in a theme file, a 256-color index or a basic color name
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "RawThemeColor")]
+pub enum ThemeColor {
+    Indexed(u8),
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawThemeColor {
+    Indexed(u8),
+    Named(String),
 }
+
+impl TryFrom<RawThemeColor> for ThemeColor {
+    type Error = String;
+
+    fn try_from(value: RawThemeColor) -> Result<Self, Self::Error> {
+        Ok(match value {
+            RawThemeColor::Indexed(n) => ThemeColor::Indexed(n),
+            RawThemeColor::Named(name) => match name.to_ascii_lowercase().as_str() {
+                "black" => ThemeColor::Black,
+                "red" => ThemeColor::Red,
+                "green" => ThemeColor::Green,
+                "yellow" => ThemeColor::Yellow,
+                "blue" => ThemeColor::Blue,
+                "magenta" => ThemeColor::Magenta,
+                "cyan" => ThemeColor::Cyan,
+                "white" => ThemeColor::White,
+                _ => return Err(format!("unknown color: {name}")),
+            },
+        })
+    }
+}
+
+impl From<ThemeColor> for Color {
+    fn from(value: ThemeColor) -> Self {
+        match value {
+            ThemeColor::Indexed(n) => Color::Color256(n),
+            ThemeColor::Black => Color::Black,
+            ThemeColor::Red => Color::Red,
+            ThemeColor::Green => Color::Green,
+            ThemeColor::Yellow => Color::Yellow,
+            ThemeColor::Blue => Color::Blue,
+            ThemeColor::Magenta => Color::Magenta,
+            ThemeColor::Cyan => Color::Cyan,
+            ThemeColor::White => Color::White,
+        }
+    }
+}
+
+#[cfg(feature = "pager")]
+impl From<ThemeColor> for ratatui::style::Color {
+    fn from(value: ThemeColor) -> Self {
+        use ratatui::style::Color as TuiColor;
+
+        match value {
+            ThemeColor::Indexed(n) => TuiColor::Indexed(n),
+            ThemeColor::Black => TuiColor::Black,
+            ThemeColor::Red => TuiColor::Red,
+            ThemeColor::Green => TuiColor::Green,
+            ThemeColor::Yellow => TuiColor::Yellow,
+            ThemeColor::Blue => TuiColor::Blue,
+            ThemeColor::Magenta => TuiColor::Magenta,
+            ThemeColor::Cyan => TuiColor::Cyan,
+            ThemeColor::White => TuiColor::White,
+        }
+    }
+}
+
+/// ColorTheme
+///
+/// `ColorTheme` overrides colors of `Action`s, it is usually loaded from a TOML theme file like:
+///
+/// ```toml
+/// warn = "red"
+/// secondary = 243
+/// highlight = 250
+/// ```
+///
+/// Colors not in the theme file follow the oma theme or the terminal theme.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ColorTheme {
+    pub emphasis: Option<ThemeColor>,
+    pub foreground: Option<ThemeColor>,
+    pub secondary: Option<ThemeColor>,
+    pub emphasis_secondary: Option<ThemeColor>,
+    pub warn: Option<ThemeColor>,
+    pub purple: Option<ThemeColor>,
+    pub note: Option<ThemeColor>,
+    pub upgrade_tips: Option<ThemeColor>,
+    pub pending_bg: Option<ThemeColor>,
+    pub title_bg: Option<ThemeColor>,
+    pub title_fg: Option<ThemeColor>,
+    pub tips_bg: Option<ThemeColor>,
+    pub tips_fg: Option<ThemeColor>,
+    pub highlight: Option<ThemeColor>,
+    pub key_hint: Option<ThemeColor>,
+}
+
+impl ColorTheme {
+    /// Get the color of `action`, if the theme defines it
+    pub fn get(&self, action: Action) -> Option<ThemeColor> {
+        match action {
+            Action::Emphasis => self.emphasis,
+            Action::Foreground => self.foreground,
+            Action::Secondary => self.secondary,
+            Action::EmphasisSecondary => self.emphasis_secondary,
+            Action::WARN => self.warn,
+            Action::Purple => self.purple,
+            Action::Note => self.note,
+            Action::UpgradeTips => self.upgrade_tips,
+            Action::PendingBg => self.pending_bg,
+            Action::TitleBg => self.title_bg,
+            Action::TitleFg => self.title_fg,
+            Action::TipsBg => self.tips_bg,
+            Action::TipsFg => self.tips_fg,
+            Action::Highlight => self.highlight,
+            Action::KeyHint => self.key_hint,
+        }
+    }
+}
+
 /// OmaColorFormat
 ///
 /// `OmaColorFormat` is a structure that defines the color format and theme settings for oma.
@@ -64,6 +231,8 @@ pub struct OmaColorFormat {
     follow: StyleFollow,
     /// An optional `Theme` object that defined by oma.
     pub theme: Option<Theme>,
+    /// Colors defined by the user, which take precedence over both themes.
+    custom: ColorTheme,
 }
 
 impl OmaColorFormat {
@@ -86,8 +255,16 @@ impl OmaColorFormat {
             } else {
                 None
             },
+            custom: ColorTheme::default(),
         }
     }
+
+    /// Override colors with a user defined theme
+    pub fn with_theme(mut self, theme: ColorTheme) -> Self {
+        self.custom = theme;
+        self
+    }
+
     /// Convert input into StyledObject
     ///
     /// This function applies a color scheme to the given input string based on the specified action and the current terminal color schemes.
@@ -101,6 +278,14 @@ impl OmaColorFormat {
     ///
     /// Returns a `StyledObject` that contains the styled input data.
     pub fn color_str<D>(&self, input: D, color: Action) -> StyledObject<D> {
+        if let Some(custom) = self.custom.get(color) {
+            return match color {
+                Action::PendingBg => style(input).bg(custom.into()).bold(),
+                x if x.is_background() => style(input).bg(custom.into()),
+                _ => style(input).fg(custom.into()),
+            };
+        }
+
         match self.follow {
             StyleFollow::OmaTheme => match self.theme {
                 Some(Theme::Dark) => match color {
@@ -116,6 +301,40 @@ impl OmaColorFormat {
             StyleFollow::TermTheme => term_color(input, color),
         }
     }
+
+    /// Get the color of `action` for `ratatui` widgets
+    ///
+    /// Background colors like `Action::Highlight` should be set as background of the style.
+    #[cfg(feature = "pager")]
+    pub fn tui_color(&self, action: Action) -> ratatui::style::Color {
+        use ratatui::style::Color as TuiColor;
+
+        if let Some(custom) = self.custom.get(action) {
+            return custom.into();
+        }
+
+        match (&self.follow, self.theme) {
+            (StyleFollow::OmaTheme, Some(Theme::Dark)) => TuiColor::Indexed(action.dark()),
+            (StyleFollow::OmaTheme, Some(Theme::Light)) => TuiColor::Indexed(action.light()),
+            _ => match action {
+                Action::Emphasis => TuiColor::Green,
+                Action::Secondary => TuiColor::DarkGray,
+                Action::EmphasisSecondary => TuiColor::Cyan,
+                Action::WARN => TuiColor::Yellow,
+                Action::Purple => TuiColor::Magenta,
+                Action::Note => TuiColor::Yellow,
+                Action::Foreground => TuiColor::Cyan,
+                Action::UpgradeTips => TuiColor::Blue,
+                Action::PendingBg => TuiColor::Blue,
+                Action::TitleBg => TuiColor::Indexed(25),
+                Action::TitleFg => TuiColor::White,
+                Action::TipsBg => TuiColor::White,
+                Action::TipsFg => TuiColor::Black,
+                Action::Highlight => TuiColor::Rgb(59, 64, 70),
+                Action::KeyHint => TuiColor::Blue,
+            },
+        }
+    }
 }
 
 fn term_color<D>(input: D, color: Action) -> StyledObject<D> {
@@ -129,6 +348,12 @@ fn term_color<D>(input: D, color: Action) -> StyledObject<D> {
         Action::Foreground => style(input).cyan().bold(),
         Action::UpgradeTips => style(input).blue().bold(),
         Action::PendingBg => style(input).bg(Color::Blue).bold(),
+        Action::TitleBg => style(input).bg(Color::Color256(25)),
+        Action::TitleFg => style(input).white(),
+        Action::TipsBg => style(input).bg(Color::White),
+        Action::TipsFg => style(input).black(),
+        Action::Highlight => style(input).bg(Color::Color256(237)),
+        Action::KeyHint => style(input).blue(),
     }
 }
 /// OmaLayer
@@ -119,6 +119,8 @@ pub struct InstallEntry {
     op: InstallOperation,
     #[builder(default)]
     automatic: bool,
+    /// Index in the apt cache, differs between machines so it is not serialized
+    #[serde(skip)]
     index: u64,
 }
 
@@ -135,6 +137,8 @@ pub struct RemoveEntry {
     size: u64,
     details: Vec<RemoveTag>,
     arch: String,
+    /// Index in the apt cache, differs between machines so it is not serialized
+    #[serde(skip)]
     index: u64,
 }
 
@@ -1,12 +1,12 @@
 use std::{
-    cell::OnceCell,
+    cell::{OnceCell, RefCell},
     fmt,
     io::{self, ErrorKind},
     path::{Path, PathBuf},
     process::Command,
 };
 
-use ahash::HashSet;
+use ahash::{HashMap, HashSet};
 use apt_auth_config::AuthConfig;
 use bon::{builder, Builder};
 pub use oma_apt::cache::Upgrade;
@@ -42,10 +42,13 @@ pub use oma_pm_operation_type::*;
 use crate::{
     commit::{CommitNetworkConfig, DoInstall},
     dbus::{OmaBus, Status},
-    download::download_pkgs,
+    download::{download_pkgs, download_source_files},
+    explain::{explain, Explanation},
     matches::MatcherError,
     pkginfo::{OmaDependency, OmaPackage, OmaPackageWithoutVersion, PtrIsNone},
     progress::InstallProgressManager,
+    solver::{Reason, Solver, SolverError, Universe},
+    sources::{mirror_base_url, SourceRecord},
 };
 
 #[derive(Debug, Clone, Builder)]
@@ -67,9 +70,13 @@ pub struct OmaAptArgs {
     #[builder(default)]
     dpkg_force_confnew: bool,
     #[builder(default)]
+    dpkg_force_confold: bool,
+    #[builder(default)]
     dpkg_force_unsafe_io: bool,
     #[builder(default)]
     another_apt_options: Vec<String>,
+    #[builder(default)]
+    solver: Solver,
 }
 
 pub struct OmaApt {
@@ -78,8 +85,12 @@ pub struct OmaApt {
     autoremove: HashSet<u64>,
     dry_run: bool,
     select_pkgs: HashSet<u64>,
+    /// Packages protected from the resolver, see [`OmaApt::save_marks`]
+    protected: RefCell<HashSet<u64>>,
     unmet: Vec<Vec<BrokenPackage>>,
     archive_dir: OnceCell<PathBuf>,
+    solver: Solver,
+    solver_comparison: Option<SolverComparison>,
     pub(crate) tokio: Runtime,
     pub(crate) conn: Option<Connection>,
 }
@@ -97,7 +108,7 @@ pub enum OmaAptError {
     #[error("Failed to mark package for reinstallation: {0}")]
     MarkReinstallError(String, String),
     #[error("Dependencies unmet")]
-    DependencyIssue(Vec<Vec<BrokenPackage>>),
+    DependencyIssue(Vec<Vec<BrokenPackage>>, Box<Explanation>),
     #[error("Package: {0} is essential.")]
     PkgIsEssential(String),
     #[error("Package: {0} has no available candidate.")]
@@ -138,6 +149,16 @@ pub enum OmaAptError {
     ChecksumError(#[from] ChecksumError),
     #[error("Blocking installation due to features markers.")]
     Features,
+    #[error("Can not find the mirror of source package: {0}")]
+    NoSourceMirror(String),
+    #[error("Dependencies can not be satisfied: {}", .0.iter().map(|x| x.to_string()).collect::<Vec<_>>().join("; "))]
+    SolverUnsatisfiable(Vec<Reason>),
+    #[error("The SAT solver gave up")]
+    SolverGaveUp,
+    #[error("Package {0} has been changed since the plan was made.")]
+    PlanStateMismatch(String, Option<String>, Option<String>),
+    #[error("Package file of {0} {1} is different from the one in the plan.")]
+    PlanChecksumMismatch(String, String),
 }
 
 pub type OmaAptResult<T> = Result<T, OmaAptError>;
@@ -160,6 +181,38 @@ pub enum SummarySort {
     NoSort,
 }
 
+/// Results of both solvers in compare mode
+#[derive(Debug)]
+pub struct SolverComparison {
+    pub apt: OmaOperation,
+    pub sat: Result<OmaOperation, String>,
+}
+
+/// A mark made before resolving
+#[derive(Debug, Clone)]
+enum SavedMark {
+    Install {
+        pkg: String,
+        version: String,
+        auto: bool,
+    },
+    Reinstall {
+        pkg: String,
+    },
+    Delete {
+        pkg: String,
+        purge: bool,
+    },
+}
+
+/// Marks of all packages to be changed, see [`OmaApt::save_marks`]
+#[derive(Debug, Clone)]
+pub struct SavedMarks {
+    marks: Vec<SavedMark>,
+    /// Packages protected from the resolver
+    protected: HashSet<u64>,
+}
+
 pub struct DownloadConfig<'a> {
     pub network_thread: Option<usize>,
     pub download_dir: Option<&'a Path>,
@@ -174,6 +227,7 @@ impl OmaApt {
         dry_run: bool,
         config: AptConfig,
     ) -> OmaAptResult<Self> {
+        let solver = args.solver;
         let config = Self::init_config(config, args)?;
 
         let tokio = tokio::runtime::Builder::new_multi_thread()
@@ -198,8 +252,11 @@ impl OmaApt {
             autoremove: HashSet::with_hasher(ahash::RandomState::new()),
             dry_run,
             select_pkgs: HashSet::with_hasher(ahash::RandomState::new()),
+            protected: RefCell::new(HashSet::with_hasher(ahash::RandomState::new())),
             unmet: vec![],
             archive_dir: OnceCell::new(),
+            solver,
+            solver_comparison: None,
             tokio,
             conn,
         })
@@ -233,8 +290,10 @@ impl OmaApt {
             yes,
             force_yes,
             dpkg_force_confnew,
+            dpkg_force_confold,
             dpkg_force_unsafe_io,
             another_apt_options,
+            solver: _,
         } = args;
 
         let sysroot = Path::new(&sysroot);
@@ -290,7 +349,7 @@ impl OmaApt {
         if dpkg_force_confnew {
             dpkg_args.push("--force-confnew");
             debug!("Dpkg::Options:: is set to --force-confnew");
-        } else if yes {
+        } else if yes || dpkg_force_confold {
             // --force-confdef reason:
             // https://unix.stackexchange.com/questions/641099/any-possible-conflict-between-using-both-force-confold-and-force-confnew-wit/642541#642541
             let args = &["--force-confold", "--force-confdef"];
@@ -363,6 +422,54 @@ impl OmaApt {
         Ok(())
     }
 
+    /// Get upgradable packages which have security updates
+    ///
+    /// A candidate version is a security update if it comes from a security repository
+    /// (e.g. `bookworm-security'), or `is_security` returns true for its package name and version.
+    pub fn security_upgradable(&self, is_security: impl Fn(&str, &str) -> bool) -> Vec<Package> {
+        let sort = PackageSort::default().upgradable();
+
+        self.cache
+            .packages(&sort)
+            .filter(|pkg| {
+                pkg.candidate().is_some_and(|cand| {
+                    is_security_version(&cand) || is_security(pkg.name(), cand.version())
+                })
+            })
+            .collect()
+    }
+
+    /// Set apt manager status as upgrade, but only for security updates
+    pub fn upgrade_security(&self, is_security: impl Fn(&str, &str) -> bool) -> OmaAptResult<()> {
+        self.upgrade_matching(|pkg, cand| {
+            is_security_version(cand) || is_security(pkg.name(), cand.version())
+        })
+    }
+
+    /// Set apt manager status as upgrade, for packages whose candidate version satisfies `filter`
+    pub fn upgrade_matching(
+        &self,
+        filter: impl Fn(&Package, &Version) -> bool,
+    ) -> OmaAptResult<()> {
+        let sort = PackageSort::default().upgradable();
+
+        for pkg in self.cache.packages(&sort) {
+            let Some(cand) = pkg.candidate() else {
+                continue;
+            };
+
+            if !filter(&pkg, &cand) {
+                continue;
+            }
+
+            debug!("{} {} will be upgraded", pkg.fullname(true), cand.version());
+            self.protect(&pkg);
+            mark_install_inner(&pkg);
+        }
+
+        Ok(())
+    }
+
     /// Set apt manager status as install
     pub fn install(
         &mut self,
@@ -374,7 +481,10 @@ impl OmaApt {
         let install_recommends = self.config.bool("APT::Install-Recommends", true);
 
         for pkg in pkgs {
-            let marked_install = mark_install(&self.cache, pkg, reinstall, install_recommends)?;
+            let marked_install =
+                mark_install(&self.cache, pkg, reinstall, install_recommends, |pkg| {
+                    self.protect(pkg)
+                })?;
 
             let pkg_index = pkg.raw_pkg.index();
 
@@ -465,6 +575,65 @@ impl OmaApt {
         Ok(res)
     }
 
+    /// Set apt manager status as install, newly installed packages are marked as automatically
+    /// installed (like build dependencies), so they can be removed by autoremove later
+    pub fn install_as_auto(&mut self, pkgs: &[OmaPackage]) -> OmaAptResult<()> {
+        for pkg in pkgs {
+            let ver = pkg.version(&self.cache);
+            let pkg = pkg.package(&self.cache);
+
+            ver.set_candidate();
+
+            // Not from user, so apt marks them as automatically installed
+            pkg.mark_install(true, false);
+            self.protect(&pkg);
+
+            debug!(
+                "{} {} marked install as auto",
+                pkg.fullname(true),
+                ver.version()
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Download files of a source package, verifying them with checksums from the `Sources` index
+    pub fn download_source<F, Fut>(
+        &self,
+        client: &Client,
+        record: &SourceRecord,
+        config: DownloadConfig<'_>,
+        dry_run: bool,
+        callback: F,
+    ) -> OmaAptResult<Summary>
+    where
+        F: Fn(Event) -> Fut,
+        Fut: Future<Output = ()>,
+    {
+        let base_url = record
+            .index
+            .as_deref()
+            .and_then(|index| {
+                let sysroot = self.config.get("Dir").unwrap_or("/".to_string());
+                mirror_base_url(Path::new(&sysroot), index)
+            })
+            .ok_or_else(|| OmaAptError::NoSourceMirror(record.package.clone()))?;
+
+        debug!("Source package {} mirror: {base_url}", record.package);
+
+        if dry_run {
+            return Ok(Summary {
+                success: vec![],
+                failed: vec![],
+            });
+        }
+
+        self.tokio.block_on(download_source_files(
+            client, record, &base_url, config, callback,
+        ))
+    }
+
     /// Set apt manager status as remove
     pub fn remove(
         &mut self,
@@ -477,7 +646,7 @@ impl OmaApt {
 
         for pkg in pkgs {
             let pkg = pkg.package(&self.cache);
-            let is_marked_delete = mark_delete(&pkg, purge)?;
+            let is_marked_delete = mark_delete(&pkg, purge, |pkg| self.protect(pkg))?;
             if !is_marked_delete {
                 no_marked_remove.push(pkg.fullname(true));
             } else if !self.select_pkgs.contains(&pkg.index()) {
@@ -499,7 +668,7 @@ impl OmaApt {
                     .filter(|pkg| pkg.marked_delete())
                     .for_each(|pkg| {
                         pkg.mark_delete(true);
-                        pkg.protect();
+                        self.protect(&pkg);
                     });
             }
         }
@@ -515,7 +684,7 @@ impl OmaApt {
         for pkg in pkgs {
             if pkg.is_auto_removable() && !pkg.marked_delete() {
                 pkg.mark_delete(purge);
-                pkg.protect();
+                self.protect(&pkg);
 
                 self.autoremove.insert(pkg.index());
             }
@@ -554,6 +723,36 @@ impl OmaApt {
         Ok(())
     }
 
+    /// Download packages of `op` into the archive directory, without installing them
+    pub fn download_only<F, Fut>(
+        &self,
+        client: &Client,
+        op: &OmaOperation,
+        config: CommitNetworkConfig,
+        callback: F,
+    ) -> OmaAptResult<Summary>
+    where
+        F: Fn(Event) -> Fut,
+        Fut: Future<Output = ()>,
+    {
+        if self.dry_run {
+            debug!("op: {op:?}");
+            return Ok(Summary {
+                success: vec![],
+                failed: vec![],
+            });
+        }
+
+        let config = DownloadConfig {
+            network_thread: config.network_thread,
+            download_dir: Some(self.get_archive_dir()),
+            auth: config.auth_config,
+        };
+
+        self.tokio
+            .block_on(download_pkgs(client, &op.install, config, callback))
+    }
+
     pub fn fix_resolver_broken(&self) {
         self.cache.fix_broken();
     }
@@ -621,8 +820,23 @@ impl OmaApt {
         Ok(())
     }
 
-    /// Resolve apt dependencies
+    /// Resolve apt dependencies, with the solver set in [`OmaAptArgs`]
     pub fn resolve(&mut self, no_fixbroken: bool, all_purge: bool) -> OmaAptResult<()> {
+        debug!("Solver is: {:?}", self.solver);
+
+        match self.solver {
+            Solver::Apt => self.resolve_apt(no_fixbroken, all_purge),
+            Solver::Sat => self.resolve_sat(all_purge),
+            Solver::Compare => self.resolve_compare(no_fixbroken, all_purge),
+        }
+    }
+
+    /// Results of both solvers, if the solver is `compare`
+    pub fn solver_comparison(&self) -> Option<&SolverComparison> {
+        self.solver_comparison.as_ref()
+    }
+
+    fn resolve_apt(&mut self, no_fixbroken: bool, all_purge: bool) -> OmaAptResult<()> {
         self.resolve_inner(no_fixbroken)?;
 
         if all_purge {
@@ -631,7 +845,7 @@ impl OmaApt {
                 .filter(|x| x.marked_delete())
                 .for_each(|pkg| {
                     pkg.mark_delete(true);
-                    pkg.protect();
+                    self.protect(&pkg);
                 });
 
             self.resolve_inner(no_fixbroken)?;
@@ -640,21 +854,273 @@ impl OmaApt {
         Ok(())
     }
 
+    fn resolve_sat(&mut self, all_purge: bool) -> OmaAptResult<()> {
+        let dir = self.config.get("Dir").unwrap_or_else(|| "/".to_string());
+        let selections = get_selections(dir)?;
+
+        let universe = Universe::from_cache(&self.cache, &self.select_pkgs, |pkg| {
+            is_hold(&pkg.fullname(true), &selections)
+        });
+
+        let choices = universe.solve().map_err(|e| match e {
+            SolverError::Unsatisfiable(core) => OmaAptError::SolverUnsatisfiable(core),
+            SolverError::GaveUp => OmaAptError::SolverGaveUp,
+        })?;
+
+        let choices = choices
+            .into_iter()
+            .map(|x| (x.pkg, x.chosen))
+            .collect::<HashMap<_, _>>();
+
+        let changes = self.cache.get_changes(false).collect::<Vec<_>>();
+
+        // Marks of purging made by the user are kept
+        let purge = changes
+            .iter()
+            .filter(|x| x.marked_purge())
+            .map(|x| x.index())
+            .collect::<HashSet<_>>();
+
+        // Drop marks of packages the solver keeps as is, e.g. dependencies marked by apt
+        for pkg in &changes {
+            if !choices.contains_key(&pkg.fullname(false)) && !pkg.marked_reinstall() {
+                pkg.mark_keep();
+            }
+        }
+
+        for (name, chosen) in choices {
+            let Some(pkg) = self.cache.get(&name) else {
+                continue;
+            };
+
+            match chosen {
+                Some(version) => {
+                    let Some(ver) = pkg.versions().find(|x| x.version() == version) else {
+                        continue;
+                    };
+
+                    ver.set_candidate();
+                    pkg.mark_install(false, self.select_pkgs.contains(&pkg.index()));
+                }
+                None => {
+                    pkg.mark_delete(all_purge || purge.contains(&pkg.index()));
+                }
+            }
+        }
+
+        // Versions are marked one by one without auto-install, the result must still leave
+        // the cache consistent before it can be committed
+        if self.cache.iter().any(|pkg| pkg.is_inst_broken()) {
+            return Err(self.dependency_issue());
+        }
+
+        Ok(())
+    }
+
+    fn resolve_compare(&mut self, no_fixbroken: bool, all_purge: bool) -> OmaAptResult<()> {
+        let marks = self.save_marks();
+
+        let sat = self
+            .resolve_sat(all_purge)
+            .and_then(|_| self.summary(SummarySort::Operation, |_| true, |_| true))
+            .map_err(|e| e.to_string());
+
+        self.restore_marks(&marks);
+
+        // Only the result of apt is committed
+        self.resolve_apt(no_fixbroken, all_purge)?;
+        let apt = self.summary(SummarySort::Operation, |_| true, |_| true)?;
+
+        self.solver_comparison = Some(SolverComparison { apt, sat });
+
+        Ok(())
+    }
+
+    /// Save marks of all packages to be changed, to go back to them with [`OmaApt::restore_marks`]
+    pub fn save_marks(&self) -> SavedMarks {
+        let marks = self
+            .cache
+            .get_changes(false)
+            .filter_map(|pkg| {
+                let name = pkg.fullname(false);
+
+                if pkg.marked_delete() {
+                    Some(SavedMark::Delete {
+                        pkg: name,
+                        purge: pkg.marked_purge(),
+                    })
+                } else if pkg.marked_reinstall() {
+                    Some(SavedMark::Reinstall { pkg: name })
+                } else {
+                    pkg.install_version().map(|ver| SavedMark::Install {
+                        pkg: name,
+                        version: ver.version().to_string(),
+                        auto: pkg.is_auto_installed(),
+                    })
+                }
+            })
+            .collect();
+
+        SavedMarks {
+            marks,
+            protected: self.protected.borrow().clone(),
+        }
+    }
+
+    /// Drop all marks, then mark packages like when `marks` were saved
+    pub fn restore_marks(&self, marks: &SavedMarks) {
+        for pkg in self.cache.get_changes(false).collect::<Vec<_>>() {
+            pkg.mark_keep();
+        }
+
+        for mark in &marks.marks {
+            match mark {
+                SavedMark::Install { pkg, version, auto } => {
+                    let Some(pkg) = self.cache.get(pkg) else {
+                        continue;
+                    };
+
+                    if let Some(ver) = pkg.versions().find(|x| x.version() == version.as_str()) {
+                        ver.set_candidate();
+                        pkg.mark_install(false, self.select_pkgs.contains(&pkg.index()));
+                        pkg.mark_auto(*auto);
+                    }
+                }
+                SavedMark::Reinstall { pkg } => {
+                    if let Some(pkg) = self.cache.get(pkg) {
+                        pkg.mark_reinstall(true);
+                    }
+                }
+                SavedMark::Delete { pkg, purge } => {
+                    if let Some(pkg) = self.cache.get(pkg) {
+                        pkg.mark_delete(*purge);
+                    }
+                }
+            }
+        }
+
+        // Marks were dropped, but protection in the resolver can't be, make sure everything
+        // protected when saving still is
+        for pkg in self.cache.iter() {
+            if marks.protected.contains(&pkg.index()) {
+                pkg.protect();
+            }
+        }
+
+        *self.protected.borrow_mut() = marks.protected.clone();
+    }
+
+    /// Mark packages exactly as `op`, changes resolved before (possibly on another machine)
+    ///
+    /// Installed versions must be the same as when `op` was resolved, and package files must
+    /// have the same checksums. Dependencies are not resolved again, only checked.
+    pub fn mark_plan(&mut self, op: &OmaOperation) -> OmaAptResult<()> {
+        for entry in &op.install {
+            let unavailable = || {
+                OmaAptError::PkgUnavailable(
+                    entry.name().to_string(),
+                    entry.new_version().to_string(),
+                )
+            };
+
+            let pkg = self.cache.get(entry.name()).ok_or_else(unavailable)?;
+
+            let expected = match entry.op() {
+                InstallOperation::ReInstall => Some(entry.new_version()),
+                _ => entry.old_version(),
+            };
+
+            check_plan_state(&pkg, expected)?;
+
+            let ver = pkg
+                .versions()
+                .find(|x| x.version() == entry.new_version() && x.is_downloadable())
+                .ok_or_else(unavailable)?;
+
+            let same_checksum = [
+                (entry.sha256(), ver.get_record(RecordField::SHA256)),
+                (entry.sha512(), ver.sha512()),
+                (entry.md5(), ver.get_record(RecordField::MD5sum)),
+            ]
+            .into_iter()
+            .all(|(planned, found)| planned.is_none() || planned == found.as_deref());
+
+            if !same_checksum {
+                return Err(OmaAptError::PlanChecksumMismatch(
+                    entry.name().to_string(),
+                    entry.new_version().to_string(),
+                ));
+            }
+
+            if *entry.op() == InstallOperation::ReInstall {
+                pkg.mark_reinstall(true);
+            } else {
+                ver.set_candidate();
+                pkg.mark_install(false, !entry.automatic());
+            }
+
+            if !entry.automatic() {
+                self.select_pkgs.insert(pkg.index());
+            }
+        }
+
+        for entry in &op.remove {
+            let pkg = self
+                .cache
+                .get(entry.name())
+                .filter(|x| x.is_installed())
+                .ok_or_else(|| OmaAptError::MarkPkgNotInstalled(entry.name().to_string()))?;
+
+            check_plan_state(&pkg, entry.version())?;
+
+            pkg.mark_delete(entry.details().contains(&RemoveTag::Purge));
+        }
+
+        // Fails if dependencies are broken, other changes are found by comparing summaries
+        self.resolve_inner(true)
+    }
+
+    /// Keep packages as they are (installed or not), the resolver will not change them
+    ///
+    /// Run [`OmaApt::resolve`] after this to hold back packages depending on them.
+    pub fn keep_back(&self, pkgs: &[&str]) {
+        for name in pkgs {
+            if let Some(pkg) = self.cache.get(name) {
+                pkg.mark_keep();
+                self.protect(&pkg);
+            }
+        }
+    }
+
     fn resolve_inner(&mut self, no_fixbroken: bool) -> Result<(), OmaAptError> {
         if let Err(e) = self.cache.resolve(!no_fixbroken) {
             debug!("{e:#?}");
-            for pkg in self.cache.iter() {
-                let res = broken_pkg(&self.cache, &pkg, false);
-                if !res.is_empty() {
-                    self.unmet.extend(res);
-                }
-            }
-            return Err(OmaAptError::DependencyIssue(self.unmet.to_vec()));
+            return Err(self.dependency_issue());
         }
 
         Ok(())
     }
 
+    /// Collect unmet dependencies of the marked changes
+    fn dependency_issue(&mut self) -> OmaAptError {
+        for pkg in self.cache.iter() {
+            let res = broken_pkg(&self.cache, &pkg, false);
+            if !res.is_empty() {
+                self.unmet.extend(res);
+            }
+        }
+
+        let explanation = explain(&self.cache, &self.select_pkgs);
+
+        OmaAptError::DependencyIssue(self.unmet.to_vec(), Box::new(explanation))
+    }
+
+    /// Protect `pkg` from the resolver, and remember it for [`OmaApt::save_marks`]
+    fn protect(&self, pkg: &Package) {
+        pkg.protect();
+        self.protected.borrow_mut().insert(pkg.index());
+    }
+
     pub(crate) fn run_dpkg_configure(&self) -> OmaAptResult<()> {
         info!("Running `dpkg --configure -a' ...");
 
@@ -1139,6 +1605,46 @@ impl OmaApt {
 
         Ok(pkgs)
     }
+
+    /// Packages of a minimal system, which are Essential ones, and also ones with
+    /// `Priority: required` if `required`
+    pub fn base_system_pkgs(&self, required: bool) -> OmaAptResult<Vec<OmaPackage>> {
+        let mut res = vec![];
+
+        for pkg in self.cache.packages(&PackageSort::default()) {
+            let Some(cand) = pkg.candidate() else {
+                continue;
+            };
+
+            let is_required = required
+                && cand
+                    .get_record(RecordField::Priority)
+                    .is_some_and(|x| x.trim() == "required");
+
+            if pkg.is_essential() || is_required {
+                res.push(OmaPackage::new(&cand, &pkg)?);
+            }
+        }
+
+        Ok(res)
+    }
+
+    /// Packages marked to install (or upgrade) after resolving, with the versions to install
+    pub fn marked_install_pkgs(&self) -> OmaAptResult<Vec<OmaPackage>> {
+        let mut res = vec![];
+
+        for pkg in self.cache.packages(&PackageSort::default()) {
+            if !pkg.marked_install() && !pkg.marked_upgrade() {
+                continue;
+            }
+
+            if let Some(ver) = pkg.install_version() {
+                res.push(OmaPackage::new(&ver, &pkg)?);
+            }
+        }
+
+        Ok(res)
+    }
 }
 
 fn get_package_url(cand: &Version<'_>) -> Vec<PackageUrl> {
@@ -1227,7 +1733,7 @@ fn collect_suggest(
 }
 
 /// Mark package as delete.
-fn mark_delete(pkg: &Package, purge: bool) -> OmaAptResult<bool> {
+fn mark_delete(pkg: &Package, purge: bool, protect: impl Fn(&Package)) -> OmaAptResult<bool> {
     if pkg.marked_delete() {
         return Ok(true);
     }
@@ -1245,7 +1751,7 @@ fn mark_delete(pkg: &Package, purge: bool) -> OmaAptResult<bool> {
     }
 
     pkg.mark_delete(purge || removed_but_has_config);
-    pkg.protect();
+    protect(&pkg);
 
     Ok(true)
 }
@@ -1312,12 +1818,25 @@ fn pkg_delta(
     Ok(install_entry)
 }
 
+/// Whether a version comes from a security repository
+pub fn is_security_version(version: &Version) -> bool {
+    version.package_files().any(|pkg_file| {
+        pkg_file
+            .archive()
+            .is_some_and(|archive| archive.ends_with("-security"))
+            || pkg_file
+                .label()
+                .is_some_and(|label| label.ends_with("-Security"))
+    })
+}
+
 /// Mark package as install.
 fn mark_install(
     cache: &Cache,
     pkginfo: &OmaPackage,
     reinstall: bool,
     install_recommends: bool,
+    protect: impl Fn(&Package),
 ) -> OmaAptResult<bool> {
     let pkg = unsafe { pkginfo.raw_pkg.unique() }
         .make_safe()
@@ -1347,17 +1866,17 @@ fn mark_install(
             }
 
             let is_marked = pkg.mark_reinstall(true);
-            pkg.protect();
+            protect(&pkg);
 
             if install_recommends {
-                also_install_recommends(&ver, cache);
+                also_install_recommends(&ver, cache, &protect);
             }
 
             return Ok(is_marked);
         }
     }
 
-    pkg.protect();
+    protect(&pkg);
 
     mark_install_inner(&pkg);
 
@@ -1380,7 +1899,7 @@ fn mark_install_inner(pkg: &Package) -> bool {
 }
 
 #[cfg(feature = "aosc")]
-fn also_install_recommends(ver: &Version, cache: &Cache) {
+fn also_install_recommends(ver: &Version, cache: &Cache, protect: &impl Fn(&Package)) {
     let recommends = ver.recommends();
 
     if let Some(recommends) = recommends {
@@ -1391,7 +1910,7 @@ fn also_install_recommends(ver: &Version, cache: &Cache) {
                     if !mark_install_inner(&pkg) {
                         warn!("Failed to mark install recommend: {}", dep.name);
                     } else {
-                        pkg.protect();
+                        protect(&pkg);
                     }
                     continue;
                 }
@@ -1402,7 +1921,7 @@ fn also_install_recommends(ver: &Version, cache: &Cache) {
 }
 
 #[cfg(not(feature = "aosc"))]
-fn also_install_recommends(_ver: &Version, _cache: &Cache) {}
+fn also_install_recommends(_ver: &Version, _cache: &Cache, _protect: &impl Fn(&Package)) {}
 
 #[derive(Debug, Clone)]
 pub struct BrokenPackage {
@@ -1434,6 +1953,21 @@ impl fmt::Display for BrokenPackageReason {
     }
 }
 
+/// Check that the installed version of `pkg` is still the one in a plan
+fn check_plan_state(pkg: &Package, expected: Option<&str>) -> OmaAptResult<()> {
+    let installed = pkg.installed().map(|x| x.version().to_string());
+
+    if installed.as_deref() != expected {
+        return Err(OmaAptError::PlanStateMismatch(
+            pkg.fullname(true),
+            expected.map(|x| x.to_string()),
+            installed,
+        ));
+    }
+
+    Ok(())
+}
+
 fn broken_pkg(cache: &Cache, pkg: &Package, now: bool) -> Vec<Vec<BrokenPackage>> {
     let mut result = vec![];
     // If the package isn't broken for the state Return None
@@ -1510,3 +2044,111 @@ fn broken_pkg(cache: &Cache, pkg: &Package, now: bool) -> Vec<Vec<BrokenPackage>
 
     result
 }
+
+#[test]
+fn test_mark_plan() {
+    use crate::test::TEST_LOCK;
+    let _lock = TEST_LOCK.lock().unwrap();
+
+    const SHA256: &str = "161c32c6414792378fedcdd27d5feb3bca818ff9eaad46f37f0787e8d101e1a4";
+
+    let packages = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("test_file")
+        .join("Packages");
+
+    let mut apt = OmaApt::new(
+        vec![packages.to_string_lossy().to_string()],
+        OmaAptArgs::builder().build(),
+        true,
+        AptConfig::new(),
+    )
+    .unwrap();
+
+    let plan = |sha256: &str, old_version: Option<&str>| OmaOperation {
+        install: vec![InstallEntry::builder()
+            .name("owo-dbg".to_string())
+            .name_without_arch("owo-dbg".to_string())
+            .maybe_old_version(old_version.map(|x| x.to_string()))
+            .new_version("9999:2.6.1-2".to_string())
+            .new_size(1024)
+            .pkg_urls(vec![])
+            .arch("all".to_string())
+            .download_size(1024)
+            .op(InstallOperation::Install)
+            .sha256(sha256.to_string())
+            .index(0)
+            .build()],
+        remove: vec![],
+        disk_size: ("+".into(), 1024),
+        autoremovable: (0, 0),
+        total_download_size: 1024,
+        suggest: vec![],
+        recommend: vec![],
+    };
+
+    {
+        let pkg = apt.cache.get("owo-dbg").unwrap();
+        assert!(check_plan_state(&pkg, None).is_ok());
+        assert!(matches!(
+            check_plan_state(&pkg, Some("9999:2.6.1-1")),
+            Err(OmaAptError::PlanStateMismatch(..))
+        ));
+    }
+
+    // The package file changed since the plan was made
+    assert!(matches!(
+        apt.mark_plan(&plan(&"0".repeat(64), None)),
+        Err(OmaAptError::PlanChecksumMismatch(..))
+    ));
+
+    // The package was installed when the plan was made
+    assert!(matches!(
+        apt.mark_plan(&plan(SHA256, Some("9999:2.6.1-1"))),
+        Err(OmaAptError::PlanStateMismatch(..))
+    ));
+    assert!(!apt.cache.get("owo-dbg").unwrap().marked_install());
+
+    apt.mark_plan(&plan(SHA256, None)).unwrap();
+    assert!(apt.cache.get("owo-dbg").unwrap().marked_install());
+}
+
+#[test]
+fn test_base_system_pkgs() {
+    use crate::test::TEST_LOCK;
+    let _lock = TEST_LOCK.lock().unwrap();
+
+    let packages = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("test_file")
+        .join("bootstrap")
+        .join("Packages");
+
+    let apt = OmaApt::new(
+        vec![packages.to_string_lossy().to_string()],
+        OmaAptArgs::builder().build(),
+        true,
+        AptConfig::new(),
+    )
+    .unwrap();
+
+    // Packages of the host are in the cache as well
+    let names = |required: bool| {
+        let mut names = apt
+            .base_system_pkgs(required)
+            .unwrap()
+            .into_iter()
+            .map(|x| x.raw_pkg.fullname(true))
+            .filter(|x| x.starts_with("oma-bootstrap-test-"))
+            .collect::<Vec<_>>();
+        names.sort_unstable();
+        names
+    };
+
+    assert_eq!(names(false), vec!["oma-bootstrap-test-essential"]);
+    assert_eq!(
+        names(true),
+        vec![
+            "oma-bootstrap-test-essential",
+            "oma-bootstrap-test-required"
+        ]
+    );
+}
@@ -0,0 +1,216 @@
+use std::{
+    fs::{self, File},
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+use flate2::read::GzDecoder;
+use oma_apt::{util::cmp_versions, Version};
+
+const DOC_DIR: &str = "usr/share/doc";
+
+/// Kinds of documents describing changes of a package
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangelogKind {
+    Changelog,
+    News,
+}
+
+impl ChangelogKind {
+    /// File name used in URL templates (the `{file}` placeholder)
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            ChangelogKind::Changelog => "changelog",
+            ChangelogKind::News => "NEWS",
+        }
+    }
+
+    fn local_file_names(&self) -> &'static [&'static str] {
+        match self {
+            ChangelogKind::Changelog => &[
+                "changelog.Debian.gz",
+                "changelog.Debian",
+                "changelog.gz",
+                "changelog",
+            ],
+            ChangelogKind::News => &["NEWS.Debian.gz", "NEWS.Debian"],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangelogEntry {
+    pub version: String,
+    /// Full entry, including the heading and trailer lines
+    pub text: String,
+}
+
+/// Information needed to locate the changelog of a package version
+#[derive(Debug, Clone)]
+pub struct ChangelogInfo {
+    pub package: String,
+    pub source: String,
+    pub source_version: String,
+    pub component: Option<String>,
+    pub origin: Option<String>,
+}
+
+impl ChangelogInfo {
+    pub fn new(version: &Version) -> Self {
+        let pkg_file = version
+            .package_files()
+            .find(|x| x.index_type() != Some("Debian dpkg status file"));
+
+        Self {
+            package: version.parent().name().to_string(),
+            source: version.source_name().to_string(),
+            source_version: version.source_version().to_string(),
+            component: pkg_file
+                .as_ref()
+                .and_then(|x| x.component())
+                .map(|x| x.to_string()),
+            origin: pkg_file
+                .as_ref()
+                .and_then(|x| x.origin())
+                .map(|x| x.to_string()),
+        }
+    }
+
+    /// Expand an URL template
+    ///
+    /// Supported placeholders: `{package}`, `{source}`, `{version}` (without epoch),
+    /// `{prefix}` (pool directory prefix, e.g. `libf` or `f`), `{component}` and `{file}`.
+    pub fn url(&self, template: &str, kind: ChangelogKind) -> String {
+        let version = self
+            .source_version
+            .split_once(':')
+            .map(|(_, v)| v)
+            .unwrap_or(&self.source_version);
+
+        template
+            .replace("{package}", &self.package)
+            .replace("{source}", &self.source)
+            .replace("{version}", version)
+            .replace("{prefix}", pool_prefix(&self.source))
+            .replace("{component}", self.component.as_deref().unwrap_or("main"))
+            .replace("{file}", kind.file_name())
+    }
+}
+
+fn pool_prefix(source: &str) -> &str {
+    if source.starts_with("lib") && source.len() > 3 {
+        &source[..4]
+    } else {
+        &source[..source.chars().next().map(|c| c.len_utf8()).unwrap_or(0)]
+    }
+}
+
+/// Read the changelog or NEWS file shipped by an installed package
+pub fn read_local_changelog(
+    sysroot: &Path,
+    pkg: &str,
+    kind: ChangelogKind,
+) -> io::Result<Option<(PathBuf, String)>> {
+    let dir = sysroot.join(DOC_DIR).join(pkg);
+
+    let Some(path) = kind
+        .local_file_names()
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|p| p.is_file())
+    else {
+        return Ok(None);
+    };
+
+    let mut s = String::new();
+
+    if path.extension().is_some_and(|ext| ext == "gz") {
+        GzDecoder::new(File::open(&path)?).read_to_string(&mut s)?;
+    } else {
+        s = fs::read_to_string(&path)?;
+    }
+
+    Ok(Some((path, s)))
+}
+
+/// Parse a Debian format changelog (or NEWS) file into entries
+///
+/// Text which does not look like a Debian changelog results in an empty list.
+pub fn parse_changelog(s: &str) -> Vec<ChangelogEntry> {
+    let mut res: Vec<ChangelogEntry> = vec![];
+
+    for line in s.lines() {
+        if let Some(version) = entry_version(line) {
+            res.push(ChangelogEntry {
+                version: version.to_string(),
+                text: String::new(),
+            });
+        }
+
+        if let Some(entry) = res.last_mut() {
+            entry.text.push_str(line);
+            entry.text.push('\n');
+        }
+    }
+
+    res
+}
+
+/// Get version from an entry heading, e.g. `foo (1.0-1) unstable; urgency=medium`
+fn entry_version(line: &str) -> Option<&str> {
+    if line.starts_with(char::is_whitespace) {
+        return None;
+    }
+
+    let (_, rest) = line.split_once(" (")?;
+    let (version, rest) = rest.split_once(')')?;
+
+    if !rest.contains(';') {
+        return None;
+    }
+
+    Some(version)
+}
+
+/// Take entries newer than `version`, changelogs are sorted from the newest to the oldest
+pub fn entries_newer_than(entries: Vec<ChangelogEntry>, version: &str) -> Vec<ChangelogEntry> {
+    entries
+        .into_iter()
+        .take_while(|entry| cmp_versions(&entry.version, version).is_gt())
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_changelog() {
+        let s = "\
+foo (1.2-1) unstable; urgency=medium
+
+  * New upstream release.
+
+ -- Foo Bar <foo@example.com>  Mon, 01 Jan 2024 00:00:00 +0000
+
+foo (1.1-1) unstable; urgency=medium
+
+  * Fix (some) bugs.
+
+ -- Foo Bar <foo@example.com>  Mon, 01 Jan 2023 00:00:00 +0000
+";
+        let entries = parse_changelog(s);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].version, "1.2-1");
+        assert_eq!(entries[1].version, "1.1-1");
+        assert!(entries[0].text.contains("New upstream release"));
+        assert!(!entries[0].text.contains("Fix (some) bugs"));
+    }
+
+    #[test]
+    fn test_pool_prefix() {
+        assert_eq!(pool_prefix("libfoo"), "libf");
+        assert_eq!(pool_prefix("foo"), "f");
+        assert_eq!(pool_prefix("lib"), "l");
+    }
+}
@@ -0,0 +1,120 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use tracing::debug;
+
+const DPKG_INFO_DIR: &str = "var/lib/dpkg/info";
+
+/// Kinds of configuration files dpkg leaves next to a conffile
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConffileKind {
+    /// The package maintainer's version, shipped alongside a locally modified file (`.dpkg-dist`)
+    Dist,
+    /// A new version which has been unpacked but not yet put in place (`.dpkg-new`)
+    New,
+    /// The previous version, replaced by the package maintainer's version (`.dpkg-old`)
+    Old,
+}
+
+impl ConffileKind {
+    const ALL: [ConffileKind; 3] = [ConffileKind::Dist, ConffileKind::New, ConffileKind::Old];
+
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            ConffileKind::Dist => ".dpkg-dist",
+            ConffileKind::New => ".dpkg-new",
+            ConffileKind::Old => ".dpkg-old",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LeftoverConffile {
+    /// Package which owns this configuration file
+    pub package: String,
+    /// Path of the configuration file as recorded in the dpkg database (e.g. `/etc/foo.conf`)
+    pub conffile: PathBuf,
+    /// Path of the configuration file in the sysroot
+    pub path: PathBuf,
+    pub kind: ConffileKind,
+}
+
+impl LeftoverConffile {
+    /// Path of the file left behind by dpkg (e.g. `/etc/foo.conf.dpkg-dist`)
+    pub fn leftover_path(&self) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(self.kind.suffix());
+
+        PathBuf::from(path)
+    }
+}
+
+/// Get configuration files of an installed package from the dpkg database
+pub fn package_conffiles(sysroot: &Path, name: &str, arch: &str) -> io::Result<Vec<PathBuf>> {
+    let info_dir = sysroot.join(DPKG_INFO_DIR);
+
+    // Multi-Arch: same packages have their architecture in the file name
+    let list = [
+        info_dir.join(format!("{name}.conffiles")),
+        info_dir.join(format!("{name}:{arch}.conffiles")),
+    ];
+
+    let Some(list) = list.into_iter().find(|p| p.is_file()) else {
+        return Ok(vec![]);
+    };
+
+    let content = fs::read_to_string(list)?;
+
+    // Since dpkg 1.20.1, lines may contain flags before the path
+    // e.g. `remove-on-upgrade /etc/foo.conf'
+    let conffiles = content
+        .lines()
+        .filter_map(|line| line.split_ascii_whitespace().last())
+        .filter(|path| path.starts_with('/'))
+        .map(PathBuf::from)
+        .collect();
+
+    Ok(conffiles)
+}
+
+/// Find configuration files left behind by dpkg for the given packages
+///
+/// `pkgs` yields package names (without architecture) and their architectures.
+pub fn find_leftover_conffiles<'a>(
+    sysroot: &Path,
+    pkgs: impl IntoIterator<Item = (&'a str, &'a str)>,
+) -> Vec<LeftoverConffile> {
+    let mut res = vec![];
+
+    for (name, arch) in pkgs {
+        let conffiles = match package_conffiles(sysroot, name, arch) {
+            Ok(conffiles) => conffiles,
+            Err(e) => {
+                debug!("Failed to read conffiles list of {name}: {e}");
+                continue;
+            }
+        };
+
+        for conffile in conffiles {
+            let path = sysroot.join(conffile.strip_prefix("/").unwrap_or(&conffile));
+
+            for kind in ConffileKind::ALL {
+                let leftover = LeftoverConffile {
+                    package: name.to_string(),
+                    conffile: conffile.clone(),
+                    path: path.clone(),
+                    kind,
+                };
+
+                if leftover.leftover_path().exists() {
+                    debug!("Found leftover: {}", leftover.leftover_path().display());
+                    res.push(leftover);
+                }
+            }
+        }
+    }
+
+    res
+}
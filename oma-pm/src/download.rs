@@ -8,7 +8,10 @@ use oma_fetch::{
 use oma_pm_operation_type::InstallEntry;
 use tracing::debug;
 
-use crate::apt::{DownloadConfig, OmaAptError, OmaAptResult};
+use crate::{
+    apt::{DownloadConfig, OmaAptError, OmaAptResult},
+    sources::SourceRecord,
+};
 
 /// Download packages (inner)
 pub async fn download_pkgs<F, Fut>(
@@ -133,3 +136,91 @@ fn apt_style_filename(entry: &InstallEntry) -> String {
 
     format!("{package}_{version}_{arch}.deb").replace("%2b", "+")
 }
+
+/// Download files of a source package (inner)
+pub async fn download_source_files<F, Fut>(
+    client: &Client,
+    record: &SourceRecord,
+    base_url: &str,
+    config: DownloadConfig<'_>,
+    callback: F,
+) -> OmaAptResult<Summary>
+where
+    F: Fn(Event) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    let DownloadConfig {
+        network_thread,
+        download_dir,
+        auth,
+    } = config;
+
+    let dir = download_dir
+        .map(|x| x.to_path_buf())
+        .unwrap_or_else(|| ".".into());
+
+    let mut download_list = vec![];
+    let mut total_size = 0;
+
+    for file in &record.files {
+        let url = format!(
+            "{}/{}/{}",
+            base_url.trim_end_matches('/'),
+            record.directory,
+            file.name
+        );
+
+        let source_type = if url.starts_with("file:") {
+            DownloadSourceType::Local(false)
+        } else {
+            let auth = auth.and_then(|auth| auth.find(&url));
+
+            DownloadSourceType::Http {
+                auth: auth.map(|x| (x.login.to_owned(), x.password.to_owned())),
+            }
+        };
+
+        let download_entry = DownloadEntry::builder()
+            .source(vec![DownloadSource { url, source_type }])
+            .filename(file.name.clone())
+            .dir(dir.clone())
+            .allow_resume(true)
+            .msg(file.name.clone())
+            .maybe_hash({
+                if let Some(checksum) = &file.sha256 {
+                    Some(Checksum::from_sha256_str(checksum)?)
+                } else if let Some(checksum) = &file.md5 {
+                    Some(Checksum::from_md5_str(checksum)?)
+                } else {
+                    None
+                }
+            })
+            .build();
+
+        total_size += file.size;
+
+        download_list.push(download_entry);
+    }
+
+    debug!("Source download list: {download_list:?}");
+
+    let downloader = DownloadManager::builder()
+        .client(client)
+        .download_list(&download_list)
+        .maybe_threads(network_thread)
+        .total_size(total_size)
+        .build();
+
+    let res = downloader
+        .start_download(|event| async {
+            callback(event).await;
+        })
+        .await
+        .unwrap();
+
+    if !res.is_download_success() {
+        return Err(OmaAptError::FailedToDownload(res.failed.len()));
+    }
+
+    Ok(res)
+}
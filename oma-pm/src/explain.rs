@@ -0,0 +1,382 @@
+use std::collections::VecDeque;
+
+use ahash::{HashMap, HashSet};
+use oma_apt::{cache::Cache, util::cmp_versions, BaseDep, DepFlags, Package, Version};
+use serde::Serialize;
+
+use crate::{
+    matches::{real_pkg, version_satisfies},
+    pkginfo::OmaDepType,
+};
+
+/// Explanation of why the resolver failed, for humans and machines
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Explanation {
+    pub problems: Vec<Problem>,
+}
+
+/// An unmet dependency
+#[derive(Debug, Clone, Serialize)]
+pub struct Problem {
+    /// Dependency chain from a requested package to `package`, `package` included
+    pub chain: Vec<String>,
+    pub package: String,
+    pub version: String,
+    #[serde(rename = "type")]
+    pub typ: OmaDepType,
+    /// The unmet dependency, like `foo (>= 2)`, alternatives are joined with ` | `
+    pub dependency: String,
+    /// No available version can satisfy the dependency
+    pub unsatisfiable: bool,
+    /// The package which keeps the dependency from being satisfied
+    pub conflict: Option<Conflict>,
+    pub suggestions: Vec<Suggestion>,
+}
+
+/// `package` (`version`) requires `dependency` by `typ`, which conflicts with the problem
+#[derive(Debug, Clone, Serialize)]
+pub struct Conflict {
+    pub package: String,
+    pub version: String,
+    #[serde(rename = "type")]
+    pub typ: OmaDepType,
+    pub dependency: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Suggestion {
+    /// Also remove the package
+    Remove { pkg: String },
+    /// Install an alternative version of the package
+    Pick { pkg: String, version: String },
+    /// Enable the topic providing the package
+    Topic { topic: String, pkg: String },
+}
+
+impl Explanation {
+    /// Packages which no available version can satisfy the dependency on
+    pub fn missing_packages(&self) -> impl Iterator<Item = &str> {
+        self.problems
+            .iter()
+            .filter(|x| x.unsatisfiable)
+            .map(|x| dep_name(&x.dependency))
+    }
+
+    /// Suggest enabling topics for unsatisfiable dependencies, `topics` yields topic names
+    /// and the packages they provide
+    pub fn suggest_topics<'a>(
+        &mut self,
+        topics: impl IntoIterator<Item = (&'a str, &'a [String])>,
+    ) {
+        let topics = topics.into_iter().collect::<Vec<_>>();
+
+        for problem in self.problems.iter_mut().filter(|x| x.unsatisfiable) {
+            let pkg = dep_name(&problem.dependency).to_string();
+
+            for (topic, pkgs) in &topics {
+                if pkgs.contains(&pkg) {
+                    problem.suggestions.push(Suggestion::Topic {
+                        topic: topic.to_string(),
+                        pkg: pkg.clone(),
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Explain the broken packages after resolving, `requested` are indexes of packages the user
+/// asked for
+pub fn explain(cache: &Cache, requested: &HashSet<u64>) -> Explanation {
+    let parents = trace_requested(cache, requested);
+    let mut problems = vec![];
+
+    for pkg in cache.iter().filter(|x| x.is_inst_broken()) {
+        let Some(ver) = pkg.install_version() else {
+            continue;
+        };
+
+        let chain = chain_of(&parents, &pkg);
+        let is_requested = requested.contains(&pkg.index());
+
+        for dep in ver.depends_map().values().flatten() {
+            let alts = dep
+                .iter()
+                .filter(|x| cache.depcache().is_important_dep(x))
+                .collect::<Vec<_>>();
+
+            // Or-groups are met when any of the alternatives is
+            if alts.is_empty()
+                || alts.iter().any(|x| {
+                    cache.depcache().dep_state(x) & DepFlags::DepInstall == DepFlags::DepInstall
+                })
+            {
+                continue;
+            }
+
+            let first = alts[0];
+            let target = first.target_package();
+
+            let mut problem = Problem {
+                chain: chain.clone(),
+                package: pkg.fullname(true),
+                version: ver.version().to_string(),
+                typ: OmaDepType::from(&first.dep_type()),
+                dependency: alts
+                    .iter()
+                    .copied()
+                    .map(dep_string)
+                    .collect::<Vec<_>>()
+                    .join(" | "),
+                unsatisfiable: false,
+                conflict: None,
+                suggestions: vec![],
+            };
+
+            match satisfying_version(cache, &target, first) {
+                Some(want) => {
+                    problem.conflict = find_conflict(&target, &want, &pkg);
+
+                    if let Some(conflict) = &problem.conflict {
+                        if !cache
+                            .get(&conflict.package)
+                            .is_some_and(|x| requested.contains(&x.index()))
+                        {
+                            problem.suggestions.push(Suggestion::Remove {
+                                pkg: conflict.package.clone(),
+                            });
+                        }
+                    }
+
+                    if !target
+                        .install_version()
+                        .is_some_and(|x| x.version() == want.version())
+                    {
+                        problem.suggestions.push(Suggestion::Pick {
+                            pkg: target.fullname(true),
+                            version: want.version().to_string(),
+                        });
+                    }
+                }
+                None => problem.unsatisfiable = true,
+            }
+
+            // An installed package broken by the transaction can be removed instead
+            if !is_requested && pkg.is_installed() {
+                problem.suggestions.push(Suggestion::Remove {
+                    pkg: pkg.fullname(true),
+                });
+            }
+
+            problems.push(problem);
+        }
+    }
+
+    Explanation { problems }
+}
+
+/// Map packages to be installed to the package pulling them in, starting from requested packages
+fn trace_requested(cache: &Cache, requested: &HashSet<u64>) -> HashMap<u64, (u64, String)> {
+    let mut parents = HashMap::with_hasher(ahash::RandomState::new());
+    let mut seen = requested.clone();
+    let mut queue = cache
+        .iter()
+        .filter(|x| requested.contains(&x.index()))
+        .collect::<VecDeque<_>>();
+
+    while let Some(pkg) = queue.pop_front() {
+        let Some(ver) = pkg.install_version() else {
+            continue;
+        };
+
+        let name = pkg.fullname(true);
+
+        for base_dep in ver.depends_map().values().flatten().flat_map(|x| x.iter()) {
+            if !cache.depcache().is_important_dep(base_dep) {
+                continue;
+            }
+
+            let target = base_dep.target_package();
+            let Some(dep) = real_pkg(&target).map(|x| Package::new(cache, x)) else {
+                continue;
+            };
+
+            if dep.install_version().is_none() || !seen.insert(dep.index()) {
+                continue;
+            }
+
+            parents.insert(dep.index(), (pkg.index(), name.clone()));
+            queue.push_back(dep);
+        }
+    }
+
+    parents
+}
+
+fn chain_of(parents: &HashMap<u64, (u64, String)>, pkg: &Package) -> Vec<String> {
+    let mut chain = vec![pkg.fullname(true)];
+    let mut index = pkg.index();
+
+    while let Some((parent, name)) = parents.get(&index) {
+        chain.push(name.clone());
+        index = *parent;
+    }
+
+    chain.reverse();
+
+    chain
+}
+
+/// The newest available version of `target` satisfying `dep`
+fn satisfying_version<'a>(
+    cache: &'a Cache,
+    target: &Package,
+    dep: &BaseDep,
+) -> Option<Version<'a>> {
+    let pkg = real_pkg(target).map(|x| Package::new(cache, x))?;
+
+    let mut versions = pkg
+        .versions()
+        .filter(|x| x.is_downloadable() || x.is_installed())
+        .filter(|x| version_satisfies(x.version(), dep.comp_type(), dep.version()))
+        .collect::<Vec<_>>();
+
+    versions.sort_by(|a, b| cmp_versions(b.version(), a.version()));
+
+    versions.into_iter().next()
+}
+
+/// Find another package in the transaction which rejects version `want` of `target`
+fn find_conflict(target: &Package, want: &Version, broken: &Package) -> Option<Conflict> {
+    for base_dep in target.rdepends().values().flatten().flat_map(|x| x.iter()) {
+        let parent = base_dep.parent_pkg();
+
+        if parent.index() == broken.index() {
+            continue;
+        }
+
+        // Only versions staying or going to be installed matter
+        let parent_ver = base_dep.parent_ver();
+        if !parent
+            .install_version()
+            .is_some_and(|x| x.version() == parent_ver.version())
+        {
+            continue;
+        }
+
+        let typ = OmaDepType::from(&base_dep.dep_type());
+
+        let rejects = match typ {
+            OmaDepType::Depends | OmaDepType::PreDepends => {
+                base_dep.comp_type().is_some()
+                    && !version_satisfies(want.version(), base_dep.comp_type(), base_dep.version())
+            }
+            OmaDepType::Conflicts | OmaDepType::Breaks => {
+                version_satisfies(want.version(), base_dep.comp_type(), base_dep.version())
+            }
+            _ => false,
+        };
+
+        if rejects {
+            return Some(Conflict {
+                package: parent.fullname(true),
+                version: parent_ver.version().to_string(),
+                typ,
+                dependency: dep_string(base_dep),
+            });
+        }
+    }
+
+    None
+}
+
+fn dep_string(dep: &BaseDep) -> String {
+    let name = dep.target_package().fullname(true);
+
+    match (dep.comp_type(), dep.version()) {
+        (Some(comp), Some(ver)) => format!("{name} ({comp} {ver})"),
+        _ => name,
+    }
+}
+
+fn dep_name(dep: &str) -> &str {
+    dep.split([' ', '|']).next().unwrap_or(dep)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_dep_name() {
+        assert_eq!(dep_name("foo (>= 2)"), "foo");
+        assert_eq!(dep_name("foo | bar"), "foo");
+        assert_eq!(dep_name("foo"), "foo");
+    }
+
+    #[test]
+    fn test_explain_conflict() {
+        use crate::test::TEST_LOCK;
+        use oma_apt::{config::Config, new_cache};
+        use std::path::Path;
+        let _lock = TEST_LOCK.lock().unwrap();
+
+        let dir = Path::new(&std::env::var_os("CARGO_MANIFEST_DIR").unwrap())
+            .join("test_file")
+            .join("explain");
+
+        let config = Config::new();
+        let status = config.find("Dir::State::status", "");
+        config.set("Dir::State::status", &dir.join("status").to_string_lossy());
+        let cache = new_cache!(&[dir.join("Packages").to_string_lossy().to_string()]);
+        config.set("Dir::State::status", &status);
+        let cache = cache.unwrap();
+
+        // Leave the installed lib 1.0 alone, so app is broken
+        let meta = cache.get("oma-explain-test-meta").unwrap();
+        meta.mark_install(false, true);
+        cache
+            .get("oma-explain-test-app")
+            .unwrap()
+            .mark_install(false, false);
+
+        let mut requested = HashSet::with_hasher(ahash::RandomState::new());
+        requested.insert(meta.index());
+
+        let explanation = explain(&cache, &requested);
+        assert_eq!(explanation.problems.len(), 1);
+
+        let problem = &explanation.problems[0];
+        assert_eq!(
+            problem.chain,
+            vec!["oma-explain-test-meta", "oma-explain-test-app"]
+        );
+        assert_eq!(problem.package, "oma-explain-test-app");
+        assert_eq!(problem.version, "1.0");
+        assert_eq!(problem.typ, OmaDepType::Depends);
+        assert_eq!(problem.dependency, "oma-explain-test-lib (>= 2.0)");
+        assert!(!problem.unsatisfiable);
+
+        // lib 2.0 would satisfy app, but the installed pin needs lib << 2.0
+        let conflict = problem.conflict.as_ref().unwrap();
+        assert_eq!(conflict.package, "oma-explain-test-pin");
+        assert_eq!(conflict.version, "1.0");
+        assert_eq!(conflict.typ, OmaDepType::Depends);
+        assert_eq!(conflict.dependency, "oma-explain-test-lib (< 2.0)");
+
+        assert_eq!(
+            problem.suggestions,
+            vec![
+                Suggestion::Remove {
+                    pkg: "oma-explain-test-pin".to_string()
+                },
+                Suggestion::Pick {
+                    pkg: "oma-explain-test-lib".to_string(),
+                    version: "2.0".to_string()
+                },
+            ]
+        );
+        assert_eq!(explanation.missing_packages().count(), 0);
+    }
+}
@@ -0,0 +1,362 @@
+use std::{collections::VecDeque, fmt::Write};
+
+use ahash::{HashMap, HashSet};
+use oma_apt::{
+    cache::{Cache, PackageSort},
+    Package,
+};
+use serde::Serialize;
+
+use crate::{
+    apt::OmaAptResult,
+    matches::real_pkg,
+    pkginfo::{OmaDepType, OmaDependency, OmaPackage},
+};
+
+/// Dependency types which keep packages from being autoremoved
+const IMPORTANT_DEP_TYPES: &[OmaDepType] = &[
+    OmaDepType::PreDepends,
+    OmaDepType::Depends,
+    OmaDepType::Recommends,
+    OmaDepType::Suggests,
+];
+
+#[derive(Debug, Serialize)]
+pub struct DepNode {
+    pub name: String,
+    pub version: String,
+    pub installed: bool,
+    pub automatic: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DepEdge {
+    pub from: String,
+    pub to: String,
+    #[serde(rename = "type")]
+    pub typ: OmaDepType,
+}
+
+/// Dependency closure of packages
+#[derive(Debug, Default, Serialize)]
+pub struct DepGraph {
+    pub nodes: Vec<DepNode>,
+    pub edges: Vec<DepEdge>,
+}
+
+impl DepGraph {
+    /// Build the dependency closure of `roots`, following dependencies of `types`
+    ///
+    /// For alternatives (`a | b`), an installed one is followed, otherwise the first available
+    /// one. Installed versions of dependencies are used, otherwise candidate versions.
+    pub fn closure(
+        cache: &Cache,
+        roots: &[OmaPackage],
+        types: &[OmaDepType],
+    ) -> OmaAptResult<Self> {
+        let mut graph = Self::default();
+        let mut seen = HashSet::with_hasher(ahash::RandomState::new());
+        let mut queue = VecDeque::new();
+
+        for root in roots {
+            let pkg = root.package(cache);
+            if seen.insert(pkg.fullname(true)) {
+                queue.push_back(OmaPackage::new(&root.version(cache), &pkg)?);
+            }
+        }
+
+        while let Some(pkg) = queue.pop_front() {
+            let package = pkg.package(cache);
+            let name = package.fullname(true);
+
+            graph.nodes.push(DepNode {
+                name: name.clone(),
+                version: pkg.version(cache).version().to_string(),
+                installed: package.is_installed(),
+                automatic: package.is_auto_installed(),
+            });
+
+            for (typ, group) in pkg.get_deps(cache)? {
+                if !types.contains(&typ) {
+                    continue;
+                }
+
+                for alts in group.inner() {
+                    let Some(dep) = resolve_alternatives(cache, &alts) else {
+                        continue;
+                    };
+
+                    let Some(ver) = dep.installed().or_else(|| dep.candidate()) else {
+                        continue;
+                    };
+
+                    let dep_name = dep.fullname(true);
+
+                    graph.edges.push(DepEdge {
+                        from: name.clone(),
+                        to: dep_name.clone(),
+                        typ,
+                    });
+
+                    if seen.insert(dep_name) {
+                        queue.push_back(OmaPackage::new(&ver, &dep)?);
+                    }
+                }
+            }
+        }
+
+        Ok(graph)
+    }
+
+    /// Render as a Graphviz DOT graph
+    pub fn to_dot(&self) -> String {
+        let mut s = String::from("digraph dependencies {\n");
+
+        for node in &self.nodes {
+            let style = if !node.installed {
+                ", style=dashed"
+            } else if !node.automatic {
+                ", style=bold"
+            } else {
+                ""
+            };
+
+            writeln!(
+                s,
+                "  \"{}\" [label=\"{}\\n{}\"{style}];",
+                node.name, node.name, node.version
+            )
+            .ok();
+        }
+
+        for edge in &self.edges {
+            let style = match edge.typ {
+                OmaDepType::Depends | OmaDepType::PreDepends => "",
+                _ => ", style=dashed",
+            };
+
+            writeln!(
+                s,
+                "  \"{}\" -> \"{}\" [label=\"{}\"{style}];",
+                edge.from, edge.to, edge.typ
+            )
+            .ok();
+        }
+
+        s.push_str("}\n");
+
+        s
+    }
+}
+
+/// A step of a dependency chain: `pkg` depends on the next package by `typ`
+#[derive(Debug, Serialize)]
+pub struct WhyStep {
+    pub pkg: String,
+    #[serde(rename = "type")]
+    pub typ: OmaDepType,
+}
+
+#[derive(Debug, Serialize)]
+pub enum WhyInstalled {
+    NotInstalled,
+    /// Installed manually
+    Manual,
+    /// Required by the chain, starting from a manually installed package
+    Required(Vec<WhyStep>),
+    /// Installed automatically, but no longer required (will be autoremoved)
+    Unneeded,
+}
+
+/// Explain why a package is installed
+///
+/// Finds the shortest dependency chain from a manually installed package to `target`,
+/// following installed versions only, like autoremove does.
+pub fn why_installed(cache: &Cache, target: &Package) -> OmaAptResult<WhyInstalled> {
+    if !target.is_installed() {
+        return Ok(WhyInstalled::NotInstalled);
+    }
+
+    if !target.is_auto_installed() {
+        return Ok(WhyInstalled::Manual);
+    }
+
+    let mut parents: HashMap<u64, Option<(u64, OmaDepType)>> =
+        HashMap::with_hasher(ahash::RandomState::new());
+    let mut queue = VecDeque::new();
+    let mut names = HashMap::with_hasher(ahash::RandomState::new());
+
+    let sort = PackageSort::default().installed();
+
+    for pkg in cache.packages(&sort).filter(|x| !x.is_auto_installed()) {
+        parents.insert(pkg.index(), None);
+        queue.push_back(pkg);
+    }
+
+    while let Some(pkg) = queue.pop_front() {
+        names.insert(pkg.index(), pkg.fullname(true));
+
+        if pkg.index() == target.index() {
+            let mut chain = vec![];
+            let mut index = pkg.index();
+
+            while let Some(Some((parent, typ))) = parents.remove(&index) {
+                chain.push(WhyStep {
+                    pkg: names.get(&parent).cloned().unwrap_or_default(),
+                    typ,
+                });
+                index = parent;
+            }
+
+            chain.reverse();
+
+            return Ok(WhyInstalled::Required(chain));
+        }
+
+        let Some(installed) = pkg.installed() else {
+            continue;
+        };
+
+        let deps = OmaPackage::new(&installed, &pkg)?.get_deps(cache)?;
+
+        for (typ, group) in deps {
+            if !IMPORTANT_DEP_TYPES.contains(&typ) {
+                continue;
+            }
+
+            for alts in group.inner() {
+                for dep in installed_alternatives(cache, &alts) {
+                    if parents.contains_key(&dep.index()) {
+                        continue;
+                    }
+
+                    parents.insert(dep.index(), Some((pkg.index(), typ)));
+                    queue.push_back(dep);
+                }
+            }
+        }
+    }
+
+    Ok(WhyInstalled::Unneeded)
+}
+
+fn resolve_alternatives<'a>(cache: &'a Cache, alts: &[OmaDependency]) -> Option<Package<'a>> {
+    let mut first = None;
+
+    for alt in alts {
+        let Some(pkg) = resolve_dep(cache, alt) else {
+            continue;
+        };
+
+        if pkg.is_installed() {
+            return Some(pkg);
+        }
+
+        if first.is_none() && pkg.candidate().is_some() {
+            first = Some(pkg);
+        }
+    }
+
+    first
+}
+
+fn installed_alternatives<'a>(cache: &'a Cache, alts: &[OmaDependency]) -> Vec<Package<'a>> {
+    alts.iter()
+        .filter_map(|alt| resolve_dep(cache, alt))
+        .filter(|pkg| pkg.is_installed())
+        .collect()
+}
+
+/// Get the real package of a dependency, virtual packages are resolved to a provider, the
+/// installed one if any
+fn resolve_dep<'a>(cache: &'a Cache, dep: &OmaDependency) -> Option<Package<'a>> {
+    let pkg = cache.get(&dep.name)?;
+
+    if !pkg.has_versions() {
+        let installed = pkg.provides().find_map(|provide| {
+            let target = Package::new(cache, unsafe { provide.target_pkg() }.make_safe()?);
+            target.is_installed().then_some(target)
+        });
+
+        if installed.is_some() {
+            return installed;
+        }
+    }
+
+    Some(Package::new(cache, real_pkg(&pkg)?))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_to_dot() {
+        let graph = DepGraph {
+            nodes: vec![
+                DepNode {
+                    name: "foo".to_string(),
+                    version: "1.0".to_string(),
+                    installed: true,
+                    automatic: false,
+                },
+                DepNode {
+                    name: "bar".to_string(),
+                    version: "2.0".to_string(),
+                    installed: false,
+                    automatic: false,
+                },
+            ],
+            edges: vec![DepEdge {
+                from: "foo".to_string(),
+                to: "bar".to_string(),
+                typ: OmaDepType::Recommends,
+            }],
+        };
+
+        assert_eq!(
+            graph.to_dot(),
+            "digraph dependencies {\n  \"foo\" [label=\"foo\\n1.0\", style=bold];\n  \"bar\" [label=\"bar\\n2.0\", style=dashed];\n  \"foo\" -> \"bar\" [label=\"Recommends\", style=dashed];\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_resolve_virtual_dep() {
+        use crate::test::TEST_LOCK;
+        use oma_apt::{config::Config, new_cache};
+        use std::path::Path;
+        let _lock = TEST_LOCK.lock().unwrap();
+
+        let dir = Path::new(&std::env::var_os("CARGO_MANIFEST_DIR").unwrap())
+            .join("test_file")
+            .join("graph");
+
+        let config = Config::new();
+        let status = config.find("Dir::State::status", "");
+        config.set("Dir::State::status", &dir.join("status").to_string_lossy());
+        let cache = new_cache!(&[dir.join("Packages").to_string_lossy().to_string()]);
+        config.set("Dir::State::status", &status);
+        let cache = cache.unwrap();
+
+        let dep = OmaDependency {
+            name: "oma-graph-test-mta".to_string(),
+            comp_symbol: None,
+            ver: None,
+            target_ver: None,
+            comp_ver: None,
+        };
+
+        // Only the installed provider keeps the virtual package satisfied
+        assert_eq!(
+            resolve_dep(&cache, &dep).map(|x| x.name().to_string()),
+            Some("oma-graph-test-postfix".to_string())
+        );
+        assert_eq!(
+            installed_alternatives(&cache, &[dep])
+                .iter()
+                .map(|x| x.name())
+                .collect::<Vec<_>>(),
+            vec!["oma-graph-test-postfix"]
+        );
+    }
+}
@@ -1,8 +1,17 @@
 pub mod apt;
+pub mod changelog;
+pub mod conffile;
+pub mod explain;
+pub mod graph;
+pub mod localdeb;
 pub mod matches;
+pub mod multiarch;
 pub mod pkginfo;
 pub mod progress;
 pub mod search;
+pub mod search_index;
+pub mod solver;
+pub mod sources;
 pub use oma_apt::error::AptErrors;
 pub use oma_apt::PkgCurrentState;
 pub use search::PackageStatus;
@@ -0,0 +1,211 @@
+use std::path::{Path, PathBuf};
+
+use oma_apt::{cache::Cache, util::cmp_versions, DepType};
+use oma_utils::dpkg::{deb_contents, file_owners, DpkgError};
+use serde::Serialize;
+
+use crate::pkginfo::OmaPackage;
+
+/// Something worth knowing before installing a local deb file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum LocalDebWarning {
+    /// The local package is older than the installed one
+    Downgrade {
+        name: String,
+        installed: String,
+        local: String,
+    },
+    /// The installed package comes from a repository, and will be replaced by the local one
+    ReplaceOrigin {
+        name: String,
+        installed: String,
+        origin: String,
+    },
+    /// A file of the local package is owned by other installed package(s)
+    FileConflict {
+        name: String,
+        file: String,
+        owners: Vec<String>,
+    },
+}
+
+/// Check local deb files against installed packages
+///
+/// `debs` are packages matched from local deb files along with the path of the file.
+pub fn check_local_debs(
+    cache: &Cache,
+    debs: &[(OmaPackage, PathBuf)],
+    sysroot: impl AsRef<Path>,
+) -> Result<Vec<LocalDebWarning>, DpkgError> {
+    let mut res = vec![];
+
+    for (pkg, path) in debs {
+        let ver = pkg.version(cache);
+        let package = pkg.package(cache);
+        let name = package.name().to_string();
+
+        if let Some(installed) = package.installed() {
+            if cmp_versions(ver.version(), installed.version()).is_lt() {
+                res.push(LocalDebWarning::Downgrade {
+                    name: name.clone(),
+                    installed: installed.version().to_string(),
+                    local: ver.version().to_string(),
+                });
+            }
+
+            let origin = installed
+                .package_files()
+                .filter(|x| x.index_type() != Some("Debian dpkg status file"))
+                .find_map(|x| x.origin().map(|x| x.to_string()));
+
+            if let Some(origin) = origin {
+                res.push(LocalDebWarning::ReplaceOrigin {
+                    name: name.clone(),
+                    installed: installed.version().to_string(),
+                    origin,
+                });
+            }
+        }
+
+        let replaces = ver
+            .depends_map()
+            .get(&DepType::Replaces)
+            .into_iter()
+            .flatten()
+            .flat_map(|x| x.iter())
+            .map(|x| x.target_package().name().to_string())
+            .collect::<Vec<_>>();
+
+        let files = deb_contents(path)?;
+        let mut conflicts = file_owners(&files, &sysroot)?
+            .into_iter()
+            .filter_map(|(file, owners)| {
+                let owners = conflicting_owners(owners, &name, &replaces);
+                (!owners.is_empty()).then_some((file, owners))
+            })
+            .collect::<Vec<_>>();
+
+        conflicts.sort();
+
+        res.extend(
+            conflicts
+                .into_iter()
+                .map(|(file, owners)| LocalDebWarning::FileConflict {
+                    name: name.clone(),
+                    file,
+                    owners,
+                }),
+        );
+    }
+
+    Ok(res)
+}
+
+/// Owners of a file which conflict with package `name`
+///
+/// Upgrading the package itself is not a conflict, and dpkg allows overwriting files of
+/// packages in `replaces`.
+fn conflicting_owners(owners: Vec<String>, name: &str, replaces: &[String]) -> Vec<String> {
+    owners
+        .into_iter()
+        .filter(|x| {
+            let owner = x.split(':').next().unwrap_or(x);
+            owner != name && !replaces.iter().any(|r| r == owner)
+        })
+        .collect()
+}
+
+#[test]
+fn test_conflicting_owners() {
+    let owners = ["foo", "foo:i386", "bar:amd64", "baz", "qux"].map(|x| x.to_string());
+    let replaces = ["baz".to_string()];
+
+    assert_eq!(
+        conflicting_owners(owners.to_vec(), "foo", &replaces),
+        vec!["bar:amd64".to_string(), "qux".to_string()]
+    );
+    assert!(conflicting_owners(vec!["foo:amd64".to_string()], "foo", &[]).is_empty());
+}
+
+#[test]
+fn test_check_local_debs() {
+    use crate::test::TEST_LOCK;
+    use oma_apt::{config::Config, new_cache};
+    use std::{fs, process::Command};
+    let _lock = TEST_LOCK.lock().unwrap();
+
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path();
+
+    // Package files and dpkg database of the sysroot
+    let root = dir.join("root");
+    let info = dir.join("sysroot/var/lib/dpkg/info");
+    fs::create_dir_all(root.join("DEBIAN")).unwrap();
+    fs::create_dir_all(root.join("usr/share/oma-localdeb-test")).unwrap();
+    fs::create_dir_all(&info).unwrap();
+
+    fs::write(
+        root.join("DEBIAN/control"),
+        "Package: oma-localdeb-test\nVersion: 1.0\nArchitecture: all\n\
+         Maintainer: AOSC OS Maintainers <maintainers@aosc.io>\n\
+         Replaces: oma-localdeb-test-old\nDescription: Test package for local debs\n",
+    )
+    .unwrap();
+
+    let mut status = String::new();
+    for (pkg, file) in [
+        ("oma-localdeb-test", "self"),
+        ("oma-localdeb-test-old", "replaced"),
+        ("oma-localdeb-test-other", "conflict"),
+    ] {
+        let path = format!("/usr/share/oma-localdeb-test/{file}");
+        fs::write(root.join(path.trim_start_matches('/')), file).unwrap();
+        fs::write(info.join(format!("{pkg}.list")), format!("{path}\n")).unwrap();
+        status.push_str(&format!(
+            "Package: {pkg}\nStatus: install ok installed\nVersion: 2.0\nArchitecture: all\n\
+             Maintainer: AOSC OS Maintainers <maintainers@aosc.io>\nDescription: Test\n\n"
+        ));
+    }
+
+    let status_path = dir.join("status");
+    fs::write(&status_path, status).unwrap();
+
+    let deb = dir.join("oma-localdeb-test_1.0_all.deb");
+    assert!(Command::new("dpkg-deb")
+        .arg("--build")
+        .arg(&root)
+        .arg(&deb)
+        .status()
+        .unwrap()
+        .success());
+
+    let config = Config::new();
+    let old_status = config.find("Dir::State::status", "");
+    config.set("Dir::State::status", &status_path.to_string_lossy());
+    let cache = new_cache!(&[deb.to_string_lossy().to_string()]);
+    config.set("Dir::State::status", &old_status);
+    let cache = cache.unwrap();
+
+    let pkg = cache.get("oma-localdeb-test").unwrap();
+    let ver = pkg.get_version("1.0").unwrap();
+    let debs = [(OmaPackage::new(&ver, &pkg).unwrap(), deb)];
+
+    let res = check_local_debs(&cache, &debs, dir.join("sysroot"));
+
+    // Files of the package itself and of replaced packages are not conflicts
+    assert_eq!(
+        res.unwrap(),
+        vec![
+            LocalDebWarning::Downgrade {
+                name: "oma-localdeb-test".to_string(),
+                installed: "2.0".to_string(),
+                local: "1.0".to_string(),
+            },
+            LocalDebWarning::FileConflict {
+                name: "oma-localdeb-test".to_string(),
+                file: "/usr/share/oma-localdeb-test/conflict".to_string(),
+                owners: vec!["oma-localdeb-test-other".to_string()],
+            },
+        ]
+    );
+}
@@ -1,4 +1,4 @@
-use std::{borrow::Cow, path::Path};
+use std::{borrow::Cow, cmp::Ordering, path::Path};
 
 use bon::{builder, Builder};
 use cxx::UniquePtr;
@@ -8,6 +8,7 @@ use oma_apt::{
     error::{AptError, AptErrors},
     raw::{IntoRawIter, PkgIterator},
     records::RecordField,
+    util::cmp_versions,
     Package, Version,
 };
 use oma_utils::{
@@ -47,6 +48,76 @@ pub enum SearchEngine {
     Indicium(Box<dyn Fn(usize)>),
     Strsim,
     Text,
+    Regex,
+    Exact,
+}
+
+/// apt-style action suffix of a package argument (`foo+` to install, `foo-` to remove)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PkgAction {
+    Install,
+    Remove,
+}
+
+/// Relation of a version constraint (like: `apt>=2.5`)
+///
+/// `>` and `<` are strict, same as `>>` and `<<`, and as apt reports relations of dependencies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VersionRelation {
+    GreaterEq,
+    LessEq,
+    Greater,
+    Less,
+    Eq,
+    NotEq,
+}
+
+impl VersionRelation {
+    const OPERATORS: &'static [(&'static str, VersionRelation)] = &[
+        (">=", VersionRelation::GreaterEq),
+        ("<=", VersionRelation::LessEq),
+        (">>", VersionRelation::Greater),
+        ("<<", VersionRelation::Less),
+        (">", VersionRelation::Greater),
+        ("<", VersionRelation::Less),
+    ];
+
+    /// Parse a relation operator (like: `>=`)
+    pub fn parse(op: &str) -> Option<Self> {
+        match op {
+            "=" => Some(VersionRelation::Eq),
+            "!=" => Some(VersionRelation::NotEq),
+            op => Self::OPERATORS
+                .iter()
+                .find(|(x, _)| *x == op)
+                .map(|(_, relation)| *relation),
+        }
+    }
+
+    /// Whether a version comparing to the constraint version as `ord` satisfies the constraint
+    pub fn satisfied_by(&self, ord: Ordering) -> bool {
+        match self {
+            VersionRelation::GreaterEq => ord.is_ge(),
+            VersionRelation::LessEq => ord.is_le(),
+            VersionRelation::Greater => ord.is_gt(),
+            VersionRelation::Less => ord.is_lt(),
+            VersionRelation::Eq => ord.is_eq(),
+            VersionRelation::NotEq => ord.is_ne(),
+        }
+    }
+}
+
+/// Whether `version` satisfies the relation `comp` to `req` (see [`VersionRelation`]), no
+/// relation is satisfied by any version
+pub fn version_satisfies(version: &str, comp: Option<&str>, req: Option<&str>) -> bool {
+    let (Some(comp), Some(req)) = (comp, req) else {
+        return true;
+    };
+
+    match VersionRelation::parse(comp) {
+        Some(relation) => relation.satisfied_by(cmp_versions(version, req)),
+        None => true,
+    }
 }
 
 pub enum GetArchMethod<'a> {
@@ -82,6 +153,7 @@ impl<'a> PackagesMatcher<'a> {
         for keyword in keywords {
             let res = match keyword {
                 x if x.ends_with(".deb") => self.match_local_glob(x)?,
+                x if parse_version_constraint(x).is_some() => self.match_from_constraint(x)?,
                 x if x.split_once('/').is_some() => self.match_from_branch(x)?,
                 x if x.split_once('=').is_some() => self.match_from_version(x)?,
                 x => self.match_pkgs_and_versions_from_glob(x)?,
@@ -102,6 +174,34 @@ impl<'a> PackagesMatcher<'a> {
         Ok((pkgs, no_result))
     }
 
+    /// Split package arguments by apt-style action suffixes
+    ///
+    /// Arguments without a suffix take the `default` action. A suffix is only recognized if the
+    /// argument does not name a package by itself (like: `g++`).
+    /// Returns package arguments to install and to remove.
+    pub fn split_actions<'b>(
+        &self,
+        keywords: impl IntoIterator<Item = &'b str>,
+        default: PkgAction,
+    ) -> (Vec<&'b str>, Vec<&'b str>) {
+        let mut install = vec![];
+        let mut remove = vec![];
+
+        for keyword in keywords {
+            let (keyword, action) = match strip_action_suffix(keyword) {
+                Some((stripped, action)) if self.cache.get(keyword).is_none() => (stripped, action),
+                _ => (keyword, default),
+            };
+
+            match action {
+                PkgAction::Install => install.push(keyword),
+                PkgAction::Remove => remove.push(keyword),
+            }
+        }
+
+        (install, remove)
+    }
+
     /// Query package from give local file glob
     pub fn match_local_glob(&self, file_glob: &str) -> MatcherResult<Vec<OmaPackage>> {
         let mut res = vec![];
@@ -278,6 +378,59 @@ impl<'a> PackagesMatcher<'a> {
         Ok(res)
     }
 
+    /// Query package from give package and version constraint (like: apt>=2.5, apt:amd64<<3~)
+    ///
+    /// The candidate version is preferred if it satisfies the constraint, otherwise the highest
+    /// satisfying version is selected.
+    pub fn match_from_constraint(&self, pat: &str) -> MatcherResult<Vec<OmaPackage>> {
+        let (pkgname, relation, version_str) = parse_version_constraint(pat)
+            .ok_or_else(|| MatcherError::InvalidPattern(pat.to_string()))?;
+
+        let pkg = self
+            .cache
+            .get(pkgname)
+            .ok_or_else(|| MatcherError::NoPackage(pkgname.to_string()))?;
+
+        let mut versions = pkg
+            .versions()
+            .filter(|ver| relation.satisfied_by(cmp_versions(ver.version(), version_str)))
+            .filter(|ver| !self.filter_downloadable_candidate || ver.is_downloadable())
+            .collect::<Vec<_>>();
+
+        if versions.is_empty() {
+            return Err(MatcherError::NoVersion(
+                pkgname.to_string(),
+                pat[pkgname.len()..].to_string(),
+            ));
+        }
+
+        versions.sort_by(|a, b| cmp_versions(b.version(), a.version()));
+
+        // 确保数组第一个是最佳匹配的版本
+        let cand = pkg.candidate();
+        if let Some(pos) = versions.iter().position(|ver| cand.as_ref() == Some(ver)) {
+            let cand = versions.remove(pos);
+            versions.insert(0, cand);
+        }
+
+        if self.filter_candidate {
+            versions.truncate(1);
+        }
+
+        let mut res = vec![];
+
+        for ver in versions {
+            debug!("Constraint {pat} selected version: {}", ver.version());
+            res.push(OmaPackage::new(&ver, &pkg)?);
+
+            if self.select_dbg && has_dbg(self.cache, &pkg, &ver) {
+                self.match_debug_packages(&pkg, &ver, &mut res)?;
+            }
+        }
+
+        Ok(res)
+    }
+
     /// Query package from give package and branch (like: apt/stable)
     pub fn match_from_branch(&self, pat: &str) -> MatcherResult<Vec<OmaPackage>> {
         let mut res = vec![];
@@ -376,6 +529,37 @@ impl<'a> PackagesMatcher<'a> {
     }
 }
 
+/// Parse a version constraint (like: `apt>=2.5`) to package name, relation and version
+pub fn parse_version_constraint(pat: &str) -> Option<(&str, VersionRelation, &str)> {
+    let pos = pat.find(['<', '>'])?;
+    let (pkgname, rest) = pat.split_at(pos);
+
+    let (version, relation) = VersionRelation::OPERATORS
+        .iter()
+        .find_map(|(op, relation)| Some((rest.strip_prefix(op)?, *relation)))?;
+
+    if pkgname.is_empty() || version.is_empty() {
+        return None;
+    }
+
+    Some((pkgname, relation, version))
+}
+
+/// Strip apt-style action suffix from a package argument (like: `foo-`)
+fn strip_action_suffix(keyword: &str) -> Option<(&str, PkgAction)> {
+    if keyword.ends_with(".deb") {
+        return None;
+    }
+
+    let (stripped, action) = if let Some(stripped) = keyword.strip_suffix('+') {
+        (stripped, PkgAction::Install)
+    } else {
+        (keyword.strip_suffix('-')?, PkgAction::Remove)
+    };
+
+    (!stripped.is_empty()).then_some((stripped, action))
+}
+
 /// Get real pkg from real pkg or virtual package
 pub fn real_pkg(pkg: &Package) -> Option<UniquePtr<PkgIterator>> {
     if !pkg.has_versions() {
@@ -387,6 +571,13 @@ pub fn real_pkg(pkg: &Package) -> Option<UniquePtr<PkgIterator>> {
     unsafe { pkg.unique() }.make_safe()
 }
 
+/// Whether `name` (which may be qualified with an architecture) is a debug symbol package
+pub fn is_dbg_pkg(name: &str) -> bool {
+    name.split_once(':')
+        .map_or(name, |(name, _)| name)
+        .ends_with("-dbg")
+}
+
 pub fn has_dbg(cache: &Cache, pkg: &Package<'_>, ver: &Version) -> bool {
     let dbg_pkg = format!("{}-dbg:{}", pkg.name(), ver.arch());
     let dbg_pkg = cache.get(&dbg_pkg);
@@ -402,10 +593,138 @@ pub fn has_dbg(cache: &Cache, pkg: &Package<'_>, ver: &Version) -> bool {
 
 #[cfg(test)]
 mod test {
-    use crate::{matches::GetArchMethod, test::TEST_LOCK};
+    use crate::{
+        matches::{
+            is_dbg_pkg, parse_version_constraint, strip_action_suffix, version_satisfies,
+            GetArchMethod, MatcherError, PkgAction, VersionRelation,
+        },
+        pkginfo::OmaPackage,
+        test::TEST_LOCK,
+    };
 
     use super::PackagesMatcher;
-    use oma_apt::new_cache;
+    use oma_apt::{cache::Cache, new_cache};
+
+    #[test]
+    fn test_parse_version_constraint() {
+        assert_eq!(
+            parse_version_constraint("apt>=2.5"),
+            Some(("apt", VersionRelation::GreaterEq, "2.5"))
+        );
+        assert_eq!(
+            parse_version_constraint("apt:amd64<<3~"),
+            Some(("apt:amd64", VersionRelation::Less, "3~"))
+        );
+        assert_eq!(
+            parse_version_constraint("apt>1:2.0"),
+            Some(("apt", VersionRelation::Greater, "1:2.0"))
+        );
+        assert_eq!(parse_version_constraint("apt=2.5"), None);
+        assert_eq!(parse_version_constraint(">=2.5"), None);
+        assert_eq!(parse_version_constraint("apt>="), None);
+    }
+
+    #[test]
+    fn test_is_dbg_pkg() {
+        assert!(is_dbg_pkg("owo-dbg"));
+        assert!(is_dbg_pkg("owo-dbg:i386"));
+        assert!(!is_dbg_pkg("owo"));
+        assert!(!is_dbg_pkg("dbg-helper"));
+    }
+
+    #[test]
+    fn test_version_satisfies() {
+        assert!(version_satisfies("2.0", Some(">="), Some("2")));
+        assert!(!version_satisfies("1.9", Some(">="), Some("2")));
+        assert!(version_satisfies("1.9", Some("<<"), Some("2")));
+        assert!(!version_satisfies("2", Some("<"), Some("2")));
+        assert!(!version_satisfies("2", Some(">"), Some("2")));
+        assert!(version_satisfies("1.0-1", Some("="), Some("1.0-1")));
+        assert!(version_satisfies("1.0-2", Some("!="), Some("1.0-1")));
+        assert!(version_satisfies("1.0", None, None));
+    }
+
+    #[test]
+    fn test_strip_action_suffix() {
+        assert_eq!(
+            strip_action_suffix("foo-"),
+            Some(("foo", PkgAction::Remove))
+        );
+        assert_eq!(
+            strip_action_suffix("foo+"),
+            Some(("foo", PkgAction::Install))
+        );
+        assert_eq!(strip_action_suffix("foo"), None);
+        assert_eq!(strip_action_suffix("-"), None);
+        assert_eq!(strip_action_suffix("./foo.deb"), None);
+    }
+
+    fn fixture_cache() -> Cache {
+        let packages = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("test_file")
+            .join("Packages");
+
+        new_cache!(&[packages.to_string_lossy().to_string()]).unwrap()
+    }
+
+    fn versions(pkgs: &[OmaPackage]) -> Vec<&str> {
+        pkgs.iter().map(|x| x.version_raw.version()).collect()
+    }
+
+    #[test]
+    fn test_match_from_constraint() {
+        let _lock = TEST_LOCK.lock().unwrap();
+        let cache = fixture_cache();
+        let matcher = PackagesMatcher::builder().cache(&cache).build();
+
+        // The candidate is preferred when it satisfies the constraint
+        let res = matcher.match_from_constraint("pap>=0-3").unwrap();
+        assert_eq!(versions(&res), vec!["1-1"]);
+
+        // Otherwise the highest satisfying version
+        let res = matcher.match_from_constraint("pap<<1").unwrap();
+        assert_eq!(versions(&res), vec!["0-3"]);
+
+        let res = matcher.match_from_constraint("pap<<0-3").unwrap();
+        assert_eq!(versions(&res), vec!["0-2"]);
+
+        assert!(matches!(
+            matcher.match_from_constraint("pap>>1-1"),
+            Err(MatcherError::NoVersion(..))
+        ));
+
+        // `=` selects the exact version
+        let (res, no_result) = matcher.match_pkgs_and_versions(["pap=0-2"]).unwrap();
+        assert_eq!(versions(&res), vec!["0-2"]);
+        assert!(no_result.is_empty());
+
+        let matcher = PackagesMatcher::builder()
+            .cache(&cache)
+            .filter_candidate(false)
+            .build();
+
+        let res = matcher.match_from_constraint("pap>=0-3").unwrap();
+        assert_eq!(versions(&res), vec!["1-1", "0-3"]);
+    }
+
+    #[test]
+    fn test_split_actions() {
+        let _lock = TEST_LOCK.lock().unwrap();
+        let cache = fixture_cache();
+        let matcher = PackagesMatcher::builder().cache(&cache).build();
+
+        let (install, remove) = matcher.split_actions(
+            ["owo", "qaq-", "owo-dbg+", "pap>=0-3", "pap<<1-"],
+            PkgAction::Install,
+        );
+        assert_eq!(install, vec!["owo", "owo-dbg", "pap>=0-3"]);
+        assert_eq!(remove, vec!["qaq", "pap<<1"]);
+
+        let (install, remove) =
+            matcher.split_actions(["owo", "qaq+", "pap=0-2"], PkgAction::Remove);
+        assert_eq!(install, vec!["qaq"]);
+        assert_eq!(remove, vec!["owo", "pap=0-2"]);
+    }
 
     #[test]
     fn test_glob_search() {
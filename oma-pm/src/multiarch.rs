@@ -0,0 +1,157 @@
+use ahash::HashMap;
+use oma_apt::{cache::Cache, config::Config, records::RecordField, util::cmp_versions, Version};
+use serde::Serialize;
+
+/// Installed (or to be installed) copies of a `Multi-Arch: same` package with different versions
+///
+/// dpkg refuses to configure such packages, so every copy must share the same version.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MultiArchSkew {
+    pub name: String,
+    /// Architecture and version of each copy
+    pub versions: Vec<(String, String)>,
+}
+
+/// Whether the version is declared as `Multi-Arch: same`
+pub fn is_multiarch_same(ver: &Version) -> bool {
+    ver.get_record(RecordField::MultiArch)
+        .is_some_and(|x| x.trim() == "same")
+}
+
+/// Architectures of the installed copies of package `name`, the native one first
+pub fn installed_archs(cache: &Cache, name: &str) -> Vec<String> {
+    Config::new()
+        .get_architectures()
+        .into_iter()
+        .filter(|arch| {
+            cache
+                .get(&format!("{name}:{arch}"))
+                .is_some_and(|x| x.is_installed())
+        })
+        .collect()
+}
+
+/// Find `Multi-Arch: same` packages whose copies are going to have different versions
+///
+/// Versions are taken from the pending changes in the depcache, so this can be checked both
+/// before and after resolving.
+pub fn same_version_skew(cache: &Cache) -> Vec<MultiArchSkew> {
+    let archs = Config::new().get_architectures();
+
+    // Without foreign architectures there is only one copy of each package
+    if archs.len() <= 1 {
+        return vec![];
+    }
+
+    let mut copies: HashMap<String, Vec<(String, String)>> =
+        HashMap::with_hasher(ahash::RandomState::new());
+
+    for pkg in cache.iter() {
+        let Some(ver) = pkg.install_version() else {
+            continue;
+        };
+
+        if !is_multiarch_same(&ver) {
+            continue;
+        }
+
+        copies
+            .entry(pkg.name().to_string())
+            .or_default()
+            .push((pkg.arch().to_string(), ver.version().to_string()));
+    }
+
+    let mut res = copies
+        .into_iter()
+        .filter(|(_, v)| v.windows(2).any(|x| cmp_versions(&x[0].1, &x[1].1).is_ne()))
+        .map(|(name, mut versions)| {
+            versions.sort_by(|a, b| arch_order(&archs, &a.0).cmp(&arch_order(&archs, &b.0)));
+            MultiArchSkew { name, versions }
+        })
+        .collect::<Vec<_>>();
+
+    res.sort_by(|a, b| a.name.cmp(&b.name));
+
+    res
+}
+
+fn arch_order(archs: &[String], arch: &str) -> usize {
+    archs.iter().position(|x| x == arch).unwrap_or(archs.len())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use oma_apt::new_cache;
+    use std::{fs, process::Command};
+
+    const NAME: &str = "oma-multiarch-test-lib";
+
+    #[test]
+    fn test_multiarch_skew() {
+        // apt only reads the architectures once per process, so check them in a child
+        // process with its own apt config
+        if std::env::var_os("OMA_TEST_MULTIARCH").is_some() {
+            return check_multiarch_skew();
+        }
+
+        let native = Config::new().get("APT::Architecture").unwrap();
+        let foreign = if native == "i386" { "amd64" } else { "i386" };
+
+        let tmp = tempfile::tempdir().unwrap();
+        let status = tmp.path().join("status");
+        let apt_conf = tmp.path().join("apt.conf");
+
+        let stanza = |arch: &str, version: &str| {
+            format!(
+                "Package: {NAME}\nStatus: install ok installed\nVersion: {version}\nSection: libs\nArchitecture: {arch}\nMulti-Arch: same\nInstalled-Size: 1\nMaintainer: AOSC OS Maintainers <maintainers@aosc.io>\nDescription: Test package for multiarch\n"
+            )
+        };
+
+        fs::write(
+            &status,
+            format!("{}\n{}", stanza(&native, "1.0"), stanza(foreign, "1.1")),
+        )
+        .unwrap();
+        fs::write(
+            &apt_conf,
+            format!(
+                "APT::Architectures {{ \"{native}\"; \"{foreign}\"; }};\nDir::State::status \"{}\";\n",
+                status.display()
+            ),
+        )
+        .unwrap();
+
+        let res = Command::new(std::env::current_exe().unwrap())
+            .args(["multiarch::test::test_multiarch_skew", "--exact"])
+            .env("APT_CONFIG", &apt_conf)
+            .env("OMA_TEST_MULTIARCH", "1")
+            .output()
+            .unwrap();
+
+        assert!(
+            res.status.success(),
+            "{}",
+            String::from_utf8_lossy(&res.stdout)
+        );
+    }
+
+    fn check_multiarch_skew() {
+        let archs = Config::new().get_architectures();
+        assert_eq!(archs.len(), 2);
+
+        let cache = new_cache!().unwrap();
+
+        assert_eq!(installed_archs(&cache, NAME), archs);
+        assert_eq!(
+            same_version_skew(&cache),
+            vec![MultiArchSkew {
+                name: NAME.to_string(),
+                versions: vec![
+                    (archs[0].clone(), "1.0".to_string()),
+                    (archs[1].clone(), "1.1".to_string()),
+                ],
+            }]
+        );
+    }
+}
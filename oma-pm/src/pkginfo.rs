@@ -103,7 +103,7 @@ impl OmaDependency {
     }
 }
 
-#[derive(Debug, Eq, PartialEq, Hash, Deserialize, Serialize)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Deserialize, Serialize)]
 pub enum OmaDepType {
     Depends,
     PreDepends,
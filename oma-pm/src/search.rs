@@ -1,23 +1,22 @@
 use ahash::{AHashMap, RandomState};
-use cxx::UniquePtr;
+use bon::Builder;
 use glob_match::glob_match;
-use indexmap::map::Entry;
-use indicium::simple::{Indexable, SearchIndex};
 use memchr::memmem;
 use oma_apt::{
     cache::{Cache, PackageSort},
     error::{AptError, AptErrors},
-    raw::{IntoRawIter, PkgIterator},
-    Package,
+    raw::IntoRawIter,
+    records::RecordField,
+    Package, Version,
 };
+use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::fmt::Debug;
 
-type IndexSet<T> = indexmap::IndexSet<T, RandomState>;
 type IndexMap<K, V> = indexmap::IndexMap<K, V, RandomState>;
 
 use crate::{
-    matches::has_dbg,
+    matches::{has_dbg, is_dbg_pkg},
+    multiarch::installed_archs,
     pkginfo::{OmaPackage, PtrIsNone},
 };
 
@@ -56,39 +55,6 @@ impl Ord for PackageStatus {
     }
 }
 
-pub struct SearchEntry {
-    name: String,
-    description: String,
-    status: PackageStatus,
-    provides: IndexSet<String>,
-    has_dbg: bool,
-    raw_pkg: UniquePtr<PkgIterator>,
-    section_is_base: bool,
-}
-
-impl Debug for SearchEntry {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("SearchEntry")
-            .field("pkgname", &self.name)
-            .field("description", &self.description)
-            .field("status", &self.status)
-            .field("provides", &self.provides)
-            .field("has_dbg", &self.has_dbg)
-            .field("raw_pkg", &self.raw_pkg.fullname(true))
-            .field("section_is_base", &self.section_is_base)
-            .finish()
-    }
-}
-
-impl Indexable for SearchEntry {
-    fn strings(&self) -> Vec<String> {
-        let mut v = vec![self.name.clone(), self.description.clone()];
-        let provides = self.provides.clone().into_iter();
-        v.extend(provides);
-        v
-    }
-}
-
 #[derive(Debug, thiserror::Error)]
 pub enum OmaSearchError {
     #[error(transparent)]
@@ -103,6 +69,8 @@ pub enum OmaSearchError {
     FailedGetCandidate(String),
     #[error(transparent)]
     PtrIsNone(#[from] PtrIsNone),
+    #[error("Invalid regular expression: {0}")]
+    InvalidRegex(#[from] regex::Error),
 }
 
 pub type OmaSearchResult<T> = Result<T, OmaSearchError>;
@@ -117,200 +85,216 @@ pub struct SearchResult {
     pub dbg_package: bool,
     pub status: PackageStatus,
     pub is_base: bool,
+    /// Architectures of the installed copies of the package
+    #[serde(default)]
+    pub installed_archs: Vec<String>,
+    #[serde(default)]
+    pub section: String,
+    #[serde(default)]
+    pub maintainer: String,
+    /// Origins of the repositories providing the candidate version
+    #[serde(default)]
+    pub origins: Vec<String>,
+    #[serde(default)]
+    pub installed_size: u64,
+    #[serde(default)]
+    pub download_size: u64,
+    /// Byte range of the matched part of `name`, if known
+    #[serde(default)]
+    pub name_match: Option<(usize, usize)>,
+    /// Byte range of the matched part of `desc`, if known
+    #[serde(default)]
+    pub desc_match: Option<(usize, usize)>,
 }
 
-pub struct IndiciumSearch<'a> {
-    cache: &'a Cache,
-    pkg_map: IndexMap<String, SearchEntry>,
-    index: SearchIndex<String>,
-}
-
-pub trait OmaSearch {
-    fn search(&self, query: &str) -> OmaSearchResult<Vec<SearchResult>>;
+/// Facets to narrow down search results, applied by every [`OmaSearch`] implementation
+#[derive(Debug, Clone, Default, PartialEq, Eq, Builder)]
+pub struct SearchFilter {
+    /// Section of the candidate version, glob is supported (like: `devel`, `lib*`)
+    pub section: Option<String>,
+    /// Origin, label, suite or host of a repository providing the candidate version
+    pub origin: Option<String>,
+    /// Only installed (`true`) or not installed (`false`) packages
+    pub installed: Option<bool>,
+    /// Only upgradable packages
+    #[builder(default)]
+    pub upgradable: bool,
+    /// Part of the maintainer name or email, case insensitive
+    pub maintainer: Option<String>,
+    /// Minimum installed size of the candidate version, in bytes
+    pub min_size: Option<u64>,
+    /// Maximum installed size of the candidate version, in bytes
+    pub max_size: Option<u64>,
 }
 
-impl OmaSearch for IndiciumSearch<'_> {
-    fn search(&self, query: &str) -> OmaSearchResult<Vec<SearchResult>> {
-        let mut search_res = vec![];
-        let query = query.to_lowercase();
-        let res = self.index.search(&query);
+impl SearchFilter {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
 
-        if res.is_empty() {
-            return Err(OmaSearchError::NoResult(query));
+    pub fn is_match(&self, pkg: &Package, cand: &Version) -> bool {
+        if self.upgradable && !pkg.is_upgradable() {
+            return false;
         }
 
-        for i in res {
-            let entry = self.search_result(i, Some(&query))?;
-            search_res.push(entry);
+        if self.installed.is_some_and(|x| x != pkg.is_installed()) {
+            return false;
         }
 
-        search_res.sort_by(|a, b| b.status.cmp(&a.status));
-
-        for i in 0..search_res.len() {
-            if search_res[i].full_match {
-                let i = search_res.remove(i);
-                search_res.insert(0, i);
+        if let Some(section) = &self.section {
+            let s = version_section(cand);
+            // Sections may be prefixed by component, like `non-free/games`
+            let s_without_comp = s.rsplit_once('/').map(|x| x.1).unwrap_or(&s);
+            if !glob_match(section, &s) && !glob_match(section, s_without_comp) {
+                return false;
             }
         }
 
-        Ok(search_res)
-    }
-}
-
-impl<'a> IndiciumSearch<'a> {
-    pub fn new(cache: &'a Cache, progress: impl Fn(usize)) -> OmaSearchResult<Self> {
-        let sort = PackageSort::default().include_virtual();
-        let packages = cache.packages(&sort);
-
-        let mut pkg_map = IndexMap::with_hasher(RandomState::new());
+        if let Some(maintainer) = &self.maintainer {
+            if !version_maintainer(cand)
+                .to_lowercase()
+                .contains(&maintainer.to_lowercase())
+            {
+                return false;
+            }
+        }
 
-        for (i, pkg) in packages.enumerate() {
-            let name = pkg.fullname(true);
-            progress(i);
+        if let Some(origin) = &self.origin {
+            let origin = origin.to_lowercase();
+            let is_match = cand.package_files().any(|f| {
+                [f.origin(), f.label(), f.archive()]
+                    .into_iter()
+                    .flatten()
+                    .any(|x| x.to_lowercase() == origin)
+                    || f.index_file()
+                        .archive_uri("")
+                        .to_lowercase()
+                        .contains(&origin)
+            });
 
-            if name.contains("-dbg") {
-                continue;
+            if !is_match {
+                return false;
             }
+        }
 
-            let status = if pkg.is_upgradable() {
-                PackageStatus::Upgrade
-            } else if pkg.is_installed() {
-                PackageStatus::Installed
-            } else {
-                PackageStatus::Avail
-            };
+        let size = cand.installed_size();
 
-            if let Some(cand) = pkg.candidate() {
-                if let Entry::Vacant(e) = pkg_map.entry(name.clone()) {
-                    e.insert(SearchEntry {
-                        name,
-                        description: cand
-                            .summary()
-                            .unwrap_or_else(|| "No description".to_string()),
-                        status,
-                        provides: pkg.provides().map(|x| x.to_string()).collect(),
-                        has_dbg: has_dbg(cache, &pkg, &cand),
-                        raw_pkg: unsafe { pkg.unique() }
-                            .make_safe()
-                            .ok_or(OmaSearchError::PtrIsNone(PtrIsNone))?,
-                        section_is_base: cand.section().map(|x| x == "Bases").unwrap_or(false),
-                    });
-                }
-            } else {
-                // Provides
-                let mut real_pkgs = vec![];
-                for i in pkg.provides() {
-                    real_pkgs.push((
-                        i.name().to_string(),
-                        unsafe { i.target_pkg() }
-                            .make_safe()
-                            .ok_or(OmaSearchError::PtrIsNone(PtrIsNone))?,
-                    ));
-                }
+        if self.min_size.is_some_and(|x| size < x) || self.max_size.is_some_and(|x| size > x) {
+            return false;
+        }
 
-                for (provide, i) in real_pkgs {
-                    let pkg = Package::new(cache, i);
-                    let name = pkg.fullname(true);
+        true
+    }
 
-                    let status = if pkg.is_upgradable() {
-                        PackageStatus::Upgrade
-                    } else if pkg.is_installed() {
-                        PackageStatus::Installed
-                    } else {
-                        PackageStatus::Avail
-                    };
+    /// Take facets out of a search query, like `section:devel status:installed gcc`
+    ///
+    /// Supported facets are `section:`, `origin:` (or `repo:`), `maintainer:`,
+    /// `status:installed|not-installed|upgradable` and `size:MIN..MAX` (like `size:1M..`).
+    /// Returns the filter and the rest of the query.
+    pub fn parse_query(query: &str) -> (Self, String) {
+        let mut filter = Self::default();
+        let mut rest = vec![];
+
+        for word in query.split_whitespace() {
+            let Some((k, v)) = word.split_once(':').filter(|(_, v)| !v.is_empty()) else {
+                rest.push(word);
+                continue;
+            };
 
-                    if let Some(cand) = pkg.candidate() {
-                        pkg_map
-                            .entry(name.clone())
-                            .and_modify(|x| {
-                                if !x.provides.contains(&provide) {
-                                    x.provides.insert(provide.clone());
-                                }
-                            })
-                            .or_insert(SearchEntry {
-                                name,
-                                description: cand
-                                    .summary()
-                                    .unwrap_or_else(|| "No description".to_string()),
-                                status,
-                                provides: {
-                                    let mut set = IndexSet::with_hasher(RandomState::new());
-                                    set.insert(provide.clone());
-                                    set
-                                },
-                                has_dbg: has_dbg(cache, &pkg, &cand),
-                                raw_pkg: unsafe { pkg.unique() }
-                                    .make_safe()
-                                    .ok_or(OmaSearchError::PtrIsNone(PtrIsNone))?,
-                                section_is_base: cand
-                                    .section()
-                                    .map(|x| x == "Bases")
-                                    .unwrap_or(false),
-                            });
+            match k {
+                "section" => filter.section = Some(v.to_string()),
+                "origin" | "repo" => filter.origin = Some(v.to_string()),
+                "maintainer" => filter.maintainer = Some(v.to_string()),
+                "status" if v == "installed" => filter.installed = Some(true),
+                "status" if v == "not-installed" => filter.installed = Some(false),
+                "status" if v == "upgradable" => filter.upgradable = true,
+                "size" => match parse_size_range(v) {
+                    Some((min, max)) => {
+                        filter.min_size = min;
+                        filter.max_size = max;
                     }
-                }
+                    None => rest.push(word),
+                },
+                // Like `foo:i386`
+                _ => rest.push(word),
             }
         }
 
-        let mut search_index: SearchIndex<String> = SearchIndex::default();
-
-        pkg_map
-            .iter()
-            .for_each(|(key, value)| search_index.insert(key, value));
+        (filter, rest.join(" "))
+    }
+}
 
-        Ok(Self {
-            cache,
-            pkg_map,
-            index: search_index,
-        })
+/// Parse a human readable size like `10M` (1024-based, `K`, `M`, `G` and `T` suffixes)
+pub fn parse_size(s: &str) -> Option<u64> {
+    let s = s.trim();
+    let s = s
+        .strip_suffix(['B', 'b'])
+        .filter(|x| x.ends_with(|c: char| c.is_ascii_alphabetic()))
+        .unwrap_or(s);
+    let (num, unit) = s.split_at(s.find(|c: char| c.is_ascii_alphabetic()).unwrap_or(s.len()));
+
+    let unit = match unit.to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KI" => 1 << 10,
+        "M" | "MI" => 1 << 20,
+        "G" | "GI" => 1 << 30,
+        "T" | "TI" => 1 << 40,
+        _ => return None,
+    };
+
+    let num = num.trim().parse::<f64>().ok()?;
+
+    if num < 0.0 {
+        return None;
     }
 
-    pub fn search_result(
-        &self,
-        i: &str,
-        query: Option<&str>,
-    ) -> Result<SearchResult, OmaSearchError> {
-        let entry = self.pkg_map.get(i).unwrap();
-        let search_name = entry.name.clone();
-        let desc = entry.description.clone();
-        let status = entry.status;
-        let has_dbg = entry.has_dbg;
-        let pkg = unsafe { entry.raw_pkg.unique() }
-            .make_safe()
-            .ok_or(OmaSearchError::PtrIsNone(PtrIsNone))?;
-        let pkg = Package::new(self.cache, pkg);
-
-        let full_match = if let Some(query) = query {
-            query == search_name || entry.provides.iter().any(|x| x == query)
-        } else {
-            false
-        };
+    Some((num * unit as f64) as u64)
+}
 
-        let old_version = if status != PackageStatus::Upgrade {
-            None
+/// Parse a size range like `1M..10M`, `..10M` or `1M..`
+pub fn parse_size_range(s: &str) -> Option<(Option<u64>, Option<u64>)> {
+    let (min, max) = s.split_once("..")?;
+    let parse = |x: &str| {
+        if x.is_empty() {
+            Some(None)
         } else {
-            pkg.installed().map(|x| x.version().to_string())
-        };
+            parse_size(x).map(Some)
+        }
+    };
+
+    Some((parse(min)?, parse(max)?))
+}
+
+pub(crate) fn version_section(ver: &Version) -> String {
+    ver.section().map(|x| x.to_string()).unwrap_or_default()
+}
+
+pub(crate) fn version_maintainer(ver: &Version) -> String {
+    ver.get_record(RecordField::Maintainer).unwrap_or_default()
+}
+
+pub(crate) fn version_origins(ver: &Version) -> Vec<String> {
+    let mut origins = ver
+        .package_files()
+        .filter_map(|x| x.origin().map(|x| x.to_string()))
+        .collect::<Vec<_>>();
 
-        let new_version = pkg
-            .candidate()
-            .map(|x| x.version().to_string())
-            .ok_or_else(|| OmaSearchError::FailedGetCandidate(pkg.fullname(true)))?;
-
-        let is_base = entry.section_is_base;
-
-        Ok(SearchResult {
-            name: pkg.fullname(true),
-            desc,
-            old_version,
-            new_version,
-            full_match,
-            dbg_package: has_dbg,
-            status,
-            is_base,
-        })
+    origins.dedup();
+
+    origins
+}
+
+pub trait OmaSearch {
+    fn search(&self, query: &str) -> OmaSearchResult<Vec<SearchResult>> {
+        self.search_with_filter(query, &SearchFilter::default())
     }
+
+    /// Search and keep only results matching `filter`
+    fn search_with_filter(
+        &self,
+        query: &str,
+        filter: &SearchFilter,
+    ) -> OmaSearchResult<Vec<SearchResult>>;
 }
 
 pub struct StrSimSearch<'a> {
@@ -318,7 +302,11 @@ pub struct StrSimSearch<'a> {
 }
 
 impl OmaSearch for StrSimSearch<'_> {
-    fn search(&self, query: &str) -> Result<Vec<SearchResult>, OmaSearchError> {
+    fn search_with_filter(
+        &self,
+        query: &str,
+        filter: &SearchFilter,
+    ) -> Result<Vec<SearchResult>, OmaSearchError> {
         let pkgs = self
             .cache
             .packages(&PackageSort::default().include_virtual());
@@ -329,7 +317,7 @@ impl OmaSearch for StrSimSearch<'_> {
             let name = pkg.fullname(true);
             if let Some(cand) = pkg.candidate() {
                 if memmem::find(name.as_bytes(), query.as_bytes()).is_some()
-                    && !name.ends_with("-dbg")
+                    && !is_dbg_pkg(&name)
                     && !res.contains_key(&name)
                 {
                     let oma_pkg = OmaPackage::new(&cand, &pkg)?;
@@ -343,7 +331,7 @@ impl OmaSearch for StrSimSearch<'_> {
                     .description()
                     .is_some_and(|x| memmem::find(x.as_bytes(), query.as_bytes()).is_some())
                     && !res.contains_key(&name)
-                    && !name.ends_with("-dbg")
+                    && !is_dbg_pkg(&name)
                 {
                     let oma_pkg = OmaPackage::new(&cand, &pkg)?;
                     res.insert(
@@ -394,6 +382,10 @@ impl OmaSearch for StrSimSearch<'_> {
                 .candidate()
                 .ok_or_else(|| OmaSearchError::FailedGetCandidate(pkg.fullname(true)))?;
 
+            if !filter.is_match(&pkg, &cand) {
+                continue;
+            }
+
             let name = pkg.fullname(true);
             let is_base = name.ends_with("-base");
             let full_match = query == name;
@@ -421,6 +413,18 @@ impl OmaSearch for StrSimSearch<'_> {
                     PackageStatus::Avail
                 },
                 is_base,
+                installed_archs: if install || upgrade {
+                    installed_archs(self.cache, pkg.name())
+                } else {
+                    vec![]
+                },
+                section: version_section(&cand),
+                maintainer: version_maintainer(&cand),
+                origins: version_origins(&cand),
+                installed_size: cand.installed_size(),
+                download_size: cand.size(),
+                name_match: None,
+                desc_match: None,
             });
         }
 
@@ -462,7 +466,11 @@ impl<'a> TextSearch<'a> {
 }
 
 impl OmaSearch for TextSearch<'_> {
-    fn search(&self, query: &str) -> OmaSearchResult<Vec<SearchResult>> {
+    fn search_with_filter(
+        &self,
+        query: &str,
+        filter: &SearchFilter,
+    ) -> OmaSearchResult<Vec<SearchResult>> {
         let mut res = vec![];
         let pkgs = self.cache.packages(&PackageSort::default());
 
@@ -472,13 +480,13 @@ impl OmaSearch for TextSearch<'_> {
 
             if (memmem::find(name.as_bytes(), query.as_bytes()).is_some()
                 || glob_match(query, &name))
-                && !name.ends_with("-dbg")
+                && !is_dbg_pkg(&name)
             {
                 let full_match = query == name;
                 let is_base = name.ends_with("-base");
                 let upgrade = pkg.is_upgradable();
                 let installed = pkg.is_installed();
-                if let Some(cand) = cand {
+                if let Some(cand) = cand.filter(|cand| filter.is_match(&pkg, cand)) {
                     res.push(SearchResult {
                         name,
                         desc: cand
@@ -502,6 +510,18 @@ impl OmaSearch for TextSearch<'_> {
                             PackageStatus::Avail
                         },
                         is_base,
+                        installed_archs: if installed || upgrade {
+                            installed_archs(self.cache, pkg.name())
+                        } else {
+                            vec![]
+                        },
+                        section: version_section(&cand),
+                        maintainer: version_maintainer(&cand),
+                        origins: version_origins(&cand),
+                        installed_size: cand.installed_size(),
+                        download_size: cand.size(),
+                        name_match: None,
+                        desc_match: None,
                     })
                 }
             }
@@ -520,8 +540,249 @@ impl OmaSearch for TextSearch<'_> {
     }
 }
 
+/// Search package names and summaries using a regular expression
+///
+/// Long descriptions are searched as well, but only matches in names and summaries are
+/// highlighted. Results matching the name come first, no fuzzy ranking is applied.
+pub struct RegexSearch<'a> {
+    cache: &'a Cache,
+}
+
+impl<'a> RegexSearch<'a> {
+    pub fn new(cache: &'a Cache) -> Self {
+        Self { cache }
+    }
+}
+
+impl OmaSearch for RegexSearch<'_> {
+    fn search_with_filter(
+        &self,
+        query: &str,
+        filter: &SearchFilter,
+    ) -> OmaSearchResult<Vec<SearchResult>> {
+        let re = Regex::new(query)?;
+        let mut res = vec![];
+
+        for pkg in self.cache.packages(&PackageSort::default()) {
+            let name = pkg.fullname(true);
+
+            if is_dbg_pkg(&name) {
+                continue;
+            }
+
+            let Some(cand) = pkg.candidate() else {
+                continue;
+            };
+
+            let summary = cand
+                .summary()
+                .unwrap_or_else(|| "No description".to_string());
+
+            let name_match = re.find(&name).map(|m| (m.start(), m.end()));
+            let desc_match = re.find(&summary).map(|m| (m.start(), m.end()));
+
+            if name_match.is_none()
+                && desc_match.is_none()
+                && !cand.description().is_some_and(|x| re.is_match(&x))
+            {
+                continue;
+            }
+
+            if !filter.is_match(&pkg, &cand) {
+                continue;
+            }
+
+            let full_match = name_match == Some((0, name.len()));
+            let mut result = new_search_result(self.cache, &pkg, &cand, full_match);
+            result.name_match = name_match;
+            result.desc_match = desc_match;
+
+            res.push(result);
+        }
+
+        if res.is_empty() {
+            return Err(OmaSearchError::NoResult(query.to_string()));
+        }
+
+        res.sort_by(|a, b| {
+            b.full_match
+                .cmp(&a.full_match)
+                .then_with(|| b.name_match.is_some().cmp(&a.name_match.is_some()))
+                .then_with(|| a.name.cmp(&b.name))
+        });
+
+        Ok(res)
+    }
+}
+
+/// Search packages by exact name, or by name prefix if the query ends with `*`
+///
+/// Virtual packages matching the query are resolved to their providers.
+pub struct ExactSearch<'a> {
+    cache: &'a Cache,
+}
+
+impl<'a> ExactSearch<'a> {
+    pub fn new(cache: &'a Cache) -> Self {
+        Self { cache }
+    }
+}
+
+impl OmaSearch for ExactSearch<'_> {
+    fn search_with_filter(
+        &self,
+        query: &str,
+        filter: &SearchFilter,
+    ) -> OmaSearchResult<Vec<SearchResult>> {
+        let (needle, is_prefix) = match query.strip_suffix('*') {
+            Some(x) => (x, true),
+            None => (query, false),
+        };
+
+        let is_match = |name: &str| {
+            if is_prefix {
+                name.starts_with(needle)
+            } else {
+                name == needle
+            }
+        };
+
+        let mut res: IndexMap<String, SearchResult> = IndexMap::with_hasher(RandomState::new());
+
+        for pkg in self
+            .cache
+            .packages(&PackageSort::default().include_virtual())
+        {
+            let name = pkg.fullname(true);
+
+            if !is_match(&name) {
+                continue;
+            }
+
+            if let Some(cand) = pkg.candidate() {
+                if filter.is_match(&pkg, &cand) {
+                    let mut result = new_search_result(self.cache, &pkg, &cand, name == needle);
+                    result.name_match = Some((0, needle.len()));
+                    res.insert(name, result);
+                }
+
+                continue;
+            }
+
+            // Virtual package
+            for provide in pkg.provides() {
+                let Some(target) = unsafe { provide.target_pkg() }.make_safe() else {
+                    continue;
+                };
+
+                let target = Package::new(self.cache, target);
+                let target_name = target.fullname(true);
+
+                if res.contains_key(&target_name) {
+                    continue;
+                }
+
+                let Some(cand) = target.candidate() else {
+                    continue;
+                };
+
+                if filter.is_match(&target, &cand) {
+                    res.insert(
+                        target_name,
+                        new_search_result(self.cache, &target, &cand, name == needle),
+                    );
+                }
+            }
+        }
+
+        if res.is_empty() {
+            return Err(OmaSearchError::NoResult(query.to_string()));
+        }
+
+        let mut res = res.into_values().collect::<Vec<_>>();
+        res.sort_by(|a, b| {
+            b.full_match
+                .cmp(&a.full_match)
+                .then_with(|| a.name.cmp(&b.name))
+        });
+
+        Ok(res)
+    }
+}
+
+fn new_search_result(
+    cache: &Cache,
+    pkg: &Package,
+    cand: &Version,
+    full_match: bool,
+) -> SearchResult {
+    let status = if pkg.is_upgradable() {
+        PackageStatus::Upgrade
+    } else if pkg.is_installed() {
+        PackageStatus::Installed
+    } else {
+        PackageStatus::Avail
+    };
+
+    SearchResult {
+        name: pkg.fullname(true),
+        desc: cand
+            .summary()
+            .unwrap_or_else(|| "No description".to_string()),
+        old_version: if status == PackageStatus::Upgrade {
+            pkg.installed().map(|x| x.version().to_string())
+        } else {
+            None
+        },
+        new_version: cand.version().to_string(),
+        full_match,
+        dbg_package: has_dbg(cache, pkg, cand),
+        status,
+        is_base: pkg.name().ends_with("-base"),
+        installed_archs: if status == PackageStatus::Avail {
+            vec![]
+        } else {
+            installed_archs(cache, pkg.name())
+        },
+        section: version_section(cand),
+        maintainer: version_maintainer(cand),
+        origins: version_origins(cand),
+        installed_size: cand.installed_size(),
+        download_size: cand.size(),
+        name_match: None,
+        desc_match: None,
+    }
+}
+
+#[test]
+fn test_parse_query() {
+    let (filter, rest) =
+        SearchFilter::parse_query("section:devel status:installed foo:i386 size:1M.. gcc");
+
+    assert_eq!(rest, "foo:i386 gcc");
+    assert_eq!(
+        filter,
+        SearchFilter::builder()
+            .section("devel".to_string())
+            .installed(true)
+            .min_size(1 << 20)
+            .build()
+    );
+}
+
+#[test]
+fn test_parse_size() {
+    assert_eq!(parse_size("1024"), Some(1024));
+    assert_eq!(parse_size("10K"), Some(10 << 10));
+    assert_eq!(parse_size("1.5MiB"), Some(3 << 19));
+    assert_eq!(parse_size("2G"), Some(2 << 30));
+    assert_eq!(parse_size("1X"), None);
+    assert_eq!(parse_size_range("..10M"), Some((None, Some(10 << 20))));
+    assert_eq!(parse_size_range("10M"), None);
+}
+
 #[test]
-fn test() {
+fn test_regex_search() {
     use crate::test::TEST_LOCK;
     use oma_apt::new_cache;
     let _lock = TEST_LOCK.lock().unwrap();
@@ -531,31 +792,55 @@ fn test() {
         .join("Packages");
     let cache = new_cache!(&[packages.to_string_lossy().to_string()]).unwrap();
 
-    let searcher = IndiciumSearch::new(&cache, |_| {}).unwrap();
-    let res = searcher.search("windows-nt-kernel").unwrap();
-    let res2 = searcher.search("pwp").unwrap();
+    let searcher = RegexSearch::new(&cache);
+
+    // Spans are byte ranges of the match in names and summaries
+    let res = searcher.search("w[oq]").unwrap();
+    let owo = res.iter().find(|x| x.name == "owo").unwrap();
+    assert_eq!(owo.name_match, Some((1, 3)));
+    assert!(!owo.full_match);
+
+    let res = searcher.search("Telegram").unwrap();
+    assert_eq!(res.len(), 1);
+    assert_eq!(res[0].name, "qwq-desktop");
+    assert_eq!(res[0].name_match, None);
+    assert_eq!(res[0].desc_match, Some((13, 21)));
+
+    // Name matches come first, debug symbol packages are skipped
+    let res = searcher.search("o.o").unwrap();
+    assert_eq!(res[0].name, "owo");
+    assert!(res[0].full_match);
+    assert!(res.iter().all(|x| x.name != "owo-dbg"));
+}
 
-    for i in [res, res2] {
-        assert!(i.iter().any(|x| x.name == "qaq"));
-        assert!(i.iter().any(|x| x.new_version == "9999:1"));
-        assert!(i.iter().any(|x| x.full_match));
-        assert!(i.iter().filter(|x| x.name == "qaq").count() == 1)
-    }
+#[test]
+fn test_exact_search() {
+    use crate::test::TEST_LOCK;
+    use oma_apt::new_cache;
+    let _lock = TEST_LOCK.lock().unwrap();
 
-    let res = searcher.search("qwq").unwrap();
-    let res2 = searcher.search("qwqdesktop").unwrap();
+    let packages = std::path::Path::new(&std::env::var_os("CARGO_MANIFEST_DIR").unwrap())
+        .join("test_file")
+        .join("Packages");
+    let cache = new_cache!(&[packages.to_string_lossy().to_string()]).unwrap();
 
-    for i in [res, res2] {
-        assert!(i.iter().any(|x| x.name == "qwq-desktop"));
-        assert!(i.iter().any(|x| x.new_version == "9999:114514"));
-        assert!(i.iter().any(|x| x.full_match));
-        assert!(i.iter().filter(|x| x.name == "qwq-desktop").count() == 1)
-    }
+    let searcher = ExactSearch::new(&cache);
 
     let res = searcher.search("owo").unwrap();
-    let res = res.first().unwrap();
-
-    assert_eq!(res.name, "owo".to_string());
-    assert_eq!(res.new_version, "9999:2.6.1-2");
-    assert!(res.full_match);
+    assert_eq!(res.len(), 1);
+    assert_eq!(res[0].name, "owo");
+    assert!(res[0].full_match);
+
+    // Prefix search
+    let res = searcher.search("qw*").unwrap();
+    let qwq = res.iter().find(|x| x.name == "qwq-desktop").unwrap();
+    assert_eq!(qwq.name_match, Some((0, 2)));
+    assert!(!qwq.full_match);
+    assert!(searcher.search("qw").is_err());
+
+    // Virtual packages are resolved to providers
+    let res = searcher.search("windows-nt-kernel").unwrap();
+    assert_eq!(res.len(), 1);
+    assert_eq!(res[0].name, "qaq");
+    assert!(res[0].full_match);
 }
@@ -0,0 +1,730 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use faster_hex::hex_string;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use oma_apt::{
+    cache::{Cache, PackageSort},
+    config::Config,
+    DepType, Package,
+};
+use once_cell::unsync::OnceCell;
+use sha2::{Digest, Sha256};
+use tracing::debug;
+
+use crate::{
+    matches::{has_dbg, is_dbg_pkg},
+    multiarch::installed_archs,
+    search::{
+        version_maintainer, version_origins, version_section, OmaSearch, OmaSearchError,
+        OmaSearchResult, PackageStatus, SearchFilter, SearchResult,
+    },
+};
+
+/// Location of the search index, relative to sysroot
+pub const SEARCH_INDEX_PATH: &str = "var/cache/oma/search-index.gz";
+
+const MAGIC: &str = "oma-search-index";
+const VERSION: u32 = 2;
+
+const FIELD_NAME: u8 = 1;
+const FIELD_PROVIDES: u8 = 1 << 1;
+const FIELD_SUMMARY: u8 = 1 << 2;
+const FIELD_DESCRIPTION: u8 = 1 << 3;
+
+/// A package in the search index
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexEntry {
+    pub name: String,
+    pub summary: String,
+    pub provides: Vec<String>,
+    /// Number of packages depending on this package
+    pub popularity: u32,
+    pub is_base: bool,
+}
+
+/// Full-text search index of package names, provides, summaries and long descriptions
+///
+/// Built from the apt cache at refresh time and saved to [`SEARCH_INDEX_PATH`]. Long
+/// descriptions are taken from `Translation-*` files of the user's locale when available.
+/// Installed state is not part of the index, it is read from the cache at search time.
+#[derive(Debug, Default)]
+pub struct SearchIndex {
+    key: String,
+    entries: Vec<IndexEntry>,
+    /// Term to (entry, fields containing the term)
+    postings: BTreeMap<String, Vec<(u32, u8)>>,
+}
+
+impl SearchIndex {
+    /// Build the search index from apt cache
+    pub fn build(cache: &Cache, key: String, progress: impl Fn(usize)) -> Self {
+        let sort = PackageSort::default().include_virtual();
+        let mut entries: Vec<IndexEntry> = vec![];
+        let mut positions: HashMap<String, usize> = HashMap::new();
+        let mut postings: HashMap<String, HashMap<u32, u8>> = HashMap::new();
+
+        let mut add = |pkg: &Package, provide: Option<&str>| {
+            let name = pkg.fullname(true);
+
+            if is_dbg_pkg(&name) {
+                return;
+            }
+
+            let Some(cand) = pkg.candidate() else {
+                return;
+            };
+
+            let idx = match positions.get(&name) {
+                Some(idx) => *idx,
+                None => {
+                    let summary = cand
+                        .summary()
+                        .unwrap_or_else(|| "No description".to_string());
+
+                    let idx = entries.len();
+                    for t in name_terms(&name) {
+                        insert_posting(&mut postings, t, idx, FIELD_NAME);
+                    }
+
+                    for t in terms(&summary) {
+                        insert_posting(&mut postings, t, idx, FIELD_SUMMARY);
+                    }
+
+                    // Translated long description if available, see `Acquire::Languages'
+                    for t in cand.description().iter().flat_map(|x| terms(x)) {
+                        insert_posting(&mut postings, t, idx, FIELD_DESCRIPTION);
+                    }
+
+                    entries.push(IndexEntry {
+                        name: name.clone(),
+                        summary,
+                        provides: vec![],
+                        popularity: pkg
+                            .rdepends()
+                            .get(&DepType::Depends)
+                            .map(|x| x.len() as u32)
+                            .unwrap_or(0),
+                        is_base: cand.section().map(|x| x == "Bases").unwrap_or(false),
+                    });
+                    positions.insert(name, idx);
+
+                    idx
+                }
+            };
+
+            let Some(provide) = provide else {
+                return;
+            };
+
+            if !entries[idx].provides.iter().any(|x| x == provide) {
+                entries[idx].provides.push(provide.to_string());
+                for t in name_terms(provide) {
+                    insert_posting(&mut postings, t, idx, FIELD_PROVIDES);
+                }
+            }
+        };
+
+        for (i, pkg) in cache.packages(&sort).enumerate() {
+            progress(i);
+
+            if pkg.candidate().is_some() {
+                add(&pkg, None);
+                for p in pkg.provides() {
+                    let provide = p.name().to_string();
+                    if provide != pkg.name() {
+                        add(&pkg, Some(&provide));
+                    }
+                }
+            } else {
+                for p in pkg.provides() {
+                    let Some(target) = unsafe { p.target_pkg() }.make_safe() else {
+                        continue;
+                    };
+
+                    add(&Package::new(cache, target), Some(p.name()));
+                }
+            }
+        }
+
+        let postings = postings
+            .into_iter()
+            .map(|(term, list)| {
+                let mut list = list.into_iter().collect::<Vec<_>>();
+                list.sort_unstable();
+                (term, list)
+            })
+            .collect();
+
+        Self {
+            key,
+            entries,
+            postings,
+        }
+    }
+
+    /// Key of the repository metadata this index is built from, see [`index_key`]
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn entries(&self) -> &[IndexEntry] {
+        &self.entries
+    }
+
+    /// Load search index from `path`
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let reader = BufReader::new(GzDecoder::new(fs::File::open(path)?));
+        let mut lines = reader.lines();
+
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, "Invalid search index");
+
+        let header = lines.next().ok_or_else(invalid)??;
+        let mut header = header.split('\t');
+
+        if header.next() != Some(MAGIC)
+            || header.next().and_then(|x| x.parse::<u32>().ok()) != Some(VERSION)
+        {
+            return Err(invalid());
+        }
+
+        let key = header.next().ok_or_else(invalid)?.to_string();
+
+        let mut entries = vec![];
+        let mut postings = BTreeMap::new();
+
+        for line in lines {
+            let line = line?;
+            let mut fields = line.split('\t');
+
+            match fields.next() {
+                Some("E") => {
+                    let (
+                        Some(name),
+                        Some(is_base),
+                        Some(popularity),
+                        Some(provides),
+                        Some(summary),
+                    ) = (
+                        fields.next(),
+                        fields.next(),
+                        fields.next(),
+                        fields.next(),
+                        fields.next(),
+                    )
+                    else {
+                        return Err(invalid());
+                    };
+
+                    entries.push(IndexEntry {
+                        name: name.to_string(),
+                        summary: unescape(summary),
+                        provides: provides
+                            .split(',')
+                            .filter(|x| !x.is_empty())
+                            .map(|x| x.to_string())
+                            .collect(),
+                        popularity: popularity.parse().map_err(|_| invalid())?,
+                        is_base: is_base == "1",
+                    });
+                }
+                Some("T") => {
+                    let (Some(term), Some(list)) = (fields.next(), fields.next()) else {
+                        return Err(invalid());
+                    };
+
+                    let list = list
+                        .split(',')
+                        .map(|x| {
+                            let (idx, fields) = x.split_once(':')?;
+                            Some((idx.parse().ok()?, fields.parse().ok()?))
+                        })
+                        .collect::<Option<Vec<_>>>()
+                        .ok_or_else(invalid)?;
+
+                    postings.insert(unescape(term), list);
+                }
+                _ => return Err(invalid()),
+            }
+        }
+
+        Ok(Self {
+            key,
+            entries,
+            postings,
+        })
+    }
+
+    /// Save search index to `path`
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        // Write to a temporary file first, so readers never see a partial index
+        let tmp = path.with_extension("tmp");
+        let mut w = BufWriter::new(GzEncoder::new(fs::File::create(&tmp)?, Compression::fast()));
+
+        writeln!(w, "{MAGIC}\t{VERSION}\t{}", self.key)?;
+
+        for e in &self.entries {
+            writeln!(
+                w,
+                "E\t{}\t{}\t{}\t{}\t{}",
+                e.name,
+                if e.is_base { 1 } else { 0 },
+                e.popularity,
+                e.provides.join(","),
+                escape(&e.summary)
+            )?;
+        }
+
+        for (term, list) in &self.postings {
+            let list = list
+                .iter()
+                .map(|(idx, fields)| format!("{idx}:{fields}"))
+                .collect::<Vec<_>>()
+                .join(",");
+
+            writeln!(w, "T\t{}\t{list}", escape(term))?;
+        }
+
+        w.into_inner()
+            .map_err(|e| e.into_error())?
+            .finish()?
+            .sync_all()?;
+
+        fs::rename(tmp, path)
+    }
+
+    /// Find entries matching all words of `query`, with their text relevance
+    fn matches(&self, query: &str) -> Vec<(usize, u32)> {
+        let words = terms(query).collect::<Vec<_>>();
+        let mut scores: HashMap<u32, (usize, u32)> = HashMap::new();
+
+        for word in &words {
+            let mut best: HashMap<u32, u32> = HashMap::new();
+
+            // Prefix match, so that `fire` finds `firefox`
+            let candidates = self
+                .postings
+                .range::<str, _>((
+                    std::ops::Bound::Included(word.as_str()),
+                    std::ops::Bound::Unbounded,
+                ))
+                .take_while(|(t, _)| t.starts_with(word.as_str()));
+
+            // Words not split by whitespace (like Chinese) can only be found by substring
+            let candidates: Box<dyn Iterator<Item = (&String, &Vec<(u32, u8)>)>> =
+                if word.is_ascii() {
+                    Box::new(candidates)
+                } else {
+                    Box::new(
+                        self.postings
+                            .iter()
+                            .filter(|(t, _)| t.contains(word.as_str())),
+                    )
+                };
+
+            for (term, list) in candidates {
+                let exact = term == word;
+                for (idx, fields) in list {
+                    let score = field_score(*fields, exact);
+                    let e = best.entry(*idx).or_default();
+                    *e = (*e).max(score);
+                }
+            }
+
+            for (idx, score) in best {
+                let e = scores.entry(idx).or_default();
+                e.0 += 1;
+                e.1 += score;
+            }
+        }
+
+        scores
+            .into_iter()
+            .filter(|(_, (n, _))| *n == words.len())
+            .map(|(idx, (_, score))| (idx as usize, score))
+            .collect()
+    }
+}
+
+/// Key of repository metadata in `lists_dir`, changes whenever any index is updated
+///
+/// (In)Release files carry checksums of all index files of the repository, so hashing them
+/// is enough. Flat repositories without Release are keyed by file names and sizes.
+/// `Acquire::Languages` is part of the key as well, since translated descriptions are indexed.
+pub fn index_key(lists_dir: impl AsRef<Path>) -> io::Result<String> {
+    let mut files = fs::read_dir(lists_dir)?
+        .filter_map(|x| x.ok())
+        .map(|x| x.path())
+        .filter(|x| x.is_file())
+        .collect::<Vec<_>>();
+
+    files.sort();
+
+    let mut hasher = Sha256::new();
+    hasher.update(VERSION.to_le_bytes());
+
+    for f in files {
+        let Some(name) = f.file_name().and_then(|x| x.to_str()) else {
+            continue;
+        };
+
+        if name == "lock" || name.ends_with("Release.gpg") {
+            continue;
+        }
+
+        // Separated by NUL, so that names and contents can not run into each other
+        hasher.update(name);
+        hasher.update([0]);
+
+        if name.ends_with("Release") {
+            hasher.update(fs::read(&f)?);
+        } else {
+            hasher.update(f.metadata()?.len().to_le_bytes());
+        }
+
+        hasher.update([0]);
+    }
+
+    for lang in Config::new().find_vector("Acquire::Languages") {
+        hasher.update(lang);
+        hasher.update([0]);
+    }
+
+    Ok(hex_string(&hasher.finalize()))
+}
+
+/// Build the search index of `sysroot` and save it, used after refreshing repository metadata
+pub fn update_search_index(cache: &Cache, sysroot: impl AsRef<Path>) -> io::Result<()> {
+    let sysroot = sysroot.as_ref();
+    let key = index_key(sysroot.join("var/lib/apt/lists"))?;
+    let path = sysroot.join(SEARCH_INDEX_PATH);
+
+    if SearchIndex::load(&path).is_ok_and(|x| x.key == key) {
+        debug!("Search index is up to date");
+        return Ok(());
+    }
+
+    SearchIndex::build(cache, key, |_| {}).save(path)
+}
+
+/// Search using the persistent search index
+///
+/// The index is loaded on first search. If it is missing or outdated, it is rebuilt from the
+/// cache and saved when permitted.
+pub struct IndexSearch<'a> {
+    cache: &'a Cache,
+    sysroot: PathBuf,
+    progress: Box<dyn Fn(usize) + 'a>,
+    index: OnceCell<SearchIndex>,
+}
+
+impl<'a> IndexSearch<'a> {
+    pub fn new(cache: &'a Cache, sysroot: impl AsRef<Path>, progress: impl Fn(usize) + 'a) -> Self {
+        Self {
+            cache,
+            sysroot: sysroot.as_ref().to_path_buf(),
+            progress: Box::new(progress),
+            index: OnceCell::new(),
+        }
+    }
+
+    /// Load (or build) the search index now instead of on first search
+    pub fn load(&self) -> &SearchIndex {
+        self.index.get_or_init(|| {
+            let key = index_key(self.sysroot.join("var/lib/apt/lists"))
+                .inspect_err(|e| debug!("Failed to get search index key: {e}"))
+                .ok();
+            let path = self.sysroot.join(SEARCH_INDEX_PATH);
+
+            match SearchIndex::load(&path) {
+                Ok(index) if key.as_ref() == Some(&index.key) => return index,
+                Ok(_) => debug!("Search index is outdated"),
+                Err(e) => debug!("Failed to load search index: {e}"),
+            }
+
+            let Some(key) = key else {
+                return SearchIndex::build(self.cache, String::new(), &self.progress);
+            };
+
+            let index = SearchIndex::build(self.cache, key, &self.progress);
+
+            if let Err(e) = index.save(&path) {
+                debug!("Failed to save search index: {e}");
+            }
+
+            index
+        })
+    }
+}
+
+impl OmaSearch for IndexSearch<'_> {
+    fn search_with_filter(
+        &self,
+        query: &str,
+        filter: &SearchFilter,
+    ) -> OmaSearchResult<Vec<SearchResult>> {
+        let index = self.load();
+        let query = query.to_lowercase();
+        let mut res = vec![];
+
+        for (idx, text_score) in index.matches(&query) {
+            let entry = &index.entries[idx];
+
+            // Index may be slightly outdated, skip packages no longer available
+            let Some(pkg) = self.cache.get(&entry.name) else {
+                continue;
+            };
+
+            let Some(cand) = pkg.candidate() else {
+                continue;
+            };
+
+            if !filter.is_match(&pkg, &cand) {
+                continue;
+            }
+
+            let status = if pkg.is_upgradable() {
+                PackageStatus::Upgrade
+            } else if pkg.is_installed() {
+                PackageStatus::Installed
+            } else {
+                PackageStatus::Avail
+            };
+
+            let full_match = entry.name == query || entry.provides.iter().any(|x| *x == query);
+
+            let score = relevance(text_score, full_match, status, entry.popularity);
+
+            res.push((
+                score,
+                SearchResult {
+                    name: entry.name.clone(),
+                    desc: entry.summary.clone(),
+                    old_version: if status == PackageStatus::Upgrade {
+                        pkg.installed().map(|x| x.version().to_string())
+                    } else {
+                        None
+                    },
+                    new_version: cand.version().to_string(),
+                    full_match,
+                    dbg_package: has_dbg(self.cache, &pkg, &cand),
+                    status,
+                    is_base: entry.is_base,
+                    installed_archs: if status == PackageStatus::Avail {
+                        vec![]
+                    } else {
+                        installed_archs(self.cache, pkg.name())
+                    },
+                    section: version_section(&cand),
+                    maintainer: version_maintainer(&cand),
+                    origins: version_origins(&cand),
+                    installed_size: cand.installed_size(),
+                    download_size: cand.size(),
+                    name_match: None,
+                    desc_match: None,
+                },
+            ));
+        }
+
+        if res.is_empty() {
+            return Err(OmaSearchError::NoResult(query));
+        }
+
+        res.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.name.cmp(&b.1.name)));
+
+        Ok(res.into_iter().map(|x| x.1).collect())
+    }
+}
+
+/// Overall relevance: text relevance, then installed state and popularity
+fn relevance(text_score: u32, full_match: bool, status: PackageStatus, popularity: u32) -> u32 {
+    let full_match = if full_match { 10000 } else { 0 };
+
+    let status = match status {
+        PackageStatus::Upgrade => 30,
+        PackageStatus::Installed => 20,
+        PackageStatus::Avail => 0,
+    };
+
+    // Logarithmic, so that libraries everything depends on do not always win
+    let popularity = (popularity + 1).ilog2() * 5;
+
+    full_match + text_score + status + popularity
+}
+
+fn field_score(fields: u8, exact: bool) -> u32 {
+    let score = if fields & FIELD_NAME != 0 {
+        100
+    } else if fields & FIELD_PROVIDES != 0 {
+        60
+    } else if fields & FIELD_SUMMARY != 0 {
+        20
+    } else if fields & FIELD_DESCRIPTION != 0 {
+        5
+    } else {
+        0
+    };
+
+    if exact {
+        score * 2
+    } else {
+        score
+    }
+}
+
+fn insert_posting(
+    postings: &mut HashMap<String, HashMap<u32, u8>>,
+    term: String,
+    idx: usize,
+    field: u8,
+) {
+    *postings
+        .entry(term)
+        .or_default()
+        .entry(idx as u32)
+        .or_default() |= field;
+}
+
+/// Lowercase words of a text
+fn terms(s: &str) -> impl Iterator<Item = String> + '_ {
+    s.split(|c: char| !c.is_alphanumeric())
+        .filter(|x| !x.is_empty())
+        .map(|x| x.to_lowercase())
+}
+
+/// Terms of a package name: the name itself, its parts and the parts joined together
+///
+/// So `qwq-desktop` is found by `qwq`, `desktop`, `qwq-desktop` and `qwqdesktop`.
+fn name_terms(name: &str) -> Vec<String> {
+    let name = name.to_lowercase();
+    let name = name.split(':').next().unwrap_or(&name);
+    let mut res = terms(name).collect::<Vec<_>>();
+    let joined = res.concat();
+
+    res.push(name.to_string());
+    res.push(joined);
+    res.sort();
+    res.dedup();
+
+    res
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+}
+
+fn unescape(s: &str) -> String {
+    let mut res = String::with_capacity(s.len());
+    let mut chars = s.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            res.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('t') => res.push('\t'),
+            Some('n') => res.push('\n'),
+            Some(c) => res.push(c),
+            None => res.push('\\'),
+        }
+    }
+
+    res
+}
+
+#[test]
+fn test_escape() {
+    let s = "foo\tbar\nbaz\\n";
+    assert_eq!(unescape(&escape(s)), s);
+}
+
+#[test]
+fn test_name_terms() {
+    assert_eq!(
+        name_terms("qwq-desktop"),
+        vec!["desktop", "qwq", "qwq-desktop", "qwqdesktop"]
+    );
+}
+
+#[test]
+fn test_search_index() {
+    use crate::test::TEST_LOCK;
+    use oma_apt::new_cache;
+    let _lock = TEST_LOCK.lock().unwrap();
+
+    let packages = Path::new(&std::env::var_os("CARGO_MANIFEST_DIR").unwrap())
+        .join("test_file")
+        .join("Packages");
+    let cache = new_cache!(&[packages.to_string_lossy().to_string()]).unwrap();
+
+    let index = SearchIndex::build(&cache, "1".to_string(), |_| {});
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path().join("search-index.gz");
+    index.save(&path).unwrap();
+    let loaded = SearchIndex::load(&path).unwrap();
+
+    assert_eq!(loaded.key(), "1");
+    assert_eq!(loaded.entries(), index.entries());
+    assert_eq!(loaded.postings, index.postings);
+    assert!(loaded.entries().iter().all(|x| x.name != "owo-dbg"));
+
+    let res = loaded.matches("qwqdesktop");
+    assert!(res
+        .iter()
+        .any(|(idx, _)| loaded.entries()[*idx].name == "qwq-desktop"));
+}
+
+#[test]
+fn test_index_search() {
+    use crate::test::TEST_LOCK;
+    use oma_apt::new_cache;
+    let _lock = TEST_LOCK.lock().unwrap();
+
+    let packages = Path::new(&std::env::var_os("CARGO_MANIFEST_DIR").unwrap())
+        .join("test_file")
+        .join("Packages");
+    let cache = new_cache!(&[packages.to_string_lossy().to_string()]).unwrap();
+
+    // Without repository metadata in the sysroot, the index is built in memory
+    let sysroot = tempfile::tempdir().unwrap();
+    let searcher = IndexSearch::new(&cache, sysroot.path(), |_| {});
+
+    for query in ["windows-nt-kernel", "pwp"] {
+        let res = searcher.search(query).unwrap();
+        let qaq = res.iter().filter(|x| x.name == "qaq").collect::<Vec<_>>();
+        assert_eq!(qaq.len(), 1);
+        assert_eq!(qaq[0].new_version, "9999:1");
+        assert!(qaq[0].full_match);
+    }
+
+    for query in ["qwq", "qwqdesktop"] {
+        let res = searcher.search(query).unwrap();
+        let qwq = res
+            .iter()
+            .filter(|x| x.name == "qwq-desktop")
+            .collect::<Vec<_>>();
+        assert_eq!(qwq.len(), 1);
+        assert_eq!(qwq[0].new_version, "9999:114514");
+        assert!(qwq[0].full_match);
+    }
+
+    let res = searcher.search("owo").unwrap();
+    assert_eq!(res[0].name, "owo");
+    assert_eq!(res[0].new_version, "9999:2.6.1-2");
+    assert!(res[0].full_match);
+    assert!(res.iter().all(|x| x.name != "owo-dbg"));
+}
@@ -0,0 +1,1084 @@
+//! An alternative SAT-based dependency solver
+//!
+//! Every package version in the dependency closure of installed and marked packages is a
+//! boolean variable. Dependencies, conflicts and user requests become clauses, which are solved
+//! by DPLL with unit propagation. Decisions follow oma's preferences: keep what the user and
+//! apt marked, keep installed versions, then satisfy dependencies with the best version. When
+//! no solution exists, the unsatisfiable core is reported instead.
+//!
+//! Recommends and Suggests are not considered.
+
+use std::{fmt, str::FromStr};
+
+use ahash::{HashMap, HashSet};
+use oma_apt::{
+    cache::{Cache, PackageSort},
+    BaseDep, DepType, Package, Version,
+};
+use serde::Serialize;
+
+/// Give up after this many conflicts, the search is exponential in the worst case
+const MAX_CONFLICTS: usize = 100_000;
+
+/// Only minimize unsatisfiable cores up to this size
+const MAX_CORE_MINIMIZE: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Solver {
+    /// libapt's problem resolver
+    #[default]
+    Apt,
+    Sat,
+    /// Resolve with both solvers and keep the result of libapt
+    Compare,
+}
+
+impl FromStr for Solver {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "apt" => Ok(Self::Apt),
+            "sat" => Ok(Self::Sat),
+            "compare" => Ok(Self::Compare),
+            _ => Err(s.to_string()),
+        }
+    }
+}
+
+/// Why a clause exists, used to explain unsatisfiable cores
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Reason {
+    Install {
+        pkg: String,
+    },
+    Remove {
+        pkg: String,
+    },
+    /// Essential or held packages stay installed
+    Keep {
+        pkg: String,
+    },
+    Depends {
+        pkg: String,
+        version: String,
+        dep: String,
+    },
+    Conflicts {
+        pkg: String,
+        version: String,
+        dep: String,
+    },
+    OneVersion {
+        pkg: String,
+    },
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Reason::Install { pkg } => write!(f, "{pkg} is requested to be installed"),
+            Reason::Remove { pkg } => write!(f, "{pkg} is requested to be removed"),
+            Reason::Keep { pkg } => write!(f, "{pkg} is essential or held, it must stay installed"),
+            Reason::Depends { pkg, version, dep } => {
+                write!(f, "{pkg} ({version}) depends on {dep}")
+            }
+            Reason::Conflicts { pkg, version, dep } => {
+                write!(f, "{pkg} ({version}) conflicts with {dep}")
+            }
+            Reason::OneVersion { pkg } => write!(f, "only one version of {pkg} can be installed"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SolverError {
+    /// The requests can not be satisfied, with the clauses causing it
+    Unsatisfiable(Vec<Reason>),
+    GaveUp,
+}
+
+/// The version (or none) chosen for a package
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Choice {
+    /// Full name with architecture
+    pub pkg: String,
+    pub installed: Option<String>,
+    pub chosen: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Lit {
+    var: usize,
+    positive: bool,
+}
+
+impl Lit {
+    fn pos(var: usize) -> Self {
+        Self {
+            var,
+            positive: true,
+        }
+    }
+
+    fn neg(var: usize) -> Self {
+        Self {
+            var,
+            positive: false,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Clause {
+    lits: Vec<Lit>,
+    reason: Reason,
+}
+
+#[derive(Debug)]
+struct PkgNode {
+    name: String,
+    versions: Vec<usize>,
+    installed: Option<usize>,
+}
+
+#[derive(Debug)]
+struct VerNode {
+    pkg: usize,
+    version: String,
+    /// Lower ranks are preferred when satisfying dependencies
+    rank: u8,
+}
+
+/// Packages, versions and the clauses between them
+#[derive(Debug, Default)]
+pub struct Universe {
+    pkgs: Vec<PkgNode>,
+    vers: Vec<VerNode>,
+    pkg_index: HashMap<String, usize>,
+    deps: Vec<(usize, Vec<usize>, Reason)>,
+    conflicts: Vec<(usize, usize, Reason)>,
+    requests: Vec<Clause>,
+    /// Versions to keep or install if possible, in order
+    preferred: Vec<usize>,
+}
+
+impl Universe {
+    /// Load the dependency closure of installed and marked packages from `cache`
+    ///
+    /// Marks of `requested` packages are hard requirements, other marks (e.g. made by
+    /// `upgrade`) are preferences. Held packages stay at their installed version.
+    pub fn from_cache(
+        cache: &Cache,
+        requested: &HashSet<u64>,
+        is_held: impl Fn(&Package) -> bool,
+    ) -> Self {
+        let mut universe = Self::default();
+        let mut ids = HashMap::with_hasher(ahash::RandomState::new());
+        let mut queue = vec![];
+
+        let mut seeds = cache
+            .packages(&PackageSort::default().installed())
+            .collect::<Vec<_>>();
+        seeds.extend(cache.get_changes(false).filter(|x| !x.is_installed()));
+
+        for pkg in &seeds {
+            for ver in [pkg.installed(), pkg.candidate(), pkg.install_version()]
+                .into_iter()
+                .flatten()
+            {
+                universe.add_cache_version(&mut ids, &mut queue, ver);
+            }
+        }
+
+        let mut loaded = vec![];
+
+        // Dependency closure
+        while let Some(ver) = queue.pop() {
+            let id = ids[&version_key(&ver)];
+
+            for deps in ver.depends_map().iter().filter_map(|(typ, deps)| {
+                matches!(typ, DepType::Depends | DepType::PreDepends).then_some(deps)
+            }) {
+                for dep in deps {
+                    let mut targets = vec![];
+
+                    for base_dep in dep.iter() {
+                        for target in usable_targets(base_dep) {
+                            targets.push(universe.add_cache_version(&mut ids, &mut queue, target));
+                        }
+                    }
+
+                    targets.sort_unstable();
+                    targets.dedup();
+
+                    universe.deps.push((
+                        id,
+                        targets,
+                        Reason::Depends {
+                            pkg: ver.parent().fullname(true),
+                            version: ver.version().to_string(),
+                            dep: dep
+                                .iter()
+                                .map(base_dep_string)
+                                .collect::<Vec<_>>()
+                                .join(" | "),
+                        },
+                    ));
+                }
+            }
+
+            loaded.push((id, ver));
+        }
+
+        // Conflicts only matter between versions already in the universe
+        for (id, ver) in &loaded {
+            let id = *id;
+
+            for (typ, deps) in ver.depends_map() {
+                if !matches!(typ, DepType::Conflicts | DepType::DpkgBreaks) {
+                    continue;
+                }
+
+                for base_dep in deps.iter().flat_map(|x| x.iter()) {
+                    for target in base_dep.all_targets() {
+                        // Packages never conflict with themselves, e.g. Conflicts on a Provides
+                        if target.parent().index() == ver.parent().index() {
+                            continue;
+                        }
+
+                        if let Some(&other) = ids.get(&version_key(&target)) {
+                            universe.conflicts.push((
+                                id,
+                                other,
+                                Reason::Conflicts {
+                                    pkg: ver.parent().fullname(true),
+                                    version: ver.version().to_string(),
+                                    dep: base_dep_string(base_dep),
+                                },
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+
+        // Requests and preferences
+        let mut keep = vec![];
+
+        for pkg in &seeds {
+            let Some(&pkg_id) = universe.pkg_index.get(&pkg.fullname(false)) else {
+                continue;
+            };
+
+            let name = pkg.fullname(true);
+            let is_requested = requested.contains(&pkg.index());
+
+            if pkg.marked_delete() {
+                if is_requested {
+                    universe.request_remove(pkg_id, Reason::Remove { pkg: name });
+                }
+                continue;
+            }
+
+            let marked = (pkg.marked_install()
+                || pkg.marked_upgrade()
+                || pkg.marked_downgrade()
+                || pkg.marked_reinstall())
+            .then(|| pkg.install_version())
+            .flatten()
+            .and_then(|x| ids.get(&version_key(&x)).copied());
+
+            match marked {
+                Some(id) if is_requested => {
+                    universe.request(vec![id], Reason::Install { pkg: name });
+                }
+                Some(id) => universe.preferred.push(id),
+                None => {
+                    let Some(installed) = universe.pkgs[pkg_id].installed else {
+                        continue;
+                    };
+
+                    if is_held(pkg) {
+                        universe.request(vec![installed], Reason::Keep { pkg: name });
+                    } else if pkg.is_essential() {
+                        let versions = universe.pkgs[pkg_id].versions.clone();
+                        universe.request(versions, Reason::Keep { pkg: name });
+                    }
+
+                    keep.push(installed);
+                }
+            }
+        }
+
+        // Marked versions go first, then installed versions
+        universe.preferred.extend(keep);
+
+        for &id in &universe.preferred {
+            universe.vers[id].rank = 0;
+        }
+
+        universe
+    }
+
+    fn add_cache_version<'a>(
+        &mut self,
+        ids: &mut HashMap<(String, String), usize>,
+        queue: &mut Vec<Version<'a>>,
+        ver: Version<'a>,
+    ) -> usize {
+        let key = version_key(&ver);
+
+        if let Some(&id) = ids.get(&key) {
+            return id;
+        }
+
+        let pkg = ver.parent();
+        let pkg_id = self.add_pkg(&key.0);
+        let is_candidate = pkg
+            .candidate()
+            .is_some_and(|x| x.version() == ver.version());
+
+        let id = self.add_version(pkg_id, &key.1, if is_candidate { 1 } else { 2 });
+
+        if ver.is_installed() {
+            self.pkgs[pkg_id].installed = Some(id);
+        }
+
+        ids.insert(key, id);
+        queue.push(ver);
+
+        id
+    }
+
+    fn add_pkg(&mut self, name: &str) -> usize {
+        if let Some(&id) = self.pkg_index.get(name) {
+            return id;
+        }
+
+        self.pkgs.push(PkgNode {
+            name: name.to_string(),
+            versions: vec![],
+            installed: None,
+        });
+
+        let id = self.pkgs.len() - 1;
+        self.pkg_index.insert(name.to_string(), id);
+
+        id
+    }
+
+    fn add_version(&mut self, pkg: usize, version: &str, rank: u8) -> usize {
+        self.vers.push(VerNode {
+            pkg,
+            version: version.to_string(),
+            rank,
+        });
+
+        let id = self.vers.len() - 1;
+        self.pkgs[pkg].versions.push(id);
+
+        id
+    }
+
+    /// At least one of `versions` must be installed
+    fn request(&mut self, versions: Vec<usize>, reason: Reason) {
+        self.requests.push(Clause {
+            lits: versions.into_iter().map(Lit::pos).collect(),
+            reason,
+        });
+    }
+
+    fn request_remove(&mut self, pkg: usize, reason: Reason) {
+        for &ver in &self.pkgs[pkg].versions {
+            self.requests.push(Clause {
+                lits: vec![Lit::neg(ver)],
+                reason: reason.clone(),
+            });
+        }
+    }
+
+    fn clauses(&self) -> Vec<Clause> {
+        let mut clauses = vec![];
+
+        for clause in &self.requests {
+            clauses.push(Clause {
+                lits: clause.lits.clone(),
+                reason: clause.reason.clone(),
+            });
+        }
+
+        for (ver, targets, reason) in &self.deps {
+            let mut targets = targets.clone();
+            targets.sort_by_key(|x| (self.vers[*x].rank, *x));
+
+            let mut lits = vec![Lit::neg(*ver)];
+            lits.extend(targets.into_iter().map(Lit::pos));
+
+            clauses.push(Clause {
+                lits,
+                reason: reason.clone(),
+            });
+        }
+
+        for (ver, other, reason) in &self.conflicts {
+            clauses.push(Clause {
+                lits: vec![Lit::neg(*ver), Lit::neg(*other)],
+                reason: reason.clone(),
+            });
+        }
+
+        for pkg in &self.pkgs {
+            for (i, a) in pkg.versions.iter().enumerate() {
+                for b in &pkg.versions[i + 1..] {
+                    clauses.push(Clause {
+                        lits: vec![Lit::neg(*a), Lit::neg(*b)],
+                        reason: Reason::OneVersion {
+                            pkg: pkg.name.clone(),
+                        },
+                    });
+                }
+            }
+        }
+
+        clauses
+    }
+
+    /// Solve, returning the packages whose state changes
+    pub fn solve(&self) -> Result<Vec<Choice>, SolverError> {
+        let clauses = self.clauses();
+        let all = vec![true; clauses.len()];
+
+        let model = match Search::new(self, &clauses, &all, true).run() {
+            Outcome::Sat(model) => model,
+            Outcome::GaveUp => return Err(SolverError::GaveUp),
+            Outcome::Unsat(involved) => {
+                let core = minimize_core(self, &clauses, involved);
+                return Err(SolverError::Unsatisfiable(
+                    core.into_iter()
+                        .map(|x| clauses[x].reason.clone())
+                        .collect(),
+                ));
+            }
+        };
+
+        let mut changes = vec![];
+
+        for pkg in &self.pkgs {
+            let chosen = pkg.versions.iter().find(|x| model[**x]).copied();
+
+            if chosen == pkg.installed {
+                continue;
+            }
+
+            changes.push(Choice {
+                pkg: pkg.name.clone(),
+                installed: pkg.installed.map(|x| self.vers[x].version.clone()),
+                chosen: chosen.map(|x| self.vers[x].version.clone()),
+            });
+        }
+
+        Ok(changes)
+    }
+}
+
+/// Shrink the clauses involved in a refutation to a minimal unsatisfiable subset
+fn minimize_core(universe: &Universe, clauses: &[Clause], involved: Vec<usize>) -> Vec<usize> {
+    if involved.len() > MAX_CORE_MINIMIZE {
+        return involved;
+    }
+
+    let mut enabled = vec![false; clauses.len()];
+    for &i in &involved {
+        enabled[i] = true;
+    }
+
+    let mut core = vec![];
+
+    for &i in &involved {
+        enabled[i] = false;
+
+        match Search::new(universe, clauses, &enabled, false).run() {
+            // Still unsatisfiable without it
+            Outcome::Unsat(_) => {}
+            _ => {
+                enabled[i] = true;
+                core.push(i);
+            }
+        }
+    }
+
+    core
+}
+
+enum Outcome {
+    Sat(Vec<bool>),
+    /// Clauses used as reasons or conflicts, which form an unsatisfiable subset
+    Unsat(Vec<usize>),
+    GaveUp,
+}
+
+struct Decision {
+    trail_len: usize,
+    var: usize,
+    value: bool,
+    flipped: bool,
+}
+
+/// DPLL with chronological backtracking
+struct Search<'a> {
+    universe: &'a Universe,
+    clauses: &'a [Clause],
+    enabled: &'a [bool],
+    /// Follow preferences, otherwise only satisfiability matters
+    prefer: bool,
+    occurs: Vec<Vec<usize>>,
+    values: Vec<Option<bool>>,
+    trail: Vec<usize>,
+    head: usize,
+    decisions: Vec<Decision>,
+    involved: HashSet<usize>,
+}
+
+impl<'a> Search<'a> {
+    fn new(
+        universe: &'a Universe,
+        clauses: &'a [Clause],
+        enabled: &'a [bool],
+        prefer: bool,
+    ) -> Self {
+        let mut occurs = vec![vec![]; universe.vers.len()];
+
+        for (i, clause) in clauses.iter().enumerate() {
+            if enabled[i] {
+                for lit in &clause.lits {
+                    occurs[lit.var].push(i);
+                }
+            }
+        }
+
+        Self {
+            universe,
+            clauses,
+            enabled,
+            prefer,
+            occurs,
+            values: vec![None; universe.vers.len()],
+            trail: vec![],
+            head: 0,
+            decisions: vec![],
+            involved: HashSet::with_hasher(ahash::RandomState::new()),
+        }
+    }
+
+    fn run(mut self) -> Outcome {
+        let mut conflicts = 0;
+
+        // Unit clauses, and clauses without any literal (a requested package without versions)
+        for (i, clause) in self.clauses.iter().enumerate() {
+            if !self.enabled[i] {
+                continue;
+            }
+
+            match clause.lits.as_slice() {
+                [] => return Outcome::Unsat(vec![i]),
+                [lit] => match self.values[lit.var] {
+                    None => {
+                        self.involved.insert(i);
+                        self.assign(lit.var, lit.positive);
+                    }
+                    Some(v) if v != lit.positive => {
+                        self.involved.insert(i);
+                        // The opposite unit clause is involved as well
+                        if let Some(j) = self.unit_clause_of(lit.var, v) {
+                            self.involved.insert(j);
+                        }
+                        return self.unsat();
+                    }
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+
+        loop {
+            if let Some(conflict) = self.propagate() {
+                self.involved.insert(conflict);
+                conflicts += 1;
+
+                if conflicts > MAX_CONFLICTS {
+                    return Outcome::GaveUp;
+                }
+
+                if !self.backtrack() {
+                    return self.unsat();
+                }
+
+                continue;
+            }
+
+            match self.decide() {
+                Some((var, value)) => {
+                    self.decisions.push(Decision {
+                        trail_len: self.trail.len(),
+                        var,
+                        value,
+                        flipped: false,
+                    });
+                    self.assign(var, value);
+                }
+                None => {
+                    return Outcome::Sat(self.values.iter().map(|x| x.unwrap_or(false)).collect())
+                }
+            }
+        }
+    }
+
+    fn unsat(self) -> Outcome {
+        let mut involved = self.involved.into_iter().collect::<Vec<_>>();
+        involved.sort_unstable();
+
+        Outcome::Unsat(involved)
+    }
+
+    fn unit_clause_of(&self, var: usize, value: bool) -> Option<usize> {
+        self.occurs[var]
+            .iter()
+            .copied()
+            .find(|&i| matches!(self.clauses[i].lits.as_slice(), [lit] if lit.positive == value))
+    }
+
+    fn assign(&mut self, var: usize, value: bool) {
+        self.values[var] = Some(value);
+        self.trail.push(var);
+    }
+
+    fn lit_value(&self, lit: Lit) -> Option<bool> {
+        self.values[lit.var].map(|x| x == lit.positive)
+    }
+
+    /// Propagate unit clauses, returning the conflicting clause if any
+    fn propagate(&mut self) -> Option<usize> {
+        while self.head < self.trail.len() {
+            let var = self.trail[self.head];
+            self.head += 1;
+
+            for i in 0..self.occurs[var].len() {
+                let clause_id = self.occurs[var][i];
+                let clause = &self.clauses[clause_id];
+
+                let mut unassigned = None;
+                let mut unassigned_count = 0;
+                let mut satisfied = false;
+
+                for &lit in &clause.lits {
+                    match self.lit_value(lit) {
+                        Some(true) => {
+                            satisfied = true;
+                            break;
+                        }
+                        Some(false) => {}
+                        None => {
+                            unassigned = Some(lit);
+                            unassigned_count += 1;
+                        }
+                    }
+                }
+
+                if satisfied {
+                    continue;
+                }
+
+                match (unassigned_count, unassigned) {
+                    (0, _) => return Some(clause_id),
+                    (1, Some(lit)) => {
+                        self.involved.insert(clause_id);
+                        self.assign(lit.var, lit.positive);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Undo to the last decision not tried both ways and flip it
+    fn backtrack(&mut self) -> bool {
+        while let Some(decision) = self.decisions.pop() {
+            for var in self.trail.drain(decision.trail_len..) {
+                self.values[var] = None;
+            }
+
+            self.head = self.trail.len();
+
+            if !decision.flipped {
+                self.decisions.push(Decision {
+                    flipped: true,
+                    value: !decision.value,
+                    ..decision
+                });
+                self.assign(decision.var, !decision.value);
+
+                return true;
+            }
+        }
+
+        false
+    }
+
+    fn decide(&self) -> Option<(usize, bool)> {
+        if self.prefer {
+            if let Some(&var) = self
+                .universe
+                .preferred
+                .iter()
+                .find(|x| self.values[**x].is_none())
+            {
+                return Some((var, true));
+            }
+        }
+
+        // Satisfy the first clause which is in effect (all negative literals are false) with
+        // its best literal, clauses and their literals are in order of preference
+        for (i, clause) in self.clauses.iter().enumerate() {
+            if !self.enabled[i]
+                || clause.lits.iter().any(|x| {
+                    let value = self.lit_value(*x);
+                    value == Some(true) || (!x.positive && value.is_none())
+                })
+            {
+                continue;
+            }
+
+            if let Some(lit) = clause
+                .lits
+                .iter()
+                .find(|x| x.positive && self.values[x.var].is_none())
+            {
+                return Some((lit.var, true));
+            }
+        }
+
+        // Every clause not satisfied yet has an unassigned negative literal, so the remaining
+        // variables can be false
+        None
+    }
+}
+
+fn version_key(ver: &Version) -> (String, String) {
+    (ver.parent().fullname(false), ver.version().to_string())
+}
+
+/// Versions satisfying the dependency which can be installed
+fn usable_targets<'a>(dep: &BaseDep<'a>) -> Vec<Version<'a>> {
+    dep.all_targets()
+        .into_iter()
+        .filter(|x| x.is_downloadable() || x.is_installed())
+        .collect()
+}
+
+fn base_dep_string(dep: &BaseDep) -> String {
+    match (dep.comp_type(), dep.version()) {
+        (Some(comp), Some(ver)) => format!("{} ({comp} {ver})", dep.name()),
+        _ => dep.name().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::matches::version_satisfies;
+
+    use super::*;
+
+    /// Build a universe from a synthetic Packages file, `Status: installed` marks installed
+    /// versions, `install` are requested packages (`foo` or `foo=1.0`)
+    fn universe(packages: &str, install: &[&str], remove: &[&str]) -> Universe {
+        let mut universe = Universe::default();
+        let mut stanzas = vec![];
+
+        for stanza in packages.split("\n\n").filter(|x| !x.trim().is_empty()) {
+            let fields = stanza
+                .lines()
+                .filter_map(|x| x.split_once(": "))
+                .map(|(k, v)| (k.trim(), v.trim()))
+                .collect::<HashMap<_, _>>();
+
+            let pkg = universe.add_pkg(fields["Package"]);
+            let id = universe.add_version(pkg, fields["Version"], 1);
+
+            if fields.get("Status") == Some(&"installed") {
+                universe.pkgs[pkg].installed = Some(id);
+            }
+
+            stanzas.push((id, fields));
+        }
+
+        let satisfies = |id: usize, dep: &str| -> bool {
+            let (name, constraint) = match dep.split_once('(') {
+                Some((name, constraint)) => (name.trim(), Some(constraint.trim_end_matches(')'))),
+                None => (dep.trim(), None),
+            };
+
+            let ver = &universe.vers[id];
+            let (_, fields) = &stanzas[id];
+            let provides = fields
+                .get("Provides")
+                .is_some_and(|x| x.split(',').any(|x| x.trim() == name));
+
+            if universe.pkgs[ver.pkg].name != name {
+                return provides && constraint.is_none();
+            }
+
+            let (op, req) = constraint.and_then(|x| x.split_once(' ')).unzip();
+
+            version_satisfies(&ver.version, op, req)
+        };
+
+        let mut deps = vec![];
+        let mut conflicts = vec![];
+
+        for (id, fields) in &stanzas {
+            let name = fields["Package"].to_string();
+            let version = fields["Version"].to_string();
+
+            for dep in fields.get("Depends").into_iter().flat_map(|x| x.split(',')) {
+                let targets = (0..stanzas.len())
+                    .filter(|&t| dep.split('|').any(|alt| satisfies(t, alt)))
+                    .collect();
+
+                deps.push((
+                    *id,
+                    targets,
+                    Reason::Depends {
+                        pkg: name.clone(),
+                        version: version.clone(),
+                        dep: dep.trim().to_string(),
+                    },
+                ));
+            }
+
+            for dep in fields
+                .get("Conflicts")
+                .into_iter()
+                .flat_map(|x| x.split(','))
+            {
+                for target in (0..stanzas.len()).filter(|&t| satisfies(t, dep)) {
+                    conflicts.push((
+                        *id,
+                        target,
+                        Reason::Conflicts {
+                            pkg: name.clone(),
+                            version: version.clone(),
+                            dep: dep.trim().to_string(),
+                        },
+                    ));
+                }
+            }
+        }
+
+        universe.deps = deps;
+        universe.conflicts = conflicts;
+
+        for req in install {
+            let (name, version) = req.split_once('=').unwrap_or((req, ""));
+            let pkg = universe.pkg_index[name];
+            let versions = universe.pkgs[pkg]
+                .versions
+                .iter()
+                .copied()
+                .filter(|x| version.is_empty() || universe.vers[*x].version == version)
+                .collect();
+
+            universe.request(
+                versions,
+                Reason::Install {
+                    pkg: name.to_string(),
+                },
+            );
+        }
+
+        for name in remove {
+            let pkg = universe.pkg_index[*name];
+            universe.request_remove(
+                pkg,
+                Reason::Remove {
+                    pkg: name.to_string(),
+                },
+            );
+        }
+
+        let keep = universe
+            .pkgs
+            .iter()
+            .filter_map(|x| x.installed)
+            .collect::<Vec<_>>();
+        for &id in &keep {
+            universe.vers[id].rank = 0;
+        }
+        universe.preferred = keep;
+
+        universe
+    }
+
+    fn chosen(changes: &[Choice], pkg: &str) -> Option<Option<String>> {
+        changes
+            .iter()
+            .find(|x| x.pkg == pkg)
+            .map(|x| x.chosen.clone())
+    }
+
+    const PACKAGES: &str = "
+Package: foo
+Version: 1.0
+Depends: libbar (>= 2) | libbaz
+
+Package: libbar
+Version: 1.0
+Status: installed
+
+Package: libbar
+Version: 2.0
+
+Package: libbaz
+Version: 1.0
+
+Package: qux
+Version: 1.0
+Status: installed
+Depends: libbar (<< 2)
+
+Package: mail-transport
+Version: 1.0
+Provides: mta
+Conflicts: postfix
+
+Package: postfix
+Version: 3.0
+Provides: mta
+
+Package: mutt
+Version: 2.0
+Depends: mta
+";
+
+    #[test]
+    fn test_solve_prefers_installed() {
+        let universe = universe(PACKAGES, &["foo"], &[]);
+        let changes = universe.solve().unwrap();
+
+        // libbar 2.0 breaks qux, so the alternative libbaz is installed
+        assert_eq!(chosen(&changes, "foo"), Some(Some("1.0".to_string())));
+        assert_eq!(chosen(&changes, "libbaz"), Some(Some("1.0".to_string())));
+        assert_eq!(chosen(&changes, "libbar"), None);
+        assert_eq!(chosen(&changes, "qux"), None);
+    }
+
+    #[test]
+    fn test_solve_upgrade_removes() {
+        let universe = universe(PACKAGES, &["libbar=2.0"], &[]);
+        let changes = universe.solve().unwrap();
+
+        assert_eq!(chosen(&changes, "libbar"), Some(Some("2.0".to_string())));
+        assert_eq!(chosen(&changes, "qux"), Some(None));
+    }
+
+    #[test]
+    fn test_solve_provides() {
+        let universe = universe(PACKAGES, &["mutt"], &[]);
+        let changes = universe.solve().unwrap();
+
+        assert_eq!(chosen(&changes, "mutt"), Some(Some("2.0".to_string())));
+        assert_eq!(
+            changes
+                .iter()
+                .filter(|x| x.pkg == "mail-transport" || x.pkg == "postfix")
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_solve_unsat_core() {
+        let universe = universe(PACKAGES, &["libbar=2.0", "qux"], &[]);
+
+        let Err(SolverError::Unsatisfiable(core)) = universe.solve() else {
+            panic!("should be unsatisfiable");
+        };
+
+        assert_eq!(core.len(), 4);
+        assert!(core.contains(&Reason::OneVersion {
+            pkg: "libbar".to_string()
+        }));
+        assert!(core.contains(&Reason::Install {
+            pkg: "qux".to_string()
+        }));
+        assert!(core.contains(&Reason::Install {
+            pkg: "libbar".to_string()
+        }));
+        assert!(core.contains(&Reason::Depends {
+            pkg: "qux".to_string(),
+            version: "1.0".to_string(),
+            dep: "libbar (<< 2)".to_string()
+        }));
+    }
+
+    #[test]
+    fn test_solve_conflicts() {
+        let universe = universe(PACKAGES, &["mail-transport", "postfix"], &[]);
+
+        let Err(SolverError::Unsatisfiable(core)) = universe.solve() else {
+            panic!("should be unsatisfiable");
+        };
+
+        assert!(core.iter().any(|x| matches!(x, Reason::Conflicts { .. })));
+    }
+
+    #[test]
+    fn test_solve_remove() {
+        let universe = universe(PACKAGES, &["qux"], &["libbar"]);
+
+        assert!(matches!(
+            universe.solve(),
+            Err(SolverError::Unsatisfiable(_))
+        ));
+    }
+
+    #[test]
+    fn test_from_cache() {
+        use crate::test::TEST_LOCK;
+        use oma_apt::new_cache;
+        let _lock = TEST_LOCK.lock().unwrap();
+
+        let packages = std::path::Path::new(&std::env::var_os("CARGO_MANIFEST_DIR").unwrap())
+            .join("test_file")
+            .join("solver")
+            .join("Packages");
+        let cache = new_cache!(&[packages.to_string_lossy().to_string()]).unwrap();
+
+        let app = cache.get("oma-solver-test-app").unwrap();
+        app.mark_install(false, true);
+
+        let mut requested = HashSet::with_hasher(ahash::RandomState::new());
+        requested.insert(app.index());
+
+        let universe = Universe::from_cache(&cache, &requested, |_| false);
+        let changes = universe.solve().unwrap();
+
+        assert_eq!(
+            chosen(&changes, "oma-solver-test-app"),
+            Some(Some("1.0".to_string()))
+        );
+        // `>>' is strict, 2.0 does not satisfy `>> 2.0'
+        assert_eq!(
+            chosen(&changes, "oma-solver-test-lib"),
+            Some(Some("3.0".to_string()))
+        );
+        // The virtual package is satisfied by its only provider
+        assert_eq!(
+            chosen(&changes, "oma-solver-test-postfix"),
+            Some(Some("1.0".to_string()))
+        );
+    }
+}
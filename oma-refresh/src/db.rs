@@ -38,7 +38,7 @@ use oma_fetch::{SingleDownloadError, Summary};
 #[cfg(feature = "aosc")]
 use oma_topics::TopicManager;
 
-use oma_utils::dpkg::dpkg_arch;
+use oma_utils::dpkg::{dpkg_arch, dpkg_foreign_archs};
 #[cfg(feature = "aosc")]
 use reqwest::StatusCode;
 
@@ -473,18 +473,18 @@ impl<'a> OmaRefresh<'a> {
 
         let index_target_config = IndexTargetConfig::new(self.apt_config, &self.arch);
 
-        let archs_from_file = fs::read_to_string("/var/lib/dpkg/arch").await;
-
-        let archs_from_file = if let Ok(file) = archs_from_file {
-            let res = file.lines().map(|x| x.to_string()).collect::<Vec<_>>();
-
-            if res.is_empty() {
+        // Native and foreign architectures enabled in the sysroot, like `oma arch add i386`
+        let archs_from_dpkg = match dpkg_foreign_archs(&self.source) {
+            Ok(foreign) => Some(
+                [self.arch.clone()]
+                    .into_iter()
+                    .chain(foreign.into_iter().filter(|x| *x != self.arch))
+                    .collect::<Vec<_>>(),
+            ),
+            Err(e) => {
+                debug!("Failed to get foreign architectures: {e}");
                 None
-            } else {
-                Some(res)
             }
-        } else {
-            None
         };
 
         let mut flat_repo_no_release = vec![];
@@ -541,7 +541,7 @@ impl<'a> OmaRefresh<'a> {
 
                 let mut archs = if let Some(archs) = ose.archs() {
                     archs.iter().map(|x| x.as_str()).collect::<Vec<_>>()
-                } else if let Some(ref f) = archs_from_file {
+                } else if let Some(ref f) = archs_from_dpkg {
                     f.iter().map(|x| x.as_str()).collect::<Vec<_>>()
                 } else {
                     vec![self.arch.as_str()]
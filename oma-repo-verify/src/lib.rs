@@ -1,12 +1,14 @@
 use std::{
-    io::Read,
+    io::{Read, Write},
     path::{Path, PathBuf},
 };
 
-use anyhow::bail;
+use anyhow::{anyhow, bail};
 use oma_apt_sources_lists::Signature;
 use sequoia_openpgp::{
-    cert::CertParser,
+    armor,
+    cert::{CertBuilder, CertParser},
+    crypto::KeyPair,
     parse::{
         stream::{
             DetachedVerifierBuilder, MessageLayer, MessageStructure, VerificationError,
@@ -15,6 +17,10 @@ use sequoia_openpgp::{
         PacketParserBuilder, Parse,
     },
     policy::{AsymmetricAlgorithm, StandardPolicy},
+    serialize::{
+        stream::{Armorer, Message, Signer},
+        SerializeInto,
+    },
     types::HashAlgorithm,
     Cert, KeyHandle,
 };
@@ -156,6 +162,81 @@ pub fn verify_inrelease(
     Ok(res)
 }
 
+/// Generate a new key which is able to sign repository metadata
+///
+/// Returns the ASCII armored secret key, which must be kept private.
+pub fn generate_signing_key(userid: &str) -> VerifyResult<Vec<u8>> {
+    let (cert, _) = CertBuilder::new()
+        .add_userid(userid)
+        .add_signing_subkey()
+        .generate()?;
+
+    Ok(cert.as_tsk().armored().to_vec()?)
+}
+
+/// Export the ASCII armored public key of the secret key at `key`
+///
+/// The result can be installed to `/etc/apt/trusted.gpg.d` of clients.
+pub fn export_public_key(key: impl AsRef<Path>) -> VerifyResult<Vec<u8>> {
+    let key = key.as_ref();
+    let cert = Cert::from_file(key)
+        .map_err(|e| VerifyError::CertParseFileError(key.display().to_string(), e))?;
+
+    Ok(cert.armored().to_vec()?)
+}
+
+/// Sign Release file with the secret key at `key`
+///
+/// Returns the clear-signed InRelease and the detached signature (Release.gpg).
+pub fn sign_release(release: &str, key: impl AsRef<Path>) -> VerifyResult<(String, Vec<u8>)> {
+    let key = key.as_ref();
+    let cert = Cert::from_file(key)
+        .map_err(|e| VerifyError::CertParseFileError(key.display().to_string(), e))?;
+
+    let p = policy();
+    let keypair = || -> VerifyResult<KeyPair> {
+        let ka = cert
+            .keys()
+            .unencrypted_secret()
+            .with_policy(&p, None)
+            .supported()
+            .alive()
+            .revoked(false)
+            .for_signing()
+            .next()
+            .ok_or_else(|| anyhow!("No usable signing key in {}", key.display()))?;
+
+        Ok(ka.key().clone().into_keypair()?)
+    };
+
+    let mut inrelease = vec![];
+    let message = Message::new(&mut inrelease);
+    let mut signer = Signer::new(message, keypair()?)
+        .cleartext()
+        .hash_algo(HashAlgorithm::SHA512)?
+        .build()?;
+    signer
+        .write_all(release.as_bytes())
+        .map_err(anyhow::Error::from)?;
+    signer.finalize()?;
+
+    let mut detached = vec![];
+    let message = Message::new(&mut detached);
+    let message = Armorer::new(message).kind(armor::Kind::Signature).build()?;
+    let mut signer = Signer::new(message, keypair()?)
+        .detached()
+        .hash_algo(HashAlgorithm::SHA512)?
+        .build()?;
+    signer
+        .write_all(release.as_bytes())
+        .map_err(anyhow::Error::from)?;
+    signer.finalize()?;
+
+    let inrelease = String::from_utf8(inrelease).map_err(anyhow::Error::from)?;
+
+    Ok((inrelease, detached))
+}
+
 fn policy() -> StandardPolicy<'static> {
     // Derive p to allow configuring sequoia_openpgp's StandardPolicy.
     let mut p = StandardPolicy::new();
@@ -50,8 +50,8 @@ pub enum OmaTopicsError {
 pub struct Topic {
     pub name: String,
     pub description: Option<String>,
-    date: u64,
-    update_date: Option<u64>,
+    pub date: u64,
+    pub update_date: Option<u64>,
     #[serde(skip_serializing)]
     arch: Option<Vec<String>>,
     pub packages: Vec<String>,
@@ -80,6 +80,7 @@ pub struct TopicManager<'a> {
     arch: &'a str,
     atm_state_path: PathBuf,
     atm_source_list_path: PathBuf,
+    topics_cache_path: PathBuf,
     dry_run: bool,
     old_enabled: Vec<Topic>,
     mm: MirrorManager,
@@ -88,6 +89,7 @@ pub struct TopicManager<'a> {
 impl<'a> TopicManager<'a> {
     const ATM_STATE_PATH_SUFFIX: &'a str = "var/lib/atm/state";
     const ATM_SOURCE_LIST_PATH_SUFFIX: &'a str = "etc/apt/sources.list.d/atm.list";
+    const TOPICS_CACHE_PATH_SUFFIX: &'a str = "var/lib/atm/topics.json";
 
     pub fn new_blocking(
         client: &'a Client,
@@ -156,6 +158,7 @@ impl<'a> TopicManager<'a> {
             atm_state_path,
             dry_run,
             atm_source_list_path: sysroot.as_ref().join(Self::ATM_SOURCE_LIST_PATH_SUFFIX),
+            topics_cache_path: sysroot.as_ref().join(Self::TOPICS_CACHE_PATH_SUFFIX),
             old_enabled: enabled,
             mm: tokio::task::spawn_blocking(move || MirrorManager::new(sysroot_box))
                 .await
@@ -186,6 +189,19 @@ impl<'a> TopicManager<'a> {
 
         self.all = res;
 
+        // Cached for things that can not wait for the network, see `cached_topics`
+        if !self.dry_run {
+            let topics = self.available_topics().collect::<Vec<_>>();
+            let res = match serde_json::to_vec(&topics) {
+                Ok(v) => fs::write(&self.topics_cache_path, v).await,
+                Err(e) => Err(io::Error::other(e)),
+            };
+
+            if let Err(e) = res {
+                debug!("Failed to write {}: {e}", self.topics_cache_path.display());
+            }
+        }
+
         Ok(())
     }
 
@@ -340,6 +356,28 @@ impl<'a> TopicManager<'a> {
     }
 }
 
+/// Topics available but not enabled, from the topics cached by the last refresh
+///
+/// This never touches the network, topics are empty if they were never refreshed.
+pub fn cached_topics(sysroot: impl AsRef<Path>) -> Result<Vec<Topic>> {
+    fn read(path: &Path) -> Result<Vec<Topic>> {
+        match std::fs::read(path) {
+            Ok(v) => serde_json::from_slice(&v)
+                .map_err(|e| OmaTopicsError::ReadFile(path.display().to_string(), e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(vec![]),
+            Err(e) => Err(OmaTopicsError::OpenFile(path.display().to_string(), e)),
+        }
+    }
+
+    let sysroot = sysroot.as_ref();
+    let enabled = read(&sysroot.join(TopicManager::ATM_STATE_PATH_SUFFIX))?;
+
+    Ok(read(&sysroot.join(TopicManager::TOPICS_CACHE_PATH_SUFFIX))?
+        .into_iter()
+        .filter(|x| !enabled.contains(x))
+        .collect())
+}
+
 async fn create_empty_state(atm_state_path: &Path) -> Result<Vec<Topic>> {
     let v = vec![];
 
@@ -3,7 +3,11 @@ use logind_zbus::{
     session::SessionProxy,
 };
 use tracing::debug;
-use zbus::{proxy, zvariant::OwnedFd, Result as zResult};
+use zbus::{
+    proxy,
+    zvariant::{OwnedFd, OwnedObjectPath},
+    Result as zResult,
+};
 
 pub use zbus::Connection;
 
@@ -21,6 +25,8 @@ pub enum OmaDbusError {
     FailedGetOmaStatus(zbus::Error),
     #[error("Failed to get session state")]
     SessionState(zbus::Error),
+    #[error("Failed to restart unit {0}")]
+    FailedRestartUnit(String, zbus::Error),
 }
 
 pub type OmaDbusResult<T> = Result<T, OmaDbusError>;
@@ -45,6 +51,16 @@ trait OmaDbus {
     async fn get_status(&self) -> zResult<String>;
 }
 
+#[proxy(
+    interface = "org.freedesktop.systemd1.Manager",
+    default_service = "org.freedesktop.systemd1",
+    default_path = "/org/freedesktop/systemd1"
+)]
+trait Systemd1Manager {
+    /// RestartUnit method
+    fn restart_unit(&self, name: &str, mode: &str) -> zResult<OwnedObjectPath>;
+}
+
 pub async fn create_dbus_connection() -> OmaDbusResult<Connection> {
     Connection::system()
         .await
@@ -119,3 +135,19 @@ pub async fn is_using_battery(conn: &Connection) -> OmaDbusResult<bool> {
         .await
         .map_err(OmaDbusError::FailedGetBatteryStatus)
 }
+
+/// Restart a systemd unit, returns after the restart job has been queued
+pub async fn restart_unit(conn: &Connection, name: &str) -> OmaDbusResult<()> {
+    let proxy = Systemd1ManagerProxy::new(conn)
+        .await
+        .map_err(|e| OmaDbusError::FailedCreateProxy("systemd1", e))?;
+
+    let job = proxy
+        .restart_unit(name, "replace")
+        .await
+        .map_err(|e| OmaDbusError::FailedRestartUnit(name.to_string(), e))?;
+
+    debug!("restart {name}: {job:?}");
+
+    Ok(())
+}
@@ -1,4 +1,6 @@
 use std::{
+    collections::{HashMap, HashSet},
+    fs,
     io::{BufRead, Write},
     path::Path,
     process::{Command, Stdio},
@@ -35,6 +37,58 @@ pub fn dpkg_arch<P: AsRef<Path>>(sysroot: P) -> Result<String, DpkgError> {
     Ok(output)
 }
 
+/// Get foreign architectures from dpkg
+pub fn dpkg_foreign_archs<P: AsRef<Path>>(sysroot: P) -> Result<Vec<String>, DpkgError> {
+    let dpkg = Command::new("dpkg")
+        .arg("--root")
+        .arg(sysroot.as_ref().display().to_string())
+        .arg("--print-foreign-architectures")
+        .output()?;
+
+    if !dpkg.status.success() {
+        return Err(DpkgError::DpkgRunError(dpkg.status.code().unwrap_or(1)));
+    }
+
+    let output = std::str::from_utf8(&dpkg.stdout)?
+        .lines()
+        .map(|x| x.trim().to_string())
+        .filter(|x| !x.is_empty())
+        .collect();
+
+    Ok(output)
+}
+
+/// Add foreign architecture use dpkg --add-architecture
+pub fn add_foreign_arch<P: AsRef<Path>>(arch: &str, sysroot: P) -> Result<(), DpkgError> {
+    set_foreign_arch(arch, true, sysroot)
+}
+
+/// Remove foreign architecture use dpkg --remove-architecture
+pub fn remove_foreign_arch<P: AsRef<Path>>(arch: &str, sysroot: P) -> Result<(), DpkgError> {
+    set_foreign_arch(arch, false, sysroot)
+}
+
+fn set_foreign_arch<P: AsRef<Path>>(arch: &str, add: bool, sysroot: P) -> Result<(), DpkgError> {
+    debug!("set foreign arch {arch} add = {add}");
+
+    let status = Command::new("dpkg")
+        .arg("--root")
+        .arg(sysroot.as_ref().display().to_string())
+        .arg(if add {
+            "--add-architecture"
+        } else {
+            "--remove-architecture"
+        })
+        .arg(arch)
+        .status()?;
+
+    if !status.success() {
+        return Err(DpkgError::DpkgRunError(status.code().unwrap_or(1)));
+    }
+
+    Ok(())
+}
+
 pub fn is_hold(pkg: &str, list: &[(String, String)]) -> bool {
     list.iter()
         .find(|(x, _)| x == pkg)
@@ -125,3 +179,170 @@ pub fn get_selections<P: AsRef<Path>>(sysroot: P) -> Result<Vec<(String, String)
 
     Ok(list)
 }
+
+/// Get a control field of a deb package, like `dpkg-deb --field'
+pub fn deb_field<P: AsRef<Path>>(deb: P, field: &str) -> Result<String, DpkgError> {
+    let dpkg_deb = Command::new("dpkg-deb")
+        .arg("--field")
+        .arg(deb.as_ref())
+        .arg(field)
+        .output()?;
+
+    if !dpkg_deb.status.success() {
+        return Err(DpkgError::DpkgRunError(dpkg_deb.status.code().unwrap_or(1)));
+    }
+
+    Ok(std::str::from_utf8(&dpkg_deb.stdout)?.trim().to_string())
+}
+
+/// List files shipped by a deb package, like `dpkg-deb --contents'
+///
+/// Paths are absolute like in dpkg database, directories are not included.
+pub fn deb_contents<P: AsRef<Path>>(deb: P) -> Result<Vec<String>, DpkgError> {
+    let mut dpkg_deb = Command::new("dpkg-deb")
+        .arg("--fsys-tarfile")
+        .arg(deb.as_ref())
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    let stdout = dpkg_deb
+        .stdout
+        .take()
+        .ok_or_else(|| std::io::Error::other("Failed to capture stdout of dpkg-deb"))?;
+
+    let tar = Command::new("tar").arg("-t").stdin(stdout).output()?;
+    let status = dpkg_deb.wait()?;
+
+    if !status.success() {
+        return Err(DpkgError::DpkgRunError(status.code().unwrap_or(1)));
+    }
+
+    if !tar.status.success() {
+        return Err(DpkgError::DpkgRunError(tar.status.code().unwrap_or(1)));
+    }
+
+    let res = std::str::from_utf8(&tar.stdout)?
+        .lines()
+        .filter(|x| !x.is_empty() && !x.ends_with('/'))
+        .map(|x| format!("/{}", x.trim_start_matches("./")))
+        .collect();
+
+    Ok(res)
+}
+
+/// Find installed packages owning `files`, using file lists in dpkg database
+///
+/// Returns owners of each file which is owned by any package, keyed by file path.
+pub fn file_owners<P: AsRef<Path>>(
+    files: &[String],
+    sysroot: P,
+) -> Result<HashMap<String, Vec<String>>, DpkgError> {
+    let wanted = files.iter().map(|x| x.as_str()).collect::<HashSet<_>>();
+    let info_dir = sysroot.as_ref().join("var/lib/dpkg/info");
+    let mut res: HashMap<String, Vec<String>> = HashMap::new();
+
+    for entry in fs::read_dir(info_dir)? {
+        let path = entry?.path();
+
+        if path.extension().is_none_or(|x| x != "list") {
+            continue;
+        }
+
+        let Some(pkg) = path.file_stem().and_then(|x| x.to_str()) else {
+            continue;
+        };
+
+        for line in fs::read(&path)?.as_slice().lines().map_while(Result::ok) {
+            if wanted.contains(line.as_str()) {
+                res.entry(line).or_default().push(pkg.to_string());
+            }
+        }
+    }
+
+    Ok(res)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_file_owners() {
+        let tmp = tempfile::tempdir().unwrap();
+        let sysroot = tmp.path();
+        let info = sysroot.join("var/lib/dpkg/info");
+        fs::create_dir_all(&info).unwrap();
+
+        fs::write(info.join("foo.list"), "/.\n/usr\n/usr/bin\n/usr/bin/foo\n").unwrap();
+        fs::write(
+            info.join("bar:amd64.list"),
+            "/usr\n/usr/bin\n/usr/bin/foo\n",
+        )
+        .unwrap();
+        // Not a file list
+        fs::write(info.join("baz.md5sums"), "/usr/bin/baz\n").unwrap();
+
+        let files = ["/usr/bin/foo", "/usr/bin/baz", "/usr/bin/qux"].map(|x| x.to_string());
+        let mut res = file_owners(&files, sysroot).unwrap();
+
+        res.values_mut().for_each(|x| x.sort());
+
+        assert_eq!(
+            res,
+            HashMap::from([(
+                "/usr/bin/foo".to_string(),
+                vec!["bar:amd64".to_string(), "foo".to_string()]
+            )])
+        );
+    }
+
+    #[test]
+    fn test_foreign_archs() {
+        let tmp = tempfile::tempdir().unwrap();
+        let sysroot = tmp.path();
+        fs::create_dir_all(sysroot.join("var/lib/dpkg")).unwrap();
+
+        let native = dpkg_arch(sysroot).unwrap();
+        let foreign = if native == "i386" { "amd64" } else { "i386" };
+
+        assert!(dpkg_foreign_archs(sysroot).unwrap().is_empty());
+
+        add_foreign_arch(foreign, sysroot).unwrap();
+        assert_eq!(dpkg_foreign_archs(sysroot).unwrap(), vec![foreign]);
+
+        remove_foreign_arch(foreign, sysroot).unwrap();
+        assert!(dpkg_foreign_archs(sysroot).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_deb_contents() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let root = dir.join("root");
+
+        fs::create_dir_all(root.join("DEBIAN")).unwrap();
+        fs::create_dir_all(root.join("usr/share/doc/foo")).unwrap();
+        fs::create_dir_all(root.join("usr/lib/foo")).unwrap();
+        fs::write(
+            root.join("DEBIAN/control"),
+            "Package: foo\nVersion: 1.0\nArchitecture: all\nMaintainer: Foo <foo@example.com>\nDescription: Foo\n",
+        )
+        .unwrap();
+        fs::write(root.join("usr/share/doc/foo/README"), "foo").unwrap();
+
+        let deb = dir.join("foo.deb");
+        let status = Command::new("dpkg-deb")
+            .arg("--build")
+            .arg(&root)
+            .arg(&deb)
+            .stdout(Stdio::null())
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let res = deb_contents(&deb).unwrap();
+
+        // Empty directories are not listed
+        assert_eq!(res, vec!["/usr/share/doc/foo/README".to_string()]);
+    }
+}
@@ -11,5 +11,7 @@ pub mod dpkg;
 pub mod human_bytes;
 #[cfg(feature = "oma")]
 pub mod oma;
+#[cfg(feature = "process")]
+pub mod process;
 #[cfg(feature = "url-no-escape")]
 pub mod url_no_escape;
@@ -1,5 +1,5 @@
 use std::{
-    io::{stderr, stdin, stdout, Error, ErrorKind, IsTerminal},
+    io::{stderr, stdin, stdout, Error, IsTerminal},
     path::PathBuf,
     sync::LazyLock,
 };
@@ -15,7 +15,7 @@ pub fn lock_oma_inner() -> IOResult<()> {
         return Ok(());
     }
 
-    Err(Error::new(ErrorKind::Other, ""))
+    Err(Error::other(""))
 }
 
 /// Unlock oma
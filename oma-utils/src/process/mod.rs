@@ -0,0 +1,146 @@
+use std::{
+    collections::BTreeSet,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use tracing::debug;
+
+/// Directories which contain binaries and libraries managed by the package manager
+const SYSTEM_PREFIXES: &[&str] = &["/usr/", "/bin/", "/sbin/", "/lib/", "/lib64/", "/opt/"];
+
+#[derive(Debug, Clone)]
+pub struct StaleProcess {
+    pub pid: u32,
+    /// Process name (from `/proc/<pid>/comm')
+    pub comm: String,
+    /// systemd unit this process belongs to, if any (e.g. `sshd.service')
+    pub unit: Option<String>,
+    /// Whether the unit is a system unit (as opposed to a user unit)
+    pub system_unit: bool,
+    /// Deleted files still mapped by this process
+    pub deleted: BTreeSet<PathBuf>,
+}
+
+/// Find processes which still map binaries or libraries that have been replaced or removed
+pub fn find_stale_processes() -> io::Result<Vec<StaleProcess>> {
+    let mut res = vec![];
+
+    for entry in fs::read_dir("/proc")? {
+        let Ok(entry) = entry else {
+            continue;
+        };
+
+        let Some(pid) = entry
+            .file_name()
+            .to_str()
+            .and_then(|s| s.parse::<u32>().ok())
+        else {
+            continue;
+        };
+
+        let dir = entry.path();
+
+        // Processes may exit at any time, or belong to kernel threads without maps
+        let Ok(maps) = fs::read_to_string(dir.join("maps")) else {
+            continue;
+        };
+
+        let deleted = deleted_mappings(&maps);
+
+        if deleted.is_empty() {
+            continue;
+        }
+
+        let comm = fs::read_to_string(dir.join("comm"))
+            .map(|s| s.trim().to_string())
+            .unwrap_or_else(|_| pid.to_string());
+
+        let (unit, system_unit) = fs::read_to_string(dir.join("cgroup"))
+            .ok()
+            .and_then(|s| unit_from_cgroup(&s))
+            .map(|(unit, system)| (Some(unit), system))
+            .unwrap_or((None, false));
+
+        debug!("Process {pid} ({comm}) maps deleted files: {deleted:?}");
+
+        res.push(StaleProcess {
+            pid,
+            comm,
+            unit,
+            system_unit,
+            deleted,
+        });
+    }
+
+    Ok(res)
+}
+
+fn deleted_mappings(maps: &str) -> BTreeSet<PathBuf> {
+    maps.lines()
+        .filter_map(|line| {
+            // address perms offset dev inode pathname
+            let path = line.splitn(6, ' ').nth(5)?.trim_start();
+            let path = path.strip_suffix(" (deleted)")?;
+
+            SYSTEM_PREFIXES
+                .iter()
+                .any(|prefix| path.starts_with(prefix))
+                .then(|| PathBuf::from(path))
+        })
+        .collect()
+}
+
+/// Get the systemd unit name from `/proc/<pid>/cgroup', and whether it is a system unit
+fn unit_from_cgroup(cgroup: &str) -> Option<(String, bool)> {
+    // cgroup v2: `0::/system.slice/sshd.service'
+    // cgroup v1: `1:name=systemd:/system.slice/sshd.service'
+    let path = cgroup
+        .lines()
+        .find(|line| line.starts_with("0::") || line.contains(":name=systemd:"))?
+        .rsplit_once(':')?
+        .1;
+
+    let system = path.starts_with("/system.slice/");
+
+    let unit = Path::new(path)
+        .iter()
+        .filter_map(|s| s.to_str())
+        .rfind(|s| s.ends_with(".service"))?;
+
+    Some((unit.to_string(), system))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_deleted_mappings() {
+        let maps = "\
+55d1c6a5e000-55d1c6a6c000 r--p 00000000 00:1f 1234                       /usr/bin/sshd
+7f1c2a000000-7f1c2a022000 r--p 00000000 00:1f 5678                       /usr/lib/libc.so.6 (deleted)
+7f1c2b000000-7f1c2b001000 rw-s 00000000 00:05 91011                      /dev/shm/foo (deleted)
+7ffd5a1e4000-7ffd5a205000 rw-p 00000000 00:00 0                          [stack]";
+
+        assert_eq!(
+            deleted_mappings(maps),
+            BTreeSet::from([PathBuf::from("/usr/lib/libc.so.6")])
+        );
+    }
+
+    #[test]
+    fn test_unit_from_cgroup() {
+        assert_eq!(
+            unit_from_cgroup("0::/system.slice/sshd.service\n"),
+            Some(("sshd.service".to_string(), true))
+        );
+        assert_eq!(
+            unit_from_cgroup(
+                "0::/user.slice/user-1000.slice/user@1000.service/app.slice/foo.service\n"
+            ),
+            Some(("foo.service".to_string(), false))
+        );
+        assert_eq!(unit_from_cgroup("0::/init.scope\n"), None);
+    }
+}
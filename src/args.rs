@@ -4,6 +4,11 @@ use clap::{crate_name, crate_version, Args, Parser, Subcommand};
 use enum_dispatch::enum_dispatch;
 
 use crate::{
+    apply_plan::ApplyPlan,
+    arch::Arch,
+    bootstrap::Bootstrap,
+    bundle::Bundle,
+    changelog::Changelog,
     clean::Clean,
     command_not_found::CommandNotFound,
     config::Config,
@@ -22,10 +27,14 @@ use crate::{
     rdepends::Rdepends,
     refresh::Refresh,
     remove::{Purge, Remove},
+    repo::Repo,
     search::Search,
     show::Show,
+    source::{BuildDep, Source},
     tui::Tui,
+    unattended::Unattended,
     upgrade::Upgrade,
+    why::{Why, WhyNot},
     GlobalOptions,
 };
 
@@ -58,8 +67,14 @@ pub enum SubCmd {
     /// Upgrade packages installed on the system
     #[command(visible_alias = "full-upgrade")]
     Upgrade(Upgrade),
+    /// Upgrade packages unattended, following the policy in the configuration file
+    Unattended(Unattended),
     /// Download package(s) from the repository
     Download(Download),
+    /// Download source package(s) from the repository
+    Source(Source),
+    /// Install build dependencies of source package(s)
+    BuildDep(BuildDep),
     /// Remove the specified package(s)
     #[command(
         visible_alias = "del",
@@ -72,6 +87,8 @@ pub enum SubCmd {
     /// Show information on the specified package(s)
     #[command(visible_alias = "info")]
     Show(Show),
+    /// Show changelog of the specified package
+    Changelog(Changelog),
     /// Search for package(s) available from the repository
     Search(Search),
     /// List files in the specified package
@@ -92,6 +109,20 @@ pub enum SubCmd {
     /// List reverse dependency(ies) for the specified package(s)
     #[command(visible_alias = "rdep")]
     Rdepends(Rdepends),
+    /// Explain why a package is installed
+    Why(Why),
+    /// Explain why a package can not be installed
+    WhyNot(WhyNot),
+    /// Manage foreign architecture(s)
+    Arch(Arch),
+    /// Bootstrap a minimal system into a directory (like debootstrap)
+    Bootstrap(Bootstrap),
+    /// Create or apply offline bundles of transactions
+    Bundle(Bundle),
+    /// Verify and apply a plan made by `oma upgrade --print-plan'
+    ApplyPlan(ApplyPlan),
+    /// Create and manage local repositories
+    Repo(Repo),
     /// Clear downloaded package cache
     Clean(Clean),
     /// Show a history/log of package changes in the system
@@ -0,0 +1,705 @@
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use ahash::HashMap;
+use dialoguer::{theme::ColorfulTheme, Confirm, Select};
+use oma_console::{console::style, pager::PagerExit};
+use oma_pm::{
+    apt::{InstallOperation, OmaOperation},
+    conffile::{find_leftover_conffiles, ConffileKind, LeftoverConffile},
+};
+use serde::{Deserialize, Serialize};
+use similar::{ChangeTag, TextDiff};
+use tracing::{debug, info, warn};
+
+use crate::{error::OutputError, fl, msg, table::oma_display_with_title};
+
+const POLICY_FILE: &str = "var/lib/oma/conffile-policy.toml";
+const BASE_DIR: &str = "var/lib/oma/conffiles";
+const ARCHIVES_DIR: &str = "var/cache/apt/archives";
+
+/// Remembered choice for a configuration file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConffilePolicy {
+    Keep,
+    TakeNew,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ConffilePolicies {
+    #[serde(default)]
+    files: BTreeMap<String, ConffilePolicy>,
+}
+
+impl ConffilePolicies {
+    fn read(sysroot: &Path) -> Self {
+        let path = sysroot.join(POLICY_FILE);
+
+        let Ok(s) = fs::read_to_string(&path) else {
+            return Self::default();
+        };
+
+        toml::from_str(&s).unwrap_or_else(|e| {
+            warn!(
+                "{}",
+                fl!("failed-to-parse-file", p = path.display().to_string())
+            );
+            debug!("{e}");
+            Self::default()
+        })
+    }
+
+    fn write(&self, sysroot: &Path) -> Result<(), OutputError> {
+        let path = sysroot.join(POLICY_FILE);
+
+        let s = toml::to_string_pretty(self).map_err(|e| OutputError {
+            description: fl!("failed-to-serialize-struct"),
+            source: Some(Box::new(e)),
+        })?;
+
+        write_file(&path, s.as_bytes())
+    }
+}
+
+enum Resolution {
+    Keep,
+    TakeNew,
+    Merged,
+    Skipped,
+}
+
+enum MergeResult {
+    Clean(Vec<u8>),
+    Conflict(Vec<u8>),
+}
+
+/// Resolve configuration files left behind by dpkg for packages in this transaction
+///
+/// Saved policies are always applied. If `interactive` is true, the user will be asked
+/// how to handle the remaining `.dpkg-dist' files. Nothing is touched in dry-run mode.
+///
+/// Returns leftover files which still need attention.
+pub fn handle_conffiles(
+    sysroot: &Path,
+    op: &OmaOperation,
+    interactive: bool,
+    dry_run: bool,
+) -> Result<Vec<LeftoverConffile>, OutputError> {
+    let pkgs = op
+        .install
+        .iter()
+        .filter(|x| x.op() != &InstallOperation::Download)
+        .collect::<Vec<_>>();
+
+    let leftovers = find_leftover_conffiles(
+        sysroot,
+        pkgs.iter().map(|x| (x.name_without_arch(), x.arch())),
+    );
+
+    if leftovers.is_empty() || dry_run {
+        return Ok(leftovers);
+    }
+
+    let old_debs = pkgs
+        .iter()
+        .filter_map(|x| {
+            let old_version = x.old_version()?;
+            let deb = format!(
+                "{}_{}_{}.deb",
+                x.name_without_arch(),
+                old_version.replace(':', "%3a"),
+                x.arch()
+            );
+
+            Some((x.name_without_arch(), sysroot.join(ARCHIVES_DIR).join(deb)))
+        })
+        .collect::<HashMap<_, _>>();
+
+    let mut policies = ConffilePolicies::read(sysroot);
+    let mut policies_changed = false;
+    let mut unresolved = vec![];
+
+    for conffile in leftovers {
+        if conffile.kind != ConffileKind::Dist {
+            unresolved.push(conffile);
+            continue;
+        }
+
+        let key = conffile.conffile.display().to_string();
+
+        if let Some(policy) = policies.files.get(&key) {
+            match policy {
+                ConffilePolicy::Keep => keep_current(sysroot, &conffile)?,
+                ConffilePolicy::TakeNew => take_new(sysroot, &conffile)?,
+            }
+
+            info!(
+                "{}",
+                fl!(
+                    "conffile-policy-applied",
+                    path = key.as_str(),
+                    policy = policy_str(policy)
+                )
+            );
+
+            continue;
+        }
+
+        if !interactive {
+            unresolved.push(conffile);
+            continue;
+        }
+
+        let base = read_base(sysroot, &conffile)
+            .or_else(|| conffile_from_deb(old_debs.get(conffile.package.as_str())?, &conffile));
+
+        let policy = match prompt_conffile(sysroot, &conffile, base.as_deref())? {
+            Resolution::Keep => ConffilePolicy::Keep,
+            Resolution::TakeNew => ConffilePolicy::TakeNew,
+            Resolution::Merged => continue,
+            Resolution::Skipped => {
+                unresolved.push(conffile);
+                continue;
+            }
+        };
+
+        let remember = Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt(fl!("conffile-remember", path = key.as_str()))
+            .default(false)
+            .interact()
+            .unwrap_or(false);
+
+        if remember {
+            policies.files.insert(key, policy);
+            policies_changed = true;
+        }
+    }
+
+    if policies_changed {
+        policies.write(sysroot)?;
+    }
+
+    Ok(unresolved)
+}
+
+/// Display configuration files which still need attention after a transaction
+pub fn display_conffile_report(leftovers: &[LeftoverConffile]) {
+    if leftovers.is_empty() {
+        return;
+    }
+
+    warn!("{}", fl!("conffile-report-1"));
+
+    for conffile in leftovers {
+        let kind = match conffile.kind {
+            ConffileKind::Dist => fl!("conffile-report-kind-dist"),
+            ConffileKind::New => fl!("conffile-report-kind-new"),
+            ConffileKind::Old => fl!("conffile-report-kind-old"),
+        };
+
+        msg!(
+            "{} ({}: {kind})",
+            conffile.leftover_path().display(),
+            conffile.package
+        );
+    }
+
+    info!("{}", fl!("conffile-report-2"));
+}
+
+fn prompt_conffile(
+    sysroot: &Path,
+    conffile: &LeftoverConffile,
+    base: Option<&[u8]>,
+) -> Result<Resolution, OutputError> {
+    let path = conffile.conffile.display().to_string();
+    let theme = ColorfulTheme::default();
+
+    warn!(
+        "{}",
+        fl!(
+            "conffile-changed",
+            path = path.as_str(),
+            pkg = conffile.package.as_str()
+        )
+    );
+
+    let mut show_diff = true;
+
+    loop {
+        let current = read_file(&conffile.path)?;
+        let new = read_file(&conffile.leftover_path())?;
+
+        if show_diff && matches!(display_diff(&path, &current, &new)?, PagerExit::Sigint) {
+            return Ok(Resolution::Skipped);
+        }
+
+        show_diff = false;
+
+        let mut choices = vec![
+            (fl!("conffile-keep"), Choice::Keep),
+            (fl!("conffile-take-new"), Choice::TakeNew),
+        ];
+
+        if base.is_some() {
+            choices.push((fl!("conffile-merge"), Choice::Merge));
+        }
+
+        choices.push((fl!("conffile-edit"), Choice::Edit));
+        choices.push((fl!("conffile-show-diff"), Choice::ShowDiff));
+        choices.push((fl!("conffile-skip"), Choice::Skip));
+
+        let selected = Select::with_theme(&theme)
+            .with_prompt(fl!("conffile-prompt", path = path.as_str()))
+            .items(&choices.iter().map(|(s, _)| s).collect::<Vec<_>>())
+            .default(0)
+            .interact_opt()
+            .map_err(|e| OutputError {
+                description: e.to_string(),
+                source: None,
+            })?;
+
+        let Some(selected) = selected else {
+            return Ok(Resolution::Skipped);
+        };
+
+        match choices[selected].1 {
+            Choice::Keep => {
+                keep_current(sysroot, conffile)?;
+                return Ok(Resolution::Keep);
+            }
+            Choice::TakeNew => {
+                take_new(sysroot, conffile)?;
+                return Ok(Resolution::TakeNew);
+            }
+            Choice::Merge => {
+                // base is always some here
+                let Some(base) = base else { continue };
+
+                if merge(sysroot, conffile, base)? {
+                    return Ok(Resolution::Merged);
+                }
+            }
+            Choice::Edit => {
+                edit_file(&conffile.path)?;
+                show_diff = true;
+            }
+            Choice::ShowDiff => show_diff = true,
+            Choice::Skip => return Ok(Resolution::Skipped),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Choice {
+    Keep,
+    TakeNew,
+    Merge,
+    Edit,
+    ShowDiff,
+    Skip,
+}
+
+fn display_diff(path: &str, current: &[u8], new: &[u8]) -> Result<PagerExit, OutputError> {
+    let diff = unified_diff(
+        &String::from_utf8_lossy(current),
+        &String::from_utf8_lossy(new),
+        path,
+        &format!("{path}{}", ConffileKind::Dist.suffix()),
+    );
+
+    let mut pager = oma_display_with_title(fl!("conffile-diff-title", path = path))?;
+    let mut out = pager.get_writer().map_err(|e| OutputError {
+        description: "Failed to get writer".to_string(),
+        source: Some(Box::new(e)),
+    })?;
+
+    write!(out, "{diff}").ok();
+    drop(out);
+
+    pager.wait_for_exit().map_err(|e| OutputError {
+        description: "Failed to wait exit".to_string(),
+        source: Some(Box::new(e)),
+    })
+}
+
+/// Generate a colored unified diff
+fn unified_diff(old: &str, new: &str, old_header: &str, new_header: &str) -> String {
+    let diff = TextDiff::from_lines(old, new);
+    let mut res = String::new();
+
+    res.push_str(&format!(
+        "{}\n",
+        style(format!("--- {old_header}")).red().bold()
+    ));
+    res.push_str(&format!(
+        "{}\n",
+        style(format!("+++ {new_header}")).green().bold()
+    ));
+
+    for hunk in diff.unified_diff().context_radius(3).iter_hunks() {
+        res.push_str(&format!("{}\n", style(hunk.header()).cyan()));
+
+        for change in hunk.iter_changes() {
+            let line = change.to_string_lossy();
+            let line = line.trim_end_matches('\n');
+
+            let line = match change.tag() {
+                ChangeTag::Delete => style(format!("-{line}")).red().to_string(),
+                ChangeTag::Insert => style(format!("+{line}")).green().to_string(),
+                ChangeTag::Equal => format!(" {line}"),
+            };
+
+            res.push_str(&line);
+            res.push('\n');
+
+            if change.missing_newline() {
+                res.push_str("\\ No newline at end of file\n");
+            }
+        }
+    }
+
+    res
+}
+
+/// Keep the currently-installed version, drop the package maintainer's version
+fn keep_current(sysroot: &Path, conffile: &LeftoverConffile) -> Result<(), OutputError> {
+    let new = conffile.leftover_path();
+    save_base(sysroot, conffile, &read_file(&new)?)?;
+    remove_file(&new)
+}
+
+/// Install the package maintainer's version, save the current one as `.dpkg-old'
+fn take_new(sysroot: &Path, conffile: &LeftoverConffile) -> Result<(), OutputError> {
+    let new = conffile.leftover_path();
+    save_base(sysroot, conffile, &read_file(&new)?)?;
+
+    let mut old = conffile.path.clone().into_os_string();
+    old.push(ConffileKind::Old.suffix());
+
+    rename(&conffile.path, Path::new(&old))?;
+    rename(&new, &conffile.path)
+}
+
+/// Perform a 3-way merge, returns true if the merged file has been installed
+fn merge(sysroot: &Path, conffile: &LeftoverConffile, base: &[u8]) -> Result<bool, OutputError> {
+    let theme = ColorfulTheme::default();
+    let path = conffile.conffile.display().to_string();
+    let new = conffile.leftover_path();
+
+    // Created with O_EXCL and a random name, removed when dropped
+    let base_file = tempfile::Builder::new()
+        .prefix("oma-conffile-base-")
+        .tempfile()
+        .and_then(|mut f| f.write_all(base).map(|_| f))
+        .map_err(|e| OutputError {
+            description: fl!(
+                "failed-to-write-file",
+                p = env::temp_dir().display().to_string()
+            ),
+            source: Some(Box::new(e)),
+        })?;
+    let res = three_way_merge(&conffile.path, base_file.path(), &new);
+    drop(base_file);
+
+    let Some(res) = res? else {
+        warn!("{}", fl!("conffile-merge-unavailable"));
+        return Ok(false);
+    };
+
+    let mut merged_file = conffile.path.clone().into_os_string();
+    merged_file.push(".oma-merged");
+    let merged_file = PathBuf::from(merged_file);
+
+    match res {
+        MergeResult::Clean(merged) => write_file(&merged_file, &merged)?,
+        MergeResult::Conflict(merged) => {
+            write_file(&merged_file, &merged)?;
+            warn!("{}", fl!("conffile-merge-conflict", path = path.as_str()));
+            edit_file(&merged_file)?;
+        }
+    }
+
+    let current = read_file(&conffile.path)?;
+    let merged = read_file(&merged_file)?;
+
+    if matches!(display_diff(&path, &current, &merged)?, PagerExit::Sigint) {
+        remove_file(&merged_file)?;
+        return Ok(false);
+    }
+
+    let install = Confirm::with_theme(&theme)
+        .with_prompt(fl!("conffile-merge-confirm", path = path.as_str()))
+        .default(true)
+        .interact()
+        .unwrap_or(false);
+
+    if !install {
+        remove_file(&merged_file)?;
+        return Ok(false);
+    }
+
+    save_base(sysroot, conffile, &read_file(&new)?)?;
+
+    let mut old = conffile.path.clone().into_os_string();
+    old.push(ConffileKind::Old.suffix());
+
+    fs::copy(&conffile.path, &old).map_err(|e| OutputError {
+        description: fl!(
+            "failed-to-operate-path",
+            p = conffile.path.display().to_string()
+        ),
+        source: Some(Box::new(e)),
+    })?;
+
+    // Keep permissions and ownership of the current file, only replace its content
+    write_file(&conffile.path, &merged)?;
+    remove_file(&merged_file)?;
+    remove_file(&new)?;
+
+    Ok(true)
+}
+
+fn three_way_merge(
+    current: &Path,
+    base: &Path,
+    new: &Path,
+) -> Result<Option<MergeResult>, OutputError> {
+    let mut cmd = if which::which("git").is_ok() {
+        let mut cmd = Command::new("git");
+        cmd.arg("merge-file").arg("-p");
+        cmd
+    } else if which::which("diff3").is_ok() {
+        let mut cmd = Command::new("diff3");
+        cmd.arg("-m");
+        cmd
+    } else {
+        return Ok(None);
+    };
+
+    let out = cmd
+        .arg(current)
+        .arg(base)
+        .arg(new)
+        .stderr(Stdio::null())
+        .output()
+        .map_err(|e| OutputError {
+            description: fl!("conffile-merge-unavailable"),
+            source: Some(Box::new(e)),
+        })?;
+
+    debug!("merge exit status: {:?}", out.status);
+
+    // git merge-file returns the number of conflicts, diff3 returns 1 on conflicts
+    // and 2 on trouble, both return negative values or nothing on failures
+    match out.status.code() {
+        Some(0) => Ok(Some(MergeResult::Clean(out.stdout))),
+        Some(1..=127) if !out.stdout.is_empty() => Ok(Some(MergeResult::Conflict(out.stdout))),
+        _ => Ok(None),
+    }
+}
+
+fn edit_file(path: &Path) -> Result<(), OutputError> {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .ok()
+        .filter(|x| !x.trim().is_empty())
+        .or_else(|| {
+            ["editor", "nano", "vi"]
+                .into_iter()
+                .find(|x| which::which(x).is_ok())
+                .map(|x| x.to_string())
+        })
+        .ok_or_else(|| OutputError {
+            description: fl!("conffile-editor-failed"),
+            source: None,
+        })?;
+
+    // $EDITOR may contain arguments, e.g. `code --wait'
+    let mut args = editor.split_ascii_whitespace();
+    let Some(cmd) = args.next() else {
+        unreachable!()
+    };
+
+    let status = Command::new(cmd)
+        .args(args)
+        .arg(path)
+        .status()
+        .map_err(|e| OutputError {
+            description: fl!("conffile-editor-failed"),
+            source: Some(Box::new(e)),
+        })?;
+
+    debug!("{editor} exit status: {status}");
+
+    Ok(())
+}
+
+fn base_path(sysroot: &Path, conffile: &LeftoverConffile) -> PathBuf {
+    let conffile = &conffile.conffile;
+
+    sysroot
+        .join(BASE_DIR)
+        .join(conffile.strip_prefix("/").unwrap_or(conffile))
+}
+
+fn read_base(sysroot: &Path, conffile: &LeftoverConffile) -> Option<Vec<u8>> {
+    fs::read(base_path(sysroot, conffile)).ok()
+}
+
+/// Save the package maintainer's version as the common ancestor for future merges
+fn save_base(
+    sysroot: &Path,
+    conffile: &LeftoverConffile,
+    content: &[u8],
+) -> Result<(), OutputError> {
+    write_file(&base_path(sysroot, conffile), content)
+}
+
+/// Extract a configuration file from a package in the local archive
+fn conffile_from_deb(deb: &Path, conffile: &LeftoverConffile) -> Option<Vec<u8>> {
+    if !deb.is_file() {
+        return None;
+    }
+
+    let mut dpkg_deb = Command::new("dpkg-deb")
+        .arg("--fsys-tarfile")
+        .arg(deb)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+
+    let tar = Command::new("tar")
+        .arg("-xOf")
+        .arg("-")
+        .arg(format!(".{}", conffile.conffile.display()))
+        .stdin(dpkg_deb.stdout.take()?)
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+
+    dpkg_deb.wait().ok()?;
+
+    tar.status.success().then_some(tar.stdout)
+}
+
+fn policy_str(policy: &ConffilePolicy) -> String {
+    match policy {
+        ConffilePolicy::Keep => fl!("conffile-keep"),
+        ConffilePolicy::TakeNew => fl!("conffile-take-new"),
+    }
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>, OutputError> {
+    fs::read(path).map_err(|e| OutputError {
+        description: fl!("failed-to-operate-path", p = path.display().to_string()),
+        source: Some(Box::new(e)),
+    })
+}
+
+fn write_file(path: &Path, content: &[u8]) -> Result<(), OutputError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| OutputError {
+            description: fl!("failed-to-create-file", p = parent.display().to_string()),
+            source: Some(Box::new(e)),
+        })?;
+    }
+
+    fs::write(path, content).map_err(|e| OutputError {
+        description: fl!("failed-to-write-file", p = path.display().to_string()),
+        source: Some(Box::new(e)),
+    })
+}
+
+fn remove_file(path: &Path) -> Result<(), OutputError> {
+    fs::remove_file(path).map_err(|e| OutputError {
+        description: fl!("failed-to-operate-path", p = path.display().to_string()),
+        source: Some(Box::new(e)),
+    })
+}
+
+fn rename(from: &Path, to: &Path) -> Result<(), OutputError> {
+    fs::rename(from, to).map_err(|e| OutputError {
+        description: fl!("failed-to-operate-path", p = from.display().to_string()),
+        source: Some(Box::new(e)),
+    })
+}
+
+#[test]
+fn test_unified_diff() {
+    oma_console::console::set_colors_enabled(false);
+
+    let diff = unified_diff("a\nb\nc\n", "a\nd\nc\n", "/etc/foo", "/etc/foo.dpkg-dist");
+
+    assert_eq!(
+        diff,
+        "--- /etc/foo\n+++ /etc/foo.dpkg-dist\n@@ -1,3 +1,3 @@\n a\n-b\n+d\n c\n"
+    );
+}
+
+#[test]
+fn test_handle_conffiles_dry_run() {
+    use oma_pm::apt::InstallEntry;
+
+    let sysroot = tempfile::tempdir().unwrap();
+    let sysroot = sysroot.path();
+
+    fs::create_dir_all(sysroot.join("var/lib/dpkg/info")).unwrap();
+    fs::create_dir_all(sysroot.join("var/lib/oma")).unwrap();
+    fs::create_dir_all(sysroot.join("etc")).unwrap();
+    fs::write(
+        sysroot.join("var/lib/dpkg/info/foo.conffiles"),
+        "/etc/foo.conf\n",
+    )
+    .unwrap();
+    fs::write(sysroot.join("etc/foo.conf"), "current").unwrap();
+    fs::write(sysroot.join("etc/foo.conf.dpkg-dist"), "new").unwrap();
+    fs::write(
+        sysroot.join(POLICY_FILE),
+        "[files]\n\"/etc/foo.conf\" = \"take-new\"\n",
+    )
+    .unwrap();
+
+    let op = OmaOperation {
+        install: vec![InstallEntry::builder()
+            .name("foo".to_string())
+            .name_without_arch("foo".to_string())
+            .old_version("1.0".to_string())
+            .new_version("2.0".to_string())
+            .new_size(0)
+            .pkg_urls(vec![])
+            .arch("all".to_string())
+            .download_size(0)
+            .op(InstallOperation::Upgrade)
+            .index(0)
+            .build()],
+        remove: vec![],
+        disk_size: ("+".into(), 0),
+        autoremovable: (0, 0),
+        total_download_size: 0,
+        suggest: vec![],
+        recommend: vec![],
+    };
+
+    // Saved policies must not be applied in dry-run mode
+    let leftovers = handle_conffiles(sysroot, &op, false, true).unwrap();
+
+    assert_eq!(leftovers.len(), 1);
+    assert_eq!(
+        fs::read_to_string(sysroot.join("etc/foo.conf")).unwrap(),
+        "current"
+    );
+    assert_eq!(
+        fs::read_to_string(sysroot.join("etc/foo.conf.dpkg-dist")).unwrap(),
+        "new"
+    );
+    assert!(!sysroot.join("etc/foo.conf.dpkg-old").exists());
+    assert!(!sysroot.join(BASE_DIR).exists());
+}
@@ -1,4 +1,4 @@
-use std::borrow::Cow;
+use std::{borrow::Cow, collections::HashMap};
 
 use crate::fl;
 use anyhow::Result;
@@ -15,6 +15,9 @@ const DEFAULT_CONFIG: &str = include_str!("../data/config/oma-debian.toml");
 pub struct Config {
     pub general: Option<GeneralConfig>,
     pub network: Option<NetworkConfig>,
+    pub changelog: Option<ChangelogConfig>,
+    pub unattended: Option<UnattendedConfig>,
+    pub keymap: Option<KeymapConfig>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -33,6 +36,15 @@ pub struct GeneralConfig {
     pub bell: bool,
     #[serde(default = "GeneralConfig::default_search_engine")]
     pub search_engine: String,
+    #[serde(default = "GeneralConfig::default_native_conffile_prompt")]
+    pub native_conffile_prompt: bool,
+    #[serde(default = "GeneralConfig::default_solver")]
+    pub solver: String,
+    #[serde(default = "GeneralConfig::default_interactive_review")]
+    pub interactive_review: bool,
+    /// Path to a color theme file, empty to use built-in colors
+    #[serde(default)]
+    pub theme: String,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -41,6 +53,125 @@ pub struct NetworkConfig {
     pub network_threads: usize,
 }
 
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ChangelogConfig {
+    #[serde(default = "ChangelogConfig::default_preview")]
+    pub preview: bool,
+    /// URL templates of changelogs, by repository origin
+    #[serde(default)]
+    pub url_templates: HashMap<String, String>,
+}
+
+impl ChangelogConfig {
+    pub const fn default_preview() -> bool {
+        false
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct UnattendedConfig {
+    /// Only upgrade packages from repositories with these origins, empty to allow all
+    #[serde(default)]
+    pub allowed_origins: Vec<String>,
+    /// Packages (globs supported) which are never upgraded unattended
+    #[serde(default)]
+    pub blacklist: Vec<String>,
+    #[serde(default = "UnattendedConfig::default_security_only")]
+    pub security_only: bool,
+    /// Local time range like `02:00-05:00', empty to allow any time
+    #[serde(default)]
+    pub maintenance_window: String,
+    #[serde(default = "UnattendedConfig::default_skip_on_battery")]
+    pub skip_on_battery: bool,
+    #[serde(default = "UnattendedConfig::default_auto_reboot")]
+    pub auto_reboot: bool,
+    /// Delay before rebooting, in minutes
+    #[serde(default = "UnattendedConfig::default_auto_reboot_delay")]
+    pub auto_reboot_delay: u64,
+    /// Shell command to run after an unattended upgrade, with the report on stdin
+    #[serde(default)]
+    pub notify_command: String,
+    #[serde(default = "UnattendedConfig::default_lock_retry_times")]
+    pub lock_retry_times: usize,
+    /// Interval between retries, in seconds
+    #[serde(default = "UnattendedConfig::default_lock_retry_interval")]
+    pub lock_retry_interval: u64,
+}
+
+impl Default for UnattendedConfig {
+    fn default() -> Self {
+        Self {
+            allowed_origins: vec![],
+            blacklist: vec![],
+            security_only: Self::default_security_only(),
+            maintenance_window: String::new(),
+            skip_on_battery: Self::default_skip_on_battery(),
+            auto_reboot: Self::default_auto_reboot(),
+            auto_reboot_delay: Self::default_auto_reboot_delay(),
+            notify_command: String::new(),
+            lock_retry_times: Self::default_lock_retry_times(),
+            lock_retry_interval: Self::default_lock_retry_interval(),
+        }
+    }
+}
+
+impl UnattendedConfig {
+    pub const fn default_security_only() -> bool {
+        false
+    }
+
+    pub const fn default_skip_on_battery() -> bool {
+        true
+    }
+
+    pub const fn default_auto_reboot() -> bool {
+        false
+    }
+
+    pub const fn default_auto_reboot_delay() -> u64 {
+        10
+    }
+
+    pub const fn default_lock_retry_times() -> usize {
+        5
+    }
+
+    pub const fn default_lock_retry_interval() -> u64 {
+        60
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct KeymapConfig {
+    #[serde(default = "KeymapConfig::default_preset")]
+    pub preset: String,
+    #[serde(default = "KeymapConfig::default_mouse")]
+    pub mouse: bool,
+    /// Keys of actions, overriding the preset, like `page_down = ["C-d", "Space"]`
+    #[serde(default)]
+    pub bindings: HashMap<String, Vec<String>>,
+}
+
+impl Default for KeymapConfig {
+    fn default() -> Self {
+        Self {
+            preset: Self::default_preset(),
+            mouse: Self::default_mouse(),
+            bindings: HashMap::new(),
+        }
+    }
+}
+
+impl KeymapConfig {
+    pub fn default_preset() -> String {
+        String::from("default")
+    }
+
+    pub const fn default_mouse() -> bool {
+        false
+    }
+}
+
 impl NetworkConfig {
     pub const fn default_network_thread() -> usize {
         4
@@ -72,6 +203,18 @@ impl GeneralConfig {
         true
     }
 
+    pub const fn default_native_conffile_prompt() -> bool {
+        false
+    }
+
+    pub const fn default_interactive_review() -> bool {
+        false
+    }
+
+    pub fn default_solver() -> String {
+        String::from("apt")
+    }
+
     pub fn default_search_engine() -> String {
         if cfg!(feature = "aosc") {
             String::from("indicium")
@@ -130,6 +273,13 @@ impl Config {
             .unwrap_or_else(GeneralConfig::default_follow_terminal_color)
     }
 
+    pub fn theme(&self) -> Option<&str> {
+        self.general
+            .as_ref()
+            .map(|x| x.theme.as_str())
+            .filter(|x| !x.is_empty())
+    }
+
     pub fn search_contents_println(&self) -> bool {
         self.general
             .as_ref()
@@ -144,10 +294,54 @@ impl Config {
             .unwrap_or_else(|| Cow::Owned(GeneralConfig::default_search_engine()))
     }
 
+    pub fn solver(&self) -> Cow<String> {
+        self.general
+            .as_ref()
+            .map(|x| Cow::Borrowed(&x.solver))
+            .unwrap_or_else(|| Cow::Owned(GeneralConfig::default_solver()))
+    }
+
     pub fn bell(&self) -> bool {
         self.general
             .as_ref()
             .map(|x| x.bell)
             .unwrap_or_else(GeneralConfig::default_bell)
     }
+
+    pub fn native_conffile_prompt(&self) -> bool {
+        self.general
+            .as_ref()
+            .map(|x| x.native_conffile_prompt)
+            .unwrap_or_else(GeneralConfig::default_native_conffile_prompt)
+    }
+
+    pub fn interactive_review(&self) -> bool {
+        self.general
+            .as_ref()
+            .map(|x| x.interactive_review)
+            .unwrap_or_else(GeneralConfig::default_interactive_review)
+    }
+
+    /// Changelog config, if previewing changelogs is enabled
+    pub fn changelog_preview(&self) -> Option<&ChangelogConfig> {
+        self.changelog.as_ref().filter(|x| x.preview)
+    }
+
+    pub fn changelog_url_template(&self, origin: &str) -> Option<&str> {
+        self.changelog
+            .as_ref()
+            .and_then(|x| x.url_templates.get(origin))
+            .map(|x| x.as_str())
+    }
+
+    pub fn unattended(&self) -> Cow<UnattendedConfig> {
+        self.unattended
+            .as_ref()
+            .map(Cow::Borrowed)
+            .unwrap_or_default()
+    }
+
+    pub fn keymap(&self) -> Cow<KeymapConfig> {
+        self.keymap.as_ref().map(Cow::Borrowed).unwrap_or_default()
+    }
 }
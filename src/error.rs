@@ -13,7 +13,9 @@ use oma_history::HistoryError;
 #[cfg(feature = "aosc")]
 use oma_mirror::MirrorError;
 
+use oma_pm::explain::{Explanation, Suggestion};
 use oma_pm::search::OmaSearchError;
+use oma_pm::solver::Reason;
 use oma_pm::AptErrors;
 use oma_pm::{apt::OmaAptError, matches::MatcherError};
 use oma_refresh::db::RefreshError;
@@ -244,6 +246,10 @@ impl From<OmaDbusError> for OutputError {
                 description: value.to_string(),
                 source: None,
             },
+            OmaDbusError::FailedRestartUnit(unit, e) => Self {
+                description: fl!("failed-to-restart-service", unit = unit),
+                source: Some(Box::new(e)),
+            },
         }
     }
 }
@@ -272,6 +278,10 @@ impl From<OmaSearchError> for OutputError {
                 description: value.to_string(),
                 source: None,
             },
+            OmaSearchError::InvalidRegex(e) => OutputError {
+                description: fl!("invalid-search-regex"),
+                source: Some(Box::new(e)),
+            },
         }
     }
 }
@@ -611,7 +621,7 @@ pub fn oma_apt_error_to_output(err: OmaAptError) -> OutputError {
             description: fl!("can-not-mark-reinstall", name = pkg, version = version),
             source: None,
         },
-        OmaAptError::DependencyIssue(ref broken_deps) => {
+        OmaAptError::DependencyIssue(broken_deps, explanation) => {
             error!("{}", fl!("dep-issue-1"));
 
             if !broken_deps.is_empty() {
@@ -633,7 +643,7 @@ pub fn oma_apt_error_to_output(err: OmaAptError) -> OutputError {
 
                     let mut last_name = "";
 
-                    for dep in broken_deps {
+                    for dep in &broken_deps {
                         let mut prefix = String::new();
                         if last_name != dep[0].name {
                             prefix = format!("{}:", dep[0].name);
@@ -674,11 +684,43 @@ pub fn oma_apt_error_to_output(err: OmaAptError) -> OutputError {
                 }
             }
 
+            debug!("{}", serde_json::json!(explanation));
+            print_explanation(&explanation);
+
+            OutputError {
+                description: "".to_string(),
+                source: None,
+            }
+        }
+        OmaAptError::SolverUnsatisfiable(core) => {
+            error!("{}", fl!("solver-unsat"));
+
+            for reason in &core {
+                info!("{}", solver_reason(reason));
+            }
+
             OutputError {
                 description: "".to_string(),
                 source: None,
             }
         }
+        OmaAptError::SolverGaveUp => OutputError {
+            description: fl!("solver-gave-up"),
+            source: None,
+        },
+        OmaAptError::PlanStateMismatch(pkg, expected, found) => OutputError {
+            description: fl!(
+                "plan-state-mismatch",
+                pkg = pkg,
+                expected = expected.unwrap_or_else(|| fl!("plan-not-installed")),
+                found = found.unwrap_or_else(|| fl!("plan-not-installed"))
+            ),
+            source: None,
+        },
+        OmaAptError::PlanChecksumMismatch(pkg, ver) => OutputError {
+            description: fl!("plan-checksum-mismatch", pkg = pkg, ver = ver),
+            source: None,
+        },
         OmaAptError::PkgIsEssential(pkg) => OutputError {
             description: fl!("pkg-is-essential", name = pkg),
             source: None,
@@ -757,6 +799,10 @@ pub fn oma_apt_error_to_output(err: OmaAptError) -> OutputError {
             description: fl!("features-abort"),
             source: None,
         },
+        OmaAptError::NoSourceMirror(pkg) => OutputError {
+            description: fl!("source-no-mirror", pkg = pkg),
+            source: None,
+        },
         OmaAptError::DpkgTriggers(e) => OutputError {
             description: fl!("dpkg-triggers-only-a-non-zero"),
             source: Some(Box::new(e)),
@@ -799,6 +845,84 @@ impl From<reqwest::Error> for OutputError {
     }
 }
 
+fn print_explanation(explanation: &Explanation) {
+    if explanation.problems.is_empty() {
+        return;
+    }
+
+    info!("{}", fl!("dep-issue-explain"));
+    println!();
+
+    for problem in &explanation.problems {
+        println!("  {}", problem.chain.join(" -> "));
+        println!(
+            "    {} ({}) {}: {}",
+            problem.package, problem.version, problem.typ, problem.dependency
+        );
+
+        if let Some(conflict) = &problem.conflict {
+            println!(
+                "    {}",
+                fl!(
+                    "dep-issue-conflict",
+                    pkg = conflict.package.as_str(),
+                    ver = conflict.version.as_str(),
+                    dep = format!("{}: {}", conflict.typ, conflict.dependency)
+                )
+            );
+        }
+
+        if problem.unsatisfiable {
+            println!("    {}", fl!("dep-issue-unsatisfiable"));
+        }
+
+        if !problem.suggestions.is_empty() {
+            println!("    {}", fl!("dep-issue-suggestions"));
+        }
+
+        for suggestion in &problem.suggestions {
+            let s = match suggestion {
+                Suggestion::Remove { pkg } => fl!("dep-issue-suggest-remove", pkg = pkg.as_str()),
+                Suggestion::Pick { pkg, version } => fl!(
+                    "dep-issue-suggest-pick",
+                    pkg = pkg.as_str(),
+                    ver = version.as_str()
+                ),
+                Suggestion::Topic { topic, pkg } => fl!(
+                    "dep-issue-suggest-topic",
+                    pkg = pkg.as_str(),
+                    topic = topic.as_str()
+                ),
+            };
+
+            println!("      - {s}");
+        }
+
+        println!();
+    }
+}
+
+fn solver_reason(reason: &Reason) -> String {
+    match reason {
+        Reason::Install { pkg } => fl!("solver-reason-install", pkg = pkg.as_str()),
+        Reason::Remove { pkg } => fl!("solver-reason-remove", pkg = pkg.as_str()),
+        Reason::Keep { pkg } => fl!("solver-reason-keep", pkg = pkg.as_str()),
+        Reason::Depends { pkg, version, dep } => fl!(
+            "solver-reason-depends",
+            pkg = pkg.as_str(),
+            ver = version.as_str(),
+            dep = dep.as_str()
+        ),
+        Reason::Conflicts { pkg, version, dep } => fl!(
+            "solver-reason-conflicts",
+            pkg = pkg.as_str(),
+            ver = version.as_str(),
+            dep = dep.as_str()
+        ),
+        Reason::OneVersion { pkg } => fl!("solver-reason-one-version", pkg = pkg.as_str()),
+    }
+}
+
 fn oma_checksum_error(e: ChecksumError) -> OutputError {
     debug!("{:?}", e);
     match e {
@@ -8,11 +8,13 @@ use std::sync::{LazyLock, OnceLock};
 use std::time::Duration;
 
 mod args;
+mod conffile;
 mod config;
 mod error;
 mod install_progress;
 mod lang;
 mod pb;
+mod report;
 mod subcommand;
 mod table;
 mod tui;
@@ -27,9 +29,11 @@ use clap::{ArgAction, Args, ColorChoice, Parser};
 use error::OutputError;
 use i18n_embed::{DesktopLanguageRequester, Localizer};
 use lang::LANGUAGE_LOADER;
-use oma_console::print::{termbg, OmaColorFormat};
+use oma_console::input::{InputConfig, KeyAction, KeyBinding, Keymap, KeymapPreset};
+use oma_console::print::{termbg, ColorTheme, OmaColorFormat};
 use oma_console::writer::{writeln_inner, MessageType, Writer};
 use oma_console::OmaLayer;
+use oma_pm::solver::Solver;
 use oma_utils::dbus::{create_dbus_connection, get_another_oma_status, OmaDbusError};
 use oma_utils::oma::{terminal_ring, unlock_oma};
 use oma_utils::OsRelease;
@@ -60,6 +64,8 @@ static LOCKED: AtomicBool = AtomicBool::new(false);
 static SPAWN_NEW_OMA: AtomicBool = AtomicBool::new(false);
 static APP_USER_AGENT: &str = concat!("oma/", env!("CARGO_PKG_VERSION"));
 static COLOR_FORMATTER: OnceLock<OmaColorFormat> = OnceLock::new();
+static INPUT_CONFIG: OnceLock<InputConfig> = OnceLock::new();
+static SOLVER: OnceLock<Solver> = OnceLock::new();
 static RT: LazyLock<Runtime> = LazyLock::new(|| {
     tokio::runtime::Builder::new_multi_thread()
         .enable_all()
@@ -119,6 +125,9 @@ pub struct GlobalOptions {
     /// Set apt options
     #[arg(long, global = true, action = ArgAction::Append)]
     apt_options: Vec<String>,
+    /// Set dependency solver (apt, sat or compare)
+    #[arg(long, global = true, env = "OMA_SOLVER", value_parser = ["apt", "sat", "compare"])]
+    solver: Option<String>,
     /// Don't ring if oma completes the transaction
     #[arg(long, global = true, env = "OMA_NO_BELL", value_parser = FalseyValueParser::new()
 )]
@@ -264,6 +273,9 @@ fn try_main(oma: OhManagerAilurus) -> Result<i32, OutputError> {
     let config = Config::read()?;
 
     init_color_formatter(&oma, &config);
+    init_input_config(&config);
+
+    init_solver(&oma, &config);
 
     let no_progress =
         oma.global.no_progress || !is_terminal() || oma.global.debug || oma.global.dry_run;
@@ -343,7 +355,74 @@ fn init_color_formatter(oma: &OhManagerAilurus, config: &Config) {
             follow_term_color = true;
         }
 
-        OmaColorFormat::new(follow_term_color, timeout)
+        OmaColorFormat::new(follow_term_color, timeout).with_theme(load_theme(config))
+    });
+}
+
+fn load_theme(config: &Config) -> ColorTheme {
+    let Some(path) = config.theme() else {
+        return ColorTheme::default();
+    };
+
+    match std::fs::read_to_string(path)
+        .map_err(anyhow::Error::from)
+        .and_then(|s| toml::from_str::<ColorTheme>(&s).map_err(anyhow::Error::from))
+    {
+        Ok(theme) => theme,
+        Err(e) => {
+            warn!("{}", fl!("theme-invalid", path = path));
+            debug!("{e}");
+            ColorTheme::default()
+        }
+    }
+}
+
+fn init_input_config(config: &Config) {
+    INPUT_CONFIG.get_or_init(|| {
+        let keymap_config = config.keymap();
+
+        let preset = keymap_config.preset.parse().unwrap_or_else(|_| {
+            warn!(
+                "{}",
+                fl!(
+                    "keymap-invalid-preset",
+                    preset = keymap_config.preset.as_str()
+                )
+            );
+            KeymapPreset::default()
+        });
+
+        let mut keymap = Keymap::new(preset);
+
+        for (action, raw_keys) in &keymap_config.bindings {
+            let Ok(action) = action.parse::<KeyAction>() else {
+                warn!("{}", fl!("keymap-invalid-action", action = action.as_str()));
+                continue;
+            };
+
+            let keys = raw_keys
+                .iter()
+                .filter_map(|key| match key.parse::<KeyBinding>() {
+                    Ok(key) => Some(key),
+                    Err(_) => {
+                        warn!("{}", fl!("keymap-invalid-key", key = key.as_str()));
+                        None
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            // Keep the preset if none of the keys is valid, `[]` unbinds the action
+            if keys.is_empty() && !raw_keys.is_empty() {
+                continue;
+            }
+
+            keymap.rebind(action, keys);
+        }
+
+        InputConfig {
+            keymap,
+            mouse: keymap_config.mouse,
+        }
     });
 }
 
@@ -352,6 +431,32 @@ fn color_formatter() -> &'static OmaColorFormat {
     COLOR_FORMATTER.get().unwrap()
 }
 
+#[inline]
+fn input_config() -> &'static InputConfig {
+    INPUT_CONFIG.get().unwrap()
+}
+
+fn init_solver(oma: &OhManagerAilurus, config: &Config) {
+    SOLVER.get_or_init(|| {
+        let solver = oma
+            .global
+            .solver
+            .clone()
+            .unwrap_or_else(|| config.solver().to_string());
+
+        solver.parse().unwrap_or_else(|e| {
+            debug!("Unknown solver: {e}, fallback to apt");
+            Solver::default()
+        })
+    });
+}
+
+/// Dependency solver set by `--solver` or in the configuration file
+#[inline]
+fn solver() -> Solver {
+    SOLVER.get().copied().unwrap_or_default()
+}
+
 fn display_error_and_can_unlock(e: OutputError) -> io::Result<bool> {
     let mut unlock = true;
     if !e.description.is_empty() {
@@ -0,0 +1,217 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::Path,
+};
+
+use oma_console::print::Action;
+use oma_pm::{
+    apt::{InstallOperation, OmaOperation},
+    conffile::LeftoverConffile,
+};
+use oma_utils::{
+    dbus::{create_dbus_connection, restart_unit},
+    process::find_stale_processes,
+};
+use tracing::{debug, info, warn};
+
+use crate::{
+    color_formatter, conffile::display_conffile_report, error::OutputError, fl, msg, success, RT,
+};
+
+/// Packages which, when changed, are only fully applied after a reboot
+const REBOOT_PKG_PREFIXES: &[&str] = &["linux-kernel-", "linux-image-"];
+const REBOOT_PKGS: &[&str] = &["glibc", "libc6", "systemd", "dbus", "dbus-broker"];
+
+/// Services which would end user sessions or take down the system bus if restarted
+const NO_RESTART_UNITS: &[&str] = &[
+    "dbus.service",
+    "dbus-broker.service",
+    "systemd-logind.service",
+    "gdm.service",
+    "sddm.service",
+    "lightdm.service",
+    "greetd.service",
+    "display-manager.service",
+];
+const NO_RESTART_UNIT_PREFIXES: &[&str] = &["getty@", "serial-getty@", "user@"];
+
+/// Things that need the user's attention after a transaction
+#[derive(Debug, Default)]
+pub struct PostTransactionReport {
+    /// Configuration files left behind by dpkg
+    conffiles: Vec<LeftoverConffile>,
+    /// System services using replaced binaries or libraries, with their process names
+    services: BTreeMap<String, BTreeSet<String>>,
+    /// Other processes using replaced binaries or libraries
+    processes: BTreeSet<String>,
+    /// Why a reboot is required, empty if it is not
+    reboot_reasons: Vec<String>,
+}
+
+impl PostTransactionReport {
+    pub fn collect(sysroot: &Path, op: &OmaOperation, conffiles: Vec<LeftoverConffile>) -> Self {
+        let mut report = Self {
+            conffiles,
+            ..Default::default()
+        };
+
+        // Running processes and kernel have nothing to do with other sysroots
+        if sysroot != Path::new("/") {
+            return report;
+        }
+
+        match find_stale_processes() {
+            Ok(processes) => {
+                for process in processes {
+                    match process.unit {
+                        Some(unit) if process.system_unit => {
+                            report
+                                .services
+                                .entry(unit)
+                                .or_default()
+                                .insert(process.comm);
+                        }
+                        _ => {
+                            report.processes.insert(process.comm);
+                        }
+                    }
+                }
+            }
+            Err(e) => debug!("Failed to find processes need to restart: {e}"),
+        }
+
+        report.reboot_reasons = reboot_reasons(sysroot, op);
+
+        report
+    }
+
+    pub fn display(&self) {
+        display_conffile_report(&self.conffiles);
+
+        if !self.services.is_empty() {
+            warn!("{}", fl!("report-services-need-restart"));
+            for (unit, comms) in &self.services {
+                let comms = comms.iter().map(|s| s.as_str()).collect::<Vec<_>>();
+                msg!(
+                    "{} ({})",
+                    color_formatter().color_str(unit, Action::Emphasis),
+                    comms.join(", ")
+                );
+            }
+
+            if self.services.keys().any(|unit| can_restart(unit)) {
+                info!(
+                    "{}",
+                    fl!(
+                        "report-restart-services-tips",
+                        cmd = color_formatter()
+                            .color_str("--restart-services", Action::Secondary)
+                            .to_string()
+                    )
+                );
+            }
+        }
+
+        if !self.processes.is_empty() {
+            let processes = self
+                .processes
+                .iter()
+                .map(|s| s.as_str())
+                .collect::<Vec<_>>();
+            warn!("{}", fl!("report-processes-need-restart"));
+            msg!("{}", processes.join(", "));
+        }
+
+        if !self.reboot_reasons.is_empty() {
+            warn!(
+                "{}",
+                fl!(
+                    "report-reboot-required",
+                    reason = self.reboot_reasons.join(", ")
+                )
+            );
+        }
+    }
+
+    /// Restart system services using replaced binaries or libraries through systemd
+    pub fn restart_services(&self) -> Result<(), OutputError> {
+        let units = self
+            .services
+            .keys()
+            .filter(|unit| {
+                let res = can_restart(unit);
+                if !res {
+                    info!("{}", fl!("report-skip-restart", unit = unit.as_str()));
+                }
+                res
+            })
+            .collect::<Vec<_>>();
+
+        if units.is_empty() {
+            return Ok(());
+        }
+
+        let conn = RT.block_on(create_dbus_connection())?;
+
+        // One unit failing to restart should not keep others outdated
+        for unit in units {
+            match RT.block_on(restart_unit(&conn, unit)) {
+                Ok(()) => success!("{}", fl!("report-service-restarted", unit = unit.as_str())),
+                Err(e) => warn!(
+                    "{}",
+                    fl!(
+                        "report-restart-failed",
+                        unit = unit.as_str(),
+                        reason = e.to_string()
+                    )
+                ),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn can_restart(unit: &str) -> bool {
+    !NO_RESTART_UNITS.contains(&unit)
+        && !NO_RESTART_UNIT_PREFIXES
+            .iter()
+            .any(|prefix| unit.starts_with(prefix))
+}
+
+pub fn reboot_reasons(sysroot: &Path, op: &OmaOperation) -> Vec<String> {
+    let mut reasons = vec![];
+
+    // Debian-based systems ask for a reboot through this file
+    if sysroot.join("run/reboot-required").exists() {
+        reasons.push(fl!("report-reboot-reason-flag"));
+    }
+
+    // Modules of the running kernel are gone, it has been upgraded or removed
+    // (containers usually have no kernel modules at all)
+    let modules_dir = sysroot.join("usr/lib/modules");
+    if let Ok(release) = fs::read_to_string("/proc/sys/kernel/osrelease") {
+        if modules_dir.is_dir() && !modules_dir.join(release.trim()).exists() {
+            reasons.push(fl!("report-reboot-reason-kernel"));
+        }
+    }
+
+    let changed = op
+        .install
+        .iter()
+        .filter(|x| x.op() != &InstallOperation::Download)
+        .map(|x| x.name_without_arch())
+        .chain(op.remove.iter().map(|x| x.name()))
+        .filter(|name| {
+            REBOOT_PKGS.contains(name)
+                || REBOOT_PKG_PREFIXES
+                    .iter()
+                    .any(|prefix| name.starts_with(prefix))
+        })
+        .collect::<BTreeSet<_>>();
+
+    reasons.extend(changed.into_iter().map(|s| s.to_string()));
+
+    reasons
+}
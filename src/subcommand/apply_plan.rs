@@ -0,0 +1,268 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use ahash::HashSet;
+use clap::Args;
+use oma_history::SummaryType;
+use oma_pm::apt::{AptConfig, InstallOperation, OmaApt, OmaAptArgs, OmaOperation, RemoveTag};
+use oma_utils::dpkg::dpkg_arch;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::Config,
+    error::OutputError,
+    fl,
+    utils::{dbus_check, root},
+};
+
+use super::utils::{auth_config, io_error, lock_oma, no_check_dbus_warn, CommitChanges};
+use crate::args::CliExecuter;
+
+const PLAN_VERSION: u32 = 1;
+
+/// Changes resolved by `oma upgrade --print-plan`, to be applied by `oma apply-plan`
+#[derive(Debug, Serialize, Deserialize)]
+struct Plan {
+    version: u32,
+    arch: String,
+    op: OmaOperation,
+}
+
+#[derive(Debug, Args)]
+pub struct ApplyPlan {
+    /// Path of the plan
+    plan: PathBuf,
+    /// Bypass confirmation prompts
+    #[arg(short, long)]
+    yes: bool,
+    /// Set sysroot target directory
+    #[arg(from_global)]
+    sysroot: PathBuf,
+    /// Run oma in “dry-run” mode. Useful for testing changes and operations without making changes to the system
+    #[arg(from_global)]
+    dry_run: bool,
+    /// Run oma do not check dbus
+    #[arg(from_global)]
+    no_check_dbus: bool,
+    /// Set apt options
+    #[arg(from_global)]
+    apt_options: Vec<String>,
+}
+
+impl CliExecuter for ApplyPlan {
+    fn execute(self, config: &Config, no_progress: bool) -> Result<i32, OutputError> {
+        let ApplyPlan {
+            plan,
+            yes,
+            sysroot,
+            dry_run,
+            no_check_dbus,
+            apt_options,
+        } = self;
+
+        if !dry_run {
+            root()?;
+            lock_oma()?;
+        }
+
+        let _fds = if !no_check_dbus && !config.no_check_dbus() && !dry_run {
+            Some(dbus_check(yes)?)
+        } else {
+            no_check_dbus_warn();
+            None
+        };
+
+        let plan = fs::read(&plan).map_err(|e| io_error(&plan, e))?;
+        let plan: Plan = serde_json::from_slice(&plan).map_err(|e| OutputError {
+            description: fl!("plan-bad-file"),
+            source: Some(Box::new(e)),
+        })?;
+
+        if plan.version != PLAN_VERSION {
+            return Err(OutputError {
+                description: fl!("plan-bad-file"),
+                source: None,
+            });
+        }
+
+        let arch = dpkg_arch(&sysroot)?;
+        if plan.arch != arch {
+            return Err(OutputError {
+                description: fl!("plan-arch-mismatch", plan = plan.arch.as_str(), arch = arch),
+                source: None,
+            });
+        }
+
+        let oma_apt_args = OmaAptArgs::builder()
+            .sysroot(sysroot.to_string_lossy().to_string())
+            .yes(yes)
+            .another_apt_options(apt_options)
+            .dpkg_force_confold(config.native_conffile_prompt())
+            .build();
+
+        let mut apt = OmaApt::new(vec![], oma_apt_args, dry_run, AptConfig::new())?;
+        apt.mark_plan(&plan.op)?;
+
+        let auth_config = auth_config(&sysroot);
+
+        CommitChanges::builder()
+            .apt(apt)
+            .dry_run(dry_run)
+            .request_type(SummaryType::Changes)
+            .no_fixbroken(true)
+            .no_progress(no_progress)
+            .sysroot(sysroot.to_string_lossy().to_string())
+            .fix_dpkg_status(false)
+            .protect_essential(config.protect_essentials())
+            .native_conffile_prompt(config.native_conffile_prompt())
+            .yes(yes)
+            .maybe_auth_config(auth_config.as_ref())
+            .network_thread(config.network_thread())
+            .plan(&plan.op)
+            .build()
+            .run()
+    }
+}
+
+/// Print `op` as a plan to stdout
+///
+/// Packages are sorted by name, so plans made on different machines can be compared.
+pub fn print_plan(sysroot: &Path, mut op: OmaOperation) -> Result<(), OutputError> {
+    op.install.sort_unstable_by(|a, b| a.name().cmp(b.name()));
+    op.remove.sort_unstable_by(|a, b| a.name().cmp(b.name()));
+    op.suggest.sort_unstable();
+    op.recommend.sort_unstable();
+
+    let plan = Plan {
+        version: PLAN_VERSION,
+        arch: dpkg_arch(sysroot)?,
+        op,
+    };
+
+    let plan = serde_json::to_string_pretty(&plan).map_err(|e| OutputError {
+        description: fl!("plan-bad-file"),
+        source: Some(Box::new(e)),
+    })?;
+
+    println!("{plan}");
+
+    Ok(())
+}
+
+/// Check that the resolved `op` makes exactly the changes in `plan`
+pub(crate) fn check_plan(plan: &OmaOperation, op: &OmaOperation) -> Result<(), OutputError> {
+    let install = |op: &OmaOperation| {
+        op.install
+            .iter()
+            .map(|x| {
+                (
+                    x.name().to_string(),
+                    x.new_version().to_string(),
+                    x.op().clone(),
+                )
+            })
+            .collect::<HashSet<(String, String, InstallOperation)>>()
+    };
+
+    let remove = |op: &OmaOperation| {
+        op.remove
+            .iter()
+            .map(|x| {
+                (
+                    x.name().to_string(),
+                    x.details().contains(&RemoveTag::Purge),
+                )
+            })
+            .collect::<HashSet<_>>()
+    };
+
+    if install(plan) != install(op) || remove(plan) != remove(op) {
+        return Err(OutputError {
+            description: fl!("plan-changed"),
+            source: None,
+        });
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_check_plan() {
+    use oma_pm::apt::{InstallEntry, RemoveEntry};
+
+    let install = |name: &str, version: &str, op: InstallOperation| {
+        InstallEntry::builder()
+            .name(name.to_string())
+            .name_without_arch(name.to_string())
+            .new_version(version.to_string())
+            .new_size(0)
+            .pkg_urls(vec![])
+            .arch("all".to_string())
+            .download_size(0)
+            .op(op)
+            .index(0)
+            .build()
+    };
+
+    let remove = |name: &str, details: Vec<RemoveTag>| {
+        RemoveEntry::new(name.to_string(), None, 0, details, "all".to_string(), 0)
+    };
+
+    let operation = |install: Vec<InstallEntry>, remove: Vec<RemoveEntry>| OmaOperation {
+        install,
+        remove,
+        disk_size: ("+".into(), 0),
+        autoremovable: (0, 0),
+        total_download_size: 0,
+        suggest: vec![],
+        recommend: vec![],
+    };
+
+    let plan = operation(
+        vec![
+            install("foo", "2.0", InstallOperation::Upgrade),
+            install("bar", "1.0", InstallOperation::Install),
+        ],
+        vec![remove("baz", vec![RemoveTag::Purge])],
+    );
+
+    // Order and indexes in the cache do not matter
+    let mut same = operation(
+        vec![
+            install("bar", "1.0", InstallOperation::Install),
+            install("foo", "2.0", InstallOperation::Upgrade),
+        ],
+        vec![RemoveEntry::new(
+            "baz".to_string(),
+            None,
+            0,
+            vec![RemoveTag::Purge],
+            "all".to_string(),
+            42,
+        )],
+    );
+    assert!(check_plan(&plan, &same).is_ok());
+
+    same.install[0] = install("bar", "1.1", InstallOperation::Install);
+    assert!(check_plan(&plan, &same).is_err());
+
+    let upgraded = operation(
+        vec![
+            install("foo", "2.0", InstallOperation::Upgrade),
+            install("bar", "1.0", InstallOperation::Upgrade),
+        ],
+        vec![remove("baz", vec![RemoveTag::Purge])],
+    );
+    assert!(check_plan(&plan, &upgraded).is_err());
+
+    let not_purged = operation(
+        vec![
+            install("foo", "2.0", InstallOperation::Upgrade),
+            install("bar", "1.0", InstallOperation::Install),
+        ],
+        vec![remove("baz", vec![RemoveTag::AutoRemove])],
+    );
+    assert!(check_plan(&plan, &not_purged).is_err());
+}
@@ -0,0 +1,226 @@
+use std::path::{Path, PathBuf};
+
+use clap::{Args, Subcommand};
+use oma_console::print::Action;
+use oma_pm::apt::{AptConfig, FilterMode, OmaApt, OmaAptArgs};
+use oma_utils::dpkg::{add_foreign_arch, dpkg_arch, dpkg_foreign_archs, remove_foreign_arch};
+use tracing::{info, warn};
+
+use crate::{
+    color_formatter, config::Config, error::OutputError, fl, msg, success, utils::root, HTTP_CLIENT,
+};
+
+use super::utils::{auth_config, Refresh};
+use crate::args::CliExecuter;
+
+#[derive(Debug, Args)]
+pub struct Arch {
+    #[command(subcommand)]
+    arch_subcmd: Option<ArchSubCmd>,
+    /// Set sysroot target directory
+    #[arg(from_global)]
+    sysroot: PathBuf,
+    /// Run oma in “dry-run” mode. Useful for testing changes and operations without making changes to the system
+    #[arg(from_global)]
+    dry_run: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ArchSubCmd {
+    /// Enable foreign architecture(s)
+    Add {
+        /// Architecture(s) to enable (like: i386)
+        #[arg(required = true)]
+        archs: Vec<String>,
+        /// Do not refresh repository metadata
+        #[arg(long)]
+        no_refresh: bool,
+    },
+    /// Disable foreign architecture(s)
+    Remove {
+        /// Architecture(s) to disable (like: i386)
+        #[arg(required = true)]
+        archs: Vec<String>,
+        /// Do not refresh repository metadata
+        #[arg(long)]
+        no_refresh: bool,
+    },
+    /// List native and foreign architecture(s)
+    List {
+        /// Set output format as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+impl CliExecuter for Arch {
+    fn execute(self, config: &Config, no_progress: bool) -> Result<i32, OutputError> {
+        let Arch {
+            arch_subcmd,
+            sysroot,
+            dry_run,
+        } = self;
+
+        match arch_subcmd.unwrap_or(ArchSubCmd::List { json: false }) {
+            ArchSubCmd::List { json } => list(&sysroot, json),
+            ArchSubCmd::Add { archs, no_refresh } => {
+                if dry_run {
+                    info!("Running in dry-run mode, Exit.");
+                    return Ok(0);
+                }
+
+                root()?;
+
+                let native = dpkg_arch(&sysroot)?;
+                let foreign = dpkg_foreign_archs(&sysroot)?;
+                let mut changed = false;
+
+                for arch in &archs {
+                    if *arch == native || foreign.contains(arch) {
+                        info!("{}", fl!("arch-already-enabled", arch = arch.as_str()));
+                        continue;
+                    }
+
+                    add_foreign_arch(arch, &sysroot)?;
+                    success!("{}", fl!("arch-enabled", arch = arch.as_str()));
+                    changed = true;
+                }
+
+                if changed && !no_refresh {
+                    refresh(config, no_progress, &sysroot)?;
+                }
+
+                Ok(0)
+            }
+            ArchSubCmd::Remove { archs, no_refresh } => {
+                if dry_run {
+                    info!("Running in dry-run mode, Exit.");
+                    return Ok(0);
+                }
+
+                root()?;
+
+                let native = dpkg_arch(&sysroot)?;
+                let foreign = dpkg_foreign_archs(&sysroot)?;
+
+                let oma_apt_args = OmaAptArgs::builder()
+                    .sysroot(sysroot.to_string_lossy().to_string())
+                    .build();
+
+                let apt = OmaApt::new(vec![], oma_apt_args, false, AptConfig::new())?;
+
+                let mut changed = false;
+
+                for arch in &archs {
+                    if *arch == native {
+                        return Err(OutputError {
+                            description: fl!("arch-remove-native", arch = arch.as_str()),
+                            source: None,
+                        });
+                    }
+
+                    if !foreign.contains(arch) {
+                        info!("{}", fl!("arch-not-enabled", arch = arch.as_str()));
+                        continue;
+                    }
+
+                    // dpkg refuses to remove an architecture still in use
+                    let installed = apt
+                        .filter_pkgs(&[FilterMode::Installed])?
+                        .filter(|x| x.arch() == arch)
+                        .map(|x| x.fullname(false))
+                        .collect::<Vec<_>>();
+
+                    if !installed.is_empty() {
+                        warn!(
+                            "{}",
+                            fl!(
+                                "arch-has-installed-pkgs",
+                                arch = arch.as_str(),
+                                count = installed.len()
+                            )
+                        );
+
+                        for pkg in &installed {
+                            msg!("{}", color_formatter().color_str(pkg, Action::Emphasis));
+                        }
+
+                        return Err(OutputError {
+                            description: fl!(
+                                "arch-remove-installed-tips",
+                                cmd = format!("oma remove '*:{arch}'")
+                            ),
+                            source: None,
+                        });
+                    }
+
+                    remove_foreign_arch(arch, &sysroot)?;
+                    success!("{}", fl!("arch-disabled", arch = arch.as_str()));
+                    changed = true;
+                }
+
+                if changed && !no_refresh {
+                    refresh(config, no_progress, &sysroot)?;
+                }
+
+                Ok(0)
+            }
+        }
+    }
+}
+
+fn list(sysroot: &Path, json: bool) -> Result<i32, OutputError> {
+    let native = dpkg_arch(sysroot)?;
+    let foreign = dpkg_foreign_archs(sysroot)?;
+
+    if json {
+        println!(
+            "{}",
+            serde_json::json!({
+                "native": native,
+                "foreign": foreign,
+            })
+        );
+
+        return Ok(0);
+    }
+
+    println!(
+        "{} {}",
+        color_formatter()
+            .color_str(&native, Action::Emphasis)
+            .bold(),
+        color_formatter().color_str(fl!("arch-native"), Action::Secondary)
+    );
+
+    for arch in foreign {
+        println!("{}", color_formatter().color_str(arch, Action::Emphasis));
+    }
+
+    Ok(0)
+}
+
+fn refresh(config: &Config, no_progress: bool, sysroot: &Path) -> Result<(), OutputError> {
+    let apt_config = AptConfig::new();
+    let auth_config = auth_config(sysroot);
+    let sysroot = sysroot.to_string_lossy();
+
+    let builder = Refresh::builder()
+        .client(&HTTP_CLIENT)
+        .dry_run(false)
+        .no_progress(no_progress)
+        .network_thread(config.network_thread())
+        .sysroot(&sysroot)
+        .config(&apt_config)
+        .maybe_auth_config(auth_config.as_ref());
+
+    #[cfg(feature = "aosc")]
+    let refresh = builder.refresh_topics(!config.no_refresh_topics()).build();
+
+    #[cfg(not(feature = "aosc"))]
+    let refresh = builder.build();
+
+    refresh.run()?;
+
+    Ok(())
+}
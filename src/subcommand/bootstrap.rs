@@ -0,0 +1,567 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+    thread,
+};
+
+use clap::{Args, ValueEnum};
+use flume::unbounded;
+use oma_history::SummaryType;
+use oma_pm::{
+    apt::{AptConfig, DownloadConfig, OmaApt, OmaAptArgs},
+    matches::{GetArchMethod, PackagesMatcher},
+};
+use tracing::{debug, error, info, warn};
+
+use crate::{
+    config::Config,
+    error::OutputError,
+    fl,
+    pb::{NoProgressBar, OmaMultiProgressBar, RenderDownloadProgress},
+    success,
+    utils::{is_root, root},
+    HTTP_CLIENT,
+};
+
+use super::utils::{handle_no_result, io_error, is_terminal, CommitChanges, Refresh};
+use crate::args::CliExecuter;
+use crate::solver;
+
+#[cfg(feature = "aosc")]
+const DEFAULT_MIRROR: &str = "https://repo.aosc.io/debs";
+#[cfg(not(feature = "aosc"))]
+const DEFAULT_MIRROR: &str = "https://deb.debian.org/debian";
+
+/// Set by oma itself when it re-executes under `unshare` or `fakechroot`
+const INNER_MODE_ENV: &str = "OMA_BOOTSTRAP_INNER_MODE";
+
+const LAYOUT_DIRS: &[&str] = &[
+    "etc/apt/apt.conf.d",
+    "etc/apt/preferences.d",
+    "etc/apt/sources.list.d",
+    "etc/apt/trusted.gpg.d",
+    "var/lib/dpkg/info",
+    "var/lib/dpkg/updates",
+    "var/lib/dpkg/triggers",
+    "var/lib/apt/lists/partial",
+    "var/cache/apt/archives/partial",
+    "var/log/apt",
+    "dev",
+    "proc",
+    "sys",
+    "tmp",
+];
+
+#[derive(Debug, Args)]
+pub struct Bootstrap {
+    /// Directory to bootstrap the system into
+    target: PathBuf,
+    /// Suite (branch) to bootstrap from (like: stable)
+    #[arg(long)]
+    suite: String,
+    /// Mirror to bootstrap from
+    #[arg(long, default_value = DEFAULT_MIRROR)]
+    mirror: String,
+    /// Component(s) of the suite to enable
+    #[arg(long, value_delimiter = ',', default_value = "main")]
+    components: Vec<String>,
+    /// Additional package(s) to install (like: --include vim,curl)
+    #[arg(long, value_delimiter = ',')]
+    include: Vec<String>,
+    /// Keyring(s) to verify the mirror with
+    #[arg(long)]
+    keyring: Vec<PathBuf>,
+    /// Also trust keyrings in /etc/apt/trusted.gpg.d of the host
+    #[arg(long)]
+    host_keyrings: bool,
+    /// Package set to bootstrap
+    #[arg(long, value_enum, default_value_t)]
+    variant: Variant,
+    /// How to get privileges for unpacking and configuring packages
+    #[arg(long, value_enum, default_value_t)]
+    mode: Mode,
+    /// Run oma in “dry-run” mode. Useful for testing changes and operations without making changes to the system
+    #[arg(from_global)]
+    dry_run: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Variant {
+    /// Essential package(s) only
+    Essential,
+    /// Essential package(s) and package(s) with `Priority: required`
+    #[default]
+    Required,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Mode {
+    /// `root` when running as root, otherwise `unshare`
+    #[default]
+    Auto,
+    /// Run as root, API filesystems are mounted into the target in a private mount namespace
+    Root,
+    /// Run as root in a new user namespace, needs subordinate ids of the user
+    Unshare,
+    /// Run under `fakechroot` and `fakeroot`, no privileges are needed
+    Fakechroot,
+}
+
+impl Mode {
+    /// The mode to run in, `is_root` is whether oma runs as root
+    fn resolve(self, is_root: bool) -> RunMode {
+        match self {
+            Mode::Auto if is_root => RunMode::Root,
+            Mode::Auto => RunMode::Unshare,
+            Mode::Root => RunMode::Root,
+            Mode::Unshare => RunMode::Unshare,
+            Mode::Fakechroot => RunMode::Fakechroot,
+        }
+    }
+}
+
+/// [`Mode`] with `auto` resolved
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RunMode {
+    Root,
+    Unshare,
+    Fakechroot,
+}
+
+impl RunMode {
+    /// Parse the value of [`INNER_MODE_ENV`]
+    fn from_env(s: &str) -> Option<Self> {
+        match s {
+            "root" => Some(RunMode::Root),
+            "unshare" => Some(RunMode::Unshare),
+            "fakechroot" => Some(RunMode::Fakechroot),
+            _ => None,
+        }
+    }
+
+    fn as_env(self) -> &'static str {
+        match self {
+            RunMode::Root => "root",
+            RunMode::Unshare => "unshare",
+            RunMode::Fakechroot => "fakechroot",
+        }
+    }
+
+    /// Command to re-execute oma under
+    fn wrapper(self) -> &'static [&'static str] {
+        match self {
+            // Keep API filesystems mounted into the target away from the host
+            RunMode::Root => &["unshare", "--mount", "--propagation", "private"],
+            RunMode::Unshare => &[
+                "unshare",
+                "--map-root-user",
+                "--map-auto",
+                "--mount",
+                "--pid",
+                "--fork",
+            ],
+            RunMode::Fakechroot => &["fakechroot", "fakeroot"],
+        }
+    }
+}
+
+impl CliExecuter for Bootstrap {
+    fn execute(self, config: &Config, no_progress: bool) -> Result<i32, OutputError> {
+        if self.dry_run {
+            info!("Running in dry-run mode, Exit.");
+            return Ok(0);
+        }
+
+        // Inside `unshare` or `fakechroot`, oma already runs as (fake) root in a mount namespace
+        let mode = match env::var(INNER_MODE_ENV)
+            .ok()
+            .and_then(|x| RunMode::from_env(&x))
+        {
+            Some(mode) => mode,
+            None => {
+                let mode = self.mode.resolve(is_root());
+
+                match mode {
+                    RunMode::Root => root()?,
+                    RunMode::Unshare => check_unshare()?,
+                    RunMode::Fakechroot => {}
+                }
+
+                return reexec(mode);
+            }
+        };
+
+        self.run(config, no_progress, mode)
+    }
+}
+
+impl Bootstrap {
+    fn run(self, config: &Config, no_progress: bool, mode: RunMode) -> Result<i32, OutputError> {
+        let Bootstrap {
+            target,
+            suite,
+            mirror,
+            components,
+            include,
+            keyring,
+            host_keyrings,
+            variant,
+            ..
+        } = self;
+
+        fs::create_dir_all(&target).map_err(|e| io_error(&target, e))?;
+        let target = target.canonicalize().map_err(|e| io_error(&target, e))?;
+
+        let status = target.join("var/lib/dpkg/status");
+        if fs::metadata(&status).is_ok_and(|x| x.len() > 0) {
+            return Err(OutputError {
+                description: fl!(
+                    "bootstrap-target-not-empty",
+                    path = target.display().to_string()
+                ),
+                source: None,
+            });
+        }
+
+        info!(
+            "{}",
+            fl!(
+                "bootstrap-preparing",
+                path = target.display().to_string(),
+                suite = suite.as_str()
+            )
+        );
+
+        prepare_layout(
+            &target,
+            &mirror,
+            &suite,
+            &components,
+            &keyring,
+            host_keyrings,
+        )?;
+
+        // dpkg maintainer scripts expect /dev and /proc, `fakechroot` can not mount anything
+        if mode != RunMode::Fakechroot {
+            mount_api_fs(&target)?;
+        }
+
+        let target_str = target.to_string_lossy().to_string();
+        let apt_config = AptConfig::new();
+
+        let builder = Refresh::builder()
+            .client(&HTTP_CLIENT)
+            .no_progress(no_progress)
+            .network_thread(config.network_thread())
+            .sysroot(&target_str)
+            .config(&apt_config);
+
+        #[cfg(feature = "aosc")]
+        let refresh = builder.refresh_topics(false).build();
+
+        #[cfg(not(feature = "aosc"))]
+        let refresh = builder.build();
+
+        refresh.run()?;
+
+        // Essential packages and their dependencies must be unpacked by hand, since there is
+        // no shell or dpkg in the target yet
+        let debs = {
+            let oma_apt_args = OmaAptArgs::builder()
+                .solver(solver())
+                .sysroot(target_str.clone())
+                .no_install_recommends(true)
+                .build();
+
+            let mut apt = OmaApt::new(vec![], oma_apt_args, false, AptConfig::new())?;
+            let essential = apt.base_system_pkgs(false)?;
+            apt.install(&essential, false)?;
+            apt.resolve(false, false)?;
+            let essential = apt.marked_install_pkgs()?;
+
+            info!("{}", fl!("bootstrap-essential", count = essential.len()));
+
+            let archives = target.join("var/cache/apt/archives");
+            let (tx, rx) = unbounded();
+
+            thread::spawn(move || {
+                let mut pb: Box<dyn RenderDownloadProgress> = if no_progress || !is_terminal() {
+                    Box::new(NoProgressBar::default())
+                } else {
+                    Box::new(OmaMultiProgressBar::default())
+                };
+                pb.render_progress(&rx);
+            });
+
+            let summary = apt.download(
+                &HTTP_CLIENT,
+                essential,
+                DownloadConfig {
+                    network_thread: Some(config.network_thread()),
+                    download_dir: Some(&archives),
+                    auth: None,
+                },
+                false,
+                |event| async {
+                    if let Err(e) = tx.send_async(event).await {
+                        error!("{}", e);
+                    }
+                },
+            )?;
+
+            if !summary.is_download_success() {
+                return Err(OutputError {
+                    description: fl!("download-failed-with-len", len = summary.failed.len()),
+                    source: None,
+                });
+            }
+
+            summary
+                .success
+                .into_iter()
+                .map(|x| x.file_name)
+                .collect::<Vec<_>>()
+        };
+
+        install_essential(&target, &debs)?;
+
+        // The rest are installed by apt running dpkg inside the target
+        let apt_config = AptConfig::new();
+        apt_config.set("DPkg::Chroot-Directory", &target_str);
+
+        let oma_apt_args = OmaAptArgs::builder()
+            .sysroot(target_str.clone())
+            .no_install_recommends(true)
+            .build();
+
+        let mut apt = OmaApt::new(vec![], oma_apt_args, false, apt_config)?;
+        let mut pkgs = apt.base_system_pkgs(variant == Variant::Required)?;
+
+        let matcher = PackagesMatcher::builder()
+            .cache(&apt.cache)
+            .native_arch(GetArchMethod::SpecifySysroot(&target))
+            .build();
+
+        let (include_pkgs, no_result) =
+            matcher.match_pkgs_and_versions(include.iter().map(|x| x.as_str()))?;
+        handle_no_result(&target, no_result, no_progress)?;
+
+        let request = include_pkgs
+            .iter()
+            .map(|x| format!("{} {}", x.raw_pkg.fullname(true), x.version_raw.version()))
+            .collect::<Vec<_>>();
+
+        pkgs.extend(include_pkgs);
+        apt.install(&pkgs, false)?;
+
+        let code = CommitChanges::builder()
+            .apt(apt)
+            .dry_run(false)
+            .request_type(SummaryType::Install(request))
+            .no_progress(no_progress)
+            .sysroot(target_str)
+            .fix_dpkg_status(false)
+            .yes(true)
+            .network_thread(config.network_thread())
+            .build()
+            .run()?;
+
+        if code == 0 {
+            success!(
+                "{}",
+                fl!("bootstrap-success", path = target.display().to_string())
+            );
+        }
+
+        Ok(code)
+    }
+}
+
+/// `--map-auto` of `unshare` is available since util-linux 2.38
+fn check_unshare() -> Result<(), OutputError> {
+    let output = Command::new("unshare")
+        .arg("--version")
+        .output()
+        .map_err(|e| OutputError {
+            description: fl!("bootstrap-reexec-failed", cmd = "unshare"),
+            source: Some(Box::new(e)),
+        })?;
+
+    // Like: unshare from util-linux 2.38.1
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let version = stdout.split_whitespace().last().unwrap_or_default();
+
+    let mut parts = version.split('.').map(|x| x.parse::<u32>().unwrap_or(0));
+    let major = parts.next().unwrap_or(0);
+    let minor = parts.next().unwrap_or(0);
+
+    if (major, minor) < (2, 38) {
+        return Err(OutputError {
+            description: fl!("bootstrap-unshare-too-old", version = version),
+            source: None,
+        });
+    }
+
+    Ok(())
+}
+
+/// Run oma again with the same arguments under the wrapper of `mode`
+fn reexec(mode: RunMode) -> Result<i32, OutputError> {
+    let wrapper = mode.wrapper();
+
+    let exe = env::current_exe().map_err(|e| OutputError {
+        description: fl!("bootstrap-reexec-failed", cmd = wrapper[0]),
+        source: Some(Box::new(e)),
+    })?;
+
+    debug!("Re-executing {} under {wrapper:?}", exe.display());
+
+    let status = Command::new(wrapper[0])
+        .args(&wrapper[1..])
+        .arg(exe)
+        .args(env::args_os().skip(1))
+        .env(INNER_MODE_ENV, mode.as_env())
+        .status()
+        .map_err(|e| OutputError {
+            description: fl!("bootstrap-reexec-failed", cmd = wrapper[0]),
+            source: Some(Box::new(e)),
+        })?;
+
+    Ok(status.code().unwrap_or(1))
+}
+
+/// Create the minimal dpkg/apt layout, sources and keyrings
+fn prepare_layout(
+    target: &Path,
+    mirror: &str,
+    suite: &str,
+    components: &[String],
+    keyrings: &[PathBuf],
+    host_keyrings: bool,
+) -> Result<(), OutputError> {
+    for dir in LAYOUT_DIRS {
+        let dir = target.join(dir);
+        fs::create_dir_all(&dir).map_err(|e| io_error(&dir, e))?;
+    }
+
+    for file in ["var/lib/dpkg/status", "var/lib/dpkg/available"] {
+        let file = target.join(file);
+        if !file.exists() {
+            fs::write(&file, "").map_err(|e| io_error(&file, e))?;
+        }
+    }
+
+    let sources_list = target.join("etc/apt/sources.list");
+    fs::write(
+        &sources_list,
+        format!("deb {mirror} {suite} {}\n", components.join(" ")),
+    )
+    .map_err(|e| io_error(&sources_list, e))?;
+
+    let mut keyrings = keyrings.to_vec();
+
+    if host_keyrings {
+        let host = Path::new("/etc/apt/trusted.gpg.d");
+        keyrings.extend(
+            fs::read_dir(host)
+                .map_err(|e| io_error(host, e))?
+                .filter_map(|x| x.ok())
+                .map(|x| x.path())
+                .filter(|x| x.is_file()),
+        );
+    }
+
+    if keyrings.is_empty() {
+        warn!("{}", fl!("bootstrap-no-keyring"));
+    }
+
+    let trusted = target.join("etc/apt/trusted.gpg.d");
+
+    for keyring in keyrings {
+        let Some(name) = keyring.file_name() else {
+            continue;
+        };
+
+        fs::copy(&keyring, trusted.join(name)).map_err(|e| io_error(&keyring, e))?;
+    }
+
+    Ok(())
+}
+
+/// Unpack Essential packages with `dpkg-deb`, then let dpkg in the target register and configure
+/// them
+fn install_essential(target: &Path, debs: &[String]) -> Result<(), OutputError> {
+    let archives = target.join("var/cache/apt/archives");
+
+    for deb in debs {
+        debug!("Extracting {deb}");
+        run(Command::new("dpkg-deb")
+            .arg("--extract")
+            .arg(archives.join(deb))
+            .arg(target))?;
+    }
+
+    info!("{}", fl!("bootstrap-configure-essential"));
+
+    run(Command::new("chroot")
+        .arg(target)
+        .args(["dpkg", "--force-depends", "--install"])
+        .args(debs.iter().map(|x| format!("/var/cache/apt/archives/{x}"))))?;
+
+    Ok(())
+}
+
+/// Mount API filesystems into the target
+///
+/// oma always runs in a private mount namespace here, so these are gone with oma itself and
+/// never propagate to the host.
+fn mount_api_fs(target: &Path) -> Result<(), OutputError> {
+    run(Command::new("mount")
+        .args(["-t", "proc", "proc"])
+        .arg(target.join("proc")))?;
+
+    for dir in ["dev", "sys"] {
+        run(Command::new("mount")
+            .arg("--rbind")
+            .arg(Path::new("/").join(dir))
+            .arg(target.join(dir)))?;
+    }
+
+    Ok(())
+}
+
+fn run(cmd: &mut Command) -> Result<(), OutputError> {
+    debug!("Running {cmd:?}");
+
+    let program = cmd.get_program().to_string_lossy().to_string();
+    let status = cmd.status().map_err(|e| OutputError {
+        description: fl!("bootstrap-command-failed", cmd = program.as_str()),
+        source: Some(Box::new(e)),
+    })?;
+
+    if !status.success() {
+        return Err(OutputError {
+            description: fl!("bootstrap-command-failed", cmd = program),
+            source: None,
+        });
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_resolve_mode() {
+    assert_eq!(Mode::Auto.resolve(true), RunMode::Root);
+    assert_eq!(Mode::Auto.resolve(false), RunMode::Unshare);
+    assert_eq!(Mode::Root.resolve(false), RunMode::Root);
+    assert_eq!(Mode::Unshare.resolve(true), RunMode::Unshare);
+    assert_eq!(Mode::Fakechroot.resolve(true), RunMode::Fakechroot);
+
+    for mode in [RunMode::Root, RunMode::Unshare, RunMode::Fakechroot] {
+        assert_eq!(RunMode::from_env(mode.as_env()), Some(mode));
+    }
+
+    assert_eq!(RunMode::from_env("auto"), None);
+    assert_eq!(RunMode::from_env(""), None);
+}
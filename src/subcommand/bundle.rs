@@ -0,0 +1,640 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+    thread,
+};
+
+use ahash::HashSet;
+use bon::Builder;
+use clap::{Args, Subcommand};
+use flume::unbounded;
+use indexmap::{IndexMap, IndexSet};
+use oma_history::SummaryType;
+use oma_pm::{
+    apt::{AptConfig, DownloadConfig, OmaApt, OmaAptArgs, Upgrade as AptUpgrade},
+    matches::{GetArchMethod, PackagesMatcher},
+};
+use oma_refresh::inrelease::{verify_inrelease, InReleaseChecksum, InReleaseError, Release};
+use oma_utils::dpkg::dpkg_arch;
+use serde::{Deserialize, Serialize};
+use sha2::{Sha256, Sha512};
+use tempfile::TempDir;
+use tracing::{debug, error, info};
+
+use crate::{
+    config::Config,
+    error::OutputError,
+    fl,
+    pb::{NoProgressBar, OmaMultiProgressBar, RenderDownloadProgress},
+    success,
+    utils::{dbus_check, root},
+    HTTP_CLIENT,
+};
+
+use super::utils::{
+    auth_config, file_checksum, handle_no_result, io_error, is_terminal, lock_oma,
+    no_check_dbus_warn, CommitChanges,
+};
+use crate::args::CliExecuter;
+use crate::solver;
+
+const MANIFEST: &str = "oma-bundle.json";
+const MANIFEST_VERSION: u32 = 1;
+
+#[derive(Debug, Args)]
+pub struct Bundle {
+    #[command(subcommand)]
+    bundle_subcmd: BundleSubCmd,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum BundleSubCmd {
+    /// Create an offline bundle of a transaction, to apply it on an air-gapped system
+    Create {
+        /// Package(s) to install on the offline system
+        packages: Vec<String>,
+        /// Upgrade all packages of the offline system
+        #[arg(long, required_unless_present = "packages")]
+        upgrade: bool,
+        /// Copy of the dpkg status file (/var/lib/dpkg/status) of the offline system
+        #[arg(long)]
+        status: Option<PathBuf>,
+        /// Path of the bundle to create
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Set sysroot target directory
+        #[arg(from_global)]
+        sysroot: PathBuf,
+        /// Set apt options
+        #[arg(from_global)]
+        apt_options: Vec<String>,
+    },
+    /// Verify and apply an offline bundle
+    Apply {
+        /// Path of the bundle
+        bundle: PathBuf,
+        /// Bypass confirmation prompts
+        #[arg(short, long)]
+        yes: bool,
+        /// Set sysroot target directory
+        #[arg(from_global)]
+        sysroot: PathBuf,
+        /// Run oma in “dry-run” mode. Useful for testing changes and operations without making changes to the system
+        #[arg(from_global)]
+        dry_run: bool,
+        /// Run oma do not check dbus
+        #[arg(from_global)]
+        no_check_dbus: bool,
+    },
+}
+
+/// Describes the content of a bundle
+///
+/// Packages are verified on the offline system by their checksums in `indexes`, which are in
+/// turn verified by the signed InRelease files.
+#[derive(Debug, Serialize, Deserialize)]
+struct BundleManifest {
+    version: u32,
+    arch: String,
+    upgrade: bool,
+    /// Requested packages
+    request: Vec<String>,
+    packages: Vec<BundlePackage>,
+    indexes: Vec<BundleIndex>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BundlePackage {
+    name: String,
+    version: String,
+    arch: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BundleIndex {
+    /// InRelease file name in `lists/`
+    release: String,
+    /// Packages file names in `lists/`, which are listed in `release`
+    packages: Vec<String>,
+}
+
+impl CliExecuter for Bundle {
+    fn execute(self, config: &Config, no_progress: bool) -> Result<i32, OutputError> {
+        match self.bundle_subcmd {
+            BundleSubCmd::Create {
+                packages,
+                upgrade,
+                status,
+                output,
+                sysroot,
+                apt_options,
+            } => CreateBundle::builder()
+                .config(config)
+                .no_progress(no_progress)
+                .packages(packages)
+                .upgrade(upgrade)
+                .maybe_status(status)
+                .output(output)
+                .sysroot(sysroot)
+                .apt_options(apt_options)
+                .build()
+                .run(),
+            BundleSubCmd::Apply {
+                bundle,
+                yes,
+                sysroot,
+                dry_run,
+                no_check_dbus,
+            } => {
+                if !dry_run {
+                    root()?;
+                    lock_oma()?;
+                }
+
+                let _fds = if !no_check_dbus && !config.no_check_dbus() && !dry_run {
+                    Some(dbus_check(yes)?)
+                } else {
+                    no_check_dbus_warn();
+                    None
+                };
+
+                apply(config, no_progress, bundle, yes, sysroot, dry_run)
+            }
+        }
+    }
+}
+
+#[derive(Builder)]
+struct CreateBundle<'a> {
+    config: &'a Config,
+    no_progress: bool,
+    packages: Vec<String>,
+    upgrade: bool,
+    status: Option<PathBuf>,
+    output: PathBuf,
+    sysroot: PathBuf,
+    apt_options: Vec<String>,
+}
+
+impl CreateBundle<'_> {
+    fn run(self) -> Result<i32, OutputError> {
+        let CreateBundle {
+            config,
+            no_progress,
+            packages,
+            upgrade,
+            status,
+            output,
+            sysroot,
+            mut apt_options,
+        } = self;
+
+        // Resolve as if on the offline system
+        if let Some(status) = status {
+            let status = status.canonicalize().map_err(|e| io_error(&status, e))?;
+            apt_options.push(format!("Dir::State::status={}", status.display()));
+        }
+
+        let oma_apt_args = OmaAptArgs::builder()
+            .solver(solver())
+            .sysroot(sysroot.to_string_lossy().to_string())
+            .another_apt_options(apt_options)
+            .build();
+
+        let mut apt = OmaApt::new(vec![], oma_apt_args, false, AptConfig::new())?;
+
+        if upgrade {
+            apt.upgrade(AptUpgrade::FullUpgrade)?;
+        }
+
+        let matcher = PackagesMatcher::builder()
+            .cache(&apt.cache)
+            .filter_downloadable_candidate(true)
+            .native_arch(GetArchMethod::SpecifySysroot(&sysroot))
+            .build();
+
+        let (pkgs, no_result) =
+            matcher.match_pkgs_and_versions(packages.iter().map(|x| x.as_str()))?;
+        handle_no_result(&sysroot, no_result, no_progress)?;
+
+        let request = pkgs
+            .iter()
+            .map(|x| x.raw_pkg.fullname(true))
+            .collect::<Vec<_>>();
+
+        apt.install(&pkgs, false)?;
+        apt.resolve(false, false)?;
+
+        let to_download = apt.marked_install_pkgs()?;
+
+        if to_download.is_empty() {
+            info!("{}", fl!("bundle-nothing-to-do"));
+            return Ok(0);
+        }
+
+        // Collect the signed indexes listing the packages
+        let lists_dir = sysroot.join("var/lib/apt/lists");
+        let mut indexes: IndexMap<String, IndexSet<String>> = IndexMap::new();
+        let mut bundle_pkgs = vec![];
+
+        for pkg in &to_download {
+            let ver = pkg.version(&apt.cache);
+
+            bundle_pkgs.push(BundlePackage {
+                name: pkg.raw_pkg.fullname(true),
+                version: ver.version().to_string(),
+                arch: ver.arch().to_string(),
+            });
+
+            for pkg_file in ver.package_files().filter(|x| x.is_downloadable()) {
+                let Some(name) = pkg_file
+                    .filename()
+                    .and_then(|x| Path::new(x).file_name()?.to_str().map(|x| x.to_string()))
+                else {
+                    continue;
+                };
+
+                let release = find_release(&lists_dir, &name).ok_or_else(|| OutputError {
+                    description: fl!("bundle-no-inrelease", index = name.as_str()),
+                    source: None,
+                })?;
+
+                indexes.entry(release).or_default().insert(name);
+            }
+        }
+
+        let staging = temp_dir()?;
+        let debs_dir = staging.path().join("debs");
+        let staging_lists = staging.path().join("lists");
+
+        for dir in [&debs_dir, &staging_lists] {
+            fs::create_dir_all(dir).map_err(|e| io_error(dir, e))?;
+        }
+
+        for file in indexes
+            .iter()
+            .flat_map(|(release, pkgs)| [release].into_iter().chain(pkgs))
+        {
+            let from = lists_dir.join(file);
+            fs::copy(&from, staging_lists.join(file)).map_err(|e| io_error(&from, e))?;
+        }
+
+        let (tx, rx) = unbounded();
+
+        thread::spawn(move || {
+            let mut pb: Box<dyn RenderDownloadProgress> = if no_progress || !is_terminal() {
+                Box::new(NoProgressBar::default())
+            } else {
+                Box::new(OmaMultiProgressBar::default())
+            };
+            pb.render_progress(&rx);
+        });
+
+        let summary = apt.download(
+            &HTTP_CLIENT,
+            to_download,
+            DownloadConfig {
+                network_thread: Some(config.network_thread()),
+                download_dir: Some(&debs_dir),
+                auth: auth_config(&sysroot).as_ref(),
+            },
+            false,
+            |event| async {
+                if let Err(e) = tx.send_async(event).await {
+                    error!("{}", e);
+                }
+            },
+        )?;
+
+        if !summary.is_download_success() {
+            return Err(OutputError {
+                description: fl!("download-failed-with-len", len = summary.failed.len()),
+                source: None,
+            });
+        }
+
+        let manifest = BundleManifest {
+            version: MANIFEST_VERSION,
+            arch: dpkg_arch(&sysroot)?,
+            upgrade,
+            request,
+            packages: bundle_pkgs,
+            indexes: indexes
+                .into_iter()
+                .map(|(release, packages)| BundleIndex {
+                    release,
+                    packages: packages.into_iter().collect(),
+                })
+                .collect(),
+        };
+
+        let manifest_path = staging.path().join(MANIFEST);
+        fs::write(
+            &manifest_path,
+            serde_json::to_vec_pretty(&manifest).map_err(|e| OutputError {
+                description: e.to_string(),
+                source: None,
+            })?,
+        )
+        .map_err(|e| io_error(&manifest_path, e))?;
+
+        run_tar(
+            Command::new("tar")
+                .arg("-cf")
+                .arg(&output)
+                .arg("-C")
+                .arg(staging.path())
+                .arg("."),
+        )?;
+
+        success!(
+            "{}",
+            fl!(
+                "bundle-created",
+                count = manifest.packages.len(),
+                path = output.display().to_string()
+            )
+        );
+
+        Ok(0)
+    }
+}
+
+fn apply(
+    config: &Config,
+    no_progress: bool,
+    bundle: PathBuf,
+    yes: bool,
+    sysroot: PathBuf,
+    dry_run: bool,
+) -> Result<i32, OutputError> {
+    let dir = temp_dir()?;
+
+    run_tar(
+        Command::new("tar")
+            .arg("-xf")
+            .arg(&bundle)
+            .arg("-C")
+            .arg(dir.path()),
+    )?;
+
+    let manifest_path = dir.path().join(MANIFEST);
+    let manifest = fs::read(&manifest_path).map_err(|e| io_error(&manifest_path, e))?;
+    let manifest: BundleManifest = serde_json::from_slice(&manifest).map_err(|e| OutputError {
+        description: fl!("bundle-bad-manifest"),
+        source: Some(Box::new(e)),
+    })?;
+
+    if manifest.version != MANIFEST_VERSION {
+        return Err(OutputError {
+            description: fl!("bundle-bad-manifest"),
+            source: None,
+        });
+    }
+
+    let arch = dpkg_arch(&sysroot)?;
+    if manifest.arch != arch {
+        return Err(OutputError {
+            description: fl!(
+                "bundle-arch-mismatch",
+                bundle = manifest.arch.as_str(),
+                arch = arch
+            ),
+            source: None,
+        });
+    }
+
+    info!("{}", fl!("bundle-verifying"));
+
+    let checksums = verify_indexes(&dir.path().join("lists"), &manifest.indexes, &sysroot)?;
+
+    let debs_dir = dir.path().join("debs");
+    let mut local_debs = vec![];
+
+    for entry in fs::read_dir(&debs_dir).map_err(|e| io_error(&debs_dir, e))? {
+        let path = entry.map_err(|e| io_error(&debs_dir, e))?.path();
+        let checksum = file_checksum::<Sha256>(&path)?;
+
+        if !checksums.contains(&checksum) {
+            return Err(OutputError {
+                description: fl!("bundle-untrusted-deb", path = path.display().to_string()),
+                source: None,
+            });
+        }
+
+        debug!("{} is verified", path.display());
+        local_debs.push(path.to_string_lossy().to_string());
+    }
+
+    // Bundled packages are registered as local packages
+    let oma_apt_args = OmaAptArgs::builder()
+        .solver(solver())
+        .sysroot(sysroot.to_string_lossy().to_string())
+        .yes(yes)
+        .dpkg_force_confold(config.native_conffile_prompt())
+        .build();
+
+    let mut apt = OmaApt::new(local_debs.clone(), oma_apt_args, dry_run, AptConfig::new())?;
+
+    if manifest.upgrade {
+        apt.upgrade(AptUpgrade::FullUpgrade)?;
+    }
+
+    let matcher = PackagesMatcher::builder()
+        .cache(&apt.cache)
+        .native_arch(GetArchMethod::SpecifySysroot(&sysroot))
+        .build();
+
+    let mut pkgs = vec![];
+
+    for deb in &local_debs {
+        pkgs.extend(matcher.match_local_glob(deb)?);
+    }
+
+    // A partial bundle would silently install only some of the packages
+    for pkg in &manifest.packages {
+        let found = pkgs.iter().any(|x| {
+            x.raw_pkg.fullname(true) == pkg.name && x.version_raw.version() == pkg.version
+        });
+
+        if !found {
+            return Err(OutputError {
+                description: fl!(
+                    "bundle-missing-deb",
+                    name = pkg.name.as_str(),
+                    version = pkg.version.as_str()
+                ),
+                source: None,
+            });
+        }
+    }
+
+    pkgs.retain(|x| manifest.request.contains(&x.raw_pkg.fullname(true)));
+
+    apt.install(&pkgs, false)?;
+
+    let request_type = if manifest.upgrade {
+        SummaryType::Upgrade(manifest.request)
+    } else {
+        SummaryType::Install(manifest.request)
+    };
+
+    CommitChanges::builder()
+        .apt(apt)
+        .dry_run(dry_run)
+        .request_type(request_type)
+        .no_progress(no_progress)
+        .sysroot(sysroot.to_string_lossy().to_string())
+        .protect_essential(config.protect_essentials())
+        .native_conffile_prompt(config.native_conffile_prompt())
+        .yes(yes)
+        .network_thread(config.network_thread())
+        .build()
+        .run()
+}
+
+/// Verify InRelease files by signatures, and Packages files by checksums in them
+///
+/// Returns SHA256 checksums of all packages in the verified Packages files.
+fn verify_indexes(
+    lists: &Path,
+    indexes: &[BundleIndex],
+    sysroot: &Path,
+) -> Result<HashSet<String>, OutputError> {
+    let mut res = HashSet::with_hasher(ahash::RandomState::new());
+
+    for index in indexes {
+        let path = lists.join(&index.release);
+        let inrelease = fs::read_to_string(&path).map_err(|e| io_error(&path, e))?;
+
+        let verify_error = |e: InReleaseError| OutputError {
+            description: fl!("bundle-verify-failed", path = index.release.as_str()),
+            source: Some(Box::new(e)),
+        };
+
+        let inrelease =
+            verify_inrelease(&inrelease, None, sysroot, &path, false).map_err(verify_error)?;
+        let release: Release = inrelease.parse().map_err(verify_error)?;
+        let (typ, items) = release
+            .get_or_try_init_checksum_type_and_list()
+            .map_err(verify_error)?;
+
+        for name in &index.packages {
+            let path = lists.join(name);
+
+            let checksum = match typ {
+                InReleaseChecksum::Sha256 => file_checksum::<Sha256>(&path)?,
+                InReleaseChecksum::Sha512 => file_checksum::<Sha512>(&path)?,
+                InReleaseChecksum::Md5 => {
+                    return Err(OutputError {
+                        description: fl!("bundle-verify-failed", path = name.as_str()),
+                        source: None,
+                    })
+                }
+            };
+
+            if !items.iter().any(|x| x.checksum == checksum) {
+                return Err(OutputError {
+                    description: fl!("bundle-verify-failed", path = name.as_str()),
+                    source: None,
+                });
+            }
+
+            let packages = fs::read_to_string(&path).map_err(|e| io_error(&path, e))?;
+            res.extend(packages_sha256(&packages));
+        }
+    }
+
+    Ok(res)
+}
+
+/// Find the InRelease file of a Packages file in apt lists directory
+///
+/// Both share the same prefix, like `repo.aosc.io_debs_dists_stable_`.
+fn find_release(lists_dir: &Path, packages: &str) -> Option<String> {
+    packages
+        .rmatch_indices('_')
+        .map(|(i, _)| format!("{}_InRelease", &packages[..i]))
+        .find(|x| lists_dir.join(x).is_file())
+}
+
+/// SHA256 checksums of all stanzas in a Packages file
+fn packages_sha256(packages: &str) -> impl Iterator<Item = String> + '_ {
+    packages
+        .lines()
+        .filter_map(|x| x.strip_prefix("SHA256:"))
+        .map(|x| x.trim().to_ascii_lowercase())
+}
+
+fn run_tar(cmd: &mut Command) -> Result<(), OutputError> {
+    debug!("Running {cmd:?}");
+
+    let status = cmd.status().map_err(|e| OutputError {
+        description: fl!("bundle-tar-failed"),
+        source: Some(Box::new(e)),
+    })?;
+
+    if !status.success() {
+        return Err(OutputError {
+            description: fl!("bundle-tar-failed"),
+            source: None,
+        });
+    }
+
+    Ok(())
+}
+
+/// Create a private (0700, random name) working directory, removed when dropped
+fn temp_dir() -> Result<TempDir, OutputError> {
+    tempfile::Builder::new()
+        .prefix("oma-bundle-")
+        .tempdir()
+        .map_err(|e| io_error(&env::temp_dir(), e))
+}
+
+#[test]
+fn test_packages_sha256() {
+    let packages = "Package: foo\nVersion: 1\nSHA256: ABCD\n\nPackage: bar\nSHA256: ef01\n";
+    assert_eq!(
+        packages_sha256(packages).collect::<Vec<_>>(),
+        vec!["abcd".to_string(), "ef01".to_string()]
+    );
+}
+
+#[test]
+fn test_find_release() {
+    let lists = tempfile::tempdir().unwrap();
+    let lists = lists.path();
+
+    for name in [
+        "repo.aosc.io_debs_dists_stable_InRelease",
+        "repo.aosc.io_debs_dists_stable-proposed_InRelease",
+    ] {
+        fs::write(lists.join(name), "").unwrap();
+    }
+
+    assert_eq!(
+        find_release(
+            lists,
+            "repo.aosc.io_debs_dists_stable_main_binary-amd64_Packages"
+        )
+        .as_deref(),
+        Some("repo.aosc.io_debs_dists_stable_InRelease")
+    );
+    assert_eq!(
+        find_release(
+            lists,
+            "repo.aosc.io_debs_dists_stable-proposed_main_binary-all_Packages"
+        )
+        .as_deref(),
+        Some("repo.aosc.io_debs_dists_stable-proposed_InRelease")
+    );
+    assert_eq!(
+        find_release(
+            lists,
+            "mirrors.example.com_debs_dists_stable_main_binary-amd64_Packages"
+        ),
+        None
+    );
+}
@@ -0,0 +1,312 @@
+use std::{
+    io::{stdout, Write},
+    path::{Path, PathBuf},
+    sync::atomic::Ordering,
+};
+
+use clap::Args;
+use futures::{stream, StreamExt};
+use oma_pm::{
+    apt::{AptConfig, InstallEntry, InstallOperation, OmaApt, OmaAptArgs},
+    changelog::{
+        entries_newer_than, parse_changelog, read_local_changelog, ChangelogInfo, ChangelogKind,
+    },
+    matches::{GetArchMethod, PackagesMatcher},
+};
+use tracing::{debug, info};
+
+use crate::{
+    args::CliExecuter,
+    config::{ChangelogConfig, Config},
+    error::OutputError,
+    fl,
+    table::oma_display_with_title,
+    ALLOWCTRLC, HTTP_CLIENT, RT,
+};
+
+use super::utils::{handle_no_result, is_terminal};
+
+#[derive(Debug, Args)]
+pub struct Changelog {
+    /// Package to show changelog of
+    package: String,
+    /// Show the full changelog instead of entries newer than the installed version
+    #[arg(short, long)]
+    all: bool,
+    /// Set sysroot target directory
+    #[arg(from_global)]
+    sysroot: PathBuf,
+    /// Set apt options
+    #[arg(from_global)]
+    apt_options: Vec<String>,
+}
+
+impl CliExecuter for Changelog {
+    fn execute(self, config: &Config, no_progress: bool) -> Result<i32, OutputError> {
+        let Changelog {
+            package,
+            all,
+            sysroot,
+            apt_options,
+        } = self;
+
+        let oma_apt_args = OmaAptArgs::builder()
+            .another_apt_options(apt_options)
+            .sysroot(sysroot.to_string_lossy().to_string())
+            .build();
+
+        let apt = OmaApt::new(vec![], oma_apt_args, false, AptConfig::new())?;
+
+        let matcher = PackagesMatcher::builder()
+            .cache(&apt.cache)
+            .native_arch(GetArchMethod::SpecifySysroot(&sysroot))
+            .build();
+
+        let (pkgs, no_result) = matcher.match_pkgs_and_versions([package.as_str()])?;
+
+        handle_no_result(&sysroot, no_result, no_progress)?;
+
+        let Some(pkg) = pkgs.first() else {
+            return Ok(1);
+        };
+
+        let version = pkg.version(&apt.cache);
+        let installed = pkg.package(&apt.cache).installed();
+        let info = ChangelogInfo::new(&version);
+
+        let template = info
+            .origin
+            .as_deref()
+            .and_then(|origin| config.changelog_url_template(origin));
+
+        let remote = template.and_then(|template| {
+            let changelog = fetch_changelog(&info, template, ChangelogKind::Changelog)?;
+            let news = fetch_changelog(&info, template, ChangelogKind::News);
+            Some((changelog, news))
+        });
+
+        let (changelog, news, local) = match remote {
+            Some((changelog, news)) => (changelog, news, false),
+            None => {
+                let Some((path, changelog)) =
+                    read_local(&sysroot, &info.package, ChangelogKind::Changelog)?
+                else {
+                    return Err(OutputError {
+                        description: fl!("changelog-not-found", pkg = info.package.as_str()),
+                        source: None,
+                    });
+                };
+
+                info!(
+                    "{}",
+                    fl!(
+                        "changelog-local-fallback",
+                        path = path.display().to_string()
+                    )
+                );
+
+                let news =
+                    read_local(&sysroot, &info.package, ChangelogKind::News)?.map(|(_, news)| news);
+
+                (changelog, news, true)
+            }
+        };
+
+        // Local documents are of the installed version, they never have newer entries
+        if local && !all && installed.as_ref().is_some_and(|x| x != &version) {
+            info!(
+                "{}",
+                fl!(
+                    "changelog-no-new-entries",
+                    pkg = info.package.as_str(),
+                    ver = version.version()
+                )
+            );
+        }
+
+        // Only show what's new, unless the user asks for the full changelog
+        let since = installed
+            .filter(|_| !all && !local)
+            .map(|installed| installed.source_version().to_string());
+
+        let changelog = match since {
+            Some(ref since) => newer_entries(&changelog, since).unwrap_or(changelog),
+            None => changelog,
+        };
+
+        let news = match since {
+            Some(ref since) => news.and_then(|news| newer_entries(&news, since)),
+            None => news,
+        };
+
+        if !is_terminal() {
+            let mut stdout = stdout();
+            if let Some(news) = news {
+                writeln!(stdout, "{news}").ok();
+            }
+            write!(stdout, "{changelog}").ok();
+            return Ok(0);
+        }
+
+        ALLOWCTRLC.store(true, Ordering::Relaxed);
+
+        let title = fl!(
+            "changelog-title",
+            pkg = info.package.as_str(),
+            ver = version.version()
+        );
+
+        let mut pager = oma_display_with_title(title)?;
+        let mut out = pager.get_writer().map_err(|e| OutputError {
+            description: "Failed to get writer".to_string(),
+            source: Some(Box::new(e)),
+        })?;
+
+        if let Some(news) = news {
+            writeln!(out, "{}\n", fl!("changelog-news")).ok();
+            writeln!(out, "{news}").ok();
+            writeln!(out, "{}\n", fl!("changelog-changes")).ok();
+        }
+
+        write!(out, "{changelog}").ok();
+        drop(out);
+
+        pager.wait_for_exit().map_err(|e| OutputError {
+            description: "Failed to wait exit".to_string(),
+            source: Some(Box::new(e)),
+        })?;
+
+        Ok(0)
+    }
+}
+
+/// Fetch changelog entries of packages to be upgraded, for reviewing a transaction
+pub fn preview_changelogs(
+    apt: &OmaApt,
+    install: &[InstallEntry],
+    config: &ChangelogConfig,
+    network_thread: usize,
+) -> Vec<(String, String)> {
+    let mut targets = vec![];
+
+    for entry in install
+        .iter()
+        .filter(|x| x.op() == &InstallOperation::Upgrade)
+    {
+        let Some(pkg) = apt.cache.get(entry.name()) else {
+            continue;
+        };
+
+        let (Some(installed), Some(version)) =
+            (pkg.installed(), pkg.get_version(entry.new_version()))
+        else {
+            continue;
+        };
+
+        let info = ChangelogInfo::new(&version);
+
+        let Some(template) = info
+            .origin
+            .as_deref()
+            .and_then(|origin| config.url_templates.get(origin))
+        else {
+            continue;
+        };
+
+        targets.push((
+            entry.name().to_string(),
+            info.url(template, ChangelogKind::Changelog),
+            installed.source_version().to_string(),
+        ));
+    }
+
+    if targets.is_empty() {
+        return vec![];
+    }
+
+    info!("{}", fl!("changelog-fetching"));
+
+    let mut res = RT.block_on(
+        stream::iter(targets.into_iter().enumerate())
+            .map(|(i, (name, url, since))| async move {
+                let changelog = fetch(url).await.ok()?;
+                Some((i, name, newer_entries(&changelog, &since)?))
+            })
+            .buffer_unordered(network_thread)
+            .filter_map(|x| async move { x })
+            .collect::<Vec<_>>(),
+    );
+
+    // Keep the order of packages to be upgraded
+    res.sort_unstable_by_key(|(i, _, _)| *i);
+
+    res.into_iter()
+        .map(|(_, name, changelog)| (name, changelog))
+        .collect()
+}
+
+/// Changelog of a package, from the repository with URL `template` if possible, otherwise from
+/// the local documents
+pub fn get_changelog(
+    info: &ChangelogInfo,
+    sysroot: &Path,
+    template: Option<&str>,
+) -> Option<String> {
+    template
+        .and_then(|template| fetch_changelog(info, template, ChangelogKind::Changelog))
+        .or_else(|| {
+            read_local(sysroot, &info.package, ChangelogKind::Changelog)
+                .ok()
+                .flatten()
+                .map(|(_, changelog)| changelog)
+        })
+}
+
+fn fetch_changelog(info: &ChangelogInfo, template: &str, kind: ChangelogKind) -> Option<String> {
+    let url = info.url(template, kind);
+
+    RT.block_on(fetch(url)).ok()
+}
+
+async fn fetch(url: String) -> reqwest::Result<String> {
+    debug!("Fetching {url}");
+
+    let resp = HTTP_CLIENT.get(&url).send().await?.error_for_status();
+
+    match resp {
+        Ok(resp) => resp.text().await,
+        Err(e) => {
+            debug!("Failed to fetch {url}: {e}");
+            Err(e)
+        }
+    }
+}
+
+fn read_local(
+    sysroot: &Path,
+    pkg: &str,
+    kind: ChangelogKind,
+) -> Result<Option<(PathBuf, String)>, OutputError> {
+    read_local_changelog(sysroot, pkg, kind).map_err(|e| OutputError {
+        description: fl!(
+            "failed-to-operate-path",
+            p = sysroot
+                .join("usr/share/doc")
+                .join(pkg)
+                .display()
+                .to_string()
+        ),
+        source: Some(Box::new(e)),
+    })
+}
+
+/// Entries newer than `since`, or `None` if there are none (or the changelog can not be parsed)
+fn newer_entries(changelog: &str, since: &str) -> Option<String> {
+    let entries = entries_newer_than(parse_changelog(changelog), since);
+
+    if entries.is_empty() {
+        return None;
+    }
+
+    Some(entries.into_iter().map(|x| x.text).collect())
+}
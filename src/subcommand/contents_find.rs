@@ -6,6 +6,7 @@ use indexmap::IndexSet;
 use oma_console::indicatif::ProgressBar;
 use oma_console::pb::spinner_style;
 use oma_contents::searcher::{search, Mode};
+use oma_utils::dpkg::{deb_contents, deb_field};
 use std::io::{stdout, Write};
 use std::path::{Path, PathBuf};
 
@@ -21,7 +22,7 @@ pub struct Files {
     /// Search binary of package(s)
     #[arg(long)]
     bin: bool,
-    /// Package to display a list files of
+    /// Package (or local deb file) to display a list files of
     package: String,
     /// Output result to stdout, not pager
     #[arg(long, visible_alias = "println")]
@@ -39,6 +40,11 @@ impl CliExecuter for Files {
             no_pager,
             sysroot,
         } = self;
+
+        if package.ends_with(".deb") {
+            return local_deb_files(&package, bin, no_pager);
+        }
+
         execute(
             CliMode::Files,
             bin,
@@ -160,3 +166,39 @@ fn execute(
 
     Ok(exit.into())
 }
+
+/// List files of a local deb file, read from the archive itself
+fn local_deb_files(deb: &str, is_bin: bool, no_pager: bool) -> Result<i32, OutputError> {
+    let name = deb_field(deb, "Package")?;
+    let files = deb_contents(deb)?
+        .into_iter()
+        .filter(|x| !is_bin || x.starts_with("/usr/bin/"))
+        .collect::<Vec<_>>();
+
+    if no_pager {
+        for file in files {
+            writeln!(stdout(), "{name}: {file}").ok();
+        }
+
+        return Ok(0);
+    }
+
+    let mut pager = oma_display_with_normal_output(false, files.len())?;
+    let mut out = pager.get_writer().map_err(|e| OutputError {
+        description: "Failed to create writer".to_string(),
+        source: Some(Box::new(e)),
+    })?;
+
+    for file in files {
+        writeln!(out, "{name}: {file}").ok();
+    }
+
+    drop(out);
+
+    let exit = pager.wait_for_exit().map_err(|e| OutputError {
+        description: "Failed to wait exit".to_string(),
+        source: Some(Box::new(e)),
+    })?;
+
+    Ok(exit.into())
+}
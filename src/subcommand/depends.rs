@@ -4,10 +4,12 @@ use std::{
     path::PathBuf,
 };
 
-use clap::Args;
+use clap::{Args, ValueEnum};
 use oma_pm::{
     apt::{AptConfig, OmaApt, OmaAptArgs},
+    graph::DepGraph,
     matches::{GetArchMethod, PackagesMatcher},
+    pkginfo::OmaDepType,
 };
 
 use crate::{config::Config, error::OutputError};
@@ -24,6 +26,9 @@ pub struct Depends {
     /// Set output format as JSON
     #[arg(long)]
     json: bool,
+    /// Print the whole dependency closure as a graph
+    #[arg(long, value_enum, conflicts_with = "json")]
+    graph: Option<GraphFormat>,
     /// Set sysroot target directory
     #[arg(from_global)]
     sysroot: PathBuf,
@@ -32,11 +37,19 @@ pub struct Depends {
     apt_options: Vec<String>,
 }
 
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum GraphFormat {
+    /// Graphviz DOT
+    Dot,
+    Json,
+}
+
 impl CliExecuter for Depends {
     fn execute(self, _config: &Config, no_progress: bool) -> Result<i32, OutputError> {
         let Depends {
             packages,
             json,
+            graph,
             sysroot,
             apt_options,
         } = self;
@@ -62,6 +75,25 @@ impl CliExecuter for Depends {
 
         handle_no_result(&sysroot, no_result, no_progress)?;
 
+        if let Some(format) = graph {
+            let graph = DepGraph::closure(
+                &apt.cache,
+                &pkgs,
+                &[
+                    OmaDepType::PreDepends,
+                    OmaDepType::Depends,
+                    OmaDepType::Recommends,
+                ],
+            )?;
+
+            match format {
+                GraphFormat::Dot => print!("{}", graph.to_dot()),
+                GraphFormat::Json => println!("{}", serde_json::json!(graph)),
+            }
+
+            return Ok(0);
+        }
+
         if !json {
             for pkg in pkgs {
                 println!("{}:", pkg.raw_pkg.fullname(true));
@@ -12,6 +12,7 @@ use crate::{
 
 use super::utils::{auth_config, lock_oma, no_check_dbus_warn, CommitChanges};
 use crate::args::CliExecuter;
+use crate::solver;
 
 #[derive(Debug, Args)]
 pub struct FixBroken {
@@ -33,6 +34,9 @@ pub struct FixBroken {
     /// Remove package(s) also remove configuration file(s), like apt purge
     #[arg(long, visible_alias = "purge")]
     remove_config: bool,
+    /// Restart services using replaced binaries or libraries after the transaction (via systemd)
+    #[arg(long)]
+    restart_services: bool,
     /// Run oma in “dry-run” mode. Useful for testing changes and operations without making changes to the system
     #[arg(from_global)]
     dry_run: bool,
@@ -58,6 +62,7 @@ impl CliExecuter for FixBroken {
             force_confnew,
             autoremove,
             remove_config,
+            restart_services,
             dry_run,
             no_check_dbus,
             sysroot,
@@ -77,10 +82,12 @@ impl CliExecuter for FixBroken {
         let auth_config = auth_config.as_ref();
 
         let oma_apt_args = OmaAptArgs::builder()
+            .solver(solver())
             .sysroot(sysroot.to_string_lossy().to_string())
             .dpkg_force_unsafe_io(force_unsafe_io)
             .force_yes(force_yes)
             .dpkg_force_confnew(force_confnew)
+            .dpkg_force_confold(config.native_conffile_prompt())
             .another_apt_options(apt_options)
             .build();
         let apt = OmaApt::new(vec![], oma_apt_args, dry_run, AptConfig::new())?;
@@ -94,9 +101,11 @@ impl CliExecuter for FixBroken {
             .sysroot(sysroot.to_string_lossy().to_string())
             .fix_dpkg_status(!no_fix_dpkg_status)
             .protect_essential(config.protect_essentials())
+            .native_conffile_prompt(config.native_conffile_prompt())
             .yes(false)
             .autoremove(autoremove)
             .remove_config(remove_config)
+            .restart_services(restart_services)
             .maybe_auth_config(auth_config)
             .network_thread(config.network_thread())
             .build()
@@ -6,7 +6,7 @@ use dialoguer::{theme::ColorfulTheme, Select};
 use oma_history::{
     connect_db, find_history_by_id, list_history, HistoryListEntry, SummaryType, DATABASE_PATH,
 };
-use oma_pm::apt::{AptConfig, InstallOperation, OmaAptArgs};
+use oma_pm::apt::{AptConfig, InstallOperation, OmaAptArgs, OmaOperation};
 use oma_pm::matches::{GetArchMethod, PackagesMatcher};
 use oma_pm::pkginfo::PtrIsNone;
 use oma_pm::{
@@ -30,6 +30,7 @@ use super::utils::{
     tui_select_list_size, CommitChanges,
 };
 use crate::args::CliExecuter;
+use crate::solver;
 
 #[derive(Debug, Args)]
 pub struct History {
@@ -151,81 +152,18 @@ impl CliExecuter for Undo {
         let op = find_history_by_id(&conn, id)?;
 
         let oma_apt_args = OmaAptArgs::builder()
+            .solver(solver())
             .sysroot(sysroot.to_string_lossy().to_string())
             .another_apt_options(apt_options)
             .dpkg_force_confnew(force_confnew)
+            .dpkg_force_confold(config.native_conffile_prompt())
             .dpkg_force_unsafe_io(force_unsafe_io)
             .force_yes(force_yes)
             .build();
 
         let mut apt = OmaApt::new(vec![], oma_apt_args, false, AptConfig::new())?;
 
-        let mut glob = vec![];
-        let mut install = vec![];
-
-        if !op.install.is_empty() {
-            for i in &op.install {
-                match i.op() {
-                    InstallOperation::Default | InstallOperation::Download => unreachable!(),
-                    InstallOperation::Install => glob.push(i.name()),
-                    InstallOperation::ReInstall => continue,
-                    InstallOperation::Upgrade => install.push((i.name(), i.old_version().unwrap())),
-                    InstallOperation::Downgrade => {
-                        install.push((i.name(), i.old_version().unwrap()))
-                    }
-                }
-            }
-        }
-
-        if !op.remove.is_empty() {
-            for i in &op.remove {
-                if let Some(ver) = i.version() {
-                    install.push((i.name(), ver));
-                }
-            }
-        }
-
-        let matcher = PackagesMatcher::builder()
-            .cache(&apt.cache)
-            .native_arch(GetArchMethod::SpecifySysroot(&sysroot))
-            .build();
-
-        let mut delete = vec![];
-        let mut no_result = vec![];
-        for i in glob {
-            let res = matcher.match_pkgs_from_glob(i)?;
-            if res.is_empty() {
-                no_result.push(i);
-            } else {
-                delete.extend(res);
-            }
-        }
-
-        handle_no_result(&sysroot, no_result, no_progress)?;
-
-        apt.remove(delete, false, true)?;
-
-        let pkgs = apt.filter_pkgs(&[FilterMode::Default])?.collect::<Vec<_>>();
-
-        let install = install
-            .iter()
-            .filter_map(|(pkg, ver)| {
-                let pkg = pkgs.iter().find(move |y| &y.name() == pkg);
-
-                if let Some(pkg) = pkg {
-                    Some((pkg, pkg.get_version(ver)?))
-                } else {
-                    None
-                }
-            })
-            .map(|(x, y)| OmaPackage::new(&y, x))
-            .collect::<Result<Vec<OmaPackage>, PtrIsNone>>()
-            .map_err(|e| OutputError {
-                description: e.to_string(),
-                source: None,
-            })?;
-
-        apt.install(&install, false)?;
+        undo_changes(&mut apt, &op, &sysroot, no_progress)?;
 
         let auth_config = auth_config(&sysroot);
         let auth_config = auth_config.as_ref();
@@ -239,6 +177,7 @@ impl CliExecuter for Undo {
             .sysroot(sysroot.to_string_lossy().to_string())
             .fix_dpkg_status(!no_fix_dpkg_status)
             .protect_essential(config.protect_essentials())
+            .native_conffile_prompt(config.native_conffile_prompt())
             .yes(false)
             .remove_config(remove_config)
             .autoremove(autoremove)
@@ -249,6 +188,81 @@ impl CliExecuter for Undo {
     }
 }
 
+/// Mark changes which revert the transaction `op`
+pub(crate) fn undo_changes(
+    apt: &mut OmaApt,
+    op: &OmaOperation,
+    sysroot: &Path,
+    no_progress: bool,
+) -> Result<(), OutputError> {
+    let mut glob = vec![];
+    let mut install = vec![];
+
+    if !op.install.is_empty() {
+        for i in &op.install {
+            match i.op() {
+                InstallOperation::Default | InstallOperation::Download => unreachable!(),
+                InstallOperation::Install => glob.push(i.name()),
+                InstallOperation::ReInstall => continue,
+                InstallOperation::Upgrade => install.push((i.name(), i.old_version().unwrap())),
+                InstallOperation::Downgrade => install.push((i.name(), i.old_version().unwrap())),
+            }
+        }
+    }
+
+    if !op.remove.is_empty() {
+        for i in &op.remove {
+            if let Some(ver) = i.version() {
+                install.push((i.name(), ver));
+            }
+        }
+    }
+
+    let matcher = PackagesMatcher::builder()
+        .cache(&apt.cache)
+        .native_arch(GetArchMethod::SpecifySysroot(sysroot))
+        .build();
+
+    let mut delete = vec![];
+    let mut no_result = vec![];
+    for i in glob {
+        let res = matcher.match_pkgs_from_glob(i)?;
+        if res.is_empty() {
+            no_result.push(i);
+        } else {
+            delete.extend(res);
+        }
+    }
+
+    handle_no_result(sysroot, no_result, no_progress)?;
+
+    apt.remove(delete, false, true)?;
+
+    let pkgs = apt.filter_pkgs(&[FilterMode::Default])?.collect::<Vec<_>>();
+
+    let install = install
+        .iter()
+        .filter_map(|(pkg, ver)| {
+            let pkg = pkgs.iter().find(move |y| &y.name() == pkg);
+
+            if let Some(pkg) = pkg {
+                Some((pkg, pkg.get_version(ver)?))
+            } else {
+                None
+            }
+        })
+        .map(|(x, y)| OmaPackage::new(&y, x))
+        .collect::<Result<Vec<OmaPackage>, PtrIsNone>>()
+        .map_err(|e| OutputError {
+            description: e.to_string(),
+            source: None,
+        })?;
+
+    apt.install(&install, false)?;
+
+    Ok(())
+}
+
 fn dialoguer_select_history(
     display_list: &[String],
     old_selected: usize,
@@ -265,7 +279,7 @@ fn dialoguer_select_history(
     Ok(selected)
 }
 
-fn format_summary_log(list: &[HistoryListEntry], undo: bool) -> Vec<(String, usize)> {
+pub(crate) fn format_summary_log(list: &[HistoryListEntry], undo: bool) -> Vec<(String, usize)> {
     let display_list = list
         .iter()
         .enumerate()
@@ -5,14 +5,17 @@ use oma_history::SummaryType;
 use oma_pm::apt::AptConfig;
 use oma_pm::apt::OmaApt;
 use oma_pm::apt::OmaAptArgs;
+use oma_pm::localdeb::check_local_debs;
 use oma_pm::matches::GetArchMethod;
 use oma_pm::matches::PackagesMatcher;
+use oma_pm::matches::PkgAction;
 use tracing::info;
 use tracing::warn;
 
 use crate::config::Config;
 use crate::error::OutputError;
 use crate::fl;
+use crate::solver;
 use crate::utils::dbus_check;
 use crate::utils::root;
 use crate::HTTP_CLIENT;
@@ -21,6 +24,7 @@ use super::utils::auth_config;
 use super::utils::handle_no_result;
 use super::utils::lock_oma;
 use super::utils::no_check_dbus_warn;
+use super::utils::warn_local_debs;
 use super::utils::CommitChanges;
 use super::utils::Refresh;
 use crate::args::CliExecuter;
@@ -90,6 +94,9 @@ pub struct Install {
     /// Remove package(s) also remove configuration file(s), like apt purge
     #[arg(long, visible_alias = "purge")]
     remove_config: bool,
+    /// Restart services using replaced binaries or libraries after the transaction (via systemd)
+    #[arg(long)]
+    restart_services: bool,
 }
 
 impl CliExecuter for Install {
@@ -116,6 +123,7 @@ impl CliExecuter for Install {
             no_refresh_topics,
             autoremove,
             remove_config,
+            restart_services,
             no_fix_dpkg_status,
         } = self;
 
@@ -171,6 +179,7 @@ impl CliExecuter for Install {
         let pkgs_unparse = packages.iter().map(|x| x.as_str()).collect::<Vec<_>>();
 
         let oma_apt_args = OmaAptArgs::builder()
+            .solver(solver())
             .sysroot(sysroot.to_string_lossy().to_string())
             .install_recommends(install_recommends)
             .install_suggests(install_suggests)
@@ -179,11 +188,12 @@ impl CliExecuter for Install {
             .yes(yes)
             .force_yes(force_yes)
             .dpkg_force_confnew(force_confnew)
+            .dpkg_force_confold(config.native_conffile_prompt())
             .another_apt_options(apt_options)
             .dpkg_force_unsafe_io(force_unsafe_io)
             .build();
 
-        let mut apt = OmaApt::new(local_debs, oma_apt_args, dry_run, apt_config)?;
+        let mut apt = OmaApt::new(local_debs.clone(), oma_apt_args, dry_run, apt_config)?;
         let matcher = PackagesMatcher::builder()
             .cache(&apt.cache)
             .filter_candidate(true)
@@ -192,10 +202,43 @@ impl CliExecuter for Install {
             .native_arch(GetArchMethod::SpecifySysroot(&sysroot))
             .build();
 
-        let (pkgs, no_result) = matcher.match_pkgs_and_versions(pkgs_unparse)?;
+        // Like apt, `oma install foo bar-' installs foo and removes bar in one transaction
+        let (install_unparse, remove_unparse) =
+            matcher.split_actions(pkgs_unparse, PkgAction::Install);
+
+        let (pkgs, mut no_result) = matcher.match_pkgs_and_versions(install_unparse)?;
+
+        let mut remove_pkgs = vec![];
+        for i in remove_unparse {
+            let res = matcher.match_pkgs_from_glob(i)?;
+            if res.is_empty() {
+                no_result.push(i);
+            } else {
+                remove_pkgs.extend(res);
+            }
+        }
 
         handle_no_result(&sysroot, no_result, no_progress)?;
 
+        let mut debs = vec![];
+        for deb in &local_debs {
+            for pkg in matcher.match_local_glob(deb)? {
+                debs.push((pkg, PathBuf::from(deb)));
+            }
+        }
+
+        warn_local_debs(&check_local_debs(&apt.cache, &debs, &sysroot)?);
+
+        let request_type = if remove_pkgs.is_empty() {
+            SummaryType::Install(
+                pkgs.iter()
+                    .map(|x| format!("{} {}", x.raw_pkg.fullname(true), x.version_raw.version()))
+                    .collect::<Vec<_>>(),
+            )
+        } else {
+            SummaryType::Changes
+        };
+
         let no_marked_install = apt.install(&pkgs, reinstall)?;
 
         if !no_marked_install.is_empty() {
@@ -207,24 +250,32 @@ impl CliExecuter for Install {
             }
         }
 
+        if !remove_pkgs.is_empty() {
+            let no_marked_remove = apt.remove(remove_pkgs, remove_config, !autoremove)?;
+
+            for c in no_marked_remove {
+                info!("{}", fl!("no-need-to-remove", name = c));
+            }
+        }
+
         CommitChanges::builder()
             .apt(apt)
             .dry_run(dry_run)
-            .request_type(SummaryType::Install(
-                pkgs.iter()
-                    .map(|x| format!("{} {}", x.raw_pkg.fullname(true), x.version_raw.version()))
-                    .collect::<Vec<_>>(),
-            ))
+            .request_type(request_type)
             .no_fixbroken(!fix_broken)
             .no_progress(no_progress)
             .sysroot(sysroot.to_string_lossy().to_string())
             .protect_essential(config.protect_essentials())
+            .native_conffile_prompt(config.native_conffile_prompt())
+            .maybe_changelog_preview(config.changelog_preview())
             .yes(yes)
             .remove_config(remove_config)
+            .restart_services(restart_services)
             .autoremove(autoremove)
             .network_thread(config.network_thread())
             .maybe_auth_config(auth_config)
             .fix_dpkg_status(!no_fix_dpkg_status)
+            .interactive_review(config.interactive_review())
             .build()
             .run()
     }
@@ -4,15 +4,19 @@ use clap::Args;
 use oma_console::print::Action;
 use oma_pm::{
     apt::{AptConfig, FilterMode, OmaApt, OmaAptArgs},
+    multiarch::{installed_archs, same_version_skew},
     PkgCurrentState,
 };
 use tracing::info;
 
+use crate::upgrade::{get_security_tum_pkgs, get_tum, is_security_tum_pkg};
 use crate::{color_formatter, config::Config, error::OutputError, table::PagerPrinter};
 use crate::{fl, ALLOWCTRLC};
+use ahash::HashSet;
 use anyhow::anyhow;
 use smallvec::{smallvec, SmallVec};
 
+use super::utils::warn_multiarch_skew;
 use crate::args::CliExecuter;
 
 #[derive(Debug, Args)]
@@ -37,6 +41,12 @@ pub struct List {
     /// List only package(s) with autoremovable
     #[arg(long)]
     autoremovable: bool,
+    /// List only package(s) with security update(s) available
+    #[arg(
+        long,
+        long_help = "List only package(s) with security update(s) available. Exits with status 2 if there are pending security updates, which is suitable for monitoring"
+    )]
+    security: bool,
     /// Set output format as JSON
     #[arg(long)]
     json: bool,
@@ -58,6 +68,7 @@ impl CliExecuter for List {
             manually_installed,
             automatic,
             autoremovable,
+            security,
             json,
             sysroot,
             apt_options,
@@ -76,7 +87,7 @@ impl CliExecuter for List {
             filter_mode.push(FilterMode::Installed);
         }
 
-        if upgradable {
+        if upgradable || security {
             filter_mode.push(FilterMode::Upgradable)
         }
 
@@ -92,7 +103,23 @@ impl CliExecuter for List {
             filter_mode.push(FilterMode::AutoRemovable);
         }
 
-        let filter_pkgs = apt.filter_pkgs(&filter_mode)?;
+        let security_pkgs = if security {
+            let tum = get_tum(&sysroot)?;
+            let security_tum_pkgs = get_security_tum_pkgs(&tum);
+
+            apt.security_upgradable(|name, version| {
+                is_security_tum_pkg(&security_tum_pkgs, name, version)
+            })
+            .iter()
+            .map(|pkg| pkg.index())
+            .collect::<HashSet<_>>()
+        } else {
+            HashSet::default()
+        };
+
+        let filter_pkgs = apt
+            .filter_pkgs(&filter_mode)?
+            .filter(|pkg| !security || security_pkgs.contains(&pkg.index()));
         let filter_pkgs: Box<dyn Iterator<Item = _>> = if packages.is_empty() {
             Box::new(filter_pkgs)
         } else {
@@ -193,6 +220,10 @@ impl CliExecuter for List {
                     new_version = pkg.candidate().map(|x| Box::from(x.version()));
                 }
 
+                if security_pkgs.contains(&pkg.index()) && installed {
+                    status.push("security");
+                }
+
                 if automatic {
                     status.push("automatic");
                 }
@@ -233,6 +264,11 @@ impl CliExecuter for List {
                                 "current_version": version.version(),
                                 "new_version": new_version,
                                 "architecture": arch,
+                                "installed_archs": if installed {
+                                    installed_archs(&apt.cache, pkg.name())
+                                } else {
+                                    vec![]
+                                },
                                 "status": status,
                             }
                         ))
@@ -241,10 +277,26 @@ impl CliExecuter for List {
             }
         }
 
+        if !json {
+            let skew = same_version_skew(&apt.cache);
+            let skew = skew
+                .into_iter()
+                .filter(|x| {
+                    packages.is_empty()
+                        || packages.iter().any(|p| glob_match::glob_match(p, &x.name))
+                })
+                .collect::<Vec<_>>();
+            warn_multiarch_skew(&skew);
+        }
+
         if display_tips.0 && pkg_count == 1 && !json {
             info!("{}", fl!("additional-version", len = display_tips.1));
         }
 
+        if security && pkg_count > 0 {
+            return Ok(2);
+        }
+
         Ok(0)
     }
 }
@@ -563,7 +563,7 @@ fn sort_mirrors(mirrors: &mut [(&str, &Mirror)], enabled: &indexmap::IndexMap<Bo
     });
 }
 
-fn refresh_enabled_topics_sources_list(no_progress: bool) -> Result<(), OutputError> {
+pub(crate) fn refresh_enabled_topics_sources_list(no_progress: bool) -> Result<(), OutputError> {
     let pb = if !no_progress {
         let pb = OmaProgressBar::new_spinner(Some(fl!("refreshing-topic-metadata")));
 
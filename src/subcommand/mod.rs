@@ -1,3 +1,8 @@
+pub mod apply_plan;
+pub mod arch;
+pub mod bootstrap;
+pub mod bundle;
+pub mod changelog;
 pub mod clean;
 pub mod command_not_found;
 pub mod contents_find;
@@ -16,9 +21,13 @@ pub mod pkgnames;
 pub mod rdepends;
 pub mod refresh;
 pub mod remove;
+pub mod repo;
 pub mod search;
 pub mod show;
+pub mod source;
 #[cfg(feature = "aosc")]
 pub mod topics;
+pub mod unattended;
 pub mod upgrade;
 pub mod utils;
+pub mod why;
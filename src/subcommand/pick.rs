@@ -9,6 +9,7 @@ use oma_pm::{
 };
 
 use crate::fl;
+use crate::solver;
 use crate::{
     config::Config,
     error::OutputError,
@@ -55,6 +56,9 @@ pub struct Pick {
     /// Remove package(s) also remove configuration file(s), like apt purge
     #[arg(long, visible_alias = "purge")]
     remove_config: bool,
+    /// Restart services using replaced binaries or libraries after the transaction (via systemd)
+    #[arg(long)]
+    restart_services: bool,
     /// Run oma in “dry-run” mode. Useful for testing changes and operations without making changes to the system
     #[arg(from_global)]
     dry_run: bool,
@@ -82,6 +86,7 @@ impl CliExecuter for Pick {
             no_refresh_topics,
             autoremove,
             remove_config,
+            restart_services,
             dry_run,
             no_check_dbus,
             sysroot,
@@ -129,9 +134,11 @@ impl CliExecuter for Pick {
         }
 
         let oma_apt_args = OmaAptArgs::builder()
+            .solver(solver())
             .sysroot(sysroot.to_string_lossy().to_string())
             .another_apt_options(apt_options)
             .dpkg_force_confnew(force_confnew)
+            .dpkg_force_confold(config.native_conffile_prompt())
             .dpkg_force_unsafe_io(force_unsafe_io)
             .force_yes(force_yes)
             .build();
@@ -214,11 +221,15 @@ impl CliExecuter for Pick {
             .sysroot(sysroot.to_string_lossy().to_string())
             .fix_dpkg_status(!no_fix_dpkg_status)
             .protect_essential(config.protect_essentials())
+            .native_conffile_prompt(config.native_conffile_prompt())
+            .maybe_changelog_preview(config.changelog_preview())
             .yes(false)
             .remove_config(remove_config)
+            .restart_services(restart_services)
             .autoremove(autoremove)
             .network_thread(config.network_thread())
             .maybe_auth_config(auth_config)
+            .interactive_review(config.interactive_review())
             .build()
             .run()
     }
@@ -55,6 +55,7 @@ impl CliExecuter for Refresh {
             .network_thread(config.network_thread())
             .sysroot(&sysroot_str)
             .config(&apt_config)
+            .update_search_index(true)
             .maybe_auth_config(auth_config);
 
         #[cfg(feature = "aosc")]
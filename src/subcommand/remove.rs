@@ -7,12 +7,13 @@ use dialoguer::theme::ColorfulTheme;
 use dialoguer::{Confirm, Input};
 use oma_history::SummaryType;
 use oma_pm::apt::{AptConfig, OmaApt, OmaAptArgs};
-use oma_pm::matches::{GetArchMethod, PackagesMatcher};
+use oma_pm::matches::{GetArchMethod, PackagesMatcher, PkgAction};
 use tracing::{info, warn};
 
 use crate::config::Config;
 use crate::fl;
 use crate::pb::OmaProgressBar;
+use crate::solver;
 use crate::{
     error::OutputError,
     utils::{dbus_check, root},
@@ -61,6 +62,9 @@ pub struct Remove {
     /// Remove package(s) also remove configuration file(s), like apt purge
     #[arg(long, visible_alias = "purge")]
     remove_config: bool,
+    /// Restart services using replaced binaries or libraries after the transaction (via systemd)
+    #[arg(long)]
+    restart_services: bool,
 }
 
 #[derive(Debug, Args)]
@@ -100,6 +104,9 @@ pub struct Purge {
     /// Do not auto remove unnecessary package(s)
     #[arg(long)]
     no_autoremove: bool,
+    /// Restart services using replaced binaries or libraries after the transaction (via systemd)
+    #[arg(long)]
+    restart_services: bool,
 }
 
 impl From<Purge> for Remove {
@@ -117,6 +124,7 @@ impl From<Purge> for Remove {
             force_confnew,
             no_autoremove,
             no_fix_dpkg_status,
+            restart_services,
         } = value;
 
         Self {
@@ -133,6 +141,7 @@ impl From<Purge> for Remove {
             no_autoremove,
             no_fix_dpkg_status,
             remove_config: true,
+            restart_services,
         }
     }
 }
@@ -159,6 +168,7 @@ impl CliExecuter for Remove {
             force_confnew,
             no_autoremove,
             remove_config,
+            restart_services,
             no_fix_dpkg_status,
         } = self;
 
@@ -179,12 +189,14 @@ impl CliExecuter for Remove {
         }
 
         let oma_apt_args = OmaAptArgs::builder()
+            .solver(solver())
             .yes(yes)
             .force_yes(force_yes)
             .sysroot(sysroot.to_string_lossy().to_string())
             .another_apt_options(apt_options)
             .dpkg_force_unsafe_io(force_unsafe_io)
             .dpkg_force_confnew(force_confnew)
+            .dpkg_force_confold(config.native_conffile_prompt())
             .build();
 
         let mut apt = OmaApt::new(vec![], oma_apt_args, dry_run, AptConfig::new())?;
@@ -196,13 +208,29 @@ impl CliExecuter for Remove {
             .native_arch(GetArchMethod::SpecifySysroot(&sysroot))
             .build();
 
+        // Like apt, `oma remove foo bar+' removes foo and installs bar in one transaction
+        let (install_unparse, remove_unparse) =
+            matcher.split_actions(packages.iter().map(|x| x.as_str()), PkgAction::Remove);
+
+        let (install_pkgs, mut no_result) = if install_unparse.is_empty() {
+            (vec![], vec![])
+        } else {
+            PackagesMatcher::builder()
+                .cache(&apt.cache)
+                .filter_candidate(true)
+                .filter_downloadable_candidate(false)
+                .select_dbg(false)
+                .native_arch(GetArchMethod::SpecifySysroot(&sysroot))
+                .build()
+                .match_pkgs_and_versions(install_unparse)?
+        };
+
         let mut pkgs = vec![];
-        let mut no_result = vec![];
 
-        for i in &packages {
+        for i in remove_unparse {
             let res = matcher.match_pkgs_from_glob(i)?;
             if res.is_empty() {
-                no_result.push(i.as_str());
+                no_result.push(i);
             } else {
                 pkgs.extend(res);
             }
@@ -228,6 +256,14 @@ impl CliExecuter for Remove {
             })
             .collect::<Vec<_>>();
 
+        // Mark packages to install first, so autoremove takes their dependencies into account
+        let request_type = if install_pkgs.is_empty() {
+            SummaryType::Remove(remove_str)
+        } else {
+            apt.install(&install_pkgs, false)?;
+            SummaryType::Changes
+        };
+
         let context = apt.remove(pkgs, remove_config, no_autoremove)?;
 
         if let Some(pb) = pb {
@@ -248,17 +284,20 @@ impl CliExecuter for Remove {
         CommitChanges::builder()
             .apt(apt)
             .dry_run(dry_run)
-            .request_type(SummaryType::Remove(remove_str))
+            .request_type(request_type)
             .no_fixbroken(!fix_broken)
             .no_progress(no_progress)
             .sysroot(sysroot.to_string_lossy().to_string())
             .protect_essential(config.protect_essentials())
+            .native_conffile_prompt(config.native_conffile_prompt())
             .yes(yes)
             .remove_config(remove_config)
+            .restart_services(restart_services)
             .autoremove(!no_autoremove)
             .network_thread(config.network_thread())
             .maybe_auth_config(auth_config)
             .fix_dpkg_status(!no_fix_dpkg_status)
+            .interactive_review(config.interactive_review())
             .build()
             .run()
     }
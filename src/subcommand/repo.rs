@@ -0,0 +1,797 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    env, fs,
+    io::{self, Write},
+    os::unix::fs::{DirBuilderExt, MetadataExt, OpenOptionsExt},
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use clap::{Args, Subcommand};
+use faster_hex::hex_string;
+use indexmap::IndexMap;
+use oma_repo_verify::{export_public_key, generate_signing_key, sign_release};
+use oma_utils::dpkg::{deb_contents, dpkg_arch};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256, Sha512};
+use tracing::{debug, info};
+
+use crate::{config::Config, error::OutputError, fl, msg, success, utils::is_root};
+
+use super::utils::{file_checksum, io_error};
+use crate::args::CliExecuter;
+
+const META_DIR: &str = ".oma-repo";
+const CONFIG_FILE: &str = "config.json";
+const PUBLIC_KEY: &str = "Release.key";
+
+#[derive(Debug, Args)]
+pub struct Repo {
+    #[command(subcommand)]
+    repo_subcmd: RepoSubCmd,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum RepoSubCmd {
+    /// Create a new local repository
+    Init {
+        /// Path to the repository directory
+        path: PathBuf,
+        /// Suite name of the repository
+        #[arg(long, default_value = "stable")]
+        suite: String,
+        /// Component name of the repository
+        #[arg(long, default_value = "main")]
+        component: String,
+        /// Origin of the repository, also used as the user ID of the generated key
+        #[arg(long, default_value = "Local Repository")]
+        origin: String,
+        /// Sign the repository using an existing secret key instead of generating one
+        #[arg(long, conflicts_with = "no_sign")]
+        key: Option<PathBuf>,
+        /// Do not sign the repository
+        #[arg(long)]
+        no_sign: bool,
+    },
+    /// Add package(s) to a local repository
+    Add {
+        /// Path to the repository directory
+        path: PathBuf,
+        /// Package file(s) to add
+        #[arg(required = true)]
+        debs: Vec<PathBuf>,
+    },
+    /// Remove package(s) from a local repository
+    Remove {
+        /// Path to the repository directory
+        path: PathBuf,
+        /// Package(s) to remove (like: foo or foo=1.0-1)
+        #[arg(required = true)]
+        packages: Vec<String>,
+    },
+    /// Regenerate metadata of a local repository
+    Index {
+        /// Path to the repository directory
+        path: PathBuf,
+    },
+    /// Sign metadata of a local repository
+    Sign {
+        /// Path to the repository directory
+        path: PathBuf,
+    },
+}
+
+/// Generated secret keys of repositories created by root
+const SYSTEM_KEY_DIR: &str = "/etc/oma/repo-keys";
+
+/// Settings of a local repository, saved in `.oma-repo/config.json`
+#[derive(Debug, Serialize, Deserialize)]
+struct RepoConfig {
+    suite: String,
+    component: String,
+    origin: String,
+    architectures: Vec<String>,
+    /// Secret key to sign the repository, `None` means unsigned
+    key: Option<PathBuf>,
+}
+
+/// A package in the pool of a local repository
+#[derive(Debug)]
+struct PoolPackage {
+    path: PathBuf,
+    control: IndexMap<String, String>,
+}
+
+impl PoolPackage {
+    fn field(&self, field: &str) -> &str {
+        self.control
+            .get(field)
+            .map(|x| x.as_str())
+            .unwrap_or_default()
+    }
+}
+
+impl CliExecuter for Repo {
+    fn execute(self, _config: &Config, _no_progress: bool) -> Result<i32, OutputError> {
+        match self.repo_subcmd {
+            RepoSubCmd::Init {
+                path,
+                suite,
+                component,
+                origin,
+                key,
+                no_sign,
+            } => init(&path, suite, component, origin, key, no_sign),
+            RepoSubCmd::Add { path, debs } => add(&path, &debs),
+            RepoSubCmd::Remove { path, packages } => remove(&path, &packages),
+            RepoSubCmd::Index { path } => {
+                let config = read_config(&path)?;
+                index(&path, &config)?;
+                sign(&path, &config)?;
+
+                Ok(0)
+            }
+            RepoSubCmd::Sign { path } => {
+                let config = read_config(&path)?;
+                if config.key.is_none() {
+                    return Err(OutputError {
+                        description: fl!("repo-no-key"),
+                        source: None,
+                    });
+                }
+
+                sign(&path, &config)?;
+
+                Ok(0)
+            }
+        }
+    }
+}
+
+fn init(
+    path: &Path,
+    suite: String,
+    component: String,
+    origin: String,
+    key: Option<PathBuf>,
+    no_sign: bool,
+) -> Result<i32, OutputError> {
+    let meta_dir = path.join(META_DIR);
+    if meta_dir.join(CONFIG_FILE).exists() {
+        return Err(OutputError {
+            description: fl!("repo-already-exists", path = path.display().to_string()),
+            source: None,
+        });
+    }
+
+    fs::create_dir_all(&meta_dir).map_err(|e| io_error(&meta_dir, e))?;
+    let pool = path.join("pool").join(&component);
+    fs::create_dir_all(&pool).map_err(|e| io_error(&pool, e))?;
+
+    let key = if no_sign {
+        None
+    } else if let Some(key) = key {
+        Some(fs::canonicalize(&key).map_err(|e| io_error(&key, e))?)
+    } else {
+        info!("{}", fl!("repo-generating-key"));
+
+        let secret_key = generate_signing_key(&origin).map_err(|e| OutputError {
+            description: fl!("repo-generate-key-failed"),
+            source: Some(Box::new(e)),
+        })?;
+
+        // Keep the secret key out of the repository, so it won't be published along with it
+        let key_dir = key_dir();
+        fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(&key_dir)
+            .map_err(|e| io_error(&key_dir, e))?;
+
+        let repo = fs::canonicalize(path).map_err(|e| io_error(path, e))?;
+        let secret = key_dir.join(format!(
+            "{}.asc",
+            &hex_string(&Sha256::digest(repo.as_os_str().as_encoded_bytes()))[..16]
+        ));
+
+        // The secret key must not be readable by others
+        fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&secret)
+            .and_then(|mut f| f.write_all(&secret_key))
+            .map_err(|e| io_error(&secret, e))?;
+
+        Some(fs::canonicalize(&secret).map_err(|e| io_error(&secret, e))?)
+    };
+
+    if let Some(key) = &key {
+        let public_key = export_public_key(key).map_err(|e| OutputError {
+            description: fl!("repo-sign-failed", path = key.display().to_string()),
+            source: Some(Box::new(e)),
+        })?;
+
+        let public = path.join(PUBLIC_KEY);
+        fs::write(&public, public_key).map_err(|e| io_error(&public, e))?;
+    }
+
+    let config = RepoConfig {
+        suite,
+        component,
+        origin,
+        architectures: vec![dpkg_arch("/")?],
+        key,
+    };
+
+    write_config(path, &config)?;
+    index(path, &config)?;
+    sign(path, &config)?;
+
+    let path = fs::canonicalize(path).map_err(|e| io_error(path, e))?;
+
+    success!(
+        "{}",
+        fl!("repo-init-success", path = path.display().to_string())
+    );
+
+    let signed_by = if config.key.is_some() {
+        format!("[signed-by={}] ", path.join(PUBLIC_KEY).display())
+    } else {
+        "[trusted=yes] ".to_string()
+    };
+
+    msg!(
+        "{}",
+        fl!(
+            "repo-sources-tips",
+            line = format!(
+                "deb {signed_by}file://{} {} {}",
+                path.display(),
+                config.suite,
+                config.component
+            )
+        )
+    );
+
+    if let Some(key) = config.key.as_ref().filter(|k| k.starts_with(key_dir())) {
+        info!(
+            "{}",
+            fl!("repo-secret-key-tips", path = key.display().to_string())
+        );
+    }
+
+    Ok(0)
+}
+
+fn add(path: &Path, debs: &[PathBuf]) -> Result<i32, OutputError> {
+    let mut config = read_config(path)?;
+    let mut pool = read_pool(path, &config)?;
+    let pool_dir = path.join("pool").join(&config.component);
+
+    for deb in debs {
+        let control = parse_control(&command_output(
+            Command::new("dpkg-deb").arg("--field").arg(deb),
+        )?);
+
+        let (Some(name), Some(version), Some(arch)) = (
+            control.get("Package"),
+            control.get("Version"),
+            control.get("Architecture"),
+        ) else {
+            return Err(OutputError {
+                description: fl!("repo-invalid-deb", path = deb.display().to_string()),
+                source: None,
+            });
+        };
+
+        // Keep the epoch like apt does for downloaded packages, or 1:1.0-1 and 1.0-1 would collide
+        let dest = pool_dir.join(format!("{name}_{}_{arch}.deb", version.replace(':', "%3a")));
+
+        let exists_error = || OutputError {
+            description: fl!(
+                "repo-pkg-exists",
+                name = name.as_str(),
+                version = version.as_str(),
+                arch = arch.as_str()
+            ),
+            source: None,
+        };
+
+        // The same package may already be in the pool, possibly under another file name
+        let existing = pool
+            .iter()
+            .find(|x| {
+                x.field("Package") == name
+                    && x.field("Version") == version
+                    && x.field("Architecture") == arch
+            })
+            .map(|x| x.path.clone());
+
+        if let Some(existing) = existing {
+            // Copying a file onto itself truncates it
+            let same_file = fs::metadata(deb)
+                .and_then(|a| fs::metadata(&existing).map(|b| (a, b)))
+                .map(|(a, b)| a.dev() == b.dev() && a.ino() == b.ino())
+                .map_err(|e| io_error(deb, e))?;
+
+            if !same_file && file_checksum::<Sha256>(deb)? != file_checksum::<Sha256>(&existing)? {
+                return Err(exists_error());
+            }
+        } else if dest.exists() {
+            // Taken by a package file with mismatched name
+            return Err(exists_error());
+        } else {
+            fs::copy(deb, &dest).map_err(|e| io_error(&dest, e))?;
+            pool.push(PoolPackage {
+                path: dest,
+                control: control.clone(),
+            });
+        }
+
+        if arch != "all" && !config.architectures.contains(arch) {
+            config.architectures.push(arch.to_string());
+        }
+
+        success!(
+            "{}",
+            fl!(
+                "repo-added",
+                name = name.as_str(),
+                version = version.as_str(),
+                arch = arch.as_str()
+            )
+        );
+    }
+
+    write_config(path, &config)?;
+    index(path, &config)?;
+    sign(path, &config)?;
+
+    Ok(0)
+}
+
+fn remove(path: &Path, packages: &[String]) -> Result<i32, OutputError> {
+    let config = read_config(path)?;
+    let pool = read_pool(path, &config)?;
+
+    for pat in packages {
+        let (name, version) = pat
+            .split_once('=')
+            .map(|(n, v)| (n, Some(v)))
+            .unwrap_or((pat, None));
+
+        let matched = pool
+            .iter()
+            .filter(|x| x.field("Package") == name)
+            .filter(|x| version.is_none_or(|v| x.field("Version") == v))
+            .collect::<Vec<_>>();
+
+        if matched.is_empty() {
+            return Err(OutputError {
+                description: fl!("repo-no-match", name = pat.as_str()),
+                source: None,
+            });
+        }
+
+        for pkg in matched {
+            fs::remove_file(&pkg.path).map_err(|e| io_error(&pkg.path, e))?;
+            success!(
+                "{}",
+                fl!(
+                    "repo-removed",
+                    name = pkg.field("Package"),
+                    version = pkg.field("Version"),
+                    arch = pkg.field("Architecture")
+                )
+            );
+        }
+    }
+
+    index(path, &config)?;
+    sign(path, &config)?;
+
+    Ok(0)
+}
+
+/// Generate Packages, Contents and Release of the repository
+fn index(path: &Path, config: &RepoConfig) -> Result<(), OutputError> {
+    let mut pool = read_pool(path, config)?;
+    pool.sort_by(|a, b| {
+        (a.field("Package"), a.field("Version")).cmp(&(b.field("Package"), b.field("Version")))
+    });
+
+    info!("{}", fl!("repo-indexing", count = pool.len()));
+
+    let dist = path.join("dists").join(&config.suite);
+    let component_dir = dist.join(&config.component);
+
+    let mut stanzas = vec![];
+    let mut contents = vec![];
+
+    for pkg in &pool {
+        stanzas.push(packages_stanza(path, pkg)?);
+
+        let section = pkg
+            .control
+            .get("Section")
+            .map(|x| x.as_str())
+            .unwrap_or("misc");
+        let qualified = format!("{section}/{}", pkg.field("Package"));
+
+        // Contents files use relative paths
+        let files = deb_contents(&pkg.path)?
+            .into_iter()
+            .map(|x| x.trim_start_matches('/').to_string())
+            .collect::<Vec<_>>();
+
+        contents.push((files, qualified));
+    }
+
+    let mut indexes = vec![];
+
+    for arch in &config.architectures {
+        let wanted = |pkg: &PoolPackage| {
+            let a = pkg.field("Architecture");
+            a == arch || a == "all"
+        };
+
+        let packages = pool
+            .iter()
+            .zip(&stanzas)
+            .filter(|(pkg, _)| wanted(pkg))
+            .map(|(_, stanza)| stanza.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let mut files: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+        for (pkg, (paths, qualified)) in pool.iter().zip(&contents) {
+            if !wanted(pkg) {
+                continue;
+            }
+
+            for p in paths {
+                files.entry(p).or_default().insert(qualified);
+            }
+        }
+
+        let contents = files
+            .into_iter()
+            .map(|(file, pkgs)| {
+                format!(
+                    "{file}\t\t\t\t\t{}\n",
+                    pkgs.into_iter().collect::<Vec<_>>().join(",")
+                )
+            })
+            .collect::<String>();
+
+        let binary_dir = component_dir.join(format!("binary-{arch}"));
+        fs::create_dir_all(&binary_dir).map_err(|e| io_error(&binary_dir, e))?;
+
+        let packages_path = binary_dir.join("Packages");
+        fs::write(&packages_path, packages).map_err(|e| io_error(&packages_path, e))?;
+        indexes.push(packages_path);
+
+        let contents_path = component_dir.join(format!("Contents-{arch}"));
+        fs::write(&contents_path, contents).map_err(|e| io_error(&contents_path, e))?;
+        indexes.push(contents_path);
+    }
+
+    let release = release_file(&dist, config, &indexes)?;
+    let release_path = dist.join("Release");
+    fs::write(&release_path, release).map_err(|e| io_error(&release_path, e))?;
+
+    Ok(())
+}
+
+/// Sign Release into InRelease and Release.gpg, or remove stale signatures if unsigned
+fn sign(path: &Path, config: &RepoConfig) -> Result<(), OutputError> {
+    let dist = path.join("dists").join(&config.suite);
+    let inrelease_path = dist.join("InRelease");
+    let detached_path = dist.join("Release.gpg");
+
+    let Some(key) = &config.key else {
+        for p in [&inrelease_path, &detached_path] {
+            if p.exists() {
+                fs::remove_file(p).map_err(|e| io_error(p, e))?;
+            }
+        }
+
+        return Ok(());
+    };
+
+    let release_path = dist.join("Release");
+    let release = fs::read_to_string(&release_path).map_err(|e| io_error(&release_path, e))?;
+
+    let (inrelease, detached) = sign_release(&release, key).map_err(|e| OutputError {
+        description: fl!("repo-sign-failed", path = key.display().to_string()),
+        source: Some(Box::new(e)),
+    })?;
+
+    fs::write(&inrelease_path, inrelease).map_err(|e| io_error(&inrelease_path, e))?;
+    fs::write(&detached_path, detached).map_err(|e| io_error(&detached_path, e))?;
+
+    success!(
+        "{}",
+        fl!("repo-signed", path = inrelease_path.display().to_string())
+    );
+
+    Ok(())
+}
+
+fn packages_stanza(repo: &Path, pkg: &PoolPackage) -> Result<String, OutputError> {
+    let size = fs::metadata(&pkg.path)
+        .map_err(|e| io_error(&pkg.path, e))?
+        .len();
+
+    let filename = pkg
+        .path
+        .strip_prefix(repo)
+        .unwrap_or(&pkg.path)
+        .display()
+        .to_string();
+
+    let mut stanza = String::new();
+    for (k, v) in &pkg.control {
+        stanza.push_str(&format!("{k}: {v}\n"));
+    }
+
+    stanza.push_str(&format!("Filename: {filename}\n"));
+    stanza.push_str(&format!("Size: {size}\n"));
+    stanza.push_str(&format!(
+        "SHA256: {}\n",
+        file_checksum::<Sha256>(&pkg.path)?
+    ));
+    stanza.push_str(&format!(
+        "SHA512: {}\n",
+        file_checksum::<Sha512>(&pkg.path)?
+    ));
+
+    Ok(stanza)
+}
+
+fn release_file(
+    dist: &Path,
+    config: &RepoConfig,
+    indexes: &[PathBuf],
+) -> Result<String, OutputError> {
+    let mut sha256 = String::new();
+    let mut sha512 = String::new();
+
+    for index in indexes {
+        let size = fs::metadata(index).map_err(|e| io_error(index, e))?.len();
+        let name = index.strip_prefix(dist).unwrap_or(index).display();
+
+        sha256.push_str(&format!(
+            " {} {size} {name}\n",
+            file_checksum::<Sha256>(index)?
+        ));
+        sha512.push_str(&format!(
+            " {} {size} {name}\n",
+            file_checksum::<Sha512>(index)?
+        ));
+    }
+
+    let mut release = String::new();
+    release.push_str(&format!("Origin: {}\n", config.origin));
+    release.push_str(&format!("Label: {}\n", config.origin));
+    release.push_str(&format!("Suite: {}\n", config.suite));
+    release.push_str(&format!("Codename: {}\n", config.suite));
+    release.push_str(&format!(
+        "Date: {}\n",
+        chrono::Utc::now().format("%a, %d %b %Y %H:%M:%S UTC")
+    ));
+    release.push_str(&format!(
+        "Architectures: {}\n",
+        config.architectures.join(" ")
+    ));
+    release.push_str(&format!("Components: {}\n", config.component));
+    release.push_str(&format!("SHA256:\n{sha256}"));
+    release.push_str(&format!("SHA512:\n{sha512}"));
+
+    Ok(release)
+}
+
+fn read_pool(path: &Path, config: &RepoConfig) -> Result<Vec<PoolPackage>, OutputError> {
+    let pool = path.join("pool").join(&config.component);
+    let mut res = vec![];
+
+    for entry in fs::read_dir(&pool).map_err(|e| io_error(&pool, e))? {
+        let entry = entry.map_err(|e| io_error(&pool, e))?;
+        let deb = entry.path();
+
+        if deb.extension().is_none_or(|x| x != "deb") {
+            continue;
+        }
+
+        let control = parse_control(&command_output(
+            Command::new("dpkg-deb").arg("--field").arg(&deb),
+        )?);
+
+        res.push(PoolPackage { path: deb, control });
+    }
+
+    Ok(res)
+}
+
+fn command_output(cmd: &mut Command) -> Result<String, OutputError> {
+    debug!("Running {cmd:?}");
+
+    let cmd_str = format!("{cmd:?}");
+    let output = cmd.output().map_err(|e| OutputError {
+        description: fl!("repo-command-failed", cmd = cmd_str.as_str()),
+        source: Some(Box::new(e)),
+    })?;
+
+    if !output.status.success() {
+        return Err(OutputError {
+            description: fl!("repo-command-failed", cmd = cmd_str.as_str()),
+            source: Some(Box::new(io::Error::other(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ))),
+        });
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Parse a single deb822 stanza, continuation lines are kept as is
+fn parse_control(control: &str) -> IndexMap<String, String> {
+    let mut res: IndexMap<String, String> = IndexMap::new();
+    let mut last = None;
+
+    for line in control.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, v)) = last.and_then(|i| res.get_index_mut(i)) {
+                v.push('\n');
+                v.push_str(line);
+            }
+            continue;
+        }
+
+        if let Some((k, v)) = line.split_once(':') {
+            let (i, _) = res.insert_full(k.trim().to_string(), v.trim().to_string());
+            last = Some(i);
+        }
+    }
+
+    res
+}
+
+fn read_config(path: &Path) -> Result<RepoConfig, OutputError> {
+    let config_path = path.join(META_DIR).join(CONFIG_FILE);
+
+    let s = fs::read_to_string(&config_path).map_err(|e| OutputError {
+        description: fl!("repo-not-found", path = path.display().to_string()),
+        source: Some(Box::new(e)),
+    })?;
+
+    serde_json::from_str(&s).map_err(|e| OutputError {
+        description: fl!("repo-not-found", path = path.display().to_string()),
+        source: Some(Box::new(e)),
+    })
+}
+
+fn write_config(path: &Path, config: &RepoConfig) -> Result<(), OutputError> {
+    let config_path = path.join(META_DIR).join(CONFIG_FILE);
+    let s = serde_json::to_string_pretty(config).map_err(|e| OutputError {
+        description: fl!(
+            "failed-to-operate-path",
+            p = config_path.display().to_string()
+        ),
+        source: Some(Box::new(e)),
+    })?;
+
+    fs::write(&config_path, s).map_err(|e| io_error(&config_path, e))
+}
+
+/// Directory of generated secret keys, `$XDG_DATA_HOME/oma/repo-keys` for normal users
+fn key_dir() -> PathBuf {
+    if is_root() {
+        return PathBuf::from(SYSTEM_KEY_DIR);
+    }
+
+    let data_dir = env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .filter(|x| x.is_absolute())
+        .or_else(|| env::var_os("HOME").map(|x| PathBuf::from(x).join(".local/share")));
+
+    match data_dir {
+        Some(dir) => dir.join("oma/repo-keys"),
+        None => PathBuf::from(SYSTEM_KEY_DIR),
+    }
+}
+
+#[test]
+fn test_parse_control() {
+    let control = "Package: foo\nVersion: 1:1.0-1\nArchitecture: amd64\nDescription: Foo\n Long description\n .\n More\n";
+    let res = parse_control(control);
+
+    assert_eq!(res.get("Package").unwrap(), "foo");
+    assert_eq!(res.get("Version").unwrap(), "1:1.0-1");
+    assert_eq!(
+        res.get("Description").unwrap(),
+        "Foo\n Long description\n .\n More"
+    );
+    assert_eq!(res.keys().last().unwrap(), "Description");
+}
+
+#[test]
+fn test_index() {
+    let tmp = tempfile::tempdir().unwrap();
+    let repo = tmp.path().join("repo");
+    let root = tmp.path().join("root");
+
+    fs::create_dir_all(root.join("DEBIAN")).unwrap();
+    fs::create_dir_all(root.join("usr/share/doc/foo")).unwrap();
+    fs::write(
+        root.join("DEBIAN/control"),
+        "Package: foo\nVersion: 1:1.0-1\nArchitecture: all\nMaintainer: Foo <foo@example.com>\nDescription: Foo\n",
+    )
+    .unwrap();
+    fs::write(root.join("usr/share/doc/foo/README"), "foo").unwrap();
+
+    let deb = tmp.path().join("foo.deb");
+    command_output(Command::new("dpkg-deb").arg("--build").arg(&root).arg(&deb)).unwrap();
+
+    init(
+        &repo,
+        "stable".to_string(),
+        "main".to_string(),
+        "Test".to_string(),
+        None,
+        true,
+    )
+    .unwrap();
+    add(&repo, &[deb]).unwrap();
+
+    let arch = dpkg_arch("/").unwrap();
+    let dist = repo.join("dists/stable");
+    let pool_deb = repo.join("pool/main/foo_1%3a1.0-1_all.deb");
+    let packages_path = dist.join(format!("main/binary-{arch}/Packages"));
+    let contents_path = dist.join(format!("main/Contents-{arch}"));
+
+    let packages = fs::read_to_string(&packages_path).unwrap();
+    let size = fs::metadata(&pool_deb).unwrap().len();
+
+    assert!(packages.starts_with("Package: foo\nVersion: 1:1.0-1\nArchitecture: all\n"));
+    assert!(packages.contains("Filename: pool/main/foo_1%3a1.0-1_all.deb\n"));
+    assert!(packages.contains(&format!("Size: {size}\n")));
+    assert!(packages.contains(&format!(
+        "SHA256: {}\n",
+        file_checksum::<Sha256>(&pool_deb).unwrap()
+    )));
+    assert!(packages.contains(&format!(
+        "SHA512: {}\n",
+        file_checksum::<Sha512>(&pool_deb).unwrap()
+    )));
+
+    assert_eq!(
+        fs::read_to_string(&contents_path).unwrap(),
+        "usr/share/doc/foo/README\t\t\t\t\tmisc/foo\n"
+    );
+
+    let release = fs::read_to_string(dist.join("Release")).unwrap();
+    assert!(release.contains(&format!("Architectures: {arch}\n")));
+
+    for path in [&packages_path, &contents_path] {
+        let size = fs::metadata(path).unwrap().len();
+        let name = path.strip_prefix(&dist).unwrap().display();
+
+        assert!(release.contains(&format!(
+            " {} {size} {name}\n",
+            file_checksum::<Sha256>(path).unwrap()
+        )));
+        assert!(release.contains(&format!(
+            " {} {size} {name}\n",
+            file_checksum::<Sha512>(path).unwrap()
+        )));
+    }
+
+    // Unsigned repositories have no signatures
+    assert!(!dist.join("InRelease").exists());
+}
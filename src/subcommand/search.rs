@@ -1,11 +1,15 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use clap::{ArgAction, Args};
 use oma_console::{indicatif::ProgressBar, pager::Pager, pb::spinner_style};
 use oma_pm::{
     apt::{AptConfig, OmaApt, OmaAptArgs},
     matches::SearchEngine,
-    search::{IndiciumSearch, OmaSearch, SearchResult, StrSimSearch, TextSearch},
+    search::{
+        parse_size, ExactSearch, OmaSearch, OmaSearchError, RegexSearch, SearchFilter,
+        SearchResult, StrSimSearch, TextSearch,
+    },
+    search_index::IndexSearch,
 };
 use tracing::warn;
 
@@ -25,6 +29,36 @@ pub struct Search {
     /// Set output format as JSON
     #[arg(long)]
     json: bool,
+    /// Treat keywords as a regular expression matching package names and descriptions
+    #[arg(long, conflicts_with = "exact")]
+    regex: bool,
+    /// Only show package(s) named exactly as keywords (a trailing `*' matches name prefix)
+    #[arg(long)]
+    exact: bool,
+    /// Only show package(s) in section (glob is supported, like: devel, lib*)
+    #[arg(long)]
+    section: Option<String>,
+    /// Only show package(s) from repository (origin, label, suite or host)
+    #[arg(long, visible_alias = "repo")]
+    origin: Option<String>,
+    /// Only show installed package(s)
+    #[arg(long)]
+    installed: bool,
+    /// Only show package(s) not installed
+    #[arg(long, conflicts_with_all = ["installed", "upgradable"])]
+    not_installed: bool,
+    /// Only show upgradable package(s)
+    #[arg(long)]
+    upgradable: bool,
+    /// Only show package(s) whose maintainer name or email contains this
+    #[arg(long)]
+    maintainer: Option<String>,
+    /// Only show package(s) with at least this installed size (like: 10M)
+    #[arg(long, value_parser = parse_size_arg)]
+    min_size: Option<u64>,
+    /// Only show package(s) with at most this installed size (like: 1G)
+    #[arg(long, value_parser = parse_size_arg)]
+    max_size: Option<u64>,
     /// Set sysroot target directory
     #[arg(from_global)]
     sysroot: PathBuf,
@@ -39,10 +73,34 @@ impl CliExecuter for Search {
             pattern,
             no_pager,
             json,
+            regex,
+            exact,
+            section,
+            origin,
+            installed,
+            not_installed,
+            upgradable,
+            maintainer,
+            min_size,
+            max_size,
             sysroot,
             apt_options,
         } = self;
 
+        let filter = SearchFilter::builder()
+            .maybe_section(section)
+            .maybe_origin(origin)
+            .maybe_installed(match (installed, not_installed) {
+                (true, _) => Some(true),
+                (_, true) => Some(false),
+                _ => None,
+            })
+            .upgradable(upgradable)
+            .maybe_maintainer(maintainer)
+            .maybe_min_size(min_size)
+            .maybe_max_size(max_size)
+            .build();
+
         let no_pager = no_pager || config.search_contents_println();
 
         let oma_apt_args = OmaAptArgs::builder()
@@ -66,11 +124,17 @@ impl CliExecuter for Search {
 
         let res = search(
             &apt,
+            &sysroot,
             &pattern,
+            &filter,
             match config.search_engine().as_str() {
+                _ if regex => SearchEngine::Regex,
+                _ if exact => SearchEngine::Exact,
                 "indicium" => SearchEngine::Indicium(Box::new(|_| {})),
                 "strsim" => SearchEngine::Strsim,
                 "text" => SearchEngine::Text,
+                "regex" => SearchEngine::Regex,
+                "exact" => SearchEngine::Exact,
                 x => {
                     warn!("Unsupported mode: {x}, fallback to indicium ...");
                     SearchEngine::Indicium(Box::new(|_| {}))
@@ -121,23 +185,25 @@ impl CliExecuter for Search {
 
 pub fn search(
     apt: &OmaApt,
+    sysroot: &Path,
     keywords: &[String],
+    filter: &SearchFilter,
     engine: SearchEngine,
 ) -> Result<Vec<SearchResult>, OutputError> {
     match engine {
         SearchEngine::Indicium(f) => {
-            let searcher = IndiciumSearch::new(&apt.cache, f)?;
-            Ok(searcher.search(&keywords.join(" "))?)
+            let searcher = IndexSearch::new(&apt.cache, sysroot, f);
+            Ok(searcher.search_with_filter(&keywords.join(" "), filter)?)
         }
         SearchEngine::Strsim => {
             let searcher = StrSimSearch::new(&apt.cache);
-            Ok(searcher.search(&keywords.join(" "))?)
+            Ok(searcher.search_with_filter(&keywords.join(" "), filter)?)
         }
         SearchEngine::Text => {
             let searcher = TextSearch::new(&apt.cache);
             let mut result = vec![];
             for keyword in keywords {
-                let res = searcher.search(keyword)?;
+                let res = searcher.search_with_filter(keyword, filter)?;
                 result.extend(res);
             }
 
@@ -146,7 +212,33 @@ pub fn search(
                 result.sort_by(|a, b| b.full_match.cmp(&a.full_match));
             }
 
+            Ok(result)
+        }
+        SearchEngine::Regex => {
+            let searcher = RegexSearch::new(&apt.cache);
+            Ok(searcher.search_with_filter(&keywords.join(" "), filter)?)
+        }
+        SearchEngine::Exact => {
+            let searcher = ExactSearch::new(&apt.cache);
+            let mut result = vec![];
+            for keyword in keywords {
+                match searcher.search_with_filter(keyword, filter) {
+                    Ok(res) => result.extend(res),
+                    // Other keywords may still match
+                    Err(OmaSearchError::NoResult(_)) => continue,
+                    Err(e) => return Err(e.into()),
+                }
+            }
+
+            if result.is_empty() {
+                return Err(OmaSearchError::NoResult(keywords.join(" ")).into());
+            }
+
             Ok(result)
         }
     }
 }
+
+fn parse_size_arg(s: &str) -> Result<u64, String> {
+    parse_size(s).ok_or_else(|| format!("invalid size: {s}"))
+}
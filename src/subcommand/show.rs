@@ -24,7 +24,7 @@ pub struct Show {
     /// Set output format as JSON
     #[arg(long)]
     json: bool,
-    /// Package(s) to show
+    /// Package(s) or local deb file(s) to show
     #[arg(required = true)]
     packages: Vec<String>,
     /// Set sysroot target directory
@@ -50,7 +50,14 @@ impl CliExecuter for Show {
             .sysroot(sysroot.to_string_lossy().to_string())
             .build();
 
-        let apt = OmaApt::new(vec![], oma_apt_args, false, AptConfig::new())?;
+        // Local deb files are read by apt directly
+        let local_debs = packages
+            .iter()
+            .filter(|x| x.ends_with(".deb"))
+            .map(|x| x.to_owned())
+            .collect::<Vec<_>>();
+
+        let apt = OmaApt::new(local_debs, oma_apt_args, false, AptConfig::new())?;
 
         let matcher = PackagesMatcher::builder()
             .cache(&apt.cache)
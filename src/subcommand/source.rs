@@ -0,0 +1,335 @@
+use std::path::{Path, PathBuf};
+use std::thread;
+
+use clap::Args;
+use flume::unbounded;
+use oma_history::SummaryType;
+use oma_pm::apt::{AptConfig, DownloadConfig, OmaApt, OmaAptArgs};
+use oma_pm::sources::{find_sources, select_build_deps, SourceRecord};
+use oma_utils::dpkg::dpkg_arch;
+use tracing::{error, info, warn};
+
+use crate::config::Config;
+use crate::error::OutputError;
+use crate::pb::{NoProgressBar, OmaMultiProgressBar, RenderDownloadProgress};
+use crate::utils::{dbus_check, root};
+use crate::{fl, success, HTTP_CLIENT};
+
+use crate::args::CliExecuter;
+use crate::solver;
+
+use super::utils::{
+    auth_config, is_terminal, lock_oma, no_check_dbus_warn, CommitChanges, Refresh,
+};
+
+#[derive(Debug, Args)]
+pub struct Source {
+    /// Source or binary package(s) to download source of (like: foo, foo=1.0-1)
+    #[arg(required = true)]
+    packages: Vec<String>,
+    /// The path where source file(s) should be downloaded to
+    #[arg(short, long, default_value = ".")]
+    path: PathBuf,
+    /// Run oma in “dry-run” mode. Useful for testing changes and operations without making changes to the system
+    #[arg(from_global)]
+    dry_run: bool,
+    /// Set sysroot target directory
+    #[arg(from_global)]
+    sysroot: PathBuf,
+    /// Set apt options
+    #[arg(from_global)]
+    apt_options: Vec<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct BuildDep {
+    /// Source or binary package(s), or path(s) to debian/control file(s)
+    #[arg(required = true)]
+    packages: Vec<String>,
+    /// Bypass confirmation prompts
+    #[arg(short, long)]
+    yes: bool,
+    /// Run oma in “dry-run” mode. Useful for testing changes and operations without making changes to the system
+    #[arg(from_global)]
+    dry_run: bool,
+    /// Run oma do not check dbus
+    #[arg(from_global)]
+    no_check_dbus: bool,
+    /// Set sysroot target directory
+    #[arg(from_global)]
+    sysroot: PathBuf,
+    /// Set apt options
+    #[arg(from_global)]
+    apt_options: Vec<String>,
+    /// Do not fix dpkg broken status
+    #[arg(short, long)]
+    no_fix_dpkg_status: bool,
+    /// Install package(s) without fsync(2)
+    #[arg(long)]
+    force_unsafe_io: bool,
+    /// Do not refresh repository metadata
+    #[arg(long)]
+    no_refresh: bool,
+    #[cfg(feature = "aosc")]
+    /// Do not refresh topics manifest.json file
+    #[arg(long)]
+    no_refresh_topics: bool,
+}
+
+impl CliExecuter for Source {
+    fn execute(self, config: &Config, no_progress: bool) -> Result<i32, OutputError> {
+        let Source {
+            packages,
+            path,
+            dry_run,
+            sysroot,
+            apt_options,
+        } = self;
+
+        let path = path.canonicalize().map_err(|e| OutputError {
+            description: format!("Failed to canonicalize path: {}", path.display()),
+            source: Some(Box::new(e)),
+        })?;
+
+        let oma_apt_args = OmaAptArgs::builder()
+            .sysroot(sysroot.to_string_lossy().to_string())
+            .another_apt_options(apt_options)
+            .build();
+
+        let apt = OmaApt::new(vec![], oma_apt_args, dry_run, AptConfig::new())?;
+        let arch = dpkg_arch(&sysroot)?;
+
+        let records = packages
+            .iter()
+            .map(|pkg| find_source_record(&apt, &sysroot, &arch, pkg))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let auth_config = auth_config(&sysroot);
+        let mut count = 0;
+
+        for record in records {
+            info!(
+                "{}",
+                fl!(
+                    "source-downloading",
+                    pkg = record.package.as_str(),
+                    ver = record.version.as_str()
+                )
+            );
+
+            let (tx, rx) = unbounded();
+
+            thread::spawn(move || {
+                let mut pb: Box<dyn RenderDownloadProgress> = if no_progress || !is_terminal() {
+                    Box::new(NoProgressBar::default())
+                } else {
+                    Box::new(OmaMultiProgressBar::default())
+                };
+                pb.render_progress(&rx);
+            });
+
+            let summary = apt.download_source(
+                &HTTP_CLIENT,
+                &record,
+                DownloadConfig {
+                    network_thread: Some(config.network_thread()),
+                    download_dir: Some(&path),
+                    auth: auth_config.as_ref(),
+                },
+                dry_run,
+                |event| async {
+                    if let Err(e) = tx.send_async(event).await {
+                        error!("{}", e);
+                    }
+                },
+            )?;
+
+            count += summary.success.len();
+        }
+
+        if count != 0 {
+            success!(
+                "{}",
+                fl!(
+                    "source-successfully-download",
+                    len = count,
+                    path = path.display().to_string()
+                )
+            );
+        }
+
+        Ok(0)
+    }
+}
+
+impl CliExecuter for BuildDep {
+    fn execute(self, config: &Config, no_progress: bool) -> Result<i32, OutputError> {
+        let BuildDep {
+            packages,
+            yes,
+            dry_run,
+            no_check_dbus,
+            sysroot,
+            apt_options,
+            no_fix_dpkg_status,
+            force_unsafe_io,
+            no_refresh,
+            #[cfg(feature = "aosc")]
+            no_refresh_topics,
+        } = self;
+
+        if !dry_run {
+            root()?;
+            lock_oma()?;
+        }
+
+        let _fds = if !no_check_dbus && !config.no_check_dbus() && !dry_run {
+            Some(dbus_check(yes)?)
+        } else {
+            no_check_dbus_warn();
+            None
+        };
+
+        let apt_config = AptConfig::new();
+
+        let auth_config = auth_config(&sysroot);
+        let auth_config = auth_config.as_ref();
+
+        if !no_refresh {
+            let sysroot = sysroot.to_string_lossy();
+            let builder = Refresh::builder()
+                .client(&HTTP_CLIENT)
+                .dry_run(dry_run)
+                .no_progress(no_progress)
+                .network_thread(config.network_thread())
+                .sysroot(&sysroot)
+                .config(&apt_config)
+                .maybe_auth_config(auth_config);
+
+            #[cfg(feature = "aosc")]
+            let refresh = builder
+                .refresh_topics(!no_refresh_topics && !config.no_refresh_topics())
+                .build();
+
+            #[cfg(not(feature = "aosc"))]
+            let refresh = builder.build();
+
+            refresh.run()?;
+        }
+
+        if yes {
+            warn!("{}", fl!("automatic-mode-warn"));
+        }
+
+        let oma_apt_args = OmaAptArgs::builder()
+            .solver(solver())
+            .sysroot(sysroot.to_string_lossy().to_string())
+            .yes(yes)
+            .dpkg_force_confold(config.native_conffile_prompt())
+            .another_apt_options(apt_options)
+            .dpkg_force_unsafe_io(force_unsafe_io)
+            .build();
+
+        let mut apt = OmaApt::new(vec![], oma_apt_args, dry_run, apt_config)?;
+        let arch = dpkg_arch(&sysroot)?;
+
+        let mut deps = vec![];
+
+        for pkg in &packages {
+            let control = Path::new(pkg);
+
+            let record = if control.is_file() {
+                SourceRecord::from_control(control, &arch)
+                    .map_err(|e| OutputError {
+                        description: fl!("failed-to-operate-path", p = pkg.as_str()),
+                        source: Some(Box::new(e)),
+                    })?
+                    .ok_or_else(|| OutputError {
+                        description: fl!("build-dep-invalid-control", path = pkg.as_str()),
+                        source: None,
+                    })?
+            } else {
+                find_source_record(&apt, &sysroot, &arch, pkg)?
+            };
+
+            deps.extend(record.build_depends);
+        }
+
+        let (pkgs, unsatisfiable) = select_build_deps(&apt.cache, &deps);
+
+        if !unsatisfiable.is_empty() {
+            return Err(OutputError {
+                description: fl!("build-dep-unsatisfiable", deps = unsatisfiable.join(", ")),
+                source: None,
+            });
+        }
+
+        apt.install_as_auto(&pkgs)?;
+
+        CommitChanges::builder()
+            .apt(apt)
+            .dry_run(dry_run)
+            .request_type(SummaryType::Install(
+                pkgs.iter()
+                    .map(|x| format!("{} {}", x.raw_pkg.fullname(true), x.version_raw.version()))
+                    .collect::<Vec<_>>(),
+            ))
+            .no_fixbroken(false)
+            .no_progress(no_progress)
+            .sysroot(sysroot.to_string_lossy().to_string())
+            .protect_essential(config.protect_essentials())
+            .native_conffile_prompt(config.native_conffile_prompt())
+            .yes(yes)
+            .network_thread(config.network_thread())
+            .maybe_auth_config(auth_config)
+            .fix_dpkg_status(!no_fix_dpkg_status)
+            .build()
+            .run()
+    }
+}
+
+/// Find source package record of a source or binary package (like: foo, foo=1.0-1)
+fn find_source_record(
+    apt: &OmaApt,
+    sysroot: &Path,
+    arch: &str,
+    pkg: &str,
+) -> Result<SourceRecord, OutputError> {
+    let (name, version) = match pkg.split_once('=') {
+        Some((name, version)) => (name, Some(version)),
+        None => (pkg, None),
+    };
+
+    // Binary package names are mapped to the source package building them
+    let (source, preferred_version) = match apt.cache.get(name).and_then(|x| x.candidate()) {
+        Some(cand) => (
+            cand.source_name().to_string(),
+            Some(version.unwrap_or(cand.source_version()).to_string()),
+        ),
+        None => (name.to_string(), version.map(|x| x.to_string())),
+    };
+
+    let records = find_sources(sysroot, &source, arch).map_err(|e| OutputError {
+        description: fl!(
+            "failed-to-operate-path",
+            p = sysroot.join("var/lib/apt/lists").display().to_string()
+        ),
+        source: Some(Box::new(e)),
+    })?;
+
+    let index =
+        preferred_version.and_then(|preferred| records.iter().position(|x| x.version == preferred));
+
+    // Fall back to the newest version unless one is specified, the Sources index may be
+    // newer than Packages
+    let record = match index {
+        Some(index) => records.into_iter().nth(index),
+        None if version.is_some() => None,
+        None => records.into_iter().next(),
+    };
+
+    record.ok_or_else(|| OutputError {
+        description: fl!("source-not-found", pkg = pkg),
+        source: None,
+    })
+}
@@ -1,4 +1,7 @@
-use std::{fmt::Display, path::PathBuf};
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+};
 
 use clap::{ArgAction, Args};
 use dialoguer::console::style;
@@ -32,6 +35,7 @@ use super::utils::{
 use crate::args::CliExecuter;
 
 use crate::fl;
+use crate::solver;
 use anyhow::anyhow;
 use oma_topics::{Topic, TopicManager};
 
@@ -84,9 +88,9 @@ pub struct Topics {
     always_write_status: bool,
 }
 
-struct TopicChanged {
-    enabled_pkgs: Vec<String>,
-    downgrade_pkgs: Vec<String>,
+pub(crate) struct TopicChanged {
+    pub enabled_pkgs: Vec<String>,
+    pub downgrade_pkgs: Vec<String>,
 }
 
 struct TopicDisplay<'a> {
@@ -159,17 +163,7 @@ impl CliExecuter for Topics {
         let downgrade_pkgs = topics_changed.downgrade_pkgs;
 
         if !opt_in.is_empty() || !opt_out.is_empty() {
-            RT.block_on(tm.write_sources_list(
-                &fl!("do-not-edit-topic-sources-list"),
-                false,
-                |topic, mirror| async {
-                    warn!(
-                        "{}",
-                        fl!("topic-not-in-mirror", topic = topic, mirror = mirror)
-                    );
-                    warn!("{}", fl!("skip-write-mirror"));
-                },
-            ))?;
+            write_topic_sources_list(&tm, false)?;
         }
 
         let code = Ok(()).and_then(|_| -> Result<i32, OutputError> {
@@ -190,42 +184,18 @@ impl CliExecuter for Topics {
                 .run()?;
 
             let oma_apt_args = OmaAptArgs::builder()
+                .solver(solver())
                 .sysroot(sysroot.to_string_lossy().to_string())
                 .another_apt_options(apt_options)
                 .dpkg_force_unsafe_io(force_unsafe_io)
                 .dpkg_force_confnew(force_confnew)
+                .dpkg_force_confold(config.native_conffile_prompt())
                 .force_yes(force_yes)
                 .build();
 
             let mut apt = OmaApt::new(vec![], oma_apt_args, false, apt_config)?;
 
-            let mut pkgs = vec![];
-
-            let matcher = PackagesMatcher::builder()
-                .cache(&apt.cache)
-                .native_arch(GetArchMethod::SpecifySysroot(&sysroot))
-                .build();
-
-            for pkg in downgrade_pkgs {
-                let mut f = apt
-                    .filter_pkgs(&[FilterMode::Default])?
-                    .filter(|x| x.name() == pkg);
-
-                if let Some(pkg) = f.next() {
-                    if enabled_pkgs.contains(&pkg.name().to_string()) {
-                        continue;
-                    }
-
-                    if pkg.is_installed() {
-                        let pkginfo = matcher.find_candidate_by_pkgname(pkg.name())?;
-
-                        pkgs.push(pkginfo);
-                    }
-                }
-            }
-
-            apt.install(&pkgs, false)?;
-            apt.upgrade(Upgrade::FullUpgrade)?;
+            mark_topic_downgrades(&mut apt, &sysroot, downgrade_pkgs, &enabled_pkgs)?;
 
             let code = CommitChanges::builder()
                 .apt(apt)
@@ -239,6 +209,7 @@ impl CliExecuter for Topics {
                 .sysroot(sysroot.to_string_lossy().to_string())
                 .fix_dpkg_status(!no_fix_dpkg_status)
                 .protect_essential(config.protect_essentials())
+                .native_conffile_prompt(config.native_conffile_prompt())
                 .yes(false)
                 .remove_config(remove_config)
                 .autoremove(autoremove)
@@ -255,14 +226,14 @@ impl CliExecuter for Topics {
             Ok(x) => {
                 if x != 0 && !always_write_status {
                     error!("{}", fl!("topics-unchanged"));
-                    revert_sources_list(&tm)?;
+                    write_topic_sources_list(&tm, true)?;
                 } else {
                     RT.block_on(tm.write_enabled())?;
                 }
             }
             Err(e) => {
                 error!("{}", fl!("topics-unchanged"));
-                revert_sources_list(&tm)?;
+                write_topic_sources_list(&tm, true)?;
                 return Err(e);
             }
         };
@@ -271,10 +242,14 @@ impl CliExecuter for Topics {
     }
 }
 
-fn revert_sources_list(tm: &TopicManager<'_>) -> Result<(), OutputError> {
+/// Write sources.list of enabled topics, or of previously enabled topics if `revert`
+pub(crate) fn write_topic_sources_list(
+    tm: &TopicManager<'_>,
+    revert: bool,
+) -> Result<(), OutputError> {
     RT.block_on(tm.write_sources_list(
         &fl!("do-not-edit-topic-sources-list"),
-        true,
+        revert,
         |topic, mirror| async {
             warn!(
                 "{}",
@@ -286,7 +261,45 @@ fn revert_sources_list(tm: &TopicManager<'_>) -> Result<(), OutputError> {
     Ok(())
 }
 
-async fn topics_inner(
+/// Roll back packages of withdrawn topics to stable versions, unless an enabled topic still provides them
+pub(crate) fn mark_topic_downgrades(
+    apt: &mut OmaApt,
+    sysroot: &Path,
+    downgrade_pkgs: Vec<String>,
+    enabled_pkgs: &[String],
+) -> Result<(), OutputError> {
+    let mut pkgs = vec![];
+
+    let matcher = PackagesMatcher::builder()
+        .cache(&apt.cache)
+        .native_arch(GetArchMethod::SpecifySysroot(sysroot))
+        .build();
+
+    for pkg in downgrade_pkgs {
+        let mut f = apt
+            .filter_pkgs(&[FilterMode::Default])?
+            .filter(|x| x.name() == pkg);
+
+        if let Some(pkg) = f.next() {
+            if enabled_pkgs.contains(&pkg.name().to_string()) {
+                continue;
+            }
+
+            if pkg.is_installed() {
+                let pkginfo = matcher.find_candidate_by_pkgname(pkg.name())?;
+
+                pkgs.push(pkginfo);
+            }
+        }
+    }
+
+    apt.install(&pkgs, false)?;
+    apt.upgrade(Upgrade::FullUpgrade)?;
+
+    Ok(())
+}
+
+pub(crate) async fn topics_inner(
     opt_in: &mut Vec<String>,
     opt_out: &mut Vec<String>,
     no_progress: bool,
@@ -0,0 +1,537 @@
+use std::{
+    fs::OpenOptions,
+    io::{self, Write},
+    os::fd::AsRawFd,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    thread,
+    time::Duration,
+};
+
+use ahash::HashSet;
+use apt_auth_config::AuthConfig;
+use chrono::{Local, NaiveTime};
+use clap::Args;
+use oma_history::SummaryType;
+use oma_pm::{
+    apt::{is_security_version, AptConfig, OmaApt, OmaAptArgs, OmaOperation, SummarySort},
+    pkginfo::OmaDepType,
+};
+use oma_utils::dbus::{create_dbus_connection, take_wake_lock};
+use tracing::{debug, info, warn};
+
+use crate::{
+    args::CliExecuter,
+    config::{Config, UnattendedConfig},
+    error::OutputError,
+    fl,
+    report::reboot_reasons,
+    solver,
+    upgrade::{get_security_tum_pkgs, get_tum, is_security_tum_pkg},
+    utils::{check_battery, root},
+    HTTP_CLIENT, RT,
+};
+
+use super::utils::{lock_oma, CommitChanges, Refresh};
+
+#[derive(Debug, Args)]
+pub struct Unattended {
+    /// Only apply security updates, regardless of the configuration
+    #[arg(long)]
+    security_only: bool,
+    /// Run even outside the configured maintenance window
+    #[arg(long)]
+    ignore_window: bool,
+    /// Run oma in “dry-run” mode. Useful for testing changes and operations without making changes to the system
+    #[arg(from_global)]
+    dry_run: bool,
+    /// Set sysroot target directory
+    #[arg(from_global)]
+    sysroot: PathBuf,
+    /// Set apt options
+    #[arg(from_global)]
+    apt_options: Vec<String>,
+}
+
+struct UnattendedOutcome {
+    upgraded: Vec<String>,
+    reboot_reasons: Vec<String>,
+}
+
+impl CliExecuter for Unattended {
+    fn execute(self, config: &Config, _no_progress: bool) -> Result<i32, OutputError> {
+        let Unattended {
+            security_only,
+            ignore_window,
+            dry_run,
+            sysroot,
+            apt_options,
+        } = self;
+
+        let policy = config.unattended();
+
+        if !dry_run {
+            root()?;
+        }
+
+        if !ignore_window
+            && !in_maintenance_window(&policy.maintenance_window, Local::now().time())
+                .inspect_err(|e| notify(&policy.notify_command, Err(e)))?
+        {
+            info!(
+                "{}",
+                fl!(
+                    "unattended-outside-window",
+                    window = policy.maintenance_window.as_str()
+                )
+            );
+            return Ok(0);
+        }
+
+        // Servers may not have UPower or logind, so a failed D-Bus connection is not fatal
+        let conn = RT.block_on(create_dbus_connection()).ok();
+
+        if let Some(conn) = &conn {
+            if policy.skip_on_battery && check_battery(conn, true) {
+                info!("{}", fl!("unattended-on-battery"));
+                return Ok(0);
+            }
+        }
+
+        let _fds = conn.and_then(|conn| {
+            RT.block_on(take_wake_lock(&conn, &fl!("changing-system"), "oma"))
+                .ok()
+        });
+
+        let res = if dry_run {
+            Ok(())
+        } else {
+            lock_with_retry(&policy, &sysroot)
+        }
+        .and_then(|_| {
+            run(
+                config,
+                &policy,
+                security_only || policy.security_only,
+                dry_run,
+                &sysroot,
+                apt_options,
+            )
+        });
+
+        notify(&policy.notify_command, res.as_ref());
+
+        let outcome = res?;
+
+        if !outcome.reboot_reasons.is_empty() && policy.auto_reboot && !dry_run {
+            schedule_reboot(policy.auto_reboot_delay, &outcome.reboot_reasons)?;
+        }
+
+        Ok(0)
+    }
+}
+
+fn run(
+    config: &Config,
+    policy: &UnattendedConfig,
+    security_only: bool,
+    dry_run: bool,
+    sysroot: &Path,
+    apt_options: Vec<String>,
+) -> Result<UnattendedOutcome, OutputError> {
+    let apt_config = AptConfig::new();
+    let auth_config = AuthConfig::system(sysroot)?;
+    let sysroot_str = sysroot.to_string_lossy().to_string();
+
+    Refresh::builder()
+        .client(&HTTP_CLIENT)
+        .dry_run(dry_run)
+        .no_progress(true)
+        .network_thread(config.network_thread())
+        .sysroot(&sysroot_str)
+        .config(&apt_config)
+        .auth_config(&auth_config)
+        .build()
+        .run()?;
+
+    // `yes' makes dpkg keep modified configuration files (confold/confdef)
+    let oma_apt_args = OmaAptArgs::builder()
+        .solver(solver())
+        .sysroot(sysroot_str.clone())
+        .yes(true)
+        .another_apt_options(apt_options)
+        .build();
+
+    let mut apt = OmaApt::new(vec![], oma_apt_args, dry_run, AptConfig::new())?;
+
+    let tum = get_tum(sysroot)?;
+    let security_tum_pkgs = get_security_tum_pkgs(&tum);
+
+    let marks = apt.save_marks();
+    let mut kept_back = HashSet::with_hasher(ahash::RandomState::new());
+
+    // Blacklisted packages may still be pulled in as dependencies, keep back packages requiring
+    // them and resolve again
+    let op = loop {
+        apt.restore_marks(&marks);
+
+        apt.upgrade_matching(|pkg, cand| {
+            let origin_allowed = policy.allowed_origins.is_empty()
+                || cand.package_files().any(|pkg_file| {
+                    pkg_file
+                        .origin()
+                        .is_some_and(|origin| policy.allowed_origins.iter().any(|x| x == origin))
+                });
+
+            let is_security = is_security_version(cand)
+                || is_security_tum_pkg(&security_tum_pkgs, pkg.name(), cand.version());
+
+            origin_allowed
+                && !is_blacklisted(&policy.blacklist, pkg.name())
+                && !kept_back.contains(pkg.name())
+                && (!security_only || is_security)
+        })?;
+
+        apt.keep_back(&kept_back.iter().map(|x| x.as_str()).collect::<Vec<_>>());
+        apt.resolve(false, false)?;
+
+        // Never remove packages or touch essential packages unattended
+        let op = apt.summary(SummarySort::Operation, |_| false, |_| false)?;
+
+        let blacklisted = op
+            .install
+            .iter()
+            .filter(|x| is_blacklisted(&policy.blacklist, x.name_without_arch()))
+            .map(|x| x.name_without_arch().to_string())
+            .collect::<HashSet<_>>();
+
+        if blacklisted.is_empty() {
+            break op;
+        }
+
+        let requiring = requiring_pkgs(&apt, &op, &blacklisted)
+            .into_iter()
+            .filter(|x| !kept_back.contains(x))
+            .collect::<Vec<_>>();
+
+        // Nothing else to keep back, e.g. required through a virtual package
+        if requiring.is_empty() {
+            return Err(OutputError {
+                description: fl!(
+                    "unattended-blacklisted",
+                    pkgs = blacklisted.into_iter().collect::<Vec<_>>().join(", ")
+                ),
+                source: None,
+            });
+        }
+
+        warn!(
+            "{}",
+            fl!("unattended-kept-back", pkgs = requiring.join(", "))
+        );
+        kept_back.extend(blacklisted);
+        kept_back.extend(requiring);
+    };
+
+    if op.install.is_empty() && op.remove.is_empty() {
+        info!("{}", fl!("no-need-to-do-anything"));
+        return Ok(UnattendedOutcome {
+            upgraded: vec![],
+            reboot_reasons: vec![],
+        });
+    }
+
+    if !op.remove.is_empty() {
+        return Err(OutputError {
+            description: fl!(
+                "unattended-remove-required",
+                pkgs = op
+                    .remove
+                    .iter()
+                    .map(|x| x.name())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            source: None,
+        });
+    }
+
+    let upgraded = op
+        .install
+        .iter()
+        .map(|x| match x.old_version() {
+            Some(old) => format!("{} {} -> {}", x.name(), old, x.new_version()),
+            None => format!("{} {}", x.name(), x.new_version()),
+        })
+        .collect::<Vec<_>>();
+
+    let code = CommitChanges::builder()
+        .apt(apt)
+        .dry_run(dry_run)
+        .request_type(SummaryType::Upgrade(
+            op.install
+                .iter()
+                .map(|x| format!("{} {}", x.name(), x.new_version()))
+                .collect(),
+        ))
+        .no_fixbroken(false)
+        .no_progress(true)
+        .sysroot(sysroot_str)
+        .protect_essential(true)
+        .yes(true)
+        .network_thread(config.network_thread())
+        .auth_config(&auth_config)
+        .check_update(true)
+        .build()
+        .run()?;
+
+    if code != 0 {
+        return Err(OutputError {
+            description: fl!("unattended-failed"),
+            source: None,
+        });
+    }
+
+    let reboot_reasons = if dry_run {
+        vec![]
+    } else {
+        reboot_reasons(sysroot, &op)
+    };
+
+    Ok(UnattendedOutcome {
+        upgraded,
+        reboot_reasons,
+    })
+}
+
+fn is_blacklisted(blacklist: &[String], pkg: &str) -> bool {
+    blacklist.iter().any(|x| glob_match::glob_match(x, pkg))
+}
+
+/// Packages in `op` requiring `pkgs` to be changed, directly or through other packages in `op`
+fn requiring_pkgs(apt: &OmaApt, op: &OmaOperation, pkgs: &HashSet<String>) -> Vec<String> {
+    let mut required = pkgs.clone();
+    let mut res = vec![];
+
+    loop {
+        let found = op
+            .install
+            .iter()
+            .map(|x| x.name_without_arch())
+            .filter(|name| !required.contains(*name))
+            .filter(|name| {
+                let Some(pkg) = apt.cache.get(name) else {
+                    return false;
+                };
+
+                pkg.candidate().is_some_and(|cand| {
+                    // Only hard dependencies force the blacklisted packages to change
+                    cand.depends_map()
+                        .iter()
+                        .filter(|(typ, _)| {
+                            matches!(
+                                OmaDepType::from(*typ),
+                                OmaDepType::Depends | OmaDepType::PreDepends
+                            )
+                        })
+                        .flat_map(|(_, deps)| deps)
+                        .flat_map(|x| x.iter())
+                        .any(|dep| required.contains(dep.name()))
+                })
+            })
+            .map(|x| x.to_string())
+            .collect::<Vec<_>>();
+
+        if found.is_empty() {
+            return res;
+        }
+
+        required.extend(found.iter().cloned());
+        res.extend(found);
+    }
+}
+
+/// Whether `now` is in the maintenance window like `22:00-04:00`, an empty window matches any time
+fn in_maintenance_window(window: &str, now: NaiveTime) -> Result<bool, OutputError> {
+    let window = window.trim();
+
+    if window.is_empty() {
+        return Ok(true);
+    }
+
+    let parse = |s: &str| NaiveTime::parse_from_str(s.trim(), "%H:%M").ok();
+
+    let (start, end) = window
+        .split_once('-')
+        .and_then(|(start, end)| Some((parse(start)?, parse(end)?)))
+        .ok_or_else(|| OutputError {
+            description: fl!("unattended-invalid-window", window = window),
+            source: None,
+        })?;
+
+    Ok(if start <= end {
+        start <= now && now < end
+    } else {
+        // Window crosses midnight
+        now >= start || now < end
+    })
+}
+
+/// Lock oma, waiting for other package managers using dpkg as well
+fn lock_with_retry(policy: &UnattendedConfig, sysroot: &Path) -> Result<(), OutputError> {
+    let mut retry_times = 0;
+
+    loop {
+        let res = match dpkg_frontend_locked(sysroot) {
+            Ok(true) => Err(OutputError {
+                description: fl!("unattended-dpkg-locked"),
+                source: None,
+            }),
+            Ok(false) => lock_oma().map_err(OutputError::from),
+            Err(e) => {
+                // Not fatal, dpkg checks its lock again on commit
+                debug!("Failed to check dpkg frontend lock: {e}");
+                lock_oma().map_err(OutputError::from)
+            }
+        };
+
+        match res {
+            Ok(()) => return Ok(()),
+            Err(e) if retry_times < policy.lock_retry_times => {
+                retry_times += 1;
+                warn!(
+                    "{}",
+                    fl!(
+                        "unattended-lock-retry",
+                        secs = policy.lock_retry_interval,
+                        times = retry_times
+                    )
+                );
+                debug!("{e}");
+                thread::sleep(Duration::from_secs(policy.lock_retry_interval));
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Whether the dpkg frontend lock of `sysroot` is held by another process, without taking it
+fn dpkg_frontend_locked(sysroot: &Path) -> io::Result<bool> {
+    let file = match OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(sysroot.join("var/lib/dpkg/lock-frontend"))
+    {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e),
+    };
+
+    // SAFETY: flock is a plain C struct, all zeroes is a valid value of it
+    let mut fl: libc::flock = unsafe { std::mem::zeroed() };
+    fl.l_type = libc::F_WRLCK as libc::c_short;
+    fl.l_whence = libc::SEEK_SET as libc::c_short;
+
+    // SAFETY: the fd is owned by `file` and stays open during the call, F_GETLK only reads
+    // the lock described by `fl` and writes the conflicting lock back into it, and `fl`
+    // is a valid, exclusively borrowed flock for the whole call
+    if unsafe { libc::fcntl(file.as_raw_fd(), libc::F_GETLK, &mut fl) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(fl.l_type != libc::F_UNLCK as libc::c_short)
+}
+
+/// Run the notification hook with the report on its stdin
+fn notify(command: &str, res: Result<&UnattendedOutcome, &OutputError>) {
+    if command.trim().is_empty() {
+        return;
+    }
+
+    let (result, report) = match res {
+        Ok(outcome) => {
+            let mut report = fl!("unattended-report-success", count = outcome.upgraded.len());
+            for pkg in &outcome.upgraded {
+                report.push_str(&format!("\n  {pkg}"));
+            }
+            if !outcome.reboot_reasons.is_empty() {
+                report.push_str("\n\n");
+                report.push_str(&fl!(
+                    "report-reboot-required",
+                    reason = outcome.reboot_reasons.join(", ")
+                ));
+            }
+            ("success", report)
+        }
+        Err(e) => {
+            let mut report = fl!("unattended-report-failure");
+            report.push_str(&format!("\n  {}", e.description));
+            if let Some(source) = &e.source {
+                report.push_str(&format!("\n  {source}"));
+            }
+            ("failure", report)
+        }
+    };
+
+    let child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .env("OMA_RESULT", result)
+        .stdin(Stdio::piped())
+        .spawn();
+
+    let res = child.and_then(|mut child| {
+        if let Some(mut stdin) = child.stdin.take() {
+            writeln!(stdin, "{report}")?;
+        }
+        child.wait()
+    });
+
+    match res {
+        Ok(status) if status.success() => {}
+        Ok(status) => warn!(
+            "{}",
+            fl!("unattended-notify-failed", status = status.to_string())
+        ),
+        Err(e) => warn!(
+            "{}",
+            fl!("unattended-notify-failed", status = e.to_string())
+        ),
+    }
+}
+
+fn schedule_reboot(delay: u64, reasons: &[String]) -> Result<(), OutputError> {
+    warn!(
+        "{}",
+        fl!(
+            "unattended-reboot-scheduled",
+            mins = delay,
+            reason = reasons.join(", ")
+        )
+    );
+
+    Command::new("shutdown")
+        .arg("-r")
+        .arg(format!("+{delay}"))
+        .arg(fl!("unattended-reboot-message"))
+        .status()
+        .map_err(|e| OutputError {
+            description: fl!("unattended-reboot-failed"),
+            source: Some(Box::new(e)),
+        })?;
+
+    Ok(())
+}
+
+#[test]
+fn test_in_maintenance_window() {
+    let time = |s| NaiveTime::parse_from_str(s, "%H:%M").unwrap();
+
+    assert!(in_maintenance_window("", time("12:00")).unwrap());
+    assert!(in_maintenance_window("02:00-05:00", time("03:30")).unwrap());
+    assert!(!in_maintenance_window("02:00-05:00", time("05:00")).unwrap());
+    assert!(in_maintenance_window("22:00-04:00", time("23:00")).unwrap());
+    assert!(in_maintenance_window("22:00-04:00", time("01:00")).unwrap());
+    assert!(!in_maintenance_window("22:00-04:00", time("12:00")).unwrap());
+    assert!(in_maintenance_window("22:00", time("12:00")).is_err());
+}
@@ -3,7 +3,10 @@ use std::fs::read_dir;
 use std::path::Path;
 use std::thread;
 
+use crate::changelog::preview_changelogs;
+use crate::conffile::handle_conffiles;
 use crate::pb::RenderDownloadProgress;
+use crate::report::PostTransactionReport;
 use crate::subcommand::utils::display_suggest_tips;
 use crate::subcommand::utils::history_success_tips;
 use crate::subcommand::utils::undo_tips;
@@ -48,19 +51,24 @@ use crate::install_progress::OmaInstallProgressManager;
 use crate::pb::NoProgressBar;
 use crate::pb::OmaMultiProgressBar;
 use crate::pb::OmaProgressBar;
+use crate::solver;
 use crate::subcommand::utils::autoremovable_tips;
 use crate::subcommand::utils::is_terminal;
+use crate::success;
 use crate::table::table_for_install_pending;
+use crate::tui::Review;
 use crate::utils::dbus_check;
 use crate::utils::root;
 use crate::HTTP_CLIENT;
 
+use super::apply_plan;
 use super::remove::ask_user_do_as_i_say;
 use super::utils::handle_features;
 use super::utils::handle_no_result;
 use super::utils::is_nothing_to_do;
 use super::utils::lock_oma;
 use super::utils::no_check_dbus_warn;
+use super::utils::suggest_topics;
 use super::utils::Refresh;
 use crate::args::CliExecuter;
 
@@ -94,6 +102,21 @@ pub(crate) struct Upgrade {
     /// Remove package(s) also remove configuration file(s), like apt purge
     #[arg(long, visible_alias = "purge")]
     remove_config: bool,
+    /// Restart services using replaced binaries or libraries after the transaction (via systemd)
+    #[arg(long)]
+    restart_services: bool,
+    /// Only upgrade package(s) with security updates
+    #[arg(long)]
+    security_only: bool,
+    /// Review pending changes interactively, deselected package(s) are kept back
+    #[arg(long)]
+    review: bool,
+    /// Only download package(s) into the archive directory, do not install them
+    #[arg(long, conflicts_with = "print_plan")]
+    download_only: bool,
+    /// Print the resolved changes as JSON, to be applied later with `oma apply-plan'
+    #[arg(long)]
+    print_plan: bool,
     /// Bypass confirmation prompts
     #[arg(short, long)]
     yes: bool,
@@ -129,6 +152,11 @@ impl CliExecuter for Upgrade {
             no_refresh_topics,
             autoremove,
             remove_config,
+            restart_services,
+            security_only,
+            review,
+            download_only,
+            print_plan,
             yes,
             packages,
             dry_run,
@@ -202,9 +230,19 @@ impl CliExecuter for Upgrade {
         let pkgs_unparse = packages.iter().map(|x| x.as_str()).collect::<Vec<_>>();
         let mut retry_times = 1;
 
+        let review = (review || config.interactive_review())
+            && !print_plan
+            && !yes
+            && !dry_run
+            && is_terminal();
+        // Packages deselected in the review, kept back in retries as well
+        let mut kept_back: Vec<String> = vec![];
+
         let oma_apt_args = OmaAptArgs::builder()
+            .solver(solver())
             .sysroot(sysroot.to_string_lossy().to_string())
             .dpkg_force_confnew(force_confnew)
+            .dpkg_force_confold(config.native_conffile_prompt())
             .force_yes(force_yes)
             .yes(yes)
             .another_apt_options(apt_options)
@@ -229,8 +267,17 @@ impl CliExecuter for Upgrade {
                 AptUpgrade::FullUpgrade
             };
 
-            debug!("Upgrade mode is using: {:?}", mode);
-            apt.upgrade(mode)?;
+            if security_only {
+                let tum = get_tum(&sysroot)?;
+                let security_pkgs = get_security_tum_pkgs(&tum);
+
+                apt.upgrade_security(|name, version| {
+                    is_security_tum_pkg(&security_pkgs, name, version)
+                })?;
+            } else {
+                debug!("Upgrade mode is using: {:?}", mode);
+                apt.upgrade(mode)?;
+            }
 
             let matcher = PackagesMatcher::builder()
                 .cache(&apt.cache)
@@ -255,6 +302,8 @@ impl CliExecuter for Upgrade {
                 }
             }
 
+            apt.keep_back(&kept_back.iter().map(|x| x.as_str()).collect::<Vec<_>>());
+
             let pb = if !no_progress && is_terminal() {
                 OmaProgressBar::new_spinner(Some(fl!("resolving-dependencies"))).into()
             } else {
@@ -270,11 +319,13 @@ impl CliExecuter for Upgrade {
                     apt.fix_dpkg_status()?;
                 }
 
-                apt.resolve(no_fixbroken, remove_config)?;
+                apt.resolve(no_fixbroken, remove_config)
+                    .map_err(|e| suggest_topics(e, &sysroot))?;
 
                 if autoremove {
                     apt.autoremove(remove_config)?;
-                    apt.resolve(false, remove_config)?;
+                    apt.resolve(false, remove_config)
+                        .map_err(|e| suggest_topics(e, &sysroot))?;
                 }
 
                 Ok(())
@@ -298,6 +349,35 @@ impl CliExecuter for Upgrade {
                 |features| handle_features(features, config.protect_essentials()).unwrap_or(false),
             )?;
 
+            if print_plan {
+                apply_plan::print_plan(&sysroot, op)?;
+                return Ok(0);
+            }
+
+            let op =
+                if retry_times == 1 && review && !(op.install.is_empty() && op.remove.is_empty()) {
+                    let tum = get_tum(&sysroot)?;
+                    let topics = get_pkg_topics(&tum, &op);
+
+                    match Review::builder()
+                        .apt(&mut apt)
+                        .op(op)
+                        .topics(topics)
+                        .no_fixbroken(no_fixbroken)
+                        .remove_config(remove_config)
+                        .build()
+                        .run()?
+                    {
+                        Some(reviewed) => {
+                            kept_back = reviewed.kept_back;
+                            reviewed.op
+                        }
+                        None => return Ok(PagerExit::Sigint.into()),
+                    }
+                } else {
+                    op
+                };
+
             apt.check_disk_size(&op)?;
 
             let install = &op.install;
@@ -311,15 +391,24 @@ impl CliExecuter for Upgrade {
                 return Ok(0);
             }
 
-            if retry_times == 1 {
+            // Changes were already confirmed in the review
+            if retry_times == 1 && !review {
                 let tum = get_tum(&sysroot)?;
                 let matches_tum = get_matches_tum(&tum, &op);
 
+                let changelogs = match config.changelog_preview() {
+                    Some(changelog_config) if !yes && !dry_run => {
+                        preview_changelogs(&apt, install, changelog_config, config.network_thread())
+                    }
+                    _ => vec![],
+                };
+
                 match table_for_install_pending(
                     install,
                     remove,
                     disk_size,
                     Some(matches_tum),
+                    &changelogs,
                     !yes,
                     dry_run,
                 )? {
@@ -329,6 +418,42 @@ impl CliExecuter for Upgrade {
                 }
             }
 
+            if download_only {
+                let summary = apt.download_only(
+                    &HTTP_CLIENT,
+                    &op,
+                    CommitNetworkConfig {
+                        network_thread: Some(config.network_thread()),
+                        auth_config: Some(&auth_config),
+                    },
+                    |event| async {
+                        if let Err(e) = tx.send_async(event).await {
+                            error!("{}", e);
+                        }
+                    },
+                )?;
+
+                if !summary.success.is_empty() {
+                    success!(
+                        "{}",
+                        fl!(
+                            "successfully-download-to-path",
+                            len = summary.success.len(),
+                            path = apt.get_archive_dir().display().to_string()
+                        )
+                    );
+                }
+
+                if !summary.is_download_success() {
+                    return Err(OutputError {
+                        description: fl!("download-failed-with-len", len = summary.failed.len()),
+                        source: None,
+                    });
+                }
+
+                return Ok(0);
+            }
+
             let typ = SummaryType::Upgrade(
                 pkgs.iter()
                     .map(|x| format!("{} {}", x.raw_pkg.fullname(true), x.version_raw.version()))
@@ -356,14 +481,22 @@ impl CliExecuter for Upgrade {
                 },
             ) {
                 Ok(()) => {
-                    write_oma_installed_status()?;
+                    write_oma_installed_status(&sysroot)?;
+
+                    let leftover_conffiles = handle_conffiles(
+                        &sysroot,
+                        &op,
+                        config.native_conffile_prompt() && !yes && is_terminal(),
+                        dry_run,
+                    )?;
+
                     autoremovable_tips(ar_count, ar_size)?;
 
                     write_history_entry(
                         &op,
                         typ,
                         {
-                            let db = create_db_file(sysroot)?;
+                            let db = create_db_file(&sysroot)?;
                             connect_db(db, true)?
                         },
                         dry_run,
@@ -374,6 +507,16 @@ impl CliExecuter for Upgrade {
                     history_success_tips(dry_run);
                     display_suggest_tips(suggest, recommend);
 
+                    if !dry_run {
+                        let report =
+                            PostTransactionReport::collect(&sysroot, &op, leftover_conffiles);
+                        report.display();
+
+                        if restart_services {
+                            report.restart_services()?;
+                        }
+                    }
+
                     drop(fds);
                     return Ok(0);
                 }
@@ -548,6 +691,90 @@ pub fn get_tum(sysroot: &Path) -> Result<Vec<TopicUpdateManifest>, OutputError>
     Ok(entries)
 }
 
+/// Get packages (and their versions) updated by security topic updates
+pub fn get_security_tum_pkgs(tum: &[TopicUpdateManifest]) -> HashMap<&str, &str> {
+    let mut res = HashMap::with_hasher(ahash::RandomState::new());
+
+    // Topics included in cumulative security updates are security updates as well
+    let security_topics = tum
+        .iter()
+        .flat_map(|x| x.entries.values())
+        .filter_map(|entry| match entry {
+            TopicUpdateEntry::Cumulative {
+                security: true,
+                topics,
+                ..
+            } => Some(topics),
+            _ => None,
+        })
+        .flatten()
+        .map(|x| x.as_str())
+        .collect::<HashSet<_>>();
+
+    for (name, entry) in tum.iter().flat_map(|x| &x.entries) {
+        if let TopicUpdateEntry::Conventional {
+            security, packages, ..
+        } = entry
+        {
+            if !security && !security_topics.contains(name.as_str()) {
+                continue;
+            }
+
+            for (pkg_name, version) in packages {
+                if let Some(version) = version {
+                    res.insert(pkg_name.as_str(), version.as_str());
+                }
+            }
+        }
+    }
+
+    res
+}
+
+/// Whether a package version is updated by a security topic update
+pub fn is_security_tum_pkg(security_pkgs: &HashMap<&str, &str>, name: &str, version: &str) -> bool {
+    security_pkgs
+        .get(name)
+        .is_some_and(|tum_version| topic_version_matches(version, tum_version))
+}
+
+/// Localized names of topics updating packages of `op`, by package name
+pub fn get_pkg_topics(tum: &[TopicUpdateManifest], op: &OmaOperation) -> HashMap<String, String> {
+    let lang = std::env::var("LANG").unwrap_or("en_US".into());
+    let (lang, _) = lang.split_once('.').unwrap_or(("en_US", ""));
+    let lang = if lang == "en_US" { "default" } else { lang };
+
+    let install_map = &op
+        .install
+        .iter()
+        .map(|x| (x.name_without_arch(), x.new_version()))
+        .collect::<HashMap<_, _>>();
+
+    let remove_map = &op.remove.iter().map(|x| (x.name())).collect::<HashSet<_>>();
+
+    let mut res = HashMap::with_hasher(ahash::RandomState::new());
+
+    for (_, entry) in tum.iter().flat_map(|x| &x.entries) {
+        let TopicUpdateEntry::Conventional { packages, name, .. } = entry else {
+            continue;
+        };
+
+        let Some(name) = name.get(lang).or_else(|| name.get("default")) else {
+            continue;
+        };
+
+        for (pkg_name, version) in packages {
+            if install_pkg_on_topic(install_map, pkg_name, version)
+                || remove_pkg_on_topic(remove_map, pkg_name, version)
+            {
+                res.insert(pkg_name.to_string(), name.to_string());
+            }
+        }
+    }
+
+    res
+}
+
 pub fn get_matches_tum<'a>(
     tum: &'a [TopicUpdateManifest],
     op: &OmaOperation,
@@ -628,6 +855,10 @@ fn install_pkg_on_topic(
         None => return false,
     };
 
+    topic_version_matches(install_ver, tum_version)
+}
+
+fn topic_version_matches(install_ver: &str, tum_version: &str) -> bool {
     if let Some((prefix, suffix)) = install_ver.rsplit_once("~pre") {
         if is_topic_preversion(suffix) {
             return tum_version == prefix;
@@ -8,13 +8,18 @@ use std::io::stderr;
 use std::io::stdin;
 use std::io::stdout;
 use std::io::IsTerminal;
+use std::io::Read;
 use std::panic;
 use std::path::Path;
 use std::path::PathBuf;
 use std::sync::atomic::Ordering;
 use std::thread;
 
+use crate::apply_plan::check_plan;
+use crate::changelog::preview_changelogs;
 use crate::color_formatter;
+use crate::conffile::handle_conffiles;
+use crate::config::ChangelogConfig;
 use crate::error::OutputError;
 use crate::fl;
 use crate::install_progress::NoInstallProgressManager;
@@ -25,10 +30,14 @@ use crate::pb::OmaMultiProgressBar;
 use crate::pb::OmaProgressBar;
 use crate::pb::RenderDownloadProgress;
 use crate::pb::RenderRefreshProgress;
+use crate::report::PostTransactionReport;
 use crate::success;
 use crate::table::table_for_install_pending;
+use crate::tui::Review;
 use crate::upgrade::get_matches_tum;
+use crate::upgrade::get_pkg_topics;
 use crate::upgrade::get_tum;
+use crate::upgrade::TopicUpdateManifest;
 use crate::HTTP_CLIENT;
 use crate::LOCKED;
 use crate::RT;
@@ -42,6 +51,7 @@ use dialoguer::console;
 use dialoguer::console::style;
 use dialoguer::theme::ColorfulTheme;
 use dialoguer::Confirm;
+use faster_hex::hex_string;
 use flume::unbounded;
 use indexmap::IndexSet;
 use oma_console::indicatif::HumanBytes;
@@ -59,14 +69,20 @@ use oma_pm::apt::FilterMode;
 use oma_pm::apt::OmaApt;
 use oma_pm::apt::OmaAptArgs;
 use oma_pm::apt::OmaAptError;
+use oma_pm::apt::OmaOperation;
+use oma_pm::apt::SolverComparison;
 use oma_pm::apt::SummarySort;
 use oma_pm::apt::{InstallEntry, RemoveEntry};
+use oma_pm::localdeb::LocalDebWarning;
+use oma_pm::multiarch::{same_version_skew, MultiArchSkew};
+use oma_pm::search_index::update_search_index;
 use oma_pm::CommitNetworkConfig;
 use oma_refresh::db::OmaRefresh;
-use oma_utils::dpkg::dpkg_arch;
+use oma_utils::dpkg::{dpkg_arch, dpkg_foreign_archs};
 use oma_utils::oma::lock_oma_inner;
 use oma_utils::oma::unlock_oma;
 use reqwest::Client;
+use sha2::Digest;
 use std::fmt::Display;
 use tracing::debug;
 use tracing::error;
@@ -85,6 +101,17 @@ pub(crate) fn handle_no_result(
     }
 
     let mut bin = IndexSet::with_hasher(ahash::RandomState::new());
+    let mut disabled_archs = IndexSet::with_hasher(ahash::RandomState::new());
+
+    // Keywords like `foo:i386` can not match anything if the architecture is not enabled
+    let enabled_archs = if no_result.iter().any(|x| x.contains(':')) {
+        dpkg_arch(&sysroot)
+            .into_iter()
+            .chain(dpkg_foreign_archs(&sysroot).unwrap_or_default())
+            .collect::<Vec<_>>()
+    } else {
+        vec![]
+    };
 
     let pb = if !no_progress || is_terminal() {
         Some(OmaProgressBar::new_spinner(Some(fl!("searching"))))
@@ -114,6 +141,15 @@ pub(crate) fn handle_no_result(
                 error!("{}", fl!("could-not-find-pkg-from-keyword", c = word));
             }
 
+            if let Some((_, arch)) = word.split_once(':') {
+                let arch = arch.split(['=', '/', '<', '>']).next().unwrap_or(arch);
+                if arch.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+                    && !enabled_archs.iter().any(|x| x == arch)
+                {
+                    disabled_archs.insert(arch);
+                }
+            }
+
             search(
                 sysroot.as_ref().join("var/lib/apt/lists"),
                 Mode::BinProvides,
@@ -150,6 +186,17 @@ pub(crate) fn handle_no_result(
         }
     }
 
+    for arch in disabled_archs {
+        info!(
+            "{}",
+            fl!(
+                "arch-not-enabled-tips",
+                arch = arch,
+                cmd = format!("oma arch add {arch}")
+            )
+        );
+    }
+
     Err(OutputError {
         description: fl!("has-error-on-top"),
         source: None,
@@ -200,6 +247,9 @@ pub struct Refresh<'a> {
     sysroot: &'a str,
     #[builder(default = true)]
     refresh_topics: bool,
+    /// Build the search index after refreshing, otherwise it is built on the next search
+    #[builder(default)]
+    update_search_index: bool,
     config: &'a AptConfig,
     auth_config: Option<&'a AuthConfig>,
 }
@@ -213,6 +263,7 @@ impl Refresh<'_> {
             network_thread,
             sysroot,
             refresh_topics,
+            update_search_index: update_index,
             config,
             auth_config,
         } = self;
@@ -234,7 +285,7 @@ impl Refresh<'_> {
 
         let refresh = OmaRefresh::builder()
             .download_dir(sysroot.join("var/lib/apt/lists"))
-            .source(sysroot)
+            .source(sysroot.clone())
             .threads(network_thread)
             .arch(arch)
             .apt_config(config)
@@ -269,6 +320,24 @@ impl Refresh<'_> {
                 .await
         })?;
 
+        if !update_index {
+            return Ok(());
+        }
+
+        // Build search index now, so that `oma search' and `oma tui' do not have to
+        let oma_apt_args = OmaAptArgs::builder()
+            .sysroot(sysroot.to_string_lossy().to_string())
+            .build();
+
+        match OmaApt::new(vec![], oma_apt_args, false, AptConfig::new()) {
+            Ok(apt) => {
+                if let Err(e) = update_search_index(&apt.cache, &sysroot) {
+                    debug!("Failed to update search index: {e}");
+                }
+            }
+            Err(e) => debug!("Failed to open apt cache to update search index: {e}"),
+        }
+
         Ok(())
     }
 }
@@ -280,6 +349,33 @@ pub fn auth_config(sysroot: impl AsRef<Path>) -> Option<AuthConfig> {
         .ok()
 }
 
+/// Suggest enabling topics for unsatisfiable dependencies, from the topics cached by the last
+/// refresh, this never touches the network
+#[cfg(feature = "aosc")]
+pub(crate) fn suggest_topics(e: OmaAptError, sysroot: &Path) -> OmaAptError {
+    let OmaAptError::DependencyIssue(unmet, mut explanation) = e else {
+        return e;
+    };
+
+    if explanation.missing_packages().next().is_some() {
+        match oma_topics::cached_topics(sysroot) {
+            Ok(topics) => explanation.suggest_topics(
+                topics
+                    .iter()
+                    .map(|x| (x.name.as_str(), x.packages.as_slice())),
+            ),
+            Err(e) => debug!("Failed to get topics: {e}"),
+        }
+    }
+
+    OmaAptError::DependencyIssue(unmet, explanation)
+}
+
+#[cfg(not(feature = "aosc"))]
+pub(crate) fn suggest_topics(e: OmaAptError, _sysroot: &Path) -> OmaAptError {
+    e
+}
+
 #[derive(Builder)]
 pub(crate) struct CommitChanges<'a> {
     apt: OmaApt,
@@ -306,6 +402,15 @@ pub(crate) struct CommitChanges<'a> {
     network_thread: usize,
     #[builder(default)]
     check_update: bool,
+    #[builder(default)]
+    native_conffile_prompt: bool,
+    #[builder(default)]
+    restart_services: bool,
+    changelog_preview: Option<&'a ChangelogConfig>,
+    #[builder(default)]
+    interactive_review: bool,
+    /// Changes are marked from a plan, do not resolve them again and check them against it
+    plan: Option<&'a OmaOperation>,
 }
 
 impl CommitChanges<'_> {
@@ -325,6 +430,11 @@ impl CommitChanges<'_> {
             auth_config,
             network_thread,
             check_update,
+            native_conffile_prompt,
+            restart_services,
+            changelog_preview,
+            interactive_review,
+            plan,
         } = self;
 
         let pb = if !no_progress && is_terminal() {
@@ -333,51 +443,49 @@ impl CommitChanges<'_> {
             None
         };
 
-        let res = Ok(()).and_then(|_| -> Result<(), OmaAptError> {
-            if autoremove {
-                apt.autoremove(remove_config)?;
-            }
-
-            if !no_fixbroken {
-                apt.fix_resolver_broken();
-            }
-
-            if fix_dpkg_status {
-                apt.fix_dpkg_status()?;
-            }
-
-            apt.resolve(no_fixbroken, remove_config)?;
-
-            Ok(())
-        });
+        let res = ResolveChanges {
+            autoremove,
+            remove_config,
+            no_fixbroken,
+            fix_dpkg_status,
+            resolve: plan.is_none(),
+        }
+        .run(&mut apt, Path::new(&sysroot));
 
         if let Some(pb) = pb {
             pb.inner.finish_and_clear();
         }
 
         res?;
+        warn_resolved(&apt);
 
-        let op = apt.summary(
-            SummarySort::Operation,
-            |pkg| {
-                if dry_run {
-                    true
-                } else if protect_essential {
-                    false
-                } else {
-                    ask_user_do_as_i_say(pkg).unwrap_or(false)
-                }
-            },
-            |features| {
-                if dry_run {
-                    true
-                } else {
-                    handle_features(features, protect_essential).unwrap_or(false)
-                }
-            },
-        )?;
+        let op = summary_changes(&apt, dry_run, protect_essential)?;
 
-        debug!("{op}");
+        if let Some(plan) = plan {
+            check_plan(plan, &op)?;
+        }
+
+        let review = interactive_review && !yes && !dry_run && is_terminal();
+
+        let op = if review && !(op.install.is_empty() && op.remove.is_empty()) {
+            let topics = topic_updates(Path::new(&sysroot), check_update)?
+                .map(|tum| get_pkg_topics(&tum, &op));
+
+            match Review::builder()
+                .apt(&mut apt)
+                .op(op)
+                .maybe_topics(topics)
+                .no_fixbroken(no_fixbroken)
+                .remove_config(remove_config)
+                .build()
+                .run()?
+            {
+                Some(reviewed) => reviewed.op,
+                None => return Ok(PagerExit::Sigint.into()),
+            }
+        } else {
+            op
+        };
 
         apt.check_disk_size(&op)?;
 
@@ -392,15 +500,23 @@ impl CommitChanges<'_> {
             return Ok(0);
         }
 
-        if check_update {
-            let tum = get_tum(Path::new(&sysroot))?;
-            let matches_tum = get_matches_tum(&tum, &op);
+        // Changes were already confirmed in the review
+        if !review {
+            let changelogs = match changelog_preview {
+                Some(changelog_config) if !yes && !dry_run => {
+                    preview_changelogs(&apt, install, changelog_config, network_thread)
+                }
+                _ => vec![],
+            };
+
+            let tum = topic_updates(Path::new(&sysroot), check_update)?;
 
             match table_for_install_pending(
                 install,
                 remove,
                 disk_size,
-                Some(matches_tum),
+                tum.as_deref().map(|tum| get_matches_tum(tum, &op)),
+                &changelogs,
                 !yes,
                 dry_run,
             )? {
@@ -408,12 +524,6 @@ impl CommitChanges<'_> {
                 x @ PagerExit::Sigint => return Ok(x.into()),
                 x @ PagerExit::DryRun => return Ok(x.into()),
             }
-        } else {
-            match table_for_install_pending(install, remove, disk_size, None, !yes, dry_run)? {
-                PagerExit::NormalExit => {}
-                x @ PagerExit::Sigint => return Ok(x.into()),
-                x @ PagerExit::DryRun => return Ok(x.into()),
-            }
         }
 
         let start_time = Local::now().timestamp();
@@ -448,42 +558,231 @@ impl CommitChanges<'_> {
             },
         );
 
-        match res {
-            Ok(_) => {
-                write_oma_installed_status()?;
-                autoremovable_tips(ar_count, ar_size)?;
-
-                write_history_entry(
-                    &op,
-                    typ,
-                    {
-                        let db = create_db_file(sysroot)?;
-                        connect_db(db, true)?
-                    },
-                    dry_run,
-                    start_time,
-                    true,
-                )?;
-
-                history_success_tips(dry_run);
-                display_suggest_tips(suggest, recommend);
-
-                Ok(0)
+        let report = finish_commit(
+            res,
+            &op,
+            typ,
+            Path::new(&sysroot),
+            dry_run,
+            start_time,
+            native_conffile_prompt && !yes && is_terminal(),
+        )?;
+
+        autoremovable_tips(ar_count, ar_size)?;
+        display_suggest_tips(suggest, recommend);
+
+        if let Some(report) = report {
+            report.display();
+
+            if restart_services {
+                report.restart_services()?;
             }
-            Err(e) => {
-                undo_tips();
-                write_history_entry(
-                    &op,
-                    typ,
-                    {
-                        let db = create_db_file(sysroot)?;
-                        connect_db(db, true)?
-                    },
-                    dry_run,
-                    start_time,
-                    false,
-                )?;
-                Err(e.into())
+        }
+
+        Ok(0)
+    }
+}
+
+/// Steps to resolve marked changes, shared with committing in the TUI
+pub(crate) struct ResolveChanges {
+    pub autoremove: bool,
+    pub remove_config: bool,
+    pub no_fixbroken: bool,
+    pub fix_dpkg_status: bool,
+    /// Unset if changes are already resolved, e.g. marked from a plan
+    pub resolve: bool,
+}
+
+impl ResolveChanges {
+    /// Resolve changes marked in `apt`
+    pub fn run(self, apt: &mut OmaApt, sysroot: &Path) -> Result<(), OutputError> {
+        let ResolveChanges {
+            autoremove,
+            remove_config,
+            no_fixbroken,
+            fix_dpkg_status,
+            resolve,
+        } = self;
+
+        if autoremove {
+            apt.autoremove(remove_config)?;
+        }
+
+        if !no_fixbroken {
+            apt.fix_resolver_broken();
+        }
+
+        if fix_dpkg_status {
+            apt.fix_dpkg_status()?;
+        }
+
+        if resolve {
+            apt.resolve(no_fixbroken, remove_config)
+                .map_err(|e| suggest_topics(e, sysroot))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Warn about anything suspicious in resolved changes
+pub(crate) fn warn_resolved(apt: &OmaApt) {
+    if let Some(comparison) = apt.solver_comparison() {
+        display_solver_comparison(comparison);
+    }
+
+    warn_multiarch_skew(&same_version_skew(&apt.cache));
+}
+
+/// Summarize resolved changes, removing essential packages or features has to be confirmed
+/// unless `protect_essential` is set
+pub(crate) fn summary_changes(
+    apt: &OmaApt,
+    dry_run: bool,
+    protect_essential: bool,
+) -> Result<OmaOperation, OutputError> {
+    let op = apt.summary(
+        SummarySort::Operation,
+        |pkg| {
+            if dry_run {
+                true
+            } else if protect_essential {
+                false
+            } else {
+                ask_user_do_as_i_say(pkg).unwrap_or(false)
+            }
+        },
+        |features| {
+            if dry_run {
+                true
+            } else {
+                handle_features(features, protect_essential).unwrap_or(false)
+            }
+        },
+    )?;
+
+    debug!("{op}");
+
+    Ok(op)
+}
+
+/// Topic update manifests, if `check_update` is set
+pub(crate) fn topic_updates(
+    sysroot: &Path,
+    check_update: bool,
+) -> Result<Option<Vec<TopicUpdateManifest>>, OutputError> {
+    if !check_update {
+        return Ok(None);
+    }
+
+    get_tum(sysroot).map(Some)
+}
+
+/// Record the result of committing `op`
+///
+/// Returns the post-transaction report if changes were made.
+pub(crate) fn finish_commit(
+    res: Result<(), OmaAptError>,
+    op: &OmaOperation,
+    typ: SummaryType,
+    sysroot: &Path,
+    dry_run: bool,
+    start_time: i64,
+    conffile_prompt: bool,
+) -> Result<Option<PostTransactionReport>, OutputError> {
+    let conn = connect_db(create_db_file(sysroot)?, true)?;
+
+    match res {
+        Ok(_) => {
+            write_oma_installed_status(sysroot)?;
+            let leftover_conffiles = handle_conffiles(sysroot, op, conffile_prompt, dry_run)?;
+            write_history_entry(op, typ, conn, dry_run, start_time, true)?;
+            history_success_tips(dry_run);
+
+            Ok((!dry_run).then(|| PostTransactionReport::collect(sysroot, op, leftover_conffiles)))
+        }
+        Err(e) => {
+            undo_tips();
+            write_history_entry(op, typ, conn, dry_run, start_time, false)?;
+            Err(e.into())
+        }
+    }
+}
+
+pub fn display_solver_comparison(comparison: &SolverComparison) {
+    info!("{}", fl!("solver-compare-apt"));
+    print!("{}", comparison.apt);
+
+    match &comparison.sat {
+        Ok(op) => {
+            info!("{}", fl!("solver-compare-sat"));
+            print!("{op}");
+        }
+        Err(e) => warn!("{}", fl!("solver-compare-sat-failed", reason = e.as_str())),
+    }
+}
+
+/// Warn about `Multi-Arch: same` packages which copies have different versions
+pub fn warn_multiarch_skew(skew: &[MultiArchSkew]) {
+    for i in skew {
+        warn!("{}", fl!("multiarch-version-skew", pkg = i.name.as_str()));
+
+        for (arch, version) in &i.versions {
+            msg!(
+                "{}: {}",
+                arch,
+                color_formatter().color_str(version, Action::EmphasisSecondary)
+            );
+        }
+    }
+}
+
+pub fn warn_local_debs(warnings: &[LocalDebWarning]) {
+    let mut last_conflict: Option<&str> = None;
+
+    for i in warnings {
+        match i {
+            LocalDebWarning::Downgrade {
+                name,
+                installed,
+                local,
+            } => {
+                warn!(
+                    "{}",
+                    fl!(
+                        "local-deb-downgrade",
+                        name = name.as_str(),
+                        installed = installed.as_str(),
+                        local = local.as_str()
+                    )
+                );
+            }
+            LocalDebWarning::ReplaceOrigin {
+                name,
+                installed,
+                origin,
+            } => {
+                warn!(
+                    "{}",
+                    fl!(
+                        "local-deb-replace-origin",
+                        name = name.as_str(),
+                        installed = installed.as_str(),
+                        origin = origin.as_str()
+                    )
+                );
+            }
+            LocalDebWarning::FileConflict { name, file, owners } => {
+                if last_conflict != Some(name.as_str()) {
+                    warn!("{}", fl!("local-deb-file-conflict", name = name.as_str()));
+                    last_conflict = Some(name);
+                }
+
+                msg!(
+                    "{} ({})",
+                    file,
+                    color_formatter().color_str(owners.join(", "), Action::EmphasisSecondary)
+                );
             }
         }
     }
@@ -652,9 +951,10 @@ pub fn format_features(features: &HashSet<Box<str>>) -> anyhow::Result<String> {
     Ok(res)
 }
 
-pub fn write_oma_installed_status() -> anyhow::Result<()> {
-    let status_file = Path::new("/var/lib/oma/installed");
-    let status_file_manual = Path::new("/var/lib/oma/installed-manual");
+pub fn write_oma_installed_status(sysroot: impl AsRef<Path>) -> anyhow::Result<()> {
+    let sysroot = sysroot.as_ref();
+    let status_file = sysroot.join("var/lib/oma/installed");
+    let status_file_manual = sysroot.join("var/lib/oma/installed-manual");
     let parent = status_file.parent().unwrap();
 
     if !parent.is_dir() {
@@ -663,7 +963,9 @@ pub fn write_oma_installed_status() -> anyhow::Result<()> {
 
     let apt = OmaApt::new(
         vec![],
-        OmaAptArgs::builder().build(),
+        OmaAptArgs::builder()
+            .sysroot(sysroot.to_string_lossy().to_string())
+            .build(),
         false,
         AptConfig::new(),
     )?;
@@ -679,15 +981,15 @@ pub fn write_oma_installed_status() -> anyhow::Result<()> {
         .collect::<Vec<_>>();
 
     if status_file.exists() {
-        fs::copy(status_file, parent.join("installed-old"))?;
+        fs::copy(&status_file, parent.join("installed-old"))?;
     }
 
     if status_file_manual.exists() {
-        fs::copy(status_file, parent.join("installed-manual-old"))?;
+        fs::copy(&status_file, parent.join("installed-manual-old"))?;
     }
 
-    fs::write(status_file, pkgs.join("\n"))?;
-    fs::write(status_file_manual, manual_pkgs.join("\n"))?;
+    fs::write(&status_file, pkgs.join("\n"))?;
+    fs::write(&status_file_manual, manual_pkgs.join("\n"))?;
 
     Ok(())
 }
@@ -721,3 +1023,29 @@ pub fn is_terminal() -> bool {
     debug!("is terminal: {}", res);
     res
 }
+
+/// Error of an I/O operation on `path`
+pub(crate) fn io_error(path: &Path, e: io::Error) -> OutputError {
+    OutputError {
+        description: fl!("failed-to-operate-path", p = path.display().to_string()),
+        source: Some(Box::new(e)),
+    }
+}
+
+/// Hex encoded checksum of a file, calculated using digest `D`
+pub(crate) fn file_checksum<D: Digest>(path: &Path) -> Result<String, OutputError> {
+    let mut file = fs::File::open(path).map_err(|e| io_error(path, e))?;
+    let mut hasher = D::new();
+    let mut buf = vec![0; 65536];
+
+    loop {
+        let n = file.read(&mut buf).map_err(|e| io_error(path, e))?;
+        if n == 0 {
+            break;
+        }
+
+        hasher.update(&buf[..n]);
+    }
+
+    Ok(hex_string(&hasher.finalize()))
+}
@@ -0,0 +1,269 @@
+use std::{
+    io::{stdout, Write},
+    path::PathBuf,
+};
+
+use clap::Args;
+use oma_console::print::Action;
+use oma_pm::{
+    apt::{AptConfig, OmaApt, OmaAptArgs, SummarySort},
+    graph::{why_installed, WhyInstalled},
+    matches::{GetArchMethod, PackagesMatcher},
+};
+use oma_utils::dpkg::{get_selections, is_hold};
+use tracing::{info, warn};
+
+use crate::{color_formatter, config::Config, error::OutputError, fl, msg, solver, success};
+
+use super::utils::handle_no_result;
+
+use crate::args::CliExecuter;
+
+#[derive(Debug, Args)]
+pub struct Why {
+    /// Package(s) to explain why they are installed
+    #[arg(required = true)]
+    packages: Vec<String>,
+    /// Set output format as JSON
+    #[arg(long)]
+    json: bool,
+    /// Set sysroot target directory
+    #[arg(from_global)]
+    sysroot: PathBuf,
+    /// Set apt options
+    #[arg(from_global)]
+    apt_options: Vec<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct WhyNot {
+    /// Package to explain why it can not be installed (like: foo, foo=1.0-1)
+    package: String,
+    /// Set sysroot target directory
+    #[arg(from_global)]
+    sysroot: PathBuf,
+    /// Set apt options
+    #[arg(from_global)]
+    apt_options: Vec<String>,
+}
+
+impl CliExecuter for Why {
+    fn execute(self, _config: &Config, no_progress: bool) -> Result<i32, OutputError> {
+        let Why {
+            packages,
+            json,
+            sysroot,
+            apt_options,
+        } = self;
+
+        let oma_apt_args = OmaAptArgs::builder()
+            .sysroot(sysroot.to_string_lossy().to_string())
+            .another_apt_options(apt_options)
+            .build();
+
+        let apt = OmaApt::new(vec![], oma_apt_args, false, AptConfig::new())?;
+
+        let matcher = PackagesMatcher::builder()
+            .cache(&apt.cache)
+            .native_arch(GetArchMethod::SpecifySysroot(&sysroot))
+            .build();
+
+        let mut pkgs = vec![];
+        let mut no_result = vec![];
+
+        for i in &packages {
+            let res = matcher.match_pkgs_from_glob(i)?;
+            if res.is_empty() {
+                no_result.push(i.as_str());
+            } else {
+                pkgs.extend(res);
+            }
+        }
+
+        handle_no_result(&sysroot, no_result, no_progress)?;
+
+        let mut stdout = stdout();
+
+        for pkg in pkgs {
+            let pkg = pkg.package(&apt.cache);
+            let name = pkg.fullname(true);
+            let why = why_installed(&apt.cache, &pkg)?;
+
+            if json {
+                writeln!(
+                    stdout,
+                    "{}",
+                    serde_json::json!({
+                        "name": name,
+                        "why": why,
+                    })
+                )
+                .ok();
+                continue;
+            }
+
+            match why {
+                WhyInstalled::NotInstalled => {
+                    info!("{}", fl!("why-not-installed", pkg = name));
+                }
+                WhyInstalled::Manual => {
+                    info!("{}", fl!("why-manual", pkg = name));
+                }
+                WhyInstalled::Required(chain) => {
+                    info!("{}", fl!("why-required", pkg = name.as_str()));
+
+                    let mut indent = 0;
+                    if let Some(first) = chain.first() {
+                        msg!(
+                            "{}",
+                            color_formatter().color_str(&first.pkg, Action::Emphasis)
+                        );
+                    }
+
+                    // Each step points to the next package, the last one to the target
+                    let targets = chain
+                        .iter()
+                        .skip(1)
+                        .map(|x| x.pkg.as_str())
+                        .chain([name.as_str()]);
+
+                    for (step, target) in chain.iter().zip(targets) {
+                        indent += 2;
+                        msg!(
+                            "{:indent$}{}: {}",
+                            "",
+                            step.typ,
+                            color_formatter().color_str(target, Action::Emphasis)
+                        );
+                    }
+                }
+                WhyInstalled::Unneeded => {
+                    warn!("{}", fl!("why-unneeded", pkg = name));
+                }
+            }
+        }
+
+        Ok(0)
+    }
+}
+
+impl CliExecuter for WhyNot {
+    fn execute(self, _config: &Config, no_progress: bool) -> Result<i32, OutputError> {
+        let WhyNot {
+            package,
+            sysroot,
+            apt_options,
+        } = self;
+
+        let oma_apt_args = OmaAptArgs::builder()
+            .solver(solver())
+            .sysroot(sysroot.to_string_lossy().to_string())
+            .another_apt_options(apt_options)
+            .build();
+
+        // Only try to resolve, never commit
+        let mut apt = OmaApt::new(vec![], oma_apt_args, true, AptConfig::new())?;
+
+        let matcher = PackagesMatcher::builder()
+            .cache(&apt.cache)
+            .native_arch(GetArchMethod::SpecifySysroot(&sysroot))
+            .build();
+
+        let (pkgs, no_result) = matcher.match_pkgs_and_versions([package.as_str()])?;
+
+        if !no_result.is_empty() {
+            // The package exists, but no version of it is available
+            if let Some(pkg) = apt.cache.get(&package).filter(|x| x.has_versions()) {
+                warn!("{}", fl!("why-not-no-candidate", pkg = package.as_str()));
+
+                for ver in pkg.versions() {
+                    msg!(
+                        "{} ({})",
+                        ver.version(),
+                        ver.package_files()
+                            .filter_map(|x| x.archive().map(|x| x.to_string()))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    );
+                }
+
+                return Ok(1);
+            }
+
+            handle_no_result(&sysroot, no_result, no_progress)?;
+            return Ok(1);
+        }
+
+        let selections = get_selections(&sysroot)?;
+
+        for pkg in &pkgs {
+            let version = pkg.version(&apt.cache);
+            let package = pkg.package(&apt.cache);
+            let name = package.fullname(true);
+
+            if package
+                .installed()
+                .is_some_and(|x| x.version() == version.version())
+            {
+                info!(
+                    "{}",
+                    fl!(
+                        "why-not-already-installed",
+                        pkg = name,
+                        ver = version.version()
+                    )
+                );
+                return Ok(0);
+            }
+
+            if is_hold(&name, &selections) {
+                warn!("{}", fl!("why-not-hold", pkg = name));
+                return Ok(1);
+            }
+
+            if !version.is_downloadable() {
+                warn!(
+                    "{}",
+                    fl!(
+                        "why-not-not-downloadable",
+                        pkg = name,
+                        ver = version.version()
+                    )
+                );
+                return Ok(1);
+            }
+        }
+
+        apt.install(&pkgs, false)?;
+
+        if let Err(e) = apt.resolve(false, false) {
+            warn!("{}", fl!("why-not-broken", pkg = package.as_str()));
+            return Err(e.into());
+        }
+
+        let op = apt.summary(SummarySort::Operation, |_| false, |_| false)?;
+
+        if op.remove.is_empty() {
+            success!("{}", fl!("why-not-installable", pkg = package.as_str()));
+        } else {
+            // Removing other packages is the usual reason of being held back by upgrades
+            warn!(
+                "{}",
+                fl!(
+                    "why-not-need-remove",
+                    pkg = package.as_str(),
+                    count = op.remove.len()
+                )
+            );
+
+            for entry in &op.remove {
+                msg!(
+                    "{}",
+                    color_formatter().color_str(entry.name(), Action::Emphasis)
+                );
+            }
+        }
+
+        Ok(0)
+    }
+}
@@ -8,7 +8,7 @@ use std::sync::LazyLock;
 use crate::console::style;
 use crate::error::OutputError;
 use crate::upgrade::TopicUpdateEntryRef;
-use crate::{color_formatter, fl, ALLOWCTRLC, WRITER};
+use crate::{color_formatter, fl, input_config, ALLOWCTRLC, WRITER};
 use ahash::HashMap;
 use ahash::HashSet;
 use oma_console::indicatif::HumanBytes;
@@ -132,6 +132,7 @@ pub fn oma_display_with_normal_output(
             &OmaPagerUIText { is_question: false },
             None,
             color_formatter(),
+            input_config(),
         )
         .map_err(|e| OutputError {
             description: "Failed to get pager".to_string(),
@@ -142,6 +143,19 @@ pub fn oma_display_with_normal_output(
     Ok(pager)
 }
 
+pub fn oma_display_with_title(title: String) -> Result<Pager<'static>, OutputError> {
+    Pager::external(
+        &OmaPagerUIText { is_question: false },
+        Some(title),
+        color_formatter(),
+        input_config(),
+    )
+    .map_err(|e| OutputError {
+        description: "Failed to get pager".to_string(),
+        source: Some(Box::new(e)),
+    })
+}
+
 struct OmaPagerUIText {
     is_question: bool,
 }
@@ -166,6 +180,42 @@ impl PagerUIText for OmaPagerUIText {
     fn search_tips_not_found(&self) -> String {
         fl!("search-tips-not-found")
     }
+
+    fn search_tips_with_regex_query(&self, query: &str) -> String {
+        fl!("search-tips-with-regex-query", query = query)
+    }
+
+    fn filter_tips_with_query(&self, query: &str, regex: bool) -> String {
+        if regex {
+            fl!("filter-tips-with-regex-query", query = query)
+        } else {
+            fl!("filter-tips-with-query", query = query)
+        }
+    }
+
+    fn filter_tips_with_result(&self, count: usize) -> String {
+        fl!("filter-tips-with-result", count = count)
+    }
+
+    fn tips_invalid_pattern(&self) -> String {
+        fl!("pager-tips-invalid-pattern")
+    }
+
+    fn save_tips_with_path(&self, path: &str, ansi: bool) -> String {
+        if ansi {
+            fl!("save-tips-with-ansi-path", path = path)
+        } else {
+            fl!("save-tips-with-path", path = path)
+        }
+    }
+
+    fn save_tips_done(&self, path: &str) -> String {
+        fl!("save-tips-done", path = path)
+    }
+
+    fn save_tips_failed(&self, path: &str) -> String {
+        fl!("save-tips-failed", path = path)
+    }
 }
 
 fn tips(is_question: bool) -> String {
@@ -188,15 +238,36 @@ fn tips(is_question: bool) -> String {
 
 pub struct PagerPrinter<W> {
     writer: W,
+    /// Lines printed so far
+    lines: usize,
+    /// Lines where sections start
+    sections: Vec<usize>,
 }
 
 impl<W: Write> PagerPrinter<W> {
     pub fn new(writer: W) -> PagerPrinter<W> {
-        PagerPrinter { writer }
+        PagerPrinter {
+            writer,
+            lines: 0,
+            sections: vec![],
+        }
     }
 
     pub fn println<D: Display>(&mut self, d: D) -> std::io::Result<()> {
-        writeln!(self.writer, "{d}")
+        let s = format!("{d}\n");
+        self.lines += s.matches('\n').count();
+        self.writer.write_all(s.as_bytes())
+    }
+
+    /// Print the heading of a section, which can be jumped to in the pager
+    pub fn section<D: Display>(&mut self, heading: D) -> std::io::Result<()> {
+        self.sections.push(self.lines);
+        self.println(heading)
+    }
+
+    /// Lines where sections start, for `Pager::set_sections`
+    pub fn into_sections(self) -> Vec<usize> {
+        self.sections
     }
 
     pub fn print_table<T, I>(&mut self, table: I, header: Vec<&str>) -> std::io::Result<()>
@@ -222,7 +293,7 @@ impl<W: Write> PagerPrinter<W> {
                     .keep_words(true),
             );
 
-        writeln!(self.writer, "{table}")
+        self.println(table)
     }
 }
 
@@ -231,6 +302,7 @@ pub fn table_for_install_pending(
     remove: &[RemoveEntry],
     disk_size: &(Box<str>, u64),
     tum: Option<HashMap<&str, TopicUpdateEntryRef<'_>>>,
+    changelogs: &[(String, String)],
     is_pager: bool,
     dry_run: bool,
 ) -> Result<PagerExit, OutputError> {
@@ -243,6 +315,7 @@ pub fn table_for_install_pending(
             &OmaPagerUIText { is_question: true },
             Some(fl!("pending-op")),
             color_formatter(),
+            input_config(),
         )
         .map_err(|e| OutputError {
             description: "Failed to get pager".to_string(),
@@ -262,7 +335,15 @@ pub fn table_for_install_pending(
         review_msg(&mut printer);
     }
 
-    print_pending_inner(printer, remove, install, disk_size, &tum);
+    print_pending_inner(&mut printer, remove, install, disk_size, &tum);
+
+    if is_pager {
+        print_changelogs(&mut printer, changelogs);
+    }
+
+    let sections = printer.into_sections();
+    pager.set_sections(sections);
+
     let exit = pager.wait_for_exit().map_err(|e| OutputError {
         description: "Failed to wait exit".to_string(),
         source: Some(Box::new(e)),
@@ -277,7 +358,7 @@ pub fn table_for_install_pending(
             })?;
             let mut printer = PagerPrinter::new(out);
             printer.println("").ok();
-            print_pending_inner(printer, remove, install, disk_size, &tum);
+            print_pending_inner(&mut printer, remove, install, disk_size, &tum);
             Ok(exit)
         }
         _ => Ok(exit),
@@ -293,6 +374,7 @@ pub fn table_for_history_pending(
         &OmaPagerUIText { is_question: false },
         Some(fl!("pending-op")),
         color_formatter(),
+        input_config(),
     )
     .map_err(|e| OutputError {
         description: "Failed to get pager".to_string(),
@@ -307,7 +389,9 @@ pub fn table_for_history_pending(
 
     printer.println("\n\n").ok();
 
-    print_pending_inner(printer, remove, install, disk_size, &None);
+    print_pending_inner(&mut printer, remove, install, disk_size, &None);
+    let sections = printer.into_sections();
+    pager.set_sections(sections);
     pager.wait_for_exit().map_err(|e| OutputError {
         description: "Failed to wait exit".to_string(),
         source: Some(Box::new(e)),
@@ -324,18 +408,39 @@ struct TumDisplay {
     security: bool,
 }
 
+fn print_changelogs<W: Write>(printer: &mut PagerPrinter<W>, changelogs: &[(String, String)]) {
+    if changelogs.is_empty() {
+        return;
+    }
+
+    printer
+        .section(format!("{}\n", style(fl!("changelog-preview")).bold()))
+        .ok();
+
+    for (name, changelog) in changelogs {
+        printer
+            .println(
+                color_formatter()
+                    .color_str(name, Action::Emphasis)
+                    .to_string(),
+            )
+            .ok();
+        printer.println(changelog).ok();
+    }
+}
+
 fn print_pending_inner<W: Write>(
-    mut printer: PagerPrinter<W>,
+    printer: &mut PagerPrinter<W>,
     remove: &[RemoveEntry],
     install: &[InstallEntry],
     disk_size: &(Box<str>, u64),
     tum: &Option<HashMap<&str, TopicUpdateEntryRef<'_>>>,
 ) {
-    print_tum(&mut printer, tum);
+    print_tum(printer, tum);
 
     if !remove.is_empty() {
         printer
-            .println(format!(
+            .section(format!(
                 "{} {}{}\n",
                 fl!("count-pkg-has-desc", count = remove.len()),
                 style(fl!("removed")).red().bold(),
@@ -376,7 +481,7 @@ fn print_pending_inner<W: Write>(
 
         if !install_e_display.is_empty() {
             printer
-                .println(format!(
+                .section(format!(
                     "{} {}{}\n",
                     fl!("count-pkg-has-desc", count = install_e_display.len()),
                     style(fl!("installed")).green().bold(),
@@ -405,7 +510,7 @@ fn print_pending_inner<W: Write>(
 
         if !update_display.is_empty() {
             printer
-                .println(format!(
+                .section(format!(
                     "{} {}{}\n",
                     fl!("count-pkg-has-desc", count = update_display.len()),
                     color_formatter().color_str(fl!("upgraded"), Action::UpgradeTips),
@@ -434,7 +539,7 @@ fn print_pending_inner<W: Write>(
 
         if !downgrade_display.is_empty() {
             printer
-                .println(format!(
+                .section(format!(
                     "{} {}{}\n",
                     fl!("count-pkg-has-desc", count = downgrade_display.len()),
                     style(fl!("downgraded")).yellow().bold(),
@@ -463,7 +568,7 @@ fn print_pending_inner<W: Write>(
 
         if !reinstall_display.is_empty() {
             printer
-                .println(format!(
+                .section(format!(
                     "{} {}{}\n",
                     fl!("count-pkg-has-desc", count = reinstall_display.len()),
                     style(fl!("reinstalled")).blue().bold(),
@@ -528,11 +633,11 @@ fn print_tum(
         let security_count = tum.iter().filter(|x| x.1.is_security()).count();
         if security_count == 0 {
             printer
-                .println(format!("{}\n", fl!("tum-1", updates = tum.len())))
+                .section(format!("{}\n", fl!("tum-1", updates = tum.len())))
                 .ok();
         } else {
             printer
-                .println(format!(
+                .section(format!(
                     "{}\n",
                     fl!(
                         "tum-1-with-security",
@@ -609,6 +714,14 @@ fn print_tum(
     }
 }
 
+/// Topic updates matched by pending changes as plain text, for reviewing in the TUI
+pub fn tum_text(tum: HashMap<&str, TopicUpdateEntryRef<'_>>) -> String {
+    let mut out = vec![];
+    print_tum(&mut PagerPrinter::new(&mut out), &Some(tum));
+
+    crate::console::strip_ansi_codes(&String::from_utf8_lossy(&out)).to_string()
+}
+
 fn review_msg<W: Write>(printer: &mut PagerPrinter<W>) {
     printer.println("").ok();
     printer.println(format!("{}\n", fl!("review-msg"))).ok();
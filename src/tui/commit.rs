@@ -0,0 +1,640 @@
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, BufRead, BufReader, Write},
+    os::fd::{AsFd, AsRawFd, FromRawFd, OwnedFd},
+    path::Path,
+    thread,
+    time::Duration,
+};
+
+use apt_auth_config::AuthConfig;
+use bon::Builder;
+use chrono::Local;
+use crossterm::event::{self, KeyCode};
+use dialoguer::console;
+use flume::{unbounded, Receiver, Sender};
+use oma_console::pager::PagerExit;
+use oma_fetch::Event;
+use oma_history::SummaryType;
+use oma_pm::{
+    apt::{AptConfig, OmaApt, OmaOperation},
+    progress::InstallProgressManager,
+    CommitNetworkConfig,
+};
+use oma_utils::human_bytes::HumanBytes;
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Layout, Margin, Rect},
+    style::{Color, Style, Stylize},
+    text::{Line, Span, Text},
+    widgets::{Block, Borders, Clear, Gauge, Paragraph, Wrap},
+    Frame, Terminal, TerminalOptions, Viewport,
+};
+use tracing::debug;
+
+use super::{header_style, key_style};
+use crate::{
+    error::{Chain, OutputError},
+    fl,
+    subcommand::utils::{
+        finish_commit, summary_changes, topic_updates, warn_resolved, ResolveChanges,
+    },
+    table::tum_text,
+    upgrade::get_matches_tum,
+    HTTP_CLIENT,
+};
+
+/// How many lines of the dpkg log to keep
+const MAX_LOG_LINES: usize = 5000;
+
+enum CommitEvent {
+    Download(Event),
+    Dpkg {
+        pkg: String,
+        done: u64,
+        total: u64,
+    },
+    Log(String),
+    /// Ask the user to confirm the transaction, with the text of matched topic updates
+    Review(OmaOperation, String),
+    Finished {
+        success: bool,
+        message: Vec<String>,
+    },
+}
+
+enum Outcome {
+    Done,
+    NothingToDo,
+    Cancelled,
+}
+
+/// Run a transaction without leaving the TUI
+///
+/// Progress of downloading and installing packages is rendered in panes, output of oma
+/// and dpkg is streamed to a log pane.
+#[derive(Builder)]
+pub struct TuiCommit<'a> {
+    apt: OmaApt,
+    request_type: SummaryType,
+    #[builder(default = true)]
+    no_fixbroken: bool,
+    sysroot: &'a Path,
+    #[builder(default = true)]
+    fix_dpkg_status: bool,
+    #[builder(default)]
+    remove_config: bool,
+    #[builder(default)]
+    autoremove: bool,
+    auth_config: Option<&'a AuthConfig>,
+    network_thread: usize,
+    #[builder(default)]
+    check_update: bool,
+    /// Size of the terminal, the output of oma is redirected while committing so it can not be queried
+    area: Rect,
+}
+
+impl TuiCommit<'_> {
+    /// Mark changes with `mark`, then resolve and commit them
+    ///
+    /// Returns the exit code of the transaction.
+    pub fn run(self, mark: impl FnOnce(&mut OmaApt) -> Result<(), OutputError>) -> io::Result<i32> {
+        let (tx, rx) = unbounded();
+        let (reply_tx, reply_rx) = unbounded();
+
+        let (capture, tty) = OutputCapture::start(tx.clone())?;
+
+        let mut terminal = Terminal::with_options(
+            CrosstermBackend::new(tty),
+            TerminalOptions {
+                viewport: Viewport::Fixed(self.area),
+            },
+        )?;
+
+        let renderer = thread::spawn(move || -> io::Result<()> {
+            terminal.clear()?;
+            CommitView::default().run(&mut terminal, &rx, &reply_tx)
+        });
+
+        let res = self.transaction(mark, &tx, &reply_rx);
+
+        // Restore stdout and stderr first, so the log is complete when the result is shown
+        drop(capture);
+
+        let (code, success, message) = match res {
+            Ok(Outcome::Done) => (0, true, vec![fl!("success")]),
+            Ok(Outcome::NothingToDo) => (0, true, vec![fl!("no-need-to-do-anything")]),
+            // Same as quitting the review pager in the CLI
+            Ok(Outcome::Cancelled) => (
+                PagerExit::Sigint.into(),
+                true,
+                vec![fl!("tui-commit-cancelled")],
+            ),
+            Err(e) => (
+                1,
+                false,
+                Chain::new(&e)
+                    .map(|x| x.to_string())
+                    .filter(|x| !x.is_empty())
+                    .collect(),
+            ),
+        };
+
+        tx.send(CommitEvent::Finished { success, message }).ok();
+
+        renderer
+            .join()
+            .map_err(|_| io::Error::other("BUG: renderer thread panicked"))??;
+
+        Ok(code)
+    }
+
+    fn transaction(
+        self,
+        mark: impl FnOnce(&mut OmaApt) -> Result<(), OutputError>,
+        tx: &Sender<CommitEvent>,
+        reply_rx: &Receiver<bool>,
+    ) -> Result<Outcome, OutputError> {
+        let TuiCommit {
+            mut apt,
+            request_type: typ,
+            no_fixbroken,
+            sysroot,
+            fix_dpkg_status,
+            remove_config,
+            autoremove,
+            auth_config,
+            network_thread,
+            check_update,
+            area: _,
+        } = self;
+
+        mark(&mut apt)?;
+
+        ResolveChanges {
+            autoremove,
+            remove_config,
+            no_fixbroken,
+            fix_dpkg_status,
+            resolve: true,
+        }
+        .run(&mut apt, sysroot)?;
+        warn_resolved(&apt);
+
+        // There is no way to type "Do as I say!" in here, so essential packages and
+        // features are always protected
+        let op = summary_changes(&apt, false, true)?;
+
+        apt.check_disk_size(&op)?;
+
+        if op.install.is_empty() && op.remove.is_empty() {
+            return Ok(Outcome::NothingToDo);
+        }
+
+        let tum = topic_updates(sysroot, check_update)?
+            .map(|tum| tum_text(get_matches_tum(&tum, &op)))
+            .unwrap_or_default();
+
+        tx.send(CommitEvent::Review(op.clone(), tum)).ok();
+
+        if !reply_rx.recv().unwrap_or(false) {
+            return Ok(Outcome::Cancelled);
+        }
+
+        let start_time = Local::now().timestamp();
+
+        let res = apt.commit(
+            Box::new(TuiInstallProgressManager { tx: tx.clone() }),
+            &op,
+            &HTTP_CLIENT,
+            CommitNetworkConfig {
+                network_thread: Some(network_thread),
+                auth_config,
+            },
+            |event| async {
+                if let Err(e) = tx.send_async(CommitEvent::Download(event)).await {
+                    debug!("Send progress channel got error: {}", e);
+                }
+            },
+        );
+
+        if let Some(report) = finish_commit(res, &op, typ, sysroot, false, start_time, false)? {
+            report.display();
+        }
+
+        Ok(Outcome::Done)
+    }
+}
+
+/// Reports dpkg progress to the TUI
+struct TuiInstallProgressManager {
+    tx: Sender<CommitEvent>,
+}
+
+impl InstallProgressManager for TuiInstallProgressManager {
+    fn status_change(&self, pkgname: &str, steps_done: u64, total_steps: u64, _config: &AptConfig) {
+        self.tx
+            .send(CommitEvent::Dpkg {
+                pkg: pkgname.to_string(),
+                done: steps_done,
+                total: total_steps,
+            })
+            .ok();
+    }
+
+    fn no_interactive(&self) -> bool {
+        // Maintainer scripts can not ask questions inside the TUI
+        true
+    }
+
+    fn use_pty(&self) -> bool {
+        false
+    }
+}
+
+/// Redirects stdout and stderr into the log pane while alive
+///
+/// dpkg and maintainer scripts inherit the redirected file descriptors, so their output is
+/// captured as well.
+struct OutputCapture {
+    stdout: OwnedFd,
+    stderr: OwnedFd,
+}
+
+impl OutputCapture {
+    /// Start capturing, returns the original terminal for rendering
+    fn start(tx: Sender<CommitEvent>) -> io::Result<(Self, File)> {
+        io::stdout().flush()?;
+        io::stderr().flush()?;
+
+        let mut fds = [0; 2];
+        if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let (read, write) = unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) };
+
+        let stdout = io::stdout().as_fd().try_clone_to_owned()?;
+        let stderr = io::stderr().as_fd().try_clone_to_owned()?;
+        let tty = File::from(stdout.try_clone()?);
+
+        redirect(&write, libc::STDOUT_FILENO)?;
+        redirect(&write, libc::STDERR_FILENO)?;
+
+        thread::spawn(move || {
+            let mut reader = BufReader::new(File::from(read));
+            let mut buf = vec![];
+
+            while reader.read_until(b'\n', &mut buf).is_ok_and(|n| n > 0) {
+                let s = String::from_utf8_lossy(&buf);
+                // Progress output of programs uses carriage returns, only the last state matters
+                let line = s.trim_end().rsplit('\r').next().unwrap_or_default();
+                let line = console::strip_ansi_codes(line).to_string();
+
+                if tx.send(CommitEvent::Log(line)).is_err() {
+                    break;
+                }
+
+                buf.clear();
+            }
+        });
+
+        Ok((Self { stdout, stderr }, tty))
+    }
+}
+
+impl Drop for OutputCapture {
+    fn drop(&mut self) {
+        io::stdout().flush().ok();
+        io::stderr().flush().ok();
+        redirect(&self.stdout, libc::STDOUT_FILENO).ok();
+        redirect(&self.stderr, libc::STDERR_FILENO).ok();
+    }
+}
+
+fn redirect(from: &OwnedFd, to: i32) -> io::Result<()> {
+    if unsafe { libc::dup2(from.as_raw_fd(), to) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+enum Stage {
+    Resolving,
+    /// Summary of changes, and lines scrolled from its top
+    Review {
+        text: Text<'static>,
+        scroll: u16,
+    },
+    Running,
+    Finished {
+        success: bool,
+        message: Vec<String>,
+    },
+}
+
+struct CommitView {
+    stage: Stage,
+    /// Total and downloaded size of all files
+    global: Option<(u64, u64)>,
+    /// Message, size and downloaded size of files being downloaded
+    downloads: BTreeMap<usize, (String, u64, u64)>,
+    dpkg: Option<(String, u64, u64)>,
+    log: Vec<String>,
+    /// Lines scrolled back from the end of the log
+    log_scroll: usize,
+}
+
+impl Default for CommitView {
+    fn default() -> Self {
+        Self {
+            stage: Stage::Resolving,
+            global: None,
+            downloads: BTreeMap::new(),
+            dpkg: None,
+            log: vec![],
+            log_scroll: 0,
+        }
+    }
+}
+
+impl CommitView {
+    fn run(
+        mut self,
+        terminal: &mut Terminal<CrosstermBackend<File>>,
+        rx: &Receiver<CommitEvent>,
+        reply_tx: &Sender<bool>,
+    ) -> io::Result<()> {
+        loop {
+            terminal.draw(|f| self.ui(f))?;
+
+            // Handle all pending events before drawing again
+            if let Ok(event) = rx.recv_timeout(Duration::from_millis(100)) {
+                self.handle_event(event);
+                while let Ok(event) = rx.try_recv() {
+                    self.handle_event(event);
+                }
+            }
+
+            if matches!(self.stage, Stage::Resolving | Stage::Running)
+                || !event::poll(Duration::ZERO)?
+            {
+                continue;
+            }
+
+            let event::Event::Key(key) = event::read()? else {
+                continue;
+            };
+
+            match (&mut self.stage, key.code) {
+                // The review covers the log, so scroll the review instead
+                (Stage::Review { scroll, .. }, KeyCode::Up) => *scroll = scroll.saturating_sub(1),
+                (Stage::Review { text, scroll }, KeyCode::Down) => {
+                    let max = text.height().saturating_sub(1);
+                    *scroll = scroll
+                        .saturating_add(1)
+                        .min(max.try_into().unwrap_or(u16::MAX));
+                }
+                (_, KeyCode::Up) => self.log_scroll = self.log_scroll.saturating_add(1),
+                (_, KeyCode::Down) => self.log_scroll = self.log_scroll.saturating_sub(1),
+                (Stage::Review { .. }, KeyCode::Enter | KeyCode::Char('y')) => {
+                    self.stage = Stage::Running;
+                    reply_tx.send(true).ok();
+                }
+                (Stage::Review { .. }, KeyCode::Esc | KeyCode::Char('n')) => {
+                    self.stage = Stage::Running;
+                    reply_tx.send(false).ok();
+                }
+                (Stage::Finished { .. }, KeyCode::Enter | KeyCode::Esc | KeyCode::Char('c')) => {
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn handle_event(&mut self, event: CommitEvent) {
+        match event {
+            CommitEvent::Download(event) => self.download_event(event),
+            CommitEvent::Dpkg { pkg, done, total } => self.dpkg = Some((pkg, done, total)),
+            CommitEvent::Log(line) => {
+                self.log.push(line);
+                if self.log.len() > MAX_LOG_LINES {
+                    self.log.remove(0);
+                }
+            }
+            CommitEvent::Review(op, tum) => {
+                self.stage = Stage::Review {
+                    text: Text::from(format!("{tum}{op}")),
+                    scroll: 0,
+                }
+            }
+            CommitEvent::Finished { success, message } => {
+                self.stage = Stage::Finished { success, message }
+            }
+        }
+    }
+
+    fn download_event(&mut self, event: Event) {
+        match event {
+            Event::NewGlobalProgressBar(total) => self.global = Some((total, 0)),
+            Event::GlobalProgressAdd(n) => {
+                if let Some((_, pos)) = &mut self.global {
+                    *pos += n;
+                }
+            }
+            Event::GlobalProgressSub(n) => {
+                if let Some((_, pos)) = &mut self.global {
+                    *pos = pos.saturating_sub(n);
+                }
+            }
+            Event::NewProgressBar { index, msg, size } => {
+                self.downloads.insert(index, (msg, size, 0));
+            }
+            Event::NewProgressSpinner { index, msg } => {
+                self.downloads.insert(index, (msg, 0, 0));
+            }
+            Event::ProgressInc { index, size } => {
+                if let Some((_, _, pos)) = self.downloads.get_mut(&index) {
+                    *pos += size;
+                }
+            }
+            Event::ProgressDone(index) => {
+                self.downloads.remove(&index);
+            }
+            Event::ChecksumMismatch {
+                filename, times, ..
+            } => {
+                self.log
+                    .push(fl!("checksum-mismatch-retry", c = filename, retry = times));
+            }
+            Event::NextUrl { file_name, .. } => {
+                self.log.push(fl!("download-failed", filename = file_name));
+                self.log.push(fl!("can-not-get-source-next-url"));
+            }
+            Event::Failed { file_name, .. } => {
+                self.log.push(fl!("download-failed", filename = file_name));
+            }
+            Event::DownloadDone { .. } => {}
+            Event::AllDone => {
+                self.global = None;
+                self.downloads.clear();
+            }
+        }
+    }
+
+    fn ui(&mut self, f: &mut Frame) {
+        let downloads_height = if self.downloads.is_empty() {
+            0
+        } else {
+            self.downloads.len().min(5) as u16 + 2
+        };
+
+        let [header, global, downloads, dpkg, log, footer] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(if self.global.is_some() { 3 } else { 0 }),
+            Constraint::Length(downloads_height),
+            Constraint::Length(if self.dpkg.is_some() { 3 } else { 0 }),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .areas(f.area());
+
+        f.render_widget(
+            Block::default()
+                .title(format!(" {} v{}", fl!("oma"), env!("CARGO_PKG_VERSION")))
+                .style(header_style()),
+            header,
+        );
+
+        if let Some((total, pos)) = self.global {
+            f.render_widget(
+                Gauge::default()
+                    .block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .title(fl!("tui-commit-downloading")),
+                    )
+                    .gauge_style(Style::default().fg(Color::Cyan))
+                    .ratio(ratio(pos, total))
+                    .label(format!("{} / {}", HumanBytes(pos), HumanBytes(total))),
+                global,
+            );
+        }
+
+        if !self.downloads.is_empty() {
+            let lines = self
+                .downloads
+                .values()
+                .take(5)
+                .map(|(msg, size, pos)| {
+                    Line::from(vec![
+                        Span::raw(format!("{:>4}% ", (ratio(*pos, *size) * 100.0) as u8)),
+                        Span::raw(msg.clone()),
+                    ])
+                })
+                .collect::<Vec<_>>();
+
+            f.render_widget(
+                Paragraph::new(lines).block(Block::default().borders(Borders::ALL)),
+                downloads,
+            );
+        }
+
+        if let Some((pkg, done, total)) = &self.dpkg {
+            f.render_widget(
+                Gauge::default()
+                    .block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .title(fl!("tui-commit-installing")),
+                    )
+                    .gauge_style(Style::default().fg(Color::Green))
+                    .ratio(ratio(*done, *total))
+                    .label(format!("{pkg} ({done}/{total})")),
+                dpkg,
+            );
+        }
+
+        // Follow the end of the log unless scrolled back
+        let height = log.height.saturating_sub(2) as usize;
+        let end = self
+            .log
+            .len()
+            .saturating_sub(self.log_scroll.min(self.log.len()));
+        let start = end.saturating_sub(height);
+
+        f.render_widget(
+            Paragraph::new(
+                self.log[start..end]
+                    .iter()
+                    .map(|x| Line::raw(x.clone()))
+                    .collect::<Vec<_>>(),
+            )
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(fl!("tui-commit-log")),
+            ),
+            log,
+        );
+
+        match &self.stage {
+            Stage::Resolving => {
+                f.render_widget(Line::raw(fl!("resolving-dependencies")), footer);
+            }
+            Stage::Running => {}
+            Stage::Review { text, scroll } => {
+                let area = log.inner(Margin::new(2, 1));
+                f.render_widget(Clear, area);
+                f.render_widget(
+                    Paragraph::new(text.clone())
+                        .wrap(Wrap { trim: false })
+                        .scroll((*scroll, 0))
+                        .block(
+                            Block::default()
+                                .borders(Borders::ALL)
+                                .title(fl!("tui-commit-review")),
+                        ),
+                    area,
+                );
+                f.render_widget(
+                    Line::from(vec![
+                        Span::styled("↑/↓", key_style()),
+                        Span::raw(format!(" => {}, ", fl!("tui-commit-scroll"))),
+                        Span::styled("Enter", key_style()),
+                        Span::raw(format!(" => {}, ", fl!("tui-commit-confirm"))),
+                        Span::styled("ESC", key_style()),
+                        Span::raw(format!(" => {}", fl!("tui-commit-cancel"))),
+                    ]),
+                    footer,
+                );
+            }
+            Stage::Finished { success, message } => {
+                let style = if *success {
+                    Style::new().green()
+                } else {
+                    Style::new().red()
+                };
+
+                let mut line = message
+                    .iter()
+                    .map(|x| Span::styled(format!("{x} "), style))
+                    .collect::<Vec<_>>();
+                line.push(Span::raw(fl!("tui-continue-tips")));
+
+                f.render_widget(Line::from(line), footer);
+            }
+        }
+    }
+}
+
+fn ratio(pos: u64, total: u64) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+
+    (pos as f64 / total as f64).clamp(0.0, 1.0)
+}
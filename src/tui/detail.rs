@@ -0,0 +1,378 @@
+use std::{path::Path, thread};
+
+use ahash::HashMap;
+use flume::{bounded, Receiver};
+use oma_pm::{
+    apt::OmaApt,
+    changelog::ChangelogInfo,
+    pkginfo::{OmaDepType, OmaDependencyGroup, OmaPackage},
+};
+use ratatui::text::Text;
+
+use crate::{changelog::get_changelog, config::Config, fl};
+
+use super::state::StatefulList;
+
+/// Order of dependency kinds in the dependency browser
+const DEP_TYPES: [OmaDepType; 9] = [
+    OmaDepType::PreDepends,
+    OmaDepType::Depends,
+    OmaDepType::Recommends,
+    OmaDepType::Suggests,
+    OmaDepType::Enhances,
+    OmaDepType::Breaks,
+    OmaDepType::Conflicts,
+    OmaDepType::Replaces,
+    OmaDepType::Obsoletes,
+];
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum DetailTab {
+    Info,
+    Depends,
+    ReverseDepends,
+    Versions,
+    Changelog,
+}
+
+impl DetailTab {
+    pub const ALL: [DetailTab; 5] = [
+        DetailTab::Info,
+        DetailTab::Depends,
+        DetailTab::ReverseDepends,
+        DetailTab::Versions,
+        DetailTab::Changelog,
+    ];
+
+    pub fn title(&self) -> String {
+        match self {
+            DetailTab::Info => fl!("tui-detail-info"),
+            DetailTab::Depends => fl!("tui-detail-depends"),
+            DetailTab::ReverseDepends => fl!("tui-detail-rdepends"),
+            DetailTab::Versions => fl!("tui-detail-versions"),
+            DetailTab::Changelog => fl!("tui-detail-changelog"),
+        }
+    }
+
+    fn index(&self) -> usize {
+        Self::ALL.iter().position(|x| x == self).unwrap()
+    }
+}
+
+/// A line in the dependency browser
+pub struct DepItem {
+    pub text: String,
+    /// Package to jump to, `None` for headings
+    pub target: Option<String>,
+}
+
+/// A version in the version picker
+pub struct VersionItem {
+    pub version: String,
+    /// Index of the version in the cache, versions may share the same version string
+    pub index: u64,
+    pub text: String,
+}
+
+/// Package detail pane
+pub struct Detail {
+    pub name: String,
+    /// `None` for virtual packages
+    pub version: Option<String>,
+    pub tab: DetailTab,
+    pub info: Text<'static>,
+    pub deps: StatefulList<DepItem>,
+    pub rdeps: StatefulList<DepItem>,
+    pub versions: StatefulList<VersionItem>,
+    /// Loaded in background when the changelog tab is first opened
+    pub changelog: Option<Text<'static>>,
+    changelog_rx: Option<Receiver<String>>,
+    pub scroll: u16,
+    pkg: Option<OmaPackage>,
+}
+
+impl Detail {
+    /// Detail of the candidate (or the first available) version of package `name`, or the
+    /// providers of a virtual package
+    pub fn new(apt: &OmaApt, name: &str) -> Option<Self> {
+        let raw_pkg = apt.cache.get(name)?;
+
+        if !raw_pkg.has_versions() {
+            return Self::virtual_pkg(apt, name);
+        }
+
+        let cand = raw_pkg.candidate().or(raw_pkg.versions().next())?;
+        let pkg = OmaPackage::new(&cand, &raw_pkg).ok()?;
+
+        let info = pkg.pkg_info(&apt.cache).ok()?;
+        let deps = pkg.get_deps(&apt.cache).ok()?;
+        let rdeps = pkg.get_rdeps(&apt.cache).ok()?;
+
+        let installed = raw_pkg.installed();
+        let versions = raw_pkg.versions().collect::<Vec<_>>();
+
+        let versions = versions
+            .iter()
+            .map(|ver| {
+                // Show where a version comes from if there are other versions with the same version string
+                let mut text = ver.version().to_string();
+                if versions
+                    .iter()
+                    .filter(|x| x.version() == ver.version())
+                    .count()
+                    > 1
+                {
+                    if let Some(uri) = ver.uris().next() {
+                        text = format!("{text} (from: {uri})");
+                    }
+                }
+
+                if installed.as_ref().is_some_and(|x| x == ver) {
+                    text = format!("{text} [{}]", fl!("tui-detail-installed"));
+                }
+
+                VersionItem {
+                    version: ver.version().to_string(),
+                    index: ver.index(),
+                    text,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        Some(Self {
+            name: raw_pkg.fullname(true),
+            version: Some(cand.version().to_string()),
+            tab: DetailTab::Info,
+            info: Text::from(info.to_string()),
+            deps: StatefulList::with_first_selected(dep_items(deps, false)),
+            rdeps: StatefulList::with_first_selected(dep_items(rdeps, true)),
+            versions: StatefulList::with_first_selected(versions),
+            changelog: None,
+            changelog_rx: None,
+            scroll: 0,
+            pkg: Some(pkg),
+        })
+    }
+
+    /// Providers of virtual package `name` in the dependency browser
+    fn virtual_pkg(apt: &OmaApt, name: &str) -> Option<Self> {
+        let raw_pkg = apt.cache.get(name)?;
+
+        let mut providers = raw_pkg
+            .provides()
+            .map(|provide| provide.package().fullname(true))
+            .collect::<Vec<_>>();
+
+        if providers.is_empty() {
+            return None;
+        }
+
+        providers.sort();
+        providers.dedup();
+
+        let mut deps = vec![DepItem {
+            text: format!("{}:", fl!("tui-detail-provided-by")),
+            target: None,
+        }];
+
+        deps.extend(providers.into_iter().map(|name| DepItem {
+            text: format!("  {name}"),
+            target: Some(name),
+        }));
+
+        Some(Self {
+            name: raw_pkg.fullname(true),
+            version: None,
+            tab: DetailTab::Depends,
+            info: Text::from(fl!("tui-detail-virtual", pkg = name)),
+            deps: StatefulList::with_first_selected(deps),
+            rdeps: StatefulList::with_first_selected(vec![]),
+            versions: StatefulList::with_first_selected(vec![]),
+            changelog: Some(Text::default()),
+            changelog_rx: None,
+            scroll: 0,
+            pkg: None,
+        })
+    }
+
+    pub fn next_tab(&mut self) {
+        let i = (self.tab.index() + 1) % DetailTab::ALL.len();
+        self.tab = DetailTab::ALL[i];
+        self.scroll = 0;
+    }
+
+    pub fn previous_tab(&mut self) {
+        let i = (self.tab.index() + DetailTab::ALL.len() - 1) % DetailTab::ALL.len();
+        self.tab = DetailTab::ALL[i];
+        self.scroll = 0;
+    }
+
+    pub fn tab_index(&self) -> usize {
+        self.tab.index()
+    }
+
+    /// Start loading the changelog in background, see [`Detail::update_changelog`]
+    pub fn load_changelog(&mut self, apt: &OmaApt, sysroot: &Path, config: &Config) {
+        if self.changelog.is_some() || self.changelog_rx.is_some() {
+            return;
+        }
+
+        let Some(pkg) = &self.pkg else {
+            return;
+        };
+
+        let info = ChangelogInfo::new(&pkg.version(&apt.cache));
+        let template = info
+            .origin
+            .as_deref()
+            .and_then(|origin| config.changelog_url_template(origin))
+            .map(|x| x.to_string());
+        let sysroot = sysroot.to_path_buf();
+
+        let (tx, rx) = bounded(1);
+
+        thread::spawn(move || {
+            let changelog = get_changelog(&info, &sysroot, template.as_deref())
+                .unwrap_or_else(|| fl!("changelog-not-found", pkg = info.package.as_str()));
+
+            tx.send(changelog).ok();
+        });
+
+        self.changelog_rx = Some(rx);
+    }
+
+    /// Collect the changelog if it has been loaded
+    pub fn update_changelog(&mut self) {
+        let Some(rx) = &self.changelog_rx else {
+            return;
+        };
+
+        if let Ok(changelog) = rx.try_recv() {
+            self.changelog = Some(Text::from(changelog));
+            self.changelog_rx = None;
+        }
+    }
+
+    pub fn next(&mut self) {
+        match self.tab {
+            DetailTab::Info | DetailTab::Changelog => self.scroll = self.scroll.saturating_add(1),
+            DetailTab::Depends => self.deps.next(),
+            DetailTab::ReverseDepends => self.rdeps.next(),
+            DetailTab::Versions => self.versions.next(),
+        }
+    }
+
+    pub fn previous(&mut self) {
+        match self.tab {
+            DetailTab::Info | DetailTab::Changelog => self.scroll = self.scroll.saturating_sub(1),
+            DetailTab::Depends => self.deps.previous(),
+            DetailTab::ReverseDepends => self.rdeps.previous(),
+            DetailTab::Versions => self.versions.previous(),
+        }
+    }
+
+    /// Move the selection (or scroll the text) by `delta` lines
+    pub fn select_relative(&mut self, delta: isize) {
+        let text_height = match self.tab {
+            DetailTab::Info => self.info.height(),
+            DetailTab::Changelog => self.changelog.as_ref().map(|x| x.height()).unwrap_or(0),
+            DetailTab::Depends => return self.deps.select_relative(delta),
+            DetailTab::ReverseDepends => return self.rdeps.select_relative(delta),
+            DetailTab::Versions => return self.versions.select_relative(delta),
+        };
+
+        let scroll = (self.scroll as usize)
+            .saturating_add_signed(delta)
+            .min(text_height.saturating_sub(1));
+
+        self.scroll = scroll.try_into().unwrap_or(u16::MAX);
+    }
+
+    /// Select the item shown at `row` of the list area
+    pub fn select_visible_row(&mut self, row: usize) {
+        match self.tab {
+            DetailTab::Info | DetailTab::Changelog => {}
+            DetailTab::Depends => self.deps.select_visible_row(row),
+            DetailTab::ReverseDepends => self.rdeps.select_visible_row(row),
+            DetailTab::Versions => self.versions.select_visible_row(row),
+        }
+    }
+
+    /// Package under the cursor of the dependency browser
+    pub fn selected_target(&self) -> Option<&str> {
+        let list = match self.tab {
+            DetailTab::Depends => &self.deps,
+            DetailTab::ReverseDepends => &self.rdeps,
+            _ => return None,
+        };
+
+        list.state
+            .selected()
+            .and_then(|i| list.items.get(i))
+            .and_then(|x| x.target.as_deref())
+    }
+
+    /// Version under the cursor of the version picker
+    pub fn selected_version(&self) -> Option<&VersionItem> {
+        if self.tab != DetailTab::Versions {
+            return None;
+        }
+
+        self.versions
+            .state
+            .selected()
+            .and_then(|i| self.versions.items.get(i))
+    }
+}
+
+fn dep_items(mut map: HashMap<OmaDepType, OmaDependencyGroup>, reverse: bool) -> Vec<DepItem> {
+    let mut res = vec![];
+
+    for typ in DEP_TYPES {
+        let Some(group) = map.remove(&typ) else {
+            continue;
+        };
+
+        res.push(DepItem {
+            text: format!("{typ}:"),
+            target: None,
+        });
+
+        if reverse {
+            // Version requirements are of the package itself, so only names are useful here
+            let mut names = group
+                .inner()
+                .into_iter()
+                .flatten()
+                .map(|x| x.name)
+                .collect::<Vec<_>>();
+
+            names.sort();
+            names.dedup();
+
+            res.extend(names.into_iter().map(|name| DepItem {
+                text: format!("  {name}"),
+                target: Some(name),
+            }));
+
+            continue;
+        }
+
+        for alternatives in group.inner() {
+            for (i, dep) in alternatives.into_iter().enumerate() {
+                let prefix = if i == 0 { "  " } else { "  | " };
+                let text = match dep.comp_ver {
+                    Some(comp) => format!("{prefix}{} ({comp})", dep.name),
+                    None => format!("{prefix}{}", dep.name),
+                };
+
+                res.push(DepItem {
+                    text,
+                    target: Some(dep.name),
+                });
+            }
+        }
+    }
+
+    res
+}
@@ -0,0 +1,64 @@
+use std::path::Path;
+
+use oma_history::{
+    connect_db, find_history_by_id, list_history, HistoryError, HistoryListEntry, SummaryType,
+    DATABASE_PATH,
+};
+use ratatui::text::Text;
+
+use crate::history::format_summary_log;
+
+use super::state::StatefulList;
+
+/// History browser, backed by the oma history database
+pub struct HistoryView {
+    pub entries: StatefulList<String>,
+    list: Vec<HistoryListEntry>,
+    /// Changes of the inspected entry
+    pub inspected: Option<Text<'static>>,
+}
+
+impl HistoryView {
+    pub fn new(sysroot: &Path) -> Result<Self, HistoryError> {
+        let conn = connect_db(sysroot.join(DATABASE_PATH), false)?;
+        let list = list_history(&conn)?;
+
+        let entries = format_summary_log(&list, false)
+            .into_iter()
+            .map(|(s, _)| s)
+            .collect::<Vec<_>>();
+
+        let mut entries = StatefulList::with_items(entries);
+        entries.state.select(Some(0));
+
+        Ok(Self {
+            entries,
+            list,
+            inspected: None,
+        })
+    }
+
+    fn selected(&self) -> Option<&HistoryListEntry> {
+        self.entries.state.selected().and_then(|i| self.list.get(i))
+    }
+
+    /// Show changes of the selected entry
+    pub fn inspect(&mut self, sysroot: &Path) -> Result<(), HistoryError> {
+        let Some(entry) = self.selected() else {
+            return Ok(());
+        };
+
+        let conn = connect_db(sysroot.join(DATABASE_PATH), false)?;
+        let op = find_history_by_id(&conn, entry.id)?;
+        self.inspected = Some(Text::from(op.to_string()));
+
+        Ok(())
+    }
+
+    /// ID of the selected entry, if it can be undone
+    pub fn undoable(&self) -> Option<i64> {
+        self.selected()
+            .filter(|x| x.t != SummaryType::FixBroken && x.t != SummaryType::Undo)
+            .map(|x| x.id)
+    }
+}
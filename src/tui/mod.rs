@@ -1,33 +1,68 @@
 use std::{
+    io::Stdout,
     path::{Path, PathBuf},
     time::Duration,
 };
 
 use clap::Args;
+use commit::TuiCommit;
 use oma_console::{
     indicatif::ProgressBar,
-    pager::{exit_tui, prepare_create_tui},
+    pager::{enable_mouse_capture, exit_tui, prepare_create_tui},
     pb::spinner_style,
+    print::Action,
 };
-use oma_history::SummaryType;
+use oma_history::{connect_db, find_history_by_id, SummaryType, DATABASE_PATH};
+#[cfg(feature = "aosc")]
+use oma_mirror::MirrorManager;
 use oma_pm::{
     apt::{AptConfig, OmaApt, OmaAptArgs, Upgrade},
-    search::IndiciumSearch,
+    search_index::IndexSearch,
 };
-use oma_utils::dbus::{create_dbus_connection, take_wake_lock};
+#[cfg(feature = "aosc")]
+use oma_topics::TopicManager;
+#[cfg(feature = "aosc")]
+use oma_utils::dpkg::dpkg_arch;
+use oma_utils::{
+    dbus::{create_dbus_connection, take_wake_lock},
+    oma::unlock_oma,
+};
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::Style,
+    Terminal,
+};
+#[cfg(feature = "aosc")]
+use sources::SourcesChange;
 use tracing::info;
 use tui_inner::{Task, Tui as TuiInner};
 
+pub(crate) use review::Review;
+
 use crate::{args::CliExecuter, subcommand::utils::auth_config, GlobalOptions};
+use crate::{color_formatter, input_config, solver};
 use crate::{
     config::Config,
     error::OutputError,
     find_another_oma, fl,
-    subcommand::utils::{lock_oma, no_check_dbus_warn, CommitChanges, Refresh},
+    history::undo_changes,
+    subcommand::utils::{lock_oma, no_check_dbus_warn, Refresh},
     utils::{check_battery, root},
     HTTP_CLIENT, RT,
 };
+#[cfg(feature = "aosc")]
+use crate::{
+    mirror::refresh_enabled_topics_sources_list,
+    topics::{mark_topic_downgrades, topics_inner, write_topic_sources_list},
+};
 
+mod commit;
+mod detail;
+mod history;
+mod review;
+#[cfg(feature = "aosc")]
+mod sources;
 mod state;
 mod tui_inner;
 
@@ -136,6 +171,7 @@ impl CliExecuter for Tui {
             check_battery(&conn, false);
             Some(conn)
         } else {
+            no_check_dbus_warn();
             None
         };
 
@@ -143,7 +179,7 @@ impl CliExecuter for Tui {
         let auth_config = auth_config(&sysroot);
         let auth_config = auth_config.as_ref();
 
-        if !no_refresh {
+        let refresh = || -> Result<(), OutputError> {
             let sysroot = sysroot.to_string_lossy();
             let builder = Refresh::builder()
                 .client(&HTTP_CLIENT)
@@ -163,17 +199,26 @@ impl CliExecuter for Tui {
             let refresh = builder.build();
 
             refresh.run()?;
-        }
 
-        let oma_apt_args = OmaAptArgs::builder()
-            .sysroot(sysroot.to_string_lossy().to_string())
-            .another_apt_options(apt_options)
-            .dpkg_force_confnew(force_confnew)
-            .dpkg_force_unsafe_io(force_unsafe_io)
-            .force_yes(force_yes)
-            .build();
+            Ok(())
+        };
+
+        if !no_refresh {
+            refresh()?;
+        }
 
-        let mut apt = OmaApt::new(vec![], oma_apt_args, false, apt_config)?;
+        // dpkg can not ask about configuration files inside the TUI, keep the old ones
+        let oma_apt_args = || {
+            OmaAptArgs::builder()
+                .solver(solver())
+                .sysroot(sysroot.to_string_lossy().to_string())
+                .another_apt_options(apt_options.clone())
+                .dpkg_force_confnew(force_confnew)
+                .dpkg_force_confold(!force_confnew)
+                .dpkg_force_unsafe_io(force_unsafe_io)
+                .force_yes(force_yes)
+                .build()
+        };
 
         let (sty, inv) = spinner_style();
         let pb = if no_progress {
@@ -185,89 +230,291 @@ impl CliExecuter for Tui {
             Some(pb)
         };
 
-        let upgradable = apt.count_pending_upgradable_pkgs()?;
-        let autoremovable = apt.count_pending_autoremovable_pkgs();
-        let installed = apt.count_installed_packages();
+        let mut terminal = None;
 
-        let searcher = IndiciumSearch::new(&apt.cache, |n| {
-            if let Some(ref pb) = pb {
-                pb.set_message(fl!("reading-database-with-count", count = n));
+        let tui_commit = |apt: OmaApt,
+                          request_type: SummaryType,
+                          autoremove: bool,
+                          check_update: bool,
+                          area: Rect| {
+            TuiCommit::builder()
+                .apt(apt)
+                .request_type(request_type)
+                .no_fixbroken(!fix_broken)
+                .sysroot(&sysroot)
+                .fix_dpkg_status(!no_fix_dpkg_status)
+                .remove_config(remove_config)
+                .autoremove(autoremove)
+                .maybe_auth_config(auth_config)
+                .network_thread(config.network_thread())
+                .check_update(check_update)
+                .area(area)
+                .build()
+        };
+
+        // Stay in the TUI after each transaction, until the user exits
+        let mut session = || -> Result<i32, OutputError> {
+            let mut code = 0;
+
+            loop {
+                let apt = OmaApt::new(vec![], oma_apt_args(), false, AptConfig::new())?;
+
+                let upgradable = apt.count_pending_upgradable_pkgs()?;
+                let autoremovable = apt.count_pending_autoremovable_pkgs();
+                let installed = apt.count_installed_packages();
+
+                let searcher = IndexSearch::new(&apt.cache, &sysroot, |n| {
+                    if let Some(ref pb) = pb {
+                        pb.set_message(fl!("reading-database-with-count", count = n));
+                    }
+                });
+
+                // The first search should be instant, the index is usually saved at refresh time
+                searcher.load();
+
+                if let Some(pb) = &pb {
+                    pb.finish_and_clear();
+                }
+
+                let term = match terminal {
+                    Some(ref mut terminal) => terminal,
+                    None => terminal.insert(create_terminal()?),
+                };
+
+                let tui = TuiInner::new(
+                    &apt,
+                    config,
+                    &sysroot,
+                    installed,
+                    upgradable,
+                    autoremovable,
+                    searcher,
+                );
+
+                let Task {
+                    execute_apt,
+                    install,
+                    remove,
+                    upgrade,
+                    autoremove,
+                    undo,
+                    #[cfg(feature = "aosc")]
+                    sources,
+                } = tui.run(term, Duration::from_millis(250)).unwrap();
+
+                #[cfg(feature = "aosc")]
+                let sources_changed = sources.is_some();
+
+                #[cfg(not(feature = "aosc"))]
+                let sources_changed = false;
+
+                if !execute_apt && undo.is_none() && !sources_changed {
+                    return Ok(code);
+                }
+
+                let _fds = match &conn {
+                    Some(conn) => {
+                        Some(RT.block_on(take_wake_lock(conn, &fl!("changing-system"), "oma"))?)
+                    }
+                    None => None,
+                };
+
+                lock_oma()?;
+
+                #[cfg(feature = "aosc")]
+                if let Some(sources) = sources {
+                    // Refreshing prints progress bars, leave the TUI until it is done
+                    if let Some(mut terminal) = terminal.take() {
+                        leave_terminal(&mut terminal)?;
+                    }
+
+                    let SourcesChange {
+                        mut opt_in,
+                        mut opt_out,
+                        mirrors,
+                    } = sources;
+
+                    if let Some(names) = mirrors {
+                        let mut mm = MirrorManager::new(&sysroot)?;
+                        mm.set(&names.iter().map(|x| x.as_str()).collect::<Vec<_>>())?;
+                        mm.write_status(Some(&fl!("do-not-edit-topic-sources-list")))?;
+
+                        refresh_enabled_topics_sources_list(no_progress)?;
+                    }
+
+                    if opt_in.is_empty() && opt_out.is_empty() {
+                        refresh()?;
+                        code = 0;
+                    } else {
+                        let arch = dpkg_arch(&sysroot)?;
+                        let mut tm =
+                            TopicManager::new_blocking(&HTTP_CLIENT, &sysroot, &arch, false)?;
+
+                        let changed = RT.block_on(topics_inner(
+                            &mut opt_in,
+                            &mut opt_out,
+                            no_progress,
+                            &mut tm,
+                            false,
+                        ))?;
+
+                        write_topic_sources_list(&tm, false)?;
+
+                        let res = refresh().and_then(|_| {
+                            let apt = OmaApt::new(vec![], oma_apt_args(), false, AptConfig::new())?;
+                            let term = terminal.insert(create_terminal()?);
+                            let request_type = SummaryType::TopicsChanged {
+                                add: opt_in,
+                                remove: opt_out,
+                            };
+
+                            run_commit(
+                                term,
+                                |area| tui_commit(apt, request_type, false, true, area),
+                                |apt| {
+                                    mark_topic_downgrades(
+                                        apt,
+                                        &sysroot,
+                                        changed.downgrade_pkgs,
+                                        &changed.enabled_pkgs,
+                                    )
+                                },
+                            )
+                        });
+
+                        match res {
+                            Ok(0) => RT.block_on(tm.write_enabled())?,
+                            _ => write_topic_sources_list(&tm, true)?,
+                        }
+
+                        code = res?;
+                    }
+
+                    unlock_oma().ok();
+                    continue;
+                }
+
+                let request_type = if undo.is_some() {
+                    SummaryType::Undo
+                } else {
+                    SummaryType::Changes
+                };
+
+                code = run_commit(
+                    term,
+                    |area| tui_commit(apt, request_type, autoremove, upgrade, area),
+                    |apt| match undo {
+                        Some(id) => {
+                            let conn = connect_db(sysroot.join(DATABASE_PATH), false)?;
+                            let op = find_history_by_id(&conn, id)?;
+                            undo_changes(apt, &op, &sysroot, true)
+                        }
+                        None => {
+                            if upgrade {
+                                apt.upgrade(Upgrade::FullUpgrade)?;
+                            }
+
+                            apt.install(&install, false)?;
+                            apt.remove(
+                                remove
+                                    .iter()
+                                    .flat_map(|x| x.into_oma_package_without_version()),
+                                false,
+                                !autoremove,
+                            )?;
+
+                            Ok(())
+                        }
+                    },
+                )?;
+
+                unlock_oma().ok();
             }
-        })?;
+        };
+
+        let res = session();
 
-        if let Some(pb) = pb {
-            pb.finish_and_clear();
+        if let Some(mut terminal) = terminal {
+            leave_terminal(&mut terminal)?;
         }
 
-        let mut terminal = prepare_create_tui().map_err(|e| OutputError {
-            description: "BUG: Failed to create crossterm instance".to_string(),
+        res
+    }
+}
+
+fn create_terminal() -> Result<Terminal<CrosstermBackend<Stdout>>, OutputError> {
+    let terminal = prepare_create_tui().map_err(|e| OutputError {
+        description: "BUG: Failed to create crossterm instance".to_string(),
+        source: Some(Box::new(e)),
+    })?;
+
+    if input_config().mouse {
+        enable_mouse_capture().map_err(|e| OutputError {
+            description: "BUG: Failed to enable mouse capture".to_string(),
             source: Some(Box::new(e)),
         })?;
+    }
 
-        let tui = TuiInner::new(&apt, installed, upgradable, autoremovable, searcher);
+    Ok(terminal)
+}
 
-        let Task {
-            execute_apt,
-            install,
-            remove,
-            upgrade,
-            autoremove,
-        } = tui.run(&mut terminal, Duration::from_millis(250)).unwrap();
+fn leave_terminal(terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> Result<(), OutputError> {
+    exit_tui(terminal).map_err(|e| OutputError {
+        description: "BUG: Failed to exit tui".to_string(),
+        source: Some(Box::new(e)),
+    })
+}
 
-        exit_tui(&mut terminal).map_err(|e| OutputError {
-            description: "BUG: Failed to exit tui".to_string(),
+/// Run a transaction in place of the TUI, then get the screen ready for the TUI again
+fn run_commit<'a>(
+    terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+    commit: impl FnOnce(Rect) -> TuiCommit<'a>,
+    mark: impl FnOnce(&mut OmaApt) -> Result<(), OutputError>,
+) -> Result<i32, OutputError> {
+    let size = terminal.size().map_err(|e| OutputError {
+        description: "BUG: Failed to get terminal size".to_string(),
+        source: Some(Box::new(e)),
+    })?;
+
+    let code = commit(Rect::new(0, 0, size.width, size.height))
+        .run(mark)
+        .map_err(|e| OutputError {
+            description: "BUG: Failed to run transaction in tui".to_string(),
             source: Some(Box::new(e)),
         })?;
 
-        let mut code = 0;
-
-        if execute_apt {
-            let _fds = if !no_check_dbus && !config.no_check_dbus() && !dry_run {
-                let fds = RT.block_on(take_wake_lock(
-                    &conn.unwrap(),
-                    &fl!("changing-system"),
-                    "oma",
-                ))?;
-                Some(fds)
-            } else {
-                no_check_dbus_warn();
-                None
-            };
-
-            lock_oma()?;
-
-            if upgrade {
-                apt.upgrade(Upgrade::FullUpgrade)?;
-            }
+    // The screen was drawn by the transaction view, draw everything again
+    terminal.clear().map_err(|e| OutputError {
+        description: "BUG: Failed to clear terminal".to_string(),
+        source: Some(Box::new(e)),
+    })?;
 
-            apt.install(&install, false)?;
-            apt.remove(
-                remove
-                    .iter()
-                    .flat_map(|x| x.into_oma_package_without_version()),
-                false,
-                !autoremove,
-            )?;
+    Ok(code)
+}
 
-            code = CommitChanges::builder()
-                .apt(apt)
-                .dry_run(dry_run)
-                .request_type(SummaryType::Changes)
-                .no_fixbroken(!fix_broken)
-                .no_progress(no_progress)
-                .sysroot(sysroot.to_string_lossy().to_string())
-                .fix_dpkg_status(!no_fix_dpkg_status)
-                .protect_essential(config.protect_essentials())
-                .yes(false)
-                .remove_config(remove_config)
-                .autoremove(autoremove)
-                .maybe_auth_config(auth_config)
-                .network_thread(config.network_thread())
-                .check_update(upgrade)
-                .build()
-                .run()?;
-        }
+/// Style of key names in tips
+fn key_style() -> Style {
+    Style::new().fg(color_formatter().tui_color(Action::KeyHint))
+}
 
-        Ok(code)
-    }
+/// Style of the selected item of lists and tabs
+fn highlight_style() -> Style {
+    Style::new().bg(color_formatter().tui_color(Action::Highlight))
+}
+
+/// Style of the header bar
+fn header_style() -> Style {
+    Style::new()
+        .bg(color_formatter().tui_color(Action::TipsBg))
+        .fg(color_formatter().tui_color(Action::TipsFg))
+}
+
+/// helper function to create a centered rect using up certain percentage of the available rect `r`
+fn popup_area(area: Rect, x: u16, y: u16) -> Rect {
+    let vertical = Layout::vertical([Constraint::Length(y)]).flex(Flex::Center);
+    let horizontal = Layout::horizontal([Constraint::Length(x)]).flex(Flex::Center);
+    let [area] = vertical.areas(area);
+    let [area] = horizontal.areas(area);
+
+    area
 }
@@ -0,0 +1,812 @@
+use std::{cell::RefCell, collections::BTreeMap, io};
+
+use ahash::{HashMap, HashSet};
+use bon::Builder;
+use crossterm::event::{
+    self, Event, KeyCode, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+use dialoguer::console;
+use oma_console::{input::KeyAction, print::Action};
+use oma_pm::apt::{InstallOperation, OmaApt, OmaOperation, SavedMarks, SummarySort};
+use ratatui::{
+    layout::{Constraint, Layout, Margin, Position, Rect},
+    prelude::Backend,
+    style::{Style, Stylize},
+    text::{Line, Span, Text},
+    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Tabs},
+    Frame, Terminal,
+};
+
+use crate::{color_formatter, error::OutputError, fl, input_config};
+
+use super::{
+    create_terminal, header_style, highlight_style, key_style, leave_terminal, popup_area,
+    state::StatefulList,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum GroupBy {
+    Operation,
+    Topic,
+    Section,
+}
+
+impl GroupBy {
+    fn title(&self) -> String {
+        match self {
+            GroupBy::Operation => fl!("review-group-by-operation"),
+            GroupBy::Topic => fl!("review-group-by-topic"),
+            GroupBy::Section => fl!("review-group-by-section"),
+        }
+    }
+}
+
+/// Kinds of changes, in the order of the pending operations table
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum ChangeKind {
+    Remove,
+    Install,
+    Upgrade,
+    Downgrade,
+    Reinstall,
+}
+
+impl ChangeKind {
+    fn title(&self) -> String {
+        match self {
+            ChangeKind::Remove => fl!("review-remove"),
+            ChangeKind::Install => fl!("review-install"),
+            ChangeKind::Upgrade => fl!("review-upgrade"),
+            ChangeKind::Downgrade => fl!("review-downgrade"),
+            ChangeKind::Reinstall => fl!("review-reinstall"),
+        }
+    }
+
+    fn style(&self) -> Style {
+        match self {
+            ChangeKind::Remove => Style::new().red(),
+            ChangeKind::Install => Style::new().green(),
+            ChangeKind::Upgrade => {
+                Style::new().fg(color_formatter().tui_color(Action::UpgradeTips))
+            }
+            ChangeKind::Downgrade => Style::new().yellow(),
+            ChangeKind::Reinstall => Style::new().blue(),
+        }
+    }
+}
+
+struct ReviewPackage {
+    name: String,
+    kind: ChangeKind,
+    version: String,
+    topic: Option<String>,
+    section: String,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum PackageState {
+    Selected,
+    /// Not in the reviewed changes, added because of deselected packages
+    Added,
+    Deselected,
+    /// Held back by the resolver because of deselected packages
+    HeldBack,
+}
+
+impl PackageState {
+    fn checkbox(&self) -> &'static str {
+        match self {
+            PackageState::Selected => "[x]",
+            PackageState::Added => "[+]",
+            PackageState::Deselected => "[ ]",
+            PackageState::HeldBack => "[-]",
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq)]
+enum Row {
+    Group(String),
+    /// Index of the package
+    Package(usize),
+}
+
+/// Changes confirmed in the review
+pub struct Reviewed {
+    pub op: OmaOperation,
+    /// Packages deselected in the review
+    pub kept_back: Vec<String>,
+}
+
+/// Review pending changes grouped by operation, topic or section, and deselect packages
+///
+/// Deselected packages are kept back, the changes are resolved again to show what else
+/// is held back, removed or installed because of them.
+#[derive(Builder)]
+pub struct Review<'a> {
+    apt: &'a mut OmaApt,
+    op: OmaOperation,
+    /// Names of topics updating packages, by package name
+    topics: Option<HashMap<String, String>>,
+    no_fixbroken: bool,
+    remove_config: bool,
+}
+
+impl Review<'_> {
+    /// Show the review, `None` if the user cancelled it
+    pub fn run(self) -> Result<Option<Reviewed>, OutputError> {
+        let mut terminal = create_terminal()?;
+
+        let res = ReviewView::new(self)
+            .run(&mut terminal)
+            .map_err(|e| OutputError {
+                description: "BUG: Failed to run review tui".to_string(),
+                source: Some(Box::new(e)),
+            });
+
+        leave_terminal(&mut terminal)?;
+
+        res
+    }
+}
+
+struct ReviewView<'a> {
+    apt: &'a mut OmaApt,
+    no_fixbroken: bool,
+    remove_config: bool,
+    topics: Option<HashMap<String, String>>,
+    /// Marks of the reviewed changes
+    marks: SavedMarks,
+    /// Marks of the last resolved changes
+    last_marks: SavedMarks,
+    /// Packages in the reviewed changes
+    reviewed: HashSet<String>,
+    /// Removals in the reviewed changes, including essential packages the user agreed to remove
+    reviewed_remove: HashSet<String>,
+    /// Features removed by the reviewed changes, which were already confirmed
+    reviewed_features: HashSet<Box<str>>,
+    op: OmaOperation,
+    current: HashSet<String>,
+    /// Packages of all changes resolved so far, indexes never change
+    packages: Vec<ReviewPackage>,
+    kept_back: Vec<String>,
+    group_by: GroupBy,
+    collapsed: HashSet<String>,
+    rows: StatefulList<Row>,
+    popup: Option<String>,
+    list_area: Rect,
+}
+
+impl<'a> ReviewView<'a> {
+    fn new(review: Review<'a>) -> Self {
+        let Review {
+            apt,
+            op,
+            topics,
+            no_fixbroken,
+            remove_config,
+        } = review;
+
+        let marks = apt.save_marks();
+        let reviewed = op_names(&op);
+        let reviewed_remove = op.remove.iter().map(|x| x.name().to_string()).collect();
+
+        let reviewed_features = RefCell::new(HashSet::with_hasher(ahash::RandomState::new()));
+        apt.summary(
+            SummarySort::Operation,
+            |_| true,
+            |features| {
+                reviewed_features
+                    .borrow_mut()
+                    .extend(features.iter().cloned());
+                true
+            },
+        )
+        .ok();
+
+        let mut view = Self {
+            apt,
+            no_fixbroken,
+            remove_config,
+            topics,
+            last_marks: marks.clone(),
+            marks,
+            current: reviewed.clone(),
+            reviewed,
+            reviewed_remove,
+            reviewed_features: reviewed_features.into_inner(),
+            op,
+            packages: vec![],
+            kept_back: vec![],
+            group_by: GroupBy::Operation,
+            collapsed: HashSet::with_hasher(ahash::RandomState::new()),
+            rows: StatefulList::with_items(vec![]),
+            popup: None,
+            list_area: Rect::default(),
+        };
+
+        view.update_packages();
+        view.rebuild_rows();
+
+        view
+    }
+
+    fn run<B: Backend>(mut self, terminal: &mut Terminal<B>) -> io::Result<Option<Reviewed>> {
+        loop {
+            terminal.draw(|f| self.ui(f))?;
+
+            match event::read()? {
+                Event::Mouse(mouse) if self.popup.is_none() => self.handle_mouse(mouse),
+                Event::Key(key) => {
+                    if self.popup.is_some() {
+                        if key.code == KeyCode::Char('c') {
+                            self.popup = None;
+                        }
+                        continue;
+                    }
+
+                    if key.modifiers == KeyModifiers::CONTROL && key.code == KeyCode::Char('c') {
+                        return Ok(None);
+                    }
+
+                    match key.code {
+                        KeyCode::Esc => return Ok(None),
+                        KeyCode::Enter => {
+                            return Ok(Some(Reviewed {
+                                op: self.op,
+                                kept_back: self.kept_back,
+                            }))
+                        }
+                        KeyCode::Char(' ') => self.toggle(),
+                        KeyCode::Tab => self.switch_group_by(),
+                        _ => {
+                            let page = self.list_area.height.max(1) as isize;
+
+                            match input_config().keymap.action(&key) {
+                                Some(KeyAction::Up) => self.rows.select_relative(-1),
+                                Some(KeyAction::Down) => self.rows.select_relative(1),
+                                Some(KeyAction::PageUp) => self.rows.select_relative(-page),
+                                Some(KeyAction::PageDown) => self.rows.select_relative(page),
+                                Some(KeyAction::Top) => self.rows.select_relative(isize::MIN),
+                                Some(KeyAction::Bottom) => self.rows.select_relative(isize::MAX),
+                                Some(KeyAction::Left) => self.set_collapsed(true),
+                                Some(KeyAction::Right) => self.set_collapsed(false),
+                                Some(KeyAction::NextSection) => self.jump_to_group(true),
+                                Some(KeyAction::PreviousSection) => self.jump_to_group(false),
+                                Some(KeyAction::Quit) => return Ok(None),
+                                _ => {}
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn handle_mouse(&mut self, mouse: MouseEvent) {
+        let pos = Position::new(mouse.column, mouse.row);
+
+        if !self.list_area.contains(pos) {
+            return;
+        }
+
+        match mouse.kind {
+            MouseEventKind::ScrollUp => self.rows.select_relative(-1),
+            MouseEventKind::ScrollDown => self.rows.select_relative(1),
+            MouseEventKind::Down(MouseButton::Left) => self
+                .rows
+                .select_visible_row((pos.y - self.list_area.y).into()),
+            _ => {}
+        }
+    }
+
+    fn state(&self, pkg: &ReviewPackage) -> PackageState {
+        if self.kept_back.contains(&pkg.name) {
+            PackageState::Deselected
+        } else if !self.current.contains(&pkg.name) {
+            PackageState::HeldBack
+        } else if self.reviewed.contains(&pkg.name) {
+            PackageState::Selected
+        } else {
+            PackageState::Added
+        }
+    }
+
+    fn group_by_list(&self) -> Vec<GroupBy> {
+        if self.topics.is_some() {
+            vec![GroupBy::Operation, GroupBy::Topic, GroupBy::Section]
+        } else {
+            vec![GroupBy::Operation, GroupBy::Section]
+        }
+    }
+
+    fn switch_group_by(&mut self) {
+        let list = self.group_by_list();
+        let i = list.iter().position(|x| *x == self.group_by).unwrap_or(0);
+
+        self.group_by = list[(i + 1) % list.len()];
+        self.collapsed.clear();
+        self.rebuild_rows();
+    }
+
+    /// Name and sort key of the group of a package
+    fn group_of(&self, pkg: &ReviewPackage) -> (u8, String) {
+        match self.group_by {
+            GroupBy::Operation => (pkg.kind as u8, pkg.kind.title()),
+            // Packages not updated by topics go last
+            GroupBy::Topic => match &pkg.topic {
+                Some(topic) => (0, topic.clone()),
+                None => (1, fl!("review-no-topic")),
+            },
+            GroupBy::Section => (0, pkg.section.clone()),
+        }
+    }
+
+    /// Packages added by an earlier resolution, but no longer needed, are not shown
+    fn is_shown(&self, pkg: &ReviewPackage) -> bool {
+        self.reviewed.contains(&pkg.name)
+            || self.current.contains(&pkg.name)
+            || self.kept_back.contains(&pkg.name)
+    }
+
+    /// Indexes of shown packages by group, sorted by name
+    fn groups(&self) -> BTreeMap<(u8, String), Vec<usize>> {
+        let mut groups: BTreeMap<_, Vec<_>> = BTreeMap::new();
+
+        for (i, pkg) in self.packages.iter().enumerate() {
+            if self.is_shown(pkg) {
+                groups.entry(self.group_of(pkg)).or_default().push(i);
+            }
+        }
+
+        for pkgs in groups.values_mut() {
+            pkgs.sort_by(|a, b| self.packages[*a].name.cmp(&self.packages[*b].name));
+        }
+
+        groups
+    }
+
+    fn rebuild_rows(&mut self) {
+        let selected = self
+            .rows
+            .state
+            .selected()
+            .and_then(|i| self.rows.items.get(i))
+            .cloned();
+
+        let mut rows = vec![];
+
+        for ((_, name), pkgs) in self.groups() {
+            let collapsed = self.collapsed.contains(&name);
+            rows.push(Row::Group(name));
+
+            if !collapsed {
+                rows.extend(pkgs.into_iter().map(Row::Package));
+            }
+        }
+
+        // Keep the selection on the same row, or the group of the selected package if collapsed
+        let pos = selected.and_then(|selected| {
+            rows.iter()
+                .position(|x| *x == selected)
+                .or_else(|| match selected {
+                    Row::Package(i) => {
+                        let (_, group) = self.group_of(&self.packages[i]);
+                        rows.iter().position(|x| *x == Row::Group(group.clone()))
+                    }
+                    Row::Group(_) => None,
+                })
+        });
+
+        let pos = pos.or_else(|| (!rows.is_empty()).then_some(0));
+        self.rows.items = rows;
+        self.rows.state.select(pos);
+    }
+
+    /// Collapse (`collapsed`) or expand the group of the selected row
+    fn set_collapsed(&mut self, collapsed: bool) {
+        let Some(row) = self.selected_row() else {
+            return;
+        };
+
+        let group = match row {
+            Row::Group(name) => name,
+            Row::Package(i) => self.group_of(&self.packages[i]).1,
+        };
+
+        if collapsed {
+            self.collapsed.insert(group.clone());
+        } else {
+            self.collapsed.remove(&group);
+        }
+
+        if let Some(pos) = self
+            .rows
+            .items
+            .iter()
+            .position(|x| *x == Row::Group(group.clone()))
+        {
+            self.rows.state.select(Some(pos));
+        }
+
+        self.rebuild_rows();
+    }
+
+    /// Select the next (`next`) or the previous group
+    fn jump_to_group(&mut self, next: bool) {
+        let current = self.rows.state.selected().unwrap_or(0);
+        let is_group = |x: &(usize, &Row)| matches!(x.1, Row::Group(_));
+
+        let pos = if next {
+            self.rows
+                .items
+                .iter()
+                .enumerate()
+                .skip(current + 1)
+                .find(is_group)
+        } else {
+            self.rows
+                .items
+                .iter()
+                .enumerate()
+                .take(current)
+                .rev()
+                .find(is_group)
+        };
+
+        if let Some((pos, _)) = pos {
+            self.rows.state.select(Some(pos));
+        }
+    }
+
+    fn selected_row(&self) -> Option<Row> {
+        self.rows
+            .state
+            .selected()
+            .and_then(|i| self.rows.items.get(i))
+            .cloned()
+    }
+
+    /// Select or deselect the selected package, or all packages of the selected group
+    fn toggle(&mut self) {
+        let Some(row) = self.selected_row() else {
+            return;
+        };
+
+        let pkgs = match row {
+            Row::Package(i) => vec![i],
+            Row::Group(name) => self
+                .groups()
+                .into_iter()
+                .find(|((_, x), _)| *x == name)
+                .map(|(_, pkgs)| pkgs)
+                .unwrap_or_default(),
+        };
+
+        let states = pkgs
+            .iter()
+            .map(|i| (*i, self.state(&self.packages[*i])))
+            .collect::<Vec<_>>();
+
+        let prev = self.kept_back.clone();
+
+        // Deselect all packages of a group if some of them are selected
+        if states
+            .iter()
+            .any(|(_, x)| matches!(x, PackageState::Selected | PackageState::Added))
+        {
+            for (i, state) in states {
+                if matches!(state, PackageState::Selected | PackageState::Added) {
+                    self.kept_back.push(self.packages[i].name.clone());
+                }
+            }
+        } else if states.iter().any(|(_, x)| *x == PackageState::Deselected) {
+            let names = states
+                .iter()
+                .filter(|(_, x)| *x == PackageState::Deselected)
+                .map(|(i, _)| &self.packages[*i].name)
+                .collect::<HashSet<_>>();
+
+            self.kept_back.retain(|x| !names.contains(x));
+        } else {
+            self.popup = Some(fl!("review-held-back-tips"));
+            return;
+        }
+
+        match self.resolve() {
+            Ok(op) => {
+                self.op = op;
+                self.current = op_names(&self.op);
+                self.last_marks = self.apt.save_marks();
+                self.update_packages();
+            }
+            Err(e) => {
+                self.apt.restore_marks(&self.last_marks);
+                self.kept_back = prev;
+                self.popup = Some(e.to_string());
+            }
+        }
+
+        self.rebuild_rows();
+    }
+
+    /// Resolve the reviewed changes again, with deselected packages kept back
+    fn resolve(&mut self) -> Result<OmaOperation, OutputError> {
+        self.apt.restore_marks(&self.marks);
+        self.apt.keep_back(
+            &self
+                .kept_back
+                .iter()
+                .map(|x| x.as_str())
+                .collect::<Vec<_>>(),
+        );
+        self.apt.resolve(self.no_fixbroken, self.remove_config)?;
+
+        let op = self.apt.summary(
+            SummarySort::Operation,
+            |pkg| self.reviewed_remove.contains(pkg),
+            // Features can not be confirmed in here, only those already confirmed may be removed
+            |features| features.is_subset(&self.reviewed_features),
+        )?;
+
+        Ok(op)
+    }
+
+    /// Add packages of the current changes, or update them if already known
+    fn update_packages(&mut self) {
+        let mut new = vec![];
+
+        for entry in &self.op.install {
+            let kind = match entry.op() {
+                InstallOperation::Upgrade => ChangeKind::Upgrade,
+                InstallOperation::Downgrade => ChangeKind::Downgrade,
+                InstallOperation::ReInstall => ChangeKind::Reinstall,
+                _ => ChangeKind::Install,
+            };
+
+            let version = match entry.old_version() {
+                Some(old) if old != entry.new_version() => {
+                    format!("{old} -> {}", entry.new_version())
+                }
+                _ => entry.new_version().to_string(),
+            };
+
+            new.push(ReviewPackage {
+                name: entry.name().to_string(),
+                kind,
+                version,
+                topic: self.topic_of(entry.name_without_arch()),
+                section: self.section_of(entry.name()),
+            });
+        }
+
+        for entry in &self.op.remove {
+            new.push(ReviewPackage {
+                name: entry.name().to_string(),
+                kind: ChangeKind::Remove,
+                version: entry.version().unwrap_or_default().to_string(),
+                topic: self.topic_of(entry.name()),
+                section: self.section_of(entry.name()),
+            });
+        }
+
+        for pkg in new {
+            match self.packages.iter_mut().find(|x| x.name == pkg.name) {
+                Some(old) => *old = pkg,
+                None => self.packages.push(pkg),
+            }
+        }
+    }
+
+    fn topic_of(&self, name: &str) -> Option<String> {
+        self.topics.as_ref().and_then(|x| x.get(name)).cloned()
+    }
+
+    fn section_of(&self, name: &str) -> String {
+        self.apt
+            .cache
+            .get(name)
+            .and_then(|pkg| pkg.candidate().or_else(|| pkg.installed()))
+            .and_then(|ver| ver.section().ok().map(|x| x.to_string()))
+            .unwrap_or_else(|| "unknown".to_string())
+    }
+
+    fn row_item(&self, row: &Row) -> ListItem<'static> {
+        match row {
+            Row::Group(name) => {
+                let pkgs = self
+                    .packages
+                    .iter()
+                    .filter(|x| self.is_shown(x) && self.group_of(x).1 == *name)
+                    .collect::<Vec<_>>();
+
+                let selected = pkgs
+                    .iter()
+                    .filter(|x| {
+                        matches!(self.state(x), PackageState::Selected | PackageState::Added)
+                    })
+                    .count();
+
+                let arrow = if self.collapsed.contains(name) {
+                    "▸"
+                } else {
+                    "▾"
+                };
+
+                ListItem::new(Line::from(vec![
+                    Span::raw(format!("{arrow} ")),
+                    Span::raw(name.clone()).bold(),
+                    Span::raw(format!(" ({selected}/{})", pkgs.len())),
+                ]))
+            }
+            Row::Package(i) => {
+                let pkg = &self.packages[*i];
+                let state = self.state(pkg);
+
+                let mut line = vec![
+                    Span::raw(format!("    {} ", state.checkbox())),
+                    Span::raw(pkg.name.clone()),
+                    Span::raw(format!(" {}", pkg.version)).dim(),
+                ];
+
+                if self.group_by != GroupBy::Operation {
+                    line.push(Span::styled(
+                        format!(" ({})", pkg.kind.title()),
+                        pkg.kind.style(),
+                    ));
+                }
+
+                if state == PackageState::HeldBack {
+                    line.push(Span::raw(format!(" - {}", fl!("review-held-back"))).dim());
+                }
+
+                ListItem::new(Line::from(line))
+            }
+        }
+    }
+
+    /// Changes caused by deselected packages
+    fn cascade(&self) -> Vec<Line<'static>> {
+        self.packages
+            .iter()
+            .filter(|pkg| self.is_shown(pkg))
+            .filter_map(|pkg| match self.state(pkg) {
+                PackageState::Added => Some(Line::from(vec![
+                    Span::styled(format!("{} ", pkg.kind.title()), pkg.kind.style()),
+                    Span::raw(format!("{} {}", pkg.name, pkg.version)),
+                ])),
+                PackageState::HeldBack => Some(Line::from(vec![
+                    Span::raw(format!("{} ", fl!("review-held-back"))).dim(),
+                    Span::raw(pkg.name.clone()),
+                ])),
+                PackageState::Selected | PackageState::Deselected => None,
+            })
+            .collect()
+    }
+
+    fn ui(&mut self, f: &mut Frame) {
+        let cascade = self.cascade();
+        let cascade_height = if cascade.is_empty() {
+            0
+        } else {
+            cascade.len().min(6) as u16 + 2
+        };
+
+        let [header_area, main_area, cascade_area] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(cascade_height),
+        ])
+        .areas(f.area());
+
+        f.render_widget(
+            Paragraph::new(format!(
+                " {} - {}",
+                fl!("review-title"),
+                fl!(
+                    "review-header",
+                    changes = self.op.install.len() + self.op.remove.len(),
+                    deselected = self.kept_back.len()
+                )
+            ))
+            .style(header_style()),
+            header_area,
+        );
+
+        let tips = vec![
+            Span::styled("Space", key_style()),
+            Span::raw(format!(" => {}, ", fl!("review-toggle"))),
+            Span::styled("←/→", key_style()),
+            Span::raw(format!(" => {}, ", fl!("review-collapse"))),
+            Span::styled("Tab", key_style()),
+            Span::raw(format!(" => {}, ", fl!("review-group-by"))),
+            Span::styled("Enter", key_style()),
+            Span::raw(format!(" => {}, ", fl!("review-confirm"))),
+            Span::styled("Esc", key_style()),
+            Span::raw(format!(" => {}", fl!("review-cancel"))),
+        ];
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(fl!("pending-op"))
+            .title_bottom(Line::from(tips));
+
+        let inner = block.inner(main_area);
+        f.render_widget(block, main_area);
+
+        let [tabs_area, list_area] =
+            Layout::vertical([Constraint::Length(2), Constraint::Min(0)]).areas(inner);
+
+        let group_by_list = self.group_by_list();
+        f.render_widget(
+            Tabs::new(group_by_list.iter().map(|x| x.title()))
+                .select(
+                    group_by_list
+                        .iter()
+                        .position(|x| *x == self.group_by)
+                        .unwrap_or(0),
+                )
+                .highlight_style(highlight_style()),
+            tabs_area,
+        );
+
+        let items = self
+            .rows
+            .items
+            .iter()
+            .map(|x| self.row_item(x))
+            .collect::<Vec<_>>();
+
+        f.render_stateful_widget(
+            List::new(items).highlight_style(highlight_style()),
+            list_area,
+            &mut self.rows.state,
+        );
+
+        self.list_area = list_area;
+
+        if !cascade.is_empty() {
+            let block = Block::default()
+                .borders(Borders::ALL)
+                .title(fl!("review-cascade"));
+
+            f.render_widget(
+                Paragraph::new(Text::from(cascade)),
+                block.inner(cascade_area).inner(Margin::new(1, 0)),
+            );
+            f.render_widget(block, cascade_area);
+        }
+
+        if let Some(popup) = &self.popup {
+            let block = Block::bordered();
+            let width = popup
+                .lines()
+                .map(console::measure_text_width)
+                .max()
+                .unwrap_or(0);
+            let area = popup_area(
+                main_area,
+                width as u16 + 10,
+                popup.lines().count() as u16 + 5,
+            );
+            let inner = block.inner(area);
+            f.render_widget(Clear, area);
+            f.render_widget(block, area);
+
+            let mut lines = popup.lines().map(Line::raw).collect::<Vec<_>>();
+            lines.extend([Line::raw(""), Line::raw(fl!("tui-continue-tips"))]);
+
+            f.render_widget(Text::from(lines), inner);
+        }
+    }
+}
+
+fn op_names(op: &OmaOperation) -> HashSet<String> {
+    op.install
+        .iter()
+        .map(|x| x.name())
+        .chain(op.remove.iter().map(|x| x.name()))
+        .map(|x| x.to_string())
+        .collect()
+}
@@ -0,0 +1,375 @@
+use std::{
+    path::Path,
+    thread,
+    time::{Duration, Instant},
+};
+
+use chrono::{Local, LocalResult, TimeZone};
+use flume::{unbounded, Receiver};
+use oma_mirror::MirrorManager;
+use oma_topics::{Topic, TopicManager};
+use oma_utils::dpkg::dpkg_arch;
+use reqwest::blocking;
+
+use crate::{error::OutputError, fl, APP_USER_AGENT, HTTP_CLIENT, RT};
+
+use super::state::StatefulList;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SourcesTab {
+    Topics,
+    Mirrors,
+}
+
+impl SourcesTab {
+    pub const ALL: [SourcesTab; 2] = [SourcesTab::Topics, SourcesTab::Mirrors];
+
+    pub fn title(&self) -> String {
+        match self {
+            SourcesTab::Topics => fl!("tui-sources-topics"),
+            SourcesTab::Mirrors => fl!("tui-sources-mirrors"),
+        }
+    }
+}
+
+/// Changes to apply when leaving the sources screen
+pub struct SourcesChange {
+    pub opt_in: Vec<String>,
+    pub opt_out: Vec<String>,
+    /// Names of enabled mirrors in order, `None` if they are unchanged
+    pub mirrors: Option<Vec<String>>,
+}
+
+pub struct TopicItem {
+    pub topic: Topic,
+    pub enabled: bool,
+    was_enabled: bool,
+}
+
+impl TopicItem {
+    pub fn text(&self) -> String {
+        let checkbox = if self.enabled { "[x]" } else { "[ ]" };
+        let date = format_date(self.topic.update_date.unwrap_or(self.topic.date));
+        let count = self.topic.packages.len();
+
+        match &self.topic.description {
+            Some(desc) => format!(
+                "{checkbox} {desc} ({}) - {date}, {}",
+                self.topic.name,
+                fl!("tui-sources-topic-pkgs", count = count)
+            ),
+            None => format!(
+                "{checkbox} {} - {date}, {}",
+                self.topic.name,
+                fl!("tui-sources-topic-pkgs", count = count)
+            ),
+        }
+    }
+}
+
+pub enum Latency {
+    Testing,
+    Done(Duration),
+    Failed,
+}
+
+pub struct MirrorItem {
+    pub name: String,
+    pub desc: String,
+    url: String,
+    pub enabled: bool,
+    pub latency: Option<Latency>,
+}
+
+impl MirrorItem {
+    pub fn text(&self) -> String {
+        let checkbox = if self.enabled { "[x]" } else { "[ ]" };
+        let latency = match &self.latency {
+            None => String::new(),
+            Some(Latency::Testing) => format!(" - {}", fl!("tui-sources-testing")),
+            Some(Latency::Done(dur)) => format!(" - {} ms", dur.as_millis()),
+            Some(Latency::Failed) => format!(" - {}", fl!("tui-sources-unreachable")),
+        };
+
+        format!("{checkbox} {} ({}){latency}", self.desc, self.name)
+    }
+}
+
+/// Topics and mirrors management screen
+pub struct SourcesView {
+    pub tab: SourcesTab,
+    pub topics: StatefulList<TopicItem>,
+    pub mirrors: StatefulList<MirrorItem>,
+    /// Names of mirrors enabled when the screen was opened, in order
+    was_enabled_mirrors: Vec<String>,
+    latency_rx: Option<Receiver<(String, Option<Duration>)>>,
+}
+
+impl SourcesView {
+    pub fn new(sysroot: &Path) -> Result<Self, OutputError> {
+        let arch = dpkg_arch(sysroot)?;
+        let mut tm = TopicManager::new_blocking(&HTTP_CLIENT, sysroot, &arch, false)?;
+        RT.block_on(tm.refresh())?;
+
+        let enabled_topics = tm.enabled_topics();
+
+        // Enabled topics first, like `oma topics`
+        let mut topics = tm
+            .available_topics()
+            .filter(|x| {
+                (x.description.is_some() && !x.draft.is_some_and(|x| x))
+                    || enabled_topics.contains(x)
+            })
+            .map(|x| TopicItem {
+                topic: x.to_owned(),
+                enabled: enabled_topics.contains(x),
+                was_enabled: enabled_topics.contains(x),
+            })
+            .collect::<Vec<_>>();
+
+        topics.sort_by_key(|x| !x.enabled);
+
+        let mm = MirrorManager::new(sysroot)?;
+        let enabled_mirrors = mm.enabled_mirrors();
+
+        let mut mirrors = mm
+            .mirrors_iter()?
+            .map(|(name, mirror)| MirrorItem {
+                name: name.to_string(),
+                desc: mirror.desc.to_string(),
+                url: mirror.url.to_string(),
+                enabled: enabled_mirrors.contains_key(name),
+                latency: None,
+            })
+            .collect::<Vec<_>>();
+
+        // Enabled mirrors first in their priority order, then others by name
+        mirrors.sort_by(|a, b| {
+            let a_index = enabled_mirrors.get_index_of(a.name.as_str());
+            let b_index = enabled_mirrors.get_index_of(b.name.as_str());
+
+            match (a_index, b_index) {
+                (Some(a), Some(b)) => a.cmp(&b),
+                (Some(_), None) => std::cmp::Ordering::Less,
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (None, None) => a.name.cmp(&b.name),
+            }
+        });
+
+        let was_enabled_mirrors = mirrors
+            .iter()
+            .filter(|x| x.enabled)
+            .map(|x| x.name.clone())
+            .collect();
+
+        Ok(Self {
+            tab: SourcesTab::Topics,
+            topics: StatefulList::with_first_selected(topics),
+            mirrors: StatefulList::with_first_selected(mirrors),
+            was_enabled_mirrors,
+            latency_rx: None,
+        })
+    }
+
+    pub fn switch_tab(&mut self) {
+        self.tab = match self.tab {
+            SourcesTab::Topics => SourcesTab::Mirrors,
+            SourcesTab::Mirrors => SourcesTab::Topics,
+        };
+    }
+
+    pub fn tab_index(&self) -> usize {
+        SourcesTab::ALL.iter().position(|x| *x == self.tab).unwrap()
+    }
+
+    pub fn next(&mut self) {
+        match self.tab {
+            SourcesTab::Topics if !self.topics.items.is_empty() => self.topics.next(),
+            SourcesTab::Mirrors if !self.mirrors.items.is_empty() => self.mirrors.next(),
+            _ => {}
+        }
+    }
+
+    pub fn previous(&mut self) {
+        match self.tab {
+            SourcesTab::Topics => self.topics.previous(),
+            SourcesTab::Mirrors => self.mirrors.previous(),
+        }
+    }
+
+    pub fn select_relative(&mut self, delta: isize) {
+        match self.tab {
+            SourcesTab::Topics => self.topics.select_relative(delta),
+            SourcesTab::Mirrors => self.mirrors.select_relative(delta),
+        }
+    }
+
+    pub fn select_visible_row(&mut self, row: usize) {
+        match self.tab {
+            SourcesTab::Topics => self.topics.select_visible_row(row),
+            SourcesTab::Mirrors => self.mirrors.select_visible_row(row),
+        }
+    }
+
+    /// Enable or disable the selected topic or mirror
+    pub fn toggle(&mut self) {
+        match self.tab {
+            SourcesTab::Topics => {
+                if let Some(item) = selected_mut(&mut self.topics) {
+                    item.enabled = !item.enabled;
+                }
+            }
+            SourcesTab::Mirrors => {
+                let Some(item) = selected_mut(&mut self.mirrors) else {
+                    return;
+                };
+
+                item.enabled = !item.enabled;
+                let name = item.name.clone();
+
+                // Newly enabled mirrors have the lowest priority
+                let (mut enabled, mut disabled): (Vec<_>, Vec<_>) =
+                    self.mirrors.items.drain(..).partition(|x| x.enabled);
+
+                if let Some(pos) = enabled.iter().position(|x| x.name == name) {
+                    let item = enabled.remove(pos);
+                    enabled.push(item);
+                }
+
+                disabled.sort_by(|a, b| a.name.cmp(&b.name));
+                enabled.extend(disabled);
+                self.mirrors.items = enabled;
+
+                let pos = self.mirrors.items.iter().position(|x| x.name == name);
+                self.mirrors.state.select(pos);
+            }
+        }
+    }
+
+    /// Move the selected mirror up (`up`) or down in the priority order
+    pub fn move_mirror(&mut self, up: bool) {
+        if self.tab != SourcesTab::Mirrors {
+            return;
+        }
+
+        let Some(i) = self.mirrors.state.selected() else {
+            return;
+        };
+
+        let Some(j) = (if up { i.checked_sub(1) } else { Some(i + 1) }) else {
+            return;
+        };
+
+        let items = &mut self.mirrors.items;
+
+        // Only the order of enabled mirrors matters
+        if j >= items.len() || !items[i].enabled || !items[j].enabled {
+            return;
+        }
+
+        items.swap(i, j);
+        self.mirrors.state.select(Some(j));
+    }
+
+    /// Measure latency of all mirrors in background
+    pub fn test_latency(&mut self) {
+        let (tx, rx) = unbounded();
+
+        let urls = self
+            .mirrors
+            .items
+            .iter_mut()
+            .map(|x| {
+                x.latency = Some(Latency::Testing);
+                (x.name.clone(), x.url.clone())
+            })
+            .collect::<Vec<_>>();
+
+        thread::spawn(move || {
+            let Ok(client) = blocking::ClientBuilder::new()
+                .user_agent(APP_USER_AGENT)
+                .timeout(Duration::from_secs(5))
+                .build()
+            else {
+                return;
+            };
+
+            for (name, url) in urls {
+                let timer = Instant::now();
+                let res = client.head(url).send().and_then(|x| x.error_for_status());
+
+                if tx.send((name, res.ok().map(|_| timer.elapsed()))).is_err() {
+                    return;
+                }
+            }
+        });
+
+        self.latency_rx = Some(rx);
+    }
+
+    /// Collect finished latency tests
+    pub fn update_latency(&mut self) {
+        let Some(rx) = &self.latency_rx else {
+            return;
+        };
+
+        for (name, res) in rx.try_iter() {
+            if let Some(item) = self.mirrors.items.iter_mut().find(|x| x.name == name) {
+                item.latency = Some(match res {
+                    Some(dur) => Latency::Done(dur),
+                    None => Latency::Failed,
+                });
+            }
+        }
+    }
+
+    /// Changes of both topics and mirrors, `None` if nothing was changed
+    pub fn changes(&self) -> Option<SourcesChange> {
+        let opt_in = self
+            .topics
+            .items
+            .iter()
+            .filter(|x| x.enabled && !x.was_enabled)
+            .map(|x| x.topic.name.clone())
+            .collect::<Vec<_>>();
+
+        let opt_out = self
+            .topics
+            .items
+            .iter()
+            .filter(|x| !x.enabled && x.was_enabled)
+            .map(|x| x.topic.name.clone())
+            .collect::<Vec<_>>();
+
+        let mirrors = self
+            .mirrors
+            .items
+            .iter()
+            .filter(|x| x.enabled)
+            .map(|x| x.name.clone())
+            .collect::<Vec<_>>();
+
+        let mirrors = (mirrors != self.was_enabled_mirrors).then_some(mirrors);
+
+        if opt_in.is_empty() && opt_out.is_empty() && mirrors.is_none() {
+            return None;
+        }
+
+        Some(SourcesChange {
+            opt_in,
+            opt_out,
+            mirrors,
+        })
+    }
+}
+
+fn selected_mut<T>(list: &mut StatefulList<T>) -> Option<&mut T> {
+    list.state.selected().and_then(|i| list.items.get_mut(i))
+}
+
+fn format_date(date: u64) -> String {
+    match Local.timestamp_opt(date as i64, 0) {
+        LocalResult::Single(dt) => dt.format("%Y-%m-%d").to_string(),
+        _ => date.to_string(),
+    }
+}
@@ -13,6 +13,15 @@ impl<T> StatefulList<T> {
         }
     }
 
+    pub fn with_first_selected(items: Vec<T>) -> StatefulList<T> {
+        let mut list = Self::with_items(items);
+        if !list.items.is_empty() {
+            list.state.select(Some(0));
+        }
+
+        list
+    }
+
     pub fn next(&mut self) {
         let i = match self.state.selected() {
             Some(i) => {
@@ -34,4 +43,28 @@ impl<T> StatefulList<T> {
         };
         self.state.select(Some(i));
     }
+
+    /// Move the selection by `delta` items, stopping at the first or the last item
+    pub fn select_relative(&mut self, delta: isize) {
+        if self.items.is_empty() {
+            return;
+        }
+
+        let i = self
+            .state
+            .selected()
+            .unwrap_or(0)
+            .saturating_add_signed(delta)
+            .min(self.items.len() - 1);
+
+        self.state.select(Some(i));
+    }
+
+    /// Select the item shown at `row` of the list area, like clicking on it
+    pub fn select_visible_row(&mut self, row: usize) {
+        let i = self.state.offset() + row;
+        if i < self.items.len() {
+            self.state.select(Some(i));
+        }
+    }
 }
@@ -2,38 +2,57 @@ use std::{
     fmt::Display,
     io,
     ops::ControlFlow,
+    path::Path,
     rc::Rc,
     time::{Duration, Instant},
 };
 
-use super::state::StatefulList;
+#[cfg(feature = "aosc")]
+use super::sources::{SourcesChange, SourcesTab, SourcesView};
+use super::{
+    detail::{Detail, DetailTab},
+    header_style, highlight_style,
+    history::HistoryView,
+    key_style, popup_area,
+    state::StatefulList,
+};
 use ansi_to_tui::IntoText;
-use crossterm::event::{self, KeyCode, KeyModifiers};
+use crossterm::event::{
+    self, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
 use dialoguer::console;
+use oma_console::input::KeyAction;
 use oma_pm::{
     apt::OmaApt,
     pkginfo::OmaPackage,
-    search::{IndiciumSearch, OmaSearch, SearchResult},
+    search::{OmaSearch, OmaSearchResult, SearchFilter, SearchResult},
+    search_index::IndexSearch,
 };
 
 use ratatui::{
-    layout::{Alignment, Constraint, Direction, Flex, Layout, Rect},
+    layout::{Alignment, Constraint, Direction, Layout, Margin, Position, Rect},
     prelude::Backend,
-    style::{Color, Style, Stylize},
+    style::{Style, Stylize},
     text::{Line, Span, Text},
     widgets::{
         Block, Borders, Clear, List, ListItem, Padding, Paragraph, Scrollbar, ScrollbarOrientation,
-        ScrollbarState,
+        ScrollbarState, Tabs, Wrap,
     },
     Frame, Terminal,
 };
 
-use crate::{fl, utils::SearchResultDisplay, WRITER};
+use crate::{
+    config::Config, error::OutputError, fl, input_config, utils::SearchResultDisplay, WRITER,
+};
 
-#[derive(PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq)]
 enum Mode {
     Search,
     Packages,
+    Detail,
+    History,
+    #[cfg(feature = "aosc")]
+    Sources,
     Pending,
 }
 
@@ -49,7 +68,9 @@ enum Operation {
 
 pub struct Tui<'a> {
     apt: &'a OmaApt,
-    searcher: IndiciumSearch<'a>,
+    config: &'a Config,
+    sysroot: &'a Path,
+    searcher: IndexSearch<'a>,
     mode: Mode,
     input_cursor_position: usize,
     display_pending_detail: bool,
@@ -66,6 +87,30 @@ pub struct Tui<'a> {
     upgrade: bool,
     autoremove: bool,
     popup: Option<String>,
+    detail: Option<Detail>,
+    /// Packages visited before jumping to a dependency in the detail pane
+    detail_history: Vec<String>,
+    history: Option<HistoryView>,
+    #[cfg(feature = "aosc")]
+    sources: Option<SourcesView>,
+    /// Shown panes, with where their rows start, for mouse events
+    panes: Vec<Pane>,
+    /// Rows scrolled by PageUp and PageDown
+    page_height: u16,
+}
+
+#[derive(Clone, Copy)]
+struct Pane {
+    mode: Mode,
+    area: Rect,
+    /// Area of list rows, empty if the pane is not a list
+    rows: Rect,
+}
+
+impl Pane {
+    fn new(mode: Mode, area: Rect, rows: Rect) -> Self {
+        Self { mode, area, rows }
+    }
 }
 
 impl Display for Operation {
@@ -92,27 +137,37 @@ impl From<Operation> for ListItem<'_> {
     }
 }
 
+#[derive(Default)]
 pub struct Task {
     pub execute_apt: bool,
     pub install: Vec<OmaPackage>,
     pub remove: Vec<OmaPackage>,
     pub upgrade: bool,
     pub autoremove: bool,
+    /// ID of the history entry to undo
+    pub undo: Option<i64>,
+    /// Topics or mirrors to apply
+    #[cfg(feature = "aosc")]
+    pub sources: Option<SourcesChange>,
 }
 
 impl<'a> Tui<'a> {
     pub fn new(
         apt: &'a OmaApt,
+        config: &'a Config,
+        sysroot: &'a Path,
         installed: usize,
         upgradable: usize,
         autoremovable: usize,
-        searcher: IndiciumSearch<'a>,
+        searcher: IndexSearch<'a>,
     ) -> Self {
         let pkg_results = vec![];
         let pkg_result_state = StatefulList::with_items(vec![]);
 
         Self {
             apt,
+            config,
+            sysroot,
             searcher,
             mode: Mode::Search,
             input_cursor_position: 0,
@@ -130,6 +185,13 @@ impl<'a> Tui<'a> {
             upgrade: false,
             autoremove: false,
             popup: None,
+            detail: None,
+            detail_history: vec![],
+            history: None,
+            #[cfg(feature = "aosc")]
+            sources: None,
+            panes: vec![],
+            page_height: 0,
         }
     }
 
@@ -140,10 +202,27 @@ impl<'a> Tui<'a> {
     ) -> io::Result<Task> {
         let mut last_tick = Instant::now();
         loop {
+            #[cfg(feature = "aosc")]
+            if let Some(sources) = &mut self.sources {
+                sources.update_latency();
+            }
+
+            if let Some(detail) = &mut self.detail {
+                detail.update_changelog();
+            }
+
             terminal.draw(|f| self.ui(f))?;
 
             if event::poll(tick_rate)? {
-                if let event::Event::Key(key) = event::read()? {
+                let event = event::read()?;
+
+                if let event::Event::Mouse(mouse) = event {
+                    if self.popup.is_none() {
+                        self.handle_mouse(mouse);
+                    }
+                }
+
+                if let event::Event::Key(key) = event {
                     if self.popup.is_some() {
                         match key.code {
                             KeyCode::Char('c') => {
@@ -163,6 +242,9 @@ impl<'a> Tui<'a> {
                                     remove: vec![],
                                     upgrade: false,
                                     autoremove: false,
+                                    undo: None,
+                                    #[cfg(feature = "aosc")]
+                                    sources: None,
                                 });
                             }
                             KeyCode::Char('u') => {
@@ -204,31 +286,53 @@ impl<'a> Tui<'a> {
                                     self.pending_result_state.items.push(Operation::AutoRemove);
                                 }
                             }
-                            _ => {}
+                            _ => self.handle_key_action(&key),
                         }
 
                         continue;
                     }
 
+                    #[cfg(feature = "aosc")]
+                    if self.mode == Mode::Sources {
+                        match self.handle_sources_key(key.code, key.modifiers) {
+                            ControlFlow::Break(task) => return Ok(task),
+                            ControlFlow::Continue(true) => continue,
+                            ControlFlow::Continue(false) => {}
+                        }
+                    }
+
                     match key.code {
-                        KeyCode::Up => self.handle_up(),
-                        KeyCode::Down => self.handle_down(),
                         KeyCode::Esc => break,
                         KeyCode::Char(' ') => {
                             if let ControlFlow::Break(_) = self.handle_space() {
                                 continue;
                             }
                         }
-                        KeyCode::Char('/') => self.mode = Mode::Search,
-                        KeyCode::Char(c) => {
-                            if self.mode != Mode::Search {
-                                continue;
+                        KeyCode::Char('u') if self.mode == Mode::History => {
+                            match self.history.as_ref().and_then(|x| x.undoable()) {
+                                Some(id) => {
+                                    return Ok(Task {
+                                        undo: Some(id),
+                                        ..Default::default()
+                                    })
+                                }
+                                None => self.popup = Some(fl!("tui-history-can-not-undo")),
                             }
-
-                            self.handle_input_text(c);
+                        }
+                        KeyCode::Char(c)
+                            if self.mode == Mode::Search
+                                && !key.modifiers.contains(KeyModifiers::ALT) =>
+                        {
+                            self.handle_input_text(c)
                         }
                         KeyCode::Tab => self.handle_tab(),
+                        KeyCode::Enter => self.handle_enter(),
                         KeyCode::Backspace => {
+                            if self.mode == Mode::Detail {
+                                self.detail_back();
+                                continue;
+                            }
+
                             if self.mode != Mode::Search {
                                 continue;
                             }
@@ -246,10 +350,14 @@ impl<'a> Tui<'a> {
                                 continue;
                             }
                         }
-                        KeyCode::Left => self.handle_left(),
-                        KeyCode::Right => self.handle_right(),
+                        // Arrow keys always move the cursor of the search bar
+                        KeyCode::Left if self.mode == Mode::Search => self.handle_left(),
+                        KeyCode::Right if self.mode == Mode::Search => self.handle_right(),
                         KeyCode::F(1) => self.display_pending_detail = !self.display_pending_detail,
-                        _ => {}
+                        KeyCode::F(2) => self.toggle_history(),
+                        #[cfg(feature = "aosc")]
+                        KeyCode::F(3) => self.toggle_sources(),
+                        _ => self.handle_key_action(&key),
                     }
                 }
             }
@@ -268,9 +376,213 @@ impl<'a> Tui<'a> {
             remove: self.remove,
             upgrade: self.upgrade,
             autoremove: self.autoremove,
+            undo: None,
+            #[cfg(feature = "aosc")]
+            sources: None,
         })
     }
 
+    /// Keys from the keymap, like `j`/`k` or `Ctrl+N`/`Ctrl+P`
+    fn handle_key_action(&mut self, key: &KeyEvent) {
+        let Some(action) = input_config().keymap.action(key) else {
+            return;
+        };
+
+        let page = self.page_height.max(1) as isize;
+
+        match action {
+            KeyAction::Up => self.handle_up(),
+            KeyAction::Down => self.handle_down(),
+            KeyAction::Left => self.handle_left(),
+            KeyAction::Right => self.handle_right(),
+            KeyAction::PageUp => self.select_relative(-page),
+            KeyAction::PageDown => self.select_relative(page),
+            KeyAction::Top => self.select_relative(isize::MIN),
+            KeyAction::Bottom => self.select_relative(isize::MAX),
+            KeyAction::Search => self.mode = Mode::Search,
+            // Pager only, Esc and Ctrl+C are used to quit the TUI
+            KeyAction::NextMatch
+            | KeyAction::PreviousMatch
+            | KeyAction::Filter
+            | KeyAction::NextSection
+            | KeyAction::PreviousSection
+            | KeyAction::Save
+            | KeyAction::SaveAnsi
+            | KeyAction::Quit => {}
+        }
+    }
+
+    fn handle_mouse(&mut self, mouse: MouseEvent) {
+        let pos = Position::new(mouse.column, mouse.row);
+
+        let Some(pane) = self.panes.iter().find(|x| x.area.contains(pos)).copied() else {
+            return;
+        };
+
+        match mouse.kind {
+            MouseEventKind::ScrollUp => {
+                self.focus(pane.mode);
+                self.select_relative(-1);
+            }
+            MouseEventKind::ScrollDown => {
+                self.focus(pane.mode);
+                self.select_relative(1);
+            }
+            MouseEventKind::Down(MouseButton::Left) => {
+                self.focus(pane.mode);
+                if pane.rows.contains(pos) {
+                    self.select_visible_row((pos.y - pane.rows.y).into());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn focus(&mut self, mode: Mode) {
+        match mode {
+            Mode::Packages => change_to_packages_window(&mut self.mode, &mut self.pkg_result_state),
+            Mode::Pending => {
+                change_to_pending_window(&mut self.mode, &mut self.pending_result_state)
+            }
+            mode => self.mode = mode,
+        }
+    }
+
+    /// Move the selection of the focused pane by `delta` rows
+    fn select_relative(&mut self, delta: isize) {
+        match self.mode {
+            Mode::Search => {}
+            Mode::Packages => {
+                self.pkg_result_state.select_relative(delta);
+                self.result_scroll = self
+                    .result_scroll
+                    .position(self.pkg_result_state.state.selected().unwrap_or(0));
+                self.update_detail();
+            }
+            Mode::Detail => {
+                if let Some(detail) = &mut self.detail {
+                    detail.select_relative(delta);
+                }
+            }
+            Mode::History => {
+                if let Some(history) = &mut self.history {
+                    history.entries.select_relative(delta);
+                }
+            }
+            #[cfg(feature = "aosc")]
+            Mode::Sources => {
+                if let Some(sources) = &mut self.sources {
+                    sources.select_relative(delta);
+                }
+            }
+            Mode::Pending => self.pending_result_state.select_relative(delta),
+        }
+    }
+
+    /// Select the item shown at `row` of the focused pane
+    fn select_visible_row(&mut self, row: usize) {
+        match self.mode {
+            Mode::Search => {}
+            Mode::Packages => {
+                self.pkg_result_state.select_visible_row(row);
+                self.result_scroll = self
+                    .result_scroll
+                    .position(self.pkg_result_state.state.selected().unwrap_or(0));
+                self.update_detail();
+            }
+            Mode::Detail => {
+                if let Some(detail) = &mut self.detail {
+                    detail.select_visible_row(row);
+                }
+            }
+            Mode::History => {
+                if let Some(history) = &mut self.history {
+                    history.entries.select_visible_row(row);
+                }
+            }
+            #[cfg(feature = "aosc")]
+            Mode::Sources => {
+                if let Some(sources) = &mut self.sources {
+                    sources.select_visible_row(row);
+                }
+            }
+            Mode::Pending => self.pending_result_state.select_visible_row(row),
+        }
+    }
+
+    fn toggle_history(&mut self) {
+        if self.history.take().is_some() {
+            change_to_packages_window(&mut self.mode, &mut self.pkg_result_state);
+            return;
+        }
+
+        #[cfg(feature = "aosc")]
+        {
+            self.sources = None;
+        }
+
+        match HistoryView::new(self.sysroot) {
+            Ok(history) => {
+                self.history = Some(history);
+                self.mode = Mode::History;
+            }
+            Err(e) => self.popup = Some(OutputError::from(e).to_string()),
+        }
+    }
+
+    #[cfg(feature = "aosc")]
+    fn toggle_sources(&mut self) {
+        if self.sources.take().is_some() {
+            change_to_packages_window(&mut self.mode, &mut self.pkg_result_state);
+            return;
+        }
+
+        self.history = None;
+
+        match SourcesView::new(self.sysroot) {
+            Ok(sources) => {
+                self.sources = Some(sources);
+                self.mode = Mode::Sources;
+            }
+            Err(e) => self.popup = Some(e.to_string()),
+        }
+    }
+
+    /// Keys only used by the topics and mirrors screen, `Continue(true)` if the key is handled
+    #[cfg(feature = "aosc")]
+    fn handle_sources_key(
+        &mut self,
+        code: KeyCode,
+        modifiers: KeyModifiers,
+    ) -> ControlFlow<Task, bool> {
+        let Some(sources) = &mut self.sources else {
+            return ControlFlow::Continue(false);
+        };
+
+        match code {
+            KeyCode::Up if modifiers == KeyModifiers::SHIFT => sources.move_mirror(true),
+            KeyCode::Down if modifiers == KeyModifiers::SHIFT => sources.move_mirror(false),
+            KeyCode::Char('t') if sources.tab == SourcesTab::Mirrors => sources.test_latency(),
+            KeyCode::Char('a') => match sources.changes() {
+                Some(SourcesChange {
+                    mirrors: Some(m), ..
+                }) if m.is_empty() => {
+                    self.popup = Some(fl!("tui-sources-no-mirror"));
+                }
+                Some(change) => {
+                    return ControlFlow::Break(Task {
+                        sources: Some(change),
+                        ..Default::default()
+                    });
+                }
+                None => self.popup = Some(fl!("tui-sources-unchanged")),
+            },
+            _ => return ControlFlow::Continue(false),
+        }
+
+        ControlFlow::Continue(true)
+    }
+
     fn handle_right(&mut self) {
         match self.mode {
             Mode::Search => {
@@ -281,6 +593,21 @@ impl<'a> Tui<'a> {
             Mode::Packages => {
                 change_to_pending_window(&mut self.mode, &mut self.pending_result_state);
             }
+            Mode::Detail => {
+                if let Some(detail) = &mut self.detail {
+                    detail.next_tab();
+                    if detail.tab == DetailTab::Changelog {
+                        detail.load_changelog(self.apt, self.sysroot, self.config);
+                    }
+                }
+            }
+            Mode::History => {}
+            #[cfg(feature = "aosc")]
+            Mode::Sources => {
+                if let Some(sources) = &mut self.sources {
+                    sources.switch_tab();
+                }
+            }
             Mode::Pending => {
                 change_to_packages_window(&mut self.mode, &mut self.pkg_result_state);
             }
@@ -295,6 +622,21 @@ impl<'a> Tui<'a> {
             Mode::Packages => {
                 change_to_pending_window(&mut self.mode, &mut self.pending_result_state);
             }
+            Mode::Detail => {
+                if let Some(detail) = &mut self.detail {
+                    detail.previous_tab();
+                    if detail.tab == DetailTab::Changelog {
+                        detail.load_changelog(self.apt, self.sysroot, self.config);
+                    }
+                }
+            }
+            Mode::History => {}
+            #[cfg(feature = "aosc")]
+            Mode::Sources => {
+                if let Some(sources) = &mut self.sources {
+                    sources.switch_tab();
+                }
+            }
             Mode::Pending => {
                 change_to_packages_window(&mut self.mode, &mut self.pkg_result_state);
             }
@@ -357,22 +699,42 @@ impl<'a> Tui<'a> {
     }
 
     fn handle_tab(&mut self) {
-        if self.display_pending_detail {
-            self.mode = match self.mode {
-                Mode::Search => Mode::Packages,
-                Mode::Packages => Mode::Pending,
-                Mode::Pending => Mode::Search,
-            };
+        let mut windows = vec![Mode::Search];
+
+        #[cfg(feature = "aosc")]
+        let sources = self.sources.is_some();
+        #[cfg(not(feature = "aosc"))]
+        let sources = false;
+
+        if self.history.is_some() {
+            windows.push(Mode::History);
+        } else if sources {
+            #[cfg(feature = "aosc")]
+            windows.push(Mode::Sources);
         } else {
-            self.mode = match self.mode {
-                Mode::Search => Mode::Packages,
-                Mode::Packages => Mode::Search,
-                Mode::Pending => Mode::Search,
-            };
+            windows.push(Mode::Packages);
+
+            if self.detail.is_some() {
+                windows.push(Mode::Detail);
+            }
         }
 
+        if self.display_pending_detail {
+            windows.push(Mode::Pending);
+        }
+
+        // Hidden windows go back to the search bar
+        let pos = windows
+            .iter()
+            .position(|x| *x == self.mode)
+            .unwrap_or(windows.len() - 1);
+
+        self.mode = windows[(pos + 1) % windows.len()];
+
         match self.mode {
-            Mode::Search => {}
+            Mode::Search | Mode::Detail | Mode::History => {}
+            #[cfg(feature = "aosc")]
+            Mode::Sources => {}
             Mode::Packages => {
                 change_to_packages_window(&mut self.mode, &mut self.pkg_result_state);
             }
@@ -394,7 +756,7 @@ impl<'a> Tui<'a> {
         self.input_cursor_position = self.input_cursor_position.saturating_add(1);
 
         let s = &self.input;
-        let res = self.searcher.search(s);
+        let res = search_with_facets(&self.searcher, s);
 
         if let Ok(res) = res {
             let res_display = res
@@ -413,9 +775,132 @@ impl<'a> Tui<'a> {
         }
     }
 
+    fn handle_enter(&mut self) {
+        match self.mode {
+            Mode::Search | Mode::Pending => {}
+            #[cfg(feature = "aosc")]
+            Mode::Sources => {}
+            Mode::History => {
+                if let Some(history) = &mut self.history {
+                    if let Err(e) = history.inspect(self.sysroot) {
+                        self.popup = Some(OutputError::from(e).to_string());
+                    }
+                }
+            }
+            Mode::Packages => {
+                let Some(i) = self.pkg_result_state.state.selected() else {
+                    return;
+                };
+
+                let name = &self.pkg_results[i].name;
+
+                self.detail_history.clear();
+                self.detail = Detail::new(self.apt, name);
+
+                if self.detail.is_some() {
+                    self.mode = Mode::Detail;
+                } else {
+                    self.popup = Some(fl!("can-not-get-pkg-from-database", name = name.as_str()));
+                }
+            }
+            Mode::Detail => {
+                let Some(detail) = &self.detail else {
+                    return;
+                };
+
+                if let Some(target) = detail.selected_target() {
+                    let target = target.to_string();
+                    self.jump_to(target);
+                } else if let Some(version) = detail.selected_version() {
+                    let (name, version, index) =
+                        (detail.name.clone(), version.version.clone(), version.index);
+                    self.pick_version(name, version, index);
+                }
+            }
+        }
+    }
+
+    /// Show the package detail of a dependency, and remember where we came from
+    fn jump_to(&mut self, target: String) {
+        match Detail::new(self.apt, &target) {
+            Some(detail) => {
+                if let Some(old) = self.detail.replace(detail) {
+                    self.detail_history.push(old.name);
+                }
+            }
+            None => {
+                self.popup = Some(fl!("can-not-get-pkg-from-database", name = target));
+            }
+        }
+    }
+
+    /// Go back to the previous package, or close the detail pane
+    fn detail_back(&mut self) {
+        match self.detail_history.pop() {
+            Some(name) => self.detail = Detail::new(self.apt, &name),
+            None => self.detail = None,
+        }
+
+        if self.detail.is_none() {
+            self.detail_history.clear();
+            change_to_packages_window(&mut self.mode, &mut self.pkg_result_state);
+        }
+    }
+
+    /// Follow the highlighted package if the detail pane is shown
+    fn update_detail(&mut self) {
+        if self.detail.is_none() {
+            return;
+        }
+
+        if let Some(i) = self.pkg_result_state.state.selected() {
+            self.detail_history.clear();
+            self.detail = Detail::new(self.apt, &self.pkg_results[i].name);
+        }
+    }
+
+    /// Install a specific version of a package, like `oma pick`
+    fn pick_version(&mut self, name: String, version: String, index: u64) {
+        let Some(pkg) = self.apt.cache.get(&name) else {
+            return;
+        };
+
+        // Versions from different sources can share the same version string
+        let Some(ver) = pkg.versions().find(|x| x.index() == index) else {
+            return;
+        };
+
+        // A package can only have one pending operation
+        self.install.retain(|x| x.raw_pkg.fullname(true) != name);
+        self.remove.retain(|x| x.raw_pkg.fullname(true) != name);
+        self.pending_result_state
+            .items
+            .retain(|x| !matches!(x, Operation::Package { name: n, .. } if *n == name));
+        self.pending_result_state.state.select(None);
+        self.display_pending_detail = true;
+
+        if ver.is_installed() {
+            return;
+        }
+
+        if let Ok(pkginfo) = OmaPackage::new(&ver, &pkg) {
+            self.install.push(pkginfo);
+            self.pending_result_state.items.push(Operation::Package {
+                name,
+                version: Some(version),
+            });
+        }
+    }
+
     fn handle_space(&mut self) -> ControlFlow<()> {
         match self.mode {
-            Mode::Search => {}
+            Mode::Search | Mode::Detail | Mode::History => {}
+            #[cfg(feature = "aosc")]
+            Mode::Sources => {
+                if let Some(sources) = &mut self.sources {
+                    sources.toggle();
+                }
+            }
             Mode::Packages => {
                 let selected = self.pkg_result_state.state.selected();
                 if let Some(i) = selected {
@@ -548,6 +1033,23 @@ impl<'a> Tui<'a> {
                 self.result_scroll = self
                     .result_scroll
                     .position(self.pkg_result_state.state.selected().unwrap_or(0));
+                self.update_detail();
+            }
+            Mode::Detail => {
+                if let Some(detail) = &mut self.detail {
+                    detail.next();
+                }
+            }
+            Mode::History => {
+                if let Some(history) = &mut self.history {
+                    history.entries.next();
+                }
+            }
+            #[cfg(feature = "aosc")]
+            Mode::Sources => {
+                if let Some(sources) = &mut self.sources {
+                    sources.next();
+                }
             }
             Mode::Pending => {
                 self.pending_result_state.next();
@@ -572,6 +1074,23 @@ impl<'a> Tui<'a> {
                     self.result_scroll = self
                         .result_scroll
                         .position(self.pkg_result_state.state.selected().unwrap_or(0));
+                    self.update_detail();
+                }
+            }
+            Mode::Detail => {
+                if let Some(detail) = &mut self.detail {
+                    detail.previous();
+                }
+            }
+            Mode::History => {
+                if let Some(history) = &mut self.history {
+                    history.entries.previous();
+                }
+            }
+            #[cfg(feature = "aosc")]
+            Mode::Sources => {
+                if let Some(sources) = &mut self.sources {
+                    sources.previous();
                 }
             }
             Mode::Pending => {
@@ -605,7 +1124,7 @@ impl<'a> Tui<'a> {
         f.render_widget(
             Block::default()
                 .title(format!(" {} v{}", fl!("oma"), env!("CARGO_PKG_VERSION")))
-                .style(Style::default().bg(Color::White).fg(Color::Black)),
+                .style(header_style()),
             main_layout[0],
         );
 
@@ -614,19 +1133,71 @@ impl<'a> Tui<'a> {
             .direction(Direction::Horizontal)
             .split(main_layout[2]);
 
-        show_packages(
-            &self.pkg_results,
-            f,
-            &mut self.pkg_result_state,
-            &self.mode,
-            if self.display_pending_detail {
-                chunks[0]
+        let packages_area = if self.display_pending_detail {
+            chunks[0]
+        } else {
+            main_layout[2]
+        };
+
+        self.page_height = packages_area.height.saturating_sub(2);
+        self.panes = vec![Pane::new(Mode::Search, main_layout[1], Rect::default())];
+
+        #[cfg(feature = "aosc")]
+        let sources_shown = match &mut self.sources {
+            Some(sources) => {
+                let rows = show_sources(f, sources, &self.mode, packages_area);
+                self.panes
+                    .push(Pane::new(Mode::Sources, packages_area, rows));
+                true
+            }
+            None => false,
+        };
+
+        #[cfg(not(feature = "aosc"))]
+        let sources_shown = false;
+
+        if let Some(history) = &mut self.history {
+            let rows = show_history(f, history, &self.mode, packages_area);
+            self.panes
+                .push(Pane::new(Mode::History, packages_area, rows));
+        } else if !sources_shown {
+            let packages_area = if let Some(detail) = &mut self.detail {
+                let [packages_area, detail_area] =
+                    Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                        .areas(packages_area);
+
+                let rows = show_detail(f, detail, &self.mode, detail_area);
+                self.panes.push(Pane::new(Mode::Detail, detail_area, rows));
+
+                packages_area
             } else {
-                main_layout[2]
-            },
-            (self.upgradable, self.autoremovable),
-            self.installed,
-        );
+                packages_area
+            };
+
+            show_packages(
+                &self.pkg_results,
+                f,
+                &mut self.pkg_result_state,
+                &self.mode,
+                packages_area,
+                (self.upgradable, self.autoremovable),
+                self.installed,
+            );
+
+            f.render_stateful_widget(
+                Scrollbar::new(ScrollbarOrientation::VerticalRight)
+                    .begin_symbol(Some("↑"))
+                    .end_symbol(Some("↓")),
+                packages_area,
+                &mut self.result_scroll,
+            );
+
+            self.panes.push(Pane::new(
+                Mode::Packages,
+                packages_area,
+                packages_area.inner(Margin::new(1, 1)),
+            ));
+        }
 
         if self.display_pending_detail {
             f.render_stateful_widget(
@@ -637,26 +1208,16 @@ impl<'a> Tui<'a> {
                             .title(fl!("tui-pending"))
                             .style(highlight_window(&self.mode, &Mode::Pending)),
                     )
-                    .highlight_style(Style::default().bg(Color::Rgb(59, 64, 70))),
+                    .highlight_style(highlight_style()),
                 chunks[1],
                 &mut self.pending_result_state.state,
             );
 
-            f.render_stateful_widget(
-                Scrollbar::new(ScrollbarOrientation::VerticalRight)
-                    .begin_symbol(Some("↑"))
-                    .end_symbol(Some("↓")),
-                chunks[0],
-                &mut self.result_scroll,
-            );
-        } else {
-            f.render_stateful_widget(
-                Scrollbar::new(ScrollbarOrientation::VerticalRight)
-                    .begin_symbol(Some("↑"))
-                    .end_symbol(Some("↓")),
-                main_layout[2],
-                &mut self.result_scroll,
-            );
+            self.panes.push(Pane::new(
+                Mode::Pending,
+                chunks[1],
+                chunks[1].inner(Margin::new(1, 1)),
+            ));
         }
 
         f.render_widget(
@@ -706,51 +1267,73 @@ impl<'a> Tui<'a> {
 fn render_tips(f: &mut Frame<'_>, main_layout: &Rc<[Rect]>) {
     match WRITER.get_length() {
         0..=62 => {}
-        63..=153 => {
-            f.render_widget(
-                Paragraph::new(Line::from(vec![
-                    Span::raw("Quicknav: "),
-                    Span::styled("TAB", Style::new().blue()),
-                    Span::raw(" / "),
-                    Span::styled("F1", Style::new().blue()),
-                    Span::raw(" / "),
-                    Span::styled("ESC", Style::new().blue()),
-                    Span::raw(" / "),
-                    Span::styled("Space", Style::new().blue()),
-                    Span::raw(" / "),
-                    Span::styled("/", Style::new().blue()),
-                    Span::raw(" / "),
-                    Span::styled("Ctrl+U", Style::new().blue()),
-                    Span::raw(" / "),
-                    Span::styled("Ctrl+A", Style::new().blue()),
-                    Span::raw(" / "),
-                    Span::styled("Ctrl+C", Style::new().blue()),
-                ])),
-                main_layout[3],
-            );
+        63..=199 => {
+            #[allow(unused_mut)]
+            let mut tips = vec![
+                Span::raw("Quicknav: "),
+                Span::styled("TAB", key_style()),
+                Span::raw(" / "),
+                Span::styled("F1", key_style()),
+                Span::raw(" / "),
+                Span::styled("F2", key_style()),
+                Span::raw(" / "),
+                Span::styled("ESC", key_style()),
+                Span::raw(" / "),
+                Span::styled("Space", key_style()),
+                Span::raw(" / "),
+                Span::styled("Enter", key_style()),
+                Span::raw(" / "),
+                Span::styled("/", key_style()),
+                Span::raw(" / "),
+                Span::styled("Ctrl+U", key_style()),
+                Span::raw(" / "),
+                Span::styled("Ctrl+A", key_style()),
+                Span::raw(" / "),
+                Span::styled("Ctrl+C", key_style()),
+            ];
+
+            // After F2
+            #[cfg(feature = "aosc")]
+            tips.splice(6..6, [Span::raw(" / "), Span::styled("F3", key_style())]);
+
+            f.render_widget(Paragraph::new(Line::from(tips)), main_layout[3]);
         }
-        154.. => {
-            f.render_widget(
-                Paragraph::new(Line::from(vec![
-                    Span::styled("TAB", Style::new().blue()),
-                    Span::raw(format!(" => {}, ", fl!("tui-start-2"))),
-                    Span::styled("F1", Style::new().blue()),
-                    Span::raw(format!(" => {}, ", fl!("tui-start-3"))),
-                    Span::styled("ESC", Style::new().blue()),
-                    Span::raw(format!(" => {}, ", fl!("tui-start-4"))),
-                    Span::styled("Space", Style::new().blue()),
-                    Span::raw(format!(" => {}, ", fl!("tui-start-5"))),
-                    Span::styled("/", Style::new().blue()),
-                    Span::raw(format!(" => {}, ", fl!("tui-start-6"))),
-                    Span::styled("Ctrl+U", Style::new().blue()),
-                    Span::raw(format!(" => {}, ", fl!("tui-upgrade"))),
-                    Span::styled("Ctrl+A", Style::new().blue()),
-                    Span::raw(format!(" => {}, ", fl!("tui-autoremove"))),
-                    Span::styled("Ctrl+C", Style::new().blue()),
-                    Span::raw(format!(" => {}", fl!("tui-start-7"))),
-                ])),
-                main_layout[3],
+        200.. => {
+            #[allow(unused_mut)]
+            let mut tips = vec![
+                Span::styled("TAB", key_style()),
+                Span::raw(format!(" => {}, ", fl!("tui-start-2"))),
+                Span::styled("F1", key_style()),
+                Span::raw(format!(" => {}, ", fl!("tui-start-3"))),
+                Span::styled("F2", key_style()),
+                Span::raw(format!(" => {}, ", fl!("tui-start-9"))),
+                Span::styled("ESC", key_style()),
+                Span::raw(format!(" => {}, ", fl!("tui-start-4"))),
+                Span::styled("Space", key_style()),
+                Span::raw(format!(" => {}, ", fl!("tui-start-5"))),
+                Span::styled("Enter", key_style()),
+                Span::raw(format!(" => {}, ", fl!("tui-start-8"))),
+                Span::styled("/", key_style()),
+                Span::raw(format!(" => {}, ", fl!("tui-start-6"))),
+                Span::styled("Ctrl+U", key_style()),
+                Span::raw(format!(" => {}, ", fl!("tui-upgrade"))),
+                Span::styled("Ctrl+A", key_style()),
+                Span::raw(format!(" => {}, ", fl!("tui-autoremove"))),
+                Span::styled("Ctrl+C", key_style()),
+                Span::raw(format!(" => {}", fl!("tui-start-7"))),
+            ];
+
+            // After F2
+            #[cfg(feature = "aosc")]
+            tips.splice(
+                6..6,
+                [
+                    Span::styled("F3", key_style()),
+                    Span::raw(format!(" => {}, ", fl!("tui-start-10"))),
+                ],
             );
+
+            f.render_widget(Paragraph::new(Line::from(tips)), main_layout[3]);
         }
     }
 }
@@ -778,65 +1361,274 @@ fn show_packages(
                         ))
                         .style(highlight_window(mode, &Mode::Packages)),
                 )
-                .highlight_style(Style::default().bg(Color::Rgb(59, 64, 70))),
+                .highlight_style(highlight_style()),
             area,
             &mut display_list.state,
         );
     } else {
+        #[allow(unused_mut)]
+        let mut lines = vec![
+            Line::from(fl!("tui-start-1")),
+            Line::from(""),
+            Line::from(vec![
+                Span::styled("TAB", key_style()),
+                Span::raw(format!(" => {}", fl!("tui-start-2"))),
+            ]),
+            Line::from(vec![
+                Span::styled("F1", key_style()),
+                Span::raw(format!(" => {}", fl!("tui-start-3"))),
+            ]),
+            Line::from(vec![
+                Span::styled("F2", key_style()),
+                Span::raw(format!(" => {}", fl!("tui-start-9"))),
+            ]),
+            Line::from(vec![
+                Span::styled("ESC", key_style()),
+                Span::raw(format!(" => {}", fl!("tui-start-4"))),
+            ]),
+            Line::from(vec![
+                Span::styled("Space", key_style()),
+                Span::raw(format!(" => {}", fl!("tui-start-5"))),
+            ]),
+            Line::from(vec![
+                Span::styled("Enter", key_style()),
+                Span::raw(format!(" => {}", fl!("tui-start-8"))),
+            ]),
+            Line::from(vec![
+                Span::styled("/", key_style()),
+                Span::raw(format!(" => {}", fl!("tui-start-6"))),
+            ]),
+            Line::from(vec![
+                Span::styled("Ctrl+U", key_style()),
+                Span::raw(format!(" => {}", fl!("tui-upgrade"))),
+            ]),
+            Line::from(vec![
+                Span::styled("Ctrl+A", key_style()),
+                Span::raw(format!(" => {}", fl!("tui-autoremove"))),
+            ]),
+            Line::from(vec![
+                Span::styled("Ctrl+C", key_style()),
+                Span::raw(format!(" => {}", fl!("tui-start-7"))),
+            ]),
+            Line::from(""),
+            Line::from(fl!(
+                "tui-packages",
+                u = upgradable_and_autoremovable.0,
+                r = upgradable_and_autoremovable.1,
+                i = installed
+            )),
+        ];
+
+        // After F2
+        #[cfg(feature = "aosc")]
+        lines.insert(
+            5,
+            Line::from(vec![
+                Span::styled("F3", key_style()),
+                Span::raw(format!(" => {}", fl!("tui-start-10"))),
+            ]),
+        );
+
         frame.render_widget(
-            Paragraph::new(vec![
-                Line::from(fl!("tui-start-1")),
-                Line::from(""),
-                Line::from(vec![
-                    Span::styled("TAB", Style::new().blue()),
-                    Span::raw(format!(" => {}", fl!("tui-start-2"))),
-                ]),
-                Line::from(vec![
-                    Span::styled("F1", Style::new().blue()),
-                    Span::raw(format!(" => {}", fl!("tui-start-3"))),
-                ]),
-                Line::from(vec![
-                    Span::styled("ESC", Style::new().blue()),
-                    Span::raw(format!(" => {}", fl!("tui-start-4"))),
-                ]),
-                Line::from(vec![
-                    Span::styled("Space", Style::new().blue()),
-                    Span::raw(format!(" => {}", fl!("tui-start-5"))),
-                ]),
-                Line::from(vec![
-                    Span::styled("/", Style::new().blue()),
-                    Span::raw(format!(" => {}", fl!("tui-start-6"))),
-                ]),
-                Line::from(vec![
-                    Span::styled("Ctrl+U", Style::new().blue()),
-                    Span::raw(format!(" => {}", fl!("tui-upgrade"))),
-                ]),
-                Line::from(vec![
-                    Span::styled("Ctrl+A", Style::new().blue()),
-                    Span::raw(format!(" => {}", fl!("tui-autoremove"))),
-                ]),
-                Line::from(vec![
-                    Span::styled("Ctrl+C", Style::new().blue()),
-                    Span::raw(format!(" => {}", fl!("tui-start-7"))),
-                ]),
-                Line::from(""),
-                Line::from(fl!(
-                    "tui-packages",
-                    u = upgradable_and_autoremovable.0,
-                    r = upgradable_and_autoremovable.1,
-                    i = installed
-                )),
-            ])
+            Paragraph::new(lines)
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .style(highlight_window(mode, &Mode::Packages))
+                        .padding(Padding::new(0, 0, (area.height / 2).saturating_sub(10), 0)),
+                )
+                .alignment(Alignment::Center),
+            area,
+        );
+    }
+}
+
+/// Render the detail pane, returns the area of list rows
+fn show_detail(frame: &mut Frame<'_>, detail: &mut Detail, mode: &Mode, area: Rect) -> Rect {
+    let title = match &detail.version {
+        Some(version) => format!("{} ({version})", detail.name),
+        None => detail.name.clone(),
+    };
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .title_bottom(Line::from(vec![
+            Span::styled("←/→", key_style()),
+            Span::raw(format!(" => {}, ", fl!("tui-detail-switch-tab"))),
+            Span::styled("Enter", key_style()),
+            Span::raw(format!(" => {}, ", fl!("tui-detail-enter"))),
+            Span::styled("Backspace", key_style()),
+            Span::raw(format!(" => {}", fl!("tui-detail-back"))),
+        ]))
+        .style(highlight_window(mode, &Mode::Detail));
+
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let [tabs_area, content_area] =
+        Layout::vertical([Constraint::Length(2), Constraint::Min(0)]).areas(inner);
+
+    frame.render_widget(
+        Tabs::new(DetailTab::ALL.iter().map(|x| x.title()))
+            .select(detail.tab_index())
+            .highlight_style(highlight_style()),
+        tabs_area,
+    );
+
+    let list = |items: Vec<ListItem<'static>>| List::new(items).highlight_style(highlight_style());
+
+    match detail.tab {
+        DetailTab::Info => frame.render_widget(
+            Paragraph::new(detail.info.clone())
+                .wrap(Wrap { trim: false })
+                .scroll((detail.scroll, 0)),
+            content_area,
+        ),
+        DetailTab::Changelog => frame.render_widget(
+            Paragraph::new(
+                detail
+                    .changelog
+                    .clone()
+                    .unwrap_or_else(|| Text::from(fl!("tui-detail-changelog-loading"))),
+            )
+            .scroll((detail.scroll, 0)),
+            content_area,
+        ),
+        DetailTab::Depends => frame.render_stateful_widget(
+            list(
+                detail
+                    .deps
+                    .items
+                    .iter()
+                    .map(|x| ListItem::new(x.text.clone()))
+                    .collect(),
+            ),
+            content_area,
+            &mut detail.deps.state,
+        ),
+        DetailTab::ReverseDepends => frame.render_stateful_widget(
+            list(
+                detail
+                    .rdeps
+                    .items
+                    .iter()
+                    .map(|x| ListItem::new(x.text.clone()))
+                    .collect(),
+            ),
+            content_area,
+            &mut detail.rdeps.state,
+        ),
+        DetailTab::Versions => frame.render_stateful_widget(
+            list(
+                detail
+                    .versions
+                    .items
+                    .iter()
+                    .map(|x| ListItem::new(x.text.clone()))
+                    .collect(),
+            ),
+            content_area,
+            &mut detail.versions.state,
+        ),
+    }
+
+    content_area
+}
+
+/// Render the history browser, returns the area of list rows
+fn show_history(frame: &mut Frame<'_>, history: &mut HistoryView, mode: &Mode, area: Rect) -> Rect {
+    let [list_area, inspect_area] =
+        Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(area);
+
+    frame.render_stateful_widget(
+        List::new(history.entries.items.clone())
             .block(
                 Block::default()
                     .borders(Borders::ALL)
-                    .style(highlight_window(mode, &Mode::Packages))
-                    .padding(Padding::new(0, 0, (area.height / 2).saturating_sub(8), 0)),
+                    .title(fl!("tui-history"))
+                    .title_bottom(Line::from(vec![
+                        Span::styled("Enter", key_style()),
+                        Span::raw(format!(" => {}, ", fl!("tui-history-inspect"))),
+                        Span::styled("U", key_style()),
+                        Span::raw(format!(" => {}", fl!("tui-history-undo"))),
+                    ]))
+                    .style(highlight_window(mode, &Mode::History)),
             )
-            .alignment(Alignment::Center),
-            area,
-        );
+            .highlight_style(highlight_style()),
+        list_area,
+        &mut history.entries.state,
+    );
+
+    frame.render_widget(
+        Paragraph::new(history.inspected.clone().unwrap_or_default())
+            .wrap(Wrap { trim: false })
+            .block(Block::default().borders(Borders::ALL)),
+        inspect_area,
+    );
+
+    list_area.inner(Margin::new(1, 1))
+}
+
+#[cfg(feature = "aosc")]
+/// Render the topics and mirrors screen, returns the area of list rows
+fn show_sources(frame: &mut Frame<'_>, sources: &mut SourcesView, mode: &Mode, area: Rect) -> Rect {
+    let mut tips = vec![
+        Span::styled("←/→", key_style()),
+        Span::raw(format!(" => {}, ", fl!("tui-detail-switch-tab"))),
+        Span::styled("Space", key_style()),
+        Span::raw(format!(" => {}, ", fl!("tui-sources-toggle"))),
+    ];
+
+    if sources.tab == SourcesTab::Mirrors {
+        tips.extend([
+            Span::styled("Shift+↑/↓", key_style()),
+            Span::raw(format!(" => {}, ", fl!("tui-sources-reorder"))),
+            Span::styled("T", key_style()),
+            Span::raw(format!(" => {}, ", fl!("tui-sources-latency"))),
+        ]);
     }
+
+    tips.extend([
+        Span::styled("A", key_style()),
+        Span::raw(format!(" => {}", fl!("tui-sources-apply"))),
+    ]);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(fl!("tui-sources"))
+        .title_bottom(Line::from(tips))
+        .style(highlight_window(mode, &Mode::Sources));
+
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let [tabs_area, content_area] =
+        Layout::vertical([Constraint::Length(2), Constraint::Min(0)]).areas(inner);
+
+    frame.render_widget(
+        Tabs::new(SourcesTab::ALL.iter().map(|x| x.title()))
+            .select(sources.tab_index())
+            .highlight_style(highlight_style()),
+        tabs_area,
+    );
+
+    let list = |items: Vec<String>| List::new(items).highlight_style(highlight_style());
+
+    match sources.tab {
+        SourcesTab::Topics => frame.render_stateful_widget(
+            list(sources.topics.items.iter().map(|x| x.text()).collect()),
+            content_area,
+            &mut sources.topics.state,
+        ),
+        SourcesTab::Mirrors => frame.render_stateful_widget(
+            list(sources.mirrors.items.iter().map(|x| x.text()).collect()),
+            content_area,
+            &mut sources.mirrors.state,
+        ),
+    }
+
+    content_area
 }
 
 fn highlight_window(mode: &Mode, right: &Mode) -> Style {
@@ -861,13 +1653,19 @@ fn change_to_pending_window(mode: &mut Mode, pending_display_list: &mut Stateful
     }
 }
 
+/// Search with facets typed in the search bar, like `section:devel gcc`
+fn search_with_facets(searcher: &IndexSearch<'_>, s: &str) -> OmaSearchResult<Vec<SearchResult>> {
+    let (filter, query) = SearchFilter::parse_query(s);
+    searcher.search_with_filter(&query, &filter)
+}
+
 fn update_search_result(
-    searcher: &IndiciumSearch<'_>,
+    searcher: &IndexSearch<'_>,
     s: &str,
     display_list: &mut StatefulList<Text<'_>>,
     result: &mut Vec<SearchResult>,
 ) {
-    let res = searcher.search(s);
+    let res = search_with_facets(searcher, s);
 
     if let Ok(res) = res {
         let res_display = res
@@ -895,13 +1693,3 @@ fn delete_inner(input: &mut String, before: usize, after: usize) {
 
     *input = before_char_to_delete + &after_char_to_delete;
 }
-
-/// helper function to create a centered rect using up certain percentage of the available rect `r`
-fn popup_area(area: Rect, x: u16, y: u16) -> Rect {
-    let vertical = Layout::vertical([Constraint::Length(y)]).flex(Flex::Center);
-    let horizontal = Layout::horizontal([Constraint::Length(x)]).flex(Flex::Center);
-    let [area] = vertical.areas(area);
-    let [area] = horizontal.areas(area);
-
-    area
-}
@@ -98,12 +98,15 @@ fn is_wsl() -> bool {
     false
 }
 
-pub fn check_battery(conn: &Connection, yes: bool) {
+/// Check whether the computer is using battery, and ask the user to continue if so
+///
+/// Returns whether the computer is using battery.
+pub fn check_battery(conn: &Connection, yes: bool) -> bool {
     let is_battery = RT.block_on(is_using_battery(conn)).unwrap_or(false);
 
     if is_battery {
         if yes {
-            return;
+            return true;
         }
         let theme = ColorfulTheme::default();
         warn!("{}", fl!("battery"));
@@ -117,6 +120,8 @@ pub fn check_battery(conn: &Connection, yes: bool) {
             exit(0);
         }
     }
+
+    is_battery
 }
 
 pub fn is_ssh_from_loginctl() -> bool {
@@ -137,16 +142,27 @@ pub struct SearchResultDisplay<'a>(pub &'a SearchResult);
 impl Display for SearchResultDisplay<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let i = self.0;
-        let mut pkg_info_line = if i.is_base {
-            color_formatter()
-                .color_str(&i.name, Action::Purple)
-                .bold()
-                .to_string()
+        let name_action = if i.is_base {
+            Action::Purple
         } else {
-            color_formatter()
-                .color_str(&i.name, Action::Emphasis)
+            Action::Emphasis
+        };
+
+        // Underline the part matched by regex or exact search
+        let mut pkg_info_line = match i.name_match.and_then(|x| split_match(&i.name, x)) {
+            Some((before, matched, after)) => format!(
+                "{}{}{}",
+                color_formatter().color_str(before, name_action).bold(),
+                color_formatter()
+                    .color_str(matched, name_action)
+                    .bold()
+                    .underlined(),
+                color_formatter().color_str(after, name_action).bold()
+            ),
+            None => color_formatter()
+                .color_str(&i.name, name_action)
                 .bold()
-                .to_string()
+                .to_string(),
         };
 
         pkg_info_line.push(' ');
@@ -175,6 +191,11 @@ impl Display for SearchResultDisplay<'_> {
             pkg_tags.push(fl!("full-match"))
         }
 
+        // Show every architecture when the package is installed more than once
+        if i.installed_archs.len() > 1 {
+            pkg_tags.push(i.installed_archs.join("+"));
+        }
+
         if !pkg_tags.is_empty() {
             pkg_info_line.push(' ');
             pkg_info_line.push_str(
@@ -203,11 +224,29 @@ impl Display for SearchResultDisplay<'_> {
             |t, s| {
                 match t {
                     MessageType::Msg => {
-                        writeln!(
-                            f,
-                            "{}",
-                            color_formatter().color_str(s.trim(), Action::Secondary)
-                        )
+                        let s = s.trim();
+                        // Descriptions are wrapped, so find the matched text in each line
+                        let matched = i
+                            .desc_match
+                            .and_then(|(start, end)| i.desc.get(start..end))
+                            .filter(|x| !x.is_empty())
+                            .and_then(|x| s.find(x).map(|start| (start, start + x.len())))
+                            .and_then(|x| split_match(s, x));
+
+                        match matched {
+                            Some((before, matched, after)) => writeln!(
+                                f,
+                                "{}{}{}",
+                                color_formatter().color_str(before, Action::Secondary),
+                                color_formatter()
+                                    .color_str(matched, Action::Secondary)
+                                    .underlined(),
+                                color_formatter().color_str(after, Action::Secondary)
+                            ),
+                            None => {
+                                writeln!(f, "{}", color_formatter().color_str(s, Action::Secondary))
+                            }
+                        }
                     }
                     MessageType::Prefix => write!(f, "{}", gen_prefix(s, 10)),
                 }
@@ -219,6 +258,11 @@ impl Display for SearchResultDisplay<'_> {
     }
 }
 
+/// Split `s` into the parts before, inside and after the byte range `span`
+fn split_match(s: &str, (start, end): (usize, usize)) -> Option<(&str, &str, &str)> {
+    Some((s.get(..start)?, s.get(start..end)?, s.get(end..)?))
+}
+
 /// oma display normal message
 #[macro_export]
 macro_rules! msg {